@@ -0,0 +1,104 @@
+//! Benchmarks for the per-keystroke hot paths on generated documents.
+//!
+//! Budget: at 10k tasks, `build_agenda` + `rebuild_tree` (what every edit
+//! pays) should stay under a few milliseconds. Run with `cargo bench`.
+//!
+//! The `idle` group shows what an idle app costs: a tick that finds nothing
+//! changed and skips drawing, next to the full frame the event loop used to
+//! draw ten times a second regardless.
+
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use gws::app::App;
+use gws::date::Date;
+use gws::engine;
+use gws::model::TaskState;
+use gws::parser;
+use gws::serializer;
+use gws::tui::ui;
+
+const SIZES: [usize; 3] = [1_000, 10_000, 50_000];
+
+/// A document with `tasks` tasks spread over 10 categories of 10 projects,
+/// with a note on every fifth task and a mix of states.
+fn generate(tasks: usize) -> String {
+    let per_project = tasks.div_ceil(100);
+    let mut out = String::from("# Benchmark\n\n");
+    let mut n = 0;
+    for c in 0..10 {
+        out.push_str(&format!("## Category {}\n\n", c));
+        for p in 0..10 {
+            let active = if p % 2 == 0 { "🔶 " } else { "" };
+            out.push_str(&format!("### {}Project {}-{}\n", active, c, p));
+            for t in 0..per_project {
+                if n == tasks {
+                    break;
+                }
+                let state = match t % 7 {
+                    0 => TaskState::InProgress,
+                    1 => TaskState::OnDeck,
+                    2 => TaskState::Done,
+                    _ => TaskState::Todo,
+                };
+                out.push_str(&format!("- {} Task {} with some descriptive text\n", state.symbol(), n));
+                if n % 5 == 0 {
+                    out.push_str("  A note about the task\n");
+                }
+                n += 1;
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn bench_hot_paths(c: &mut Criterion) {
+    for size in SIZES {
+        let content = generate(size);
+        let doc = parser::parse(&content);
+        let mut app = App::new(doc.clone(), PathBuf::from("/tmp/bench.md"));
+
+        let mut group = c.benchmark_group("hot_paths");
+        group.sample_size(20);
+        group.bench_with_input(BenchmarkId::new("parse", size), &content, |b, content| {
+            b.iter(|| parser::parse(content))
+        });
+        group.bench_with_input(BenchmarkId::new("parse_outline", size), &content, |b, content| {
+            b.iter(|| parser::parse_outline(content).matches(&doc))
+        });
+        group.bench_with_input(BenchmarkId::new("serialize", size), &doc, |b, doc| {
+            b.iter(|| serializer::serialize(doc))
+        });
+        group.bench_with_input(BenchmarkId::new("build_agenda", size), &doc, |b, doc| {
+            b.iter(|| engine::build_agenda(doc, &engine::DEFAULT_SECTIONS))
+        });
+        group.bench_function(BenchmarkId::new("rebuild_tree", size), |b| b.iter(|| app.rebuild_tree()));
+        group.finish();
+    }
+}
+
+fn bench_idle(c: &mut Criterion) {
+    let doc = parser::parse(&generate(10_000));
+    let mut app = App::new(doc, PathBuf::from("/tmp/bench.md"));
+    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+    let today = Date::today();
+
+    let mut group = c.benchmark_group("idle");
+    group.bench_function("tick", |b| {
+        b.iter(|| {
+            app.tick(today);
+            app.needs_redraw
+        })
+    });
+    group.bench_function("draw_frame", |b| {
+        b.iter(|| terminal.draw(|frame| ui::draw(frame, &mut app)).map(|_| ()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_hot_paths, bench_idle);
+criterion_main!(benches);
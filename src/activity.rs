@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::date::Date;
+use crate::diff::{self, Change, TaskAddress};
+use crate::model::{Document, TaskState};
+
+/// Dated changes to a todo file, stored next to it as `<name>.activity.toml`.
+/// Task state changes are logged for per-task history; the weekly report
+/// counts completions from archive dates instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActivityLog {
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub kind: EventKind,
+    pub category: String,
+    pub project: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// New state, for `Changed` events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<TaskState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Added,
+    /// A task moved to another state.
+    Changed,
+    Activated,
+    Deactivated,
+}
+
+/// Events older than this are dropped when the log is written.
+const RETENTION_DAYS: i64 = 365;
+
+impl ActivityLog {
+    /// Log tasks added or changing state, and projects (de)activated,
+    /// between two versions. A moved task takes its history with it.
+    pub fn record(&mut self, old: &Document, new: &Document, today: Date) {
+        let date = today.to_string();
+        for change in diff::diff(old, new) {
+            match change {
+                Change::Added(address) => self.events.push(Event {
+                    date: date.clone(),
+                    kind: EventKind::Added,
+                    category: address.category,
+                    project: address.project,
+                    task: Some(address.text),
+                    state: None,
+                }),
+                Change::StateChanged { address, to, .. } => self.events.push(Event {
+                    date: date.clone(),
+                    kind: EventKind::Changed,
+                    category: address.category,
+                    project: address.project,
+                    task: Some(address.text),
+                    state: Some(to),
+                }),
+                Change::Moved { from, to } => {
+                    for event in &mut self.events {
+                        if event.category == from.category
+                            && event.project == from.project
+                            && event.task.as_deref() == Some(from.text.as_str())
+                        {
+                            event.category = to.category.clone();
+                            event.project = to.project.clone();
+                        }
+                    }
+                    // The diff reports a move on its own, even if the state changed too
+                    let state = state_at(new, &to);
+                    if state.is_some() && state != state_at(old, &from) {
+                        self.events.push(Event {
+                            date: date.clone(),
+                            kind: EventKind::Changed,
+                            category: to.category,
+                            project: to.project,
+                            task: Some(to.text),
+                            state,
+                        });
+                    }
+                }
+                Change::Removed(_) | Change::NotesChanged(_) => {}
+            }
+        }
+        for category in &new.categories {
+            let Some(old_category) = old.categories.iter().find(|c| c.name == category.name) else {
+                continue;
+            };
+            for project in &category.projects {
+                let Some(old_project) = old_category.projects.iter().find(|p| p.name == project.name) else {
+                    continue;
+                };
+                if project.active != old_project.active {
+                    self.events.push(Event {
+                        date: date.clone(),
+                        kind: if project.active { EventKind::Activated } else { EventKind::Deactivated },
+                        category: category.name.clone(),
+                        project: project.name.clone(),
+                        task: None,
+                        state: None,
+                    });
+                }
+            }
+        }
+        let cutoff = today.minus_days(RETENTION_DAYS);
+        self.events.retain(|e| Date::parse(&e.date).is_some_and(|d| d >= cutoff));
+    }
+
+    /// Summary of one task's logged history as of `today`, e.g.
+    /// "created 12d ago, on deck 3d, in progress 1d". None without history.
+    pub fn task_history(&self, category: &str, project: &str, task: &str, today: Date) -> Option<String> {
+        let events: Vec<(Date, &Event)> = self
+            .events
+            .iter()
+            .filter(|e| e.category == category && e.project == project && e.task.as_deref() == Some(task))
+            .filter_map(|e| Some((Date::parse(&e.date)?, e)))
+            .collect();
+        if events.is_empty() {
+            return None;
+        }
+        let days = |from: Date, to: Date| format!("{}d", to.days_since(from));
+        let mut parts = Vec::new();
+        for (i, (date, event)) in events.iter().enumerate() {
+            let until = events.get(i + 1).map_or(today, |(next, _)| *next);
+            match (event.kind, event.state) {
+                (EventKind::Added, _) => parts.push(format!("created {} ago", days(*date, today))),
+                (EventKind::Changed, Some(TaskState::Done)) => parts.push(format!("done {} ago", days(*date, today))),
+                (EventKind::Changed, Some(state)) => {
+                    parts.push(format!("{} {}", state.label().to_lowercase(), days(*date, until)))
+                }
+                _ => {}
+            }
+        }
+        Some(parts.join(", "))
+    }
+
+    /// When the task last changed state, i.e. entered the one it is in now.
+    pub fn last_change(&self, category: &str, project: &str, task: &str) -> Option<Date> {
+        self.events
+            .iter()
+            .rev()
+            .filter(|e| e.kind == EventKind::Changed)
+            .find(|e| e.category == category && e.project == project && e.task.as_deref() == Some(task))
+            .and_then(|e| Date::parse(&e.date))
+    }
+
+    /// Events on or after `since`.
+    pub fn since(&self, since: Date) -> impl Iterator<Item = &Event> {
+        self.events
+            .iter()
+            .filter(move |e| Date::parse(&e.date).is_some_and(|d| d >= since))
+    }
+}
+
+fn state_at(doc: &Document, address: &TaskAddress) -> Option<TaskState> {
+    doc.categories
+        .iter()
+        .find(|c| c.name == address.category)?
+        .projects
+        .iter()
+        .find(|p| p.name == address.project)?
+        .tasks
+        .iter()
+        .find(|t| t.text == address.text)
+        .map(|t| t.state)
+}
+
+pub fn activity_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("activity.toml")
+}
+
+/// Load the log for a todo file; a missing or unreadable log is empty.
+pub fn load(file_path: &Path) -> ActivityLog {
+    fs::read_to_string(activity_path(file_path))
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(file_path: &Path, log: &ActivityLog) -> Result<()> {
+    let content = toml::to_string_pretty(log).context("Failed to serialize activity log")?;
+    fs::write(activity_path(file_path), content).context("Failed to write activity log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_record_activity() {
+        let old = parse("## Work\n\n### 🔶 Launch\n- 🔴 Write copy\n\n### Hiring\n");
+        let new = parse("## Work\n\n### Launch\n- 🔴 Write copy\n- 🔴 Book venue\n\n### 🔶 Hiring\n");
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        let mut log = ActivityLog {
+            events: vec![Event {
+                date: "2025-01-01".to_string(),
+                kind: EventKind::Added,
+                category: "Work".to_string(),
+                project: "Launch".to_string(),
+                task: Some("Ancient".to_string()),
+                state: None,
+            }],
+        };
+        log.record(&old, &new, today);
+
+        let kinds: Vec<_> = log.events.iter().map(|e| (e.kind, e.project.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [(EventKind::Added, "Launch"), (EventKind::Deactivated, "Launch"), (EventKind::Activated, "Hiring")]
+        );
+        assert_eq!(log.events[0].task.as_deref(), Some("Book venue"));
+        assert_eq!(log.since(today).count(), 3);
+
+        let toml = toml::to_string_pretty(&log).unwrap();
+        assert_eq!(toml::from_str::<ActivityLog>(&toml).unwrap(), log);
+    }
+
+    #[test]
+    fn test_task_history() {
+        let v1 = parse("## Work\n\n### Launch\n\n### Later\n");
+        let v2 = parse("## Work\n\n### Launch\n\n### Later\n- 🔴 Ship\n");
+        let v3 = parse("## Work\n\n### Launch\n- 🔵 Ship\n\n### Later\n");
+        let v4 = parse("## Work\n\n### Launch\n- 🔶 Ship\n\n### Later\n");
+        let day = |d| Date::from_ymd(2026, 3, d).unwrap();
+        let mut log = ActivityLog::default();
+        log.record(&v1, &v2, day(1));
+        log.record(&v2, &v3, day(9));
+        log.record(&v3, &v4, day(12));
+
+        assert_eq!(
+            log.task_history("Work", "Launch", "Ship", day(13)).as_deref(),
+            Some("created 12d ago, on deck 3d, in progress 1d")
+        );
+        assert_eq!(log.task_history("Work", "Later", "Ship", day(13)), None);
+    }
+}
@@ -1,10 +1,64 @@
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
+use crate::activity::ActivityLog;
+use crate::attach::Attachment;
+use crate::complete;
+use crate::config::{self, Config, Profile};
+use crate::date::Date;
+use crate::diff;
 use crate::engine;
 use crate::model::*;
 use crate::parser;
+use crate::planner::{self, Plan, TaskIdx};
+use crate::query::Query;
+use crate::report::GlobalItem;
 use crate::serializer;
+use crate::session::TaskRef;
+use crate::subtree;
 use crate::theme::Theme;
+use crate::tutorial::Tutorial;
+use crate::watcher::Fingerprint;
+
+/// Maximum number of document snapshots kept for undo.
+const MAX_UNDO: usize = 50;
+
+/// Home-row-first alphabet used for jump labels.
+const JUMP_ALPHABET: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Generate `count` jump labels: single letters when they suffice, otherwise
+/// two letters for every row so no label is a prefix of another.
+fn make_jump_labels(count: usize) -> Vec<String> {
+    let letters: Vec<char> = JUMP_ALPHABET.chars().collect();
+    if count <= letters.len() {
+        return letters.iter().take(count).map(|c| c.to_string()).collect();
+    }
+    letters
+        .iter()
+        .flat_map(|a| letters.iter().map(move |b| format!("{}{}", a, b)))
+        .take(count)
+        .collect()
+}
+
+/// "1 task" or "N tasks".
+fn tasks_text(count: usize) -> String {
+    format!("{} task{}", count, if count == 1 { "" } else { "s" })
+}
+
+/// The task index of `kind` when it is a task of the given project.
+fn block_task(kind: &TreeNodeKind, cat_idx: usize, proj_idx: usize) -> Option<usize> {
+    match *kind {
+        TreeNodeKind::Task { cat_idx: ci, proj_idx: pi, task_idx } if (ci, pi) == (cat_idx, proj_idx) => Some(task_idx),
+        _ => None,
+    }
+}
+
+/// Apply `step` to one task `times` times, stopping early if the task has
+/// gone. Returns true if it changed the task at all.
+fn step_task(doc: &mut Document, (ci, pi, ti): (usize, usize, usize), times: usize, step: fn(&mut Document, usize, usize, usize) -> bool) -> bool {
+    (0..times).take_while(|_| step(doc, ci, pi, ti)).count() > 0
+}
 
 /// Count section header rows between agenda items at indices `from..=to`.
 /// Each state transition (including the first item) produces one header row.
@@ -25,10 +79,13 @@ fn section_headers_between(items: &[AgendaItem], from: usize, to: usize) -> usiz
     count
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum View {
+    #[default]
     Agenda,
     Backlog,
+    Stats,
     Settings,
 }
 
@@ -46,15 +103,296 @@ pub enum Dialog {
     AddCategory,
     EditCategory,
     ConfirmDeleteCategory,
+    EditPreamble,
+    AddPreambleLine,
+    SortMenu,
+    Filter,
+    ScopePicker,
+    EditDefaultFile,
+    ConfirmQuit,
+    Command,
+    Areas,
+    ConfirmResetChecklist,
+    People,
+    Triage,
+    TriageDefer,
+    TriageDelegate,
+    TriageMove,
+    ProjectDeadline,
+    PlanDay,
+    FilterPicker,
+    Unarchive,
+    PasteImport,
+    ConfirmTriageUnsorted,
+    Duplicates,
+    SaveConflict,
+    Error,
+    RecentFiles,
+    ConfirmOpen,
+    ProfilePicker,
+    MoveTarget,
+    GlobalAgenda,
+    QuickAdd,
+    MoveToProject,
+    Tags,
+    RenameTag,
+    ConfirmDeleteTag,
+    TaskDetails,
+    Attachments,
+    Log,
+    NewDay,
+}
+
+impl Dialog {
+    /// Whether keys typed into the dialog are text rather than commands.
+    pub fn takes_text(self) -> bool {
+        matches!(
+            self,
+            Dialog::AddTask
+                | Dialog::AddProject
+                | Dialog::EditTask
+                | Dialog::EditProject
+                | Dialog::EditNote
+                | Dialog::EditExistingNote
+                | Dialog::AddCategory
+                | Dialog::EditCategory
+                | Dialog::EditPreamble
+                | Dialog::AddPreambleLine
+                | Dialog::Filter
+                | Dialog::EditDefaultFile
+                | Dialog::Command
+                | Dialog::TriageDefer
+                | Dialog::TriageDelegate
+                | Dialog::ProjectDeadline
+                | Dialog::PasteImport
+                | Dialog::QuickAdd
+                | Dialog::MoveToProject
+                | Dialog::RenameTag
+        )
+    }
+}
+
+/// Which part of the document the agenda shows. Scopes are kept by name so
+/// they survive reordering.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AgendaScope {
+    #[default]
+    All,
+    Category { category: String },
+    Project { category: String, project: String },
+}
+
+impl AgendaScope {
+    pub fn label(&self) -> String {
+        match self {
+            AgendaScope::All => "All".to_string(),
+            AgendaScope::Category { category } => category.clone(),
+            AgendaScope::Project { category, project } => format!("{} › {}", category, project),
+        }
+    }
+
+    fn includes(&self, doc: &Document, item: &AgendaItem) -> bool {
+        let category = &doc.categories[item.category_idx];
+        match self {
+            AgendaScope::All => true,
+            AgendaScope::Category { category: name } => category.name == *name,
+            AgendaScope::Project { category: cat_name, project } => {
+                category.name == *cat_name && category.projects[item.project_idx].name == *project
+            }
+        }
+    }
+}
+
+/// State of the list picker dialog: labels and the highlighted row.
+#[derive(Debug, Clone, Default)]
+pub struct Picker {
+    pub items: Vec<String>,
+    pub cursor: usize,
+}
+
+/// Names offered under a text dialog as the input is typed, best match first.
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    /// Byte offset in `input_buffer` where the word being completed starts.
+    pub start: usize,
+    pub matches: Vec<String>,
+    pub cursor: usize,
+}
+
+/// A task marked with `J`, waiting for the task it will be merged into.
+/// The text guards against the indices going stale in the meantime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeMark {
+    pub cat_idx: usize,
+    pub proj_idx: usize,
+    pub task_idx: usize,
+    pub text: String,
+}
+
+/// Progress through the inbox in triage mode (`I`). The task under review
+/// stays at `task_idx`: handled tasks either leave the inbox or are stepped past.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Triage {
+    pub cat_idx: usize,
+    pub proj_idx: usize,
+    pub task_idx: usize,
+    pub handled: usize,
+    /// Triaging the Unsorted projects of stray tasks instead of the inbox,
+    /// one after another.
+    pub unsorted: bool,
+}
+
+/// The saved filter scoping the agenda and backlog.
+#[derive(Debug, Clone)]
+pub struct ActiveFilter {
+    pub name: String,
+    pub query: Query,
+}
+
+/// A "Plan my day" proposal being reviewed (`W` or `:plan`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayPlan {
+    pub plan: Plan,
+    /// Tasks dropped from this plan; regenerating fills their time with others.
+    pub skipped: Vec<TaskIdx>,
+    pub cursor: usize,
+    /// Starts at `config.planner.hours`; +/- change it for this plan only.
+    pub hours: u32,
+}
+
+/// A read or write of the file that failed, shown in the error dialog
+/// until retried or dismissed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoError {
+    pub message: String,
+    pub retry: Retry,
+}
+
+/// What retrying a failed file operation does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    Save,
+    Reload,
+}
+
+/// A Backlog task or project on its way to another file (`:move`).
+#[derive(Debug, Clone)]
+pub struct Move {
+    /// `task "Fix sink"` or `project "Chores"`, for the status bar.
+    pub label: String,
+    /// The item alone, in its category (and project), to merge into the target.
+    pub piece: Document,
+    /// This document without the item.
+    pub remaining: Document,
+}
+
+/// Lines pasted or typed into the paste-import dialog, each to become a Todo
+/// of the project focused when it opened (`V`, or a paste on the Backlog).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasteImport {
+    pub cat_idx: usize,
+    pub proj_idx: usize,
+    pub text: String,
 }
 
 /// Tracks what kind of item is being moved and where it started.
 #[derive(Debug, Clone)]
 pub enum MoveKind {
     Task { cat_idx: usize, proj_idx: usize, original_task_idx: usize },
+    Note { cat_idx: usize, proj_idx: usize, task_idx: usize, original_note_idx: usize },
     Project { original_cat_idx: usize, original_proj_idx: usize },
     Category { original_cat_idx: usize },
     AgendaItem { original_idx: usize },
+    AgendaSection { original_idx: usize },
+    /// Tasks `anchor` to `cursor` of a project, selected with `V`; once
+    /// `grabbed`, j/k move them together. `before` is the document as it
+    /// was, for Esc and a single undo entry, and `origin` the task `V` was
+    /// pressed on.
+    Block {
+        cat_idx: usize,
+        proj_idx: usize,
+        anchor: usize,
+        cursor: usize,
+        origin: usize,
+        grabbed: bool,
+        before: Box<Document>,
+    },
+}
+
+/// Global keys listed in the Settings view's Keybindings section.
+pub const KEYBINDINGS: &[(&str, &str)] = &[
+    ("Tab", "Next view"),
+    ("j / k", "Move down / up"),
+    ("g / G", "Top / bottom"),
+    ("Ctrl+D / Ctrl+U", "Half page down / up"),
+    ("H / M / L", "Top / middle / bottom of screen"),
+    ("f", "Jump to a labelled row"),
+    ("O", "Areas of focus overview"),
+    ("@", "People mentioned in tasks"),
+    ("#", "Tags in use"),
+    ("I", "Triage the inbox"),
+    ("W", "Plan my day"),
+    ("F", "Saved filters"),
+    ("B", "Switch profile"),
+    ("E", "Agenda of every profile"),
+    ("U", "Reopen an archived task"),
+    ("D", "Hide / show Done tasks"),
+    ("X", "Clear all filters"),
+    ("u", "Undo"),
+    ("s", "Save"),
+    ("R", "Reload from disk"),
+    ("q", "Quit"),
+];
+
+/// Headings that group the rows of the Settings view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsSection {
+    General,
+    Agenda,
+    Keybindings,
+    Themes,
+    Files,
+    Categories,
+}
+
+impl SettingsSection {
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsSection::General => "General",
+            SettingsSection::Agenda => "Agenda",
+            SettingsSection::Keybindings => "Keybindings",
+            SettingsSection::Themes => "Themes",
+            SettingsSection::Files => "Files",
+            SettingsSection::Categories => "Categories",
+        }
+    }
+}
+
+/// What a row in the Settings view refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsRow {
+    Section(SettingsSection),
+    RestoreSession,
+    Accessible,
+    Autosave,
+    KeyboardLayout,
+    NewDay,
+    /// Index into `config.agenda.order`
+    AgendaSection(usize),
+    PromoteMode,
+    PromoteStrategy,
+    StalledAfter,
+    DemoteStalled,
+    ShowCategory,
+    SinkDone,
+    SingleTasking,
+    /// Index into `KEYBINDINGS`
+    Keybinding(usize),
+    Theme,
+    DefaultFile,
+    PreserveUnknown,
+    RepoLocal,
+    Category(usize),
 }
 
 pub struct App {
@@ -70,12 +408,28 @@ pub struct App {
     pub agenda_items: Vec<AgendaItem>,
     pub agenda_cursor: usize,
     pub agenda_scroll: usize,
+    pub agenda_scope: AgendaScope,
+    pub hide_done: bool,
+    /// Whether the Not Started section is expanded; auto-promote is what
+    /// surfaces Todo tasks, so by default they stay out of the way.
+    pub show_todo: bool,
+    pub hidden_todo_count: usize,
+    /// Count and estimated time per agenda section, for the section headers.
+    pub agenda_totals: Vec<SectionTotal>,
+    /// When the inbox was last triaged to the end, for the review reminder.
+    pub last_review: Option<Date>,
+    /// What the new-day routine changed, for its summary dialog.
+    pub new_day_summary: Vec<String>,
+    /// Tasks sent to the bottom of their agenda section with `b`, in the
+    /// order they were buried. Kept by name so they stay put across edits.
+    pub buried: Vec<TaskRef>,
 
     // Backlog tree state
     pub tree_nodes: Vec<TreeNode>,
     pub backlog_cursor: usize,
     pub backlog_scroll: usize,
     pub collapse: CollapseState,
+    pub backlog_filter: String,
 
     // Settings state
     pub settings_cursor: usize,
@@ -83,9 +437,21 @@ pub struct App {
 
     // Theme
     pub theme_index: usize,
+    pub config: Config,
+    /// Where preference changes are written; None keeps them in memory only.
+    pub config_path: Option<PathBuf>,
+    /// `--accessible` was given; applies on top of the config without saving.
+    pub force_accessible: bool,
 
     // Move mode
     pub moving: Option<MoveKind>,
+    pub merge_mark: Option<MergeMark>,
+    pub triage: Option<Triage>,
+    pub day_plan: Option<DayPlan>,
+    pub paste_import: Option<PasteImport>,
+    pub active_filter: Option<ActiveFilter>,
+    /// Finished checklist (category, project) the reset dialog asks about.
+    pub checklist_reset: Option<(usize, usize)>,
 
     // Last known visible height (updated each frame)
     pub visible_height: usize,
@@ -93,12 +459,65 @@ pub struct App {
     // Dialog state
     pub input_buffer: String,
     pub input_cursor: usize,
+    pub picker: Picker,
+    pub completion: Completion,
+    /// The tag being renamed or deleted from the Tags dialog.
+    pub tag_target: Option<String>,
+
+    // Document snapshots taken before undoable mutations (most recent last)
+    pub undo_stack: Vec<Document>,
+
+    // Dated task adds and project (de)activations, for the Stats view
+    pub activity: ActivityLog,
+
+    // First key of a two-key command (e.g. `z` fold commands)
+    pub pending_key: Option<char>,
+
+    // Numeric prefix typed before a command (e.g. the 5 in `5j`)
+    pub count: Option<usize>,
+
+    // Label typed so far while in jump mode (`f`); None when not jumping
+    pub jump_input: Option<String>,
+
+    // Guided tutorial overlay (`gws tutorial`)
+    pub tutorial: Option<Tutorial>,
+
+    // The file version last loaded or saved; saving over another one asks first
+    pub on_disk: Option<Fingerprint>,
+    /// With `[files] archive_file`, that file's lines as last read or
+    /// written; a reload puts them back after the todo file's own archive
+    /// section.
+    pub archive_on_disk: Archive,
+    /// A save over external changes was cancelled: autosave waits for `s`.
+    pub hold_autosave: bool,
+    /// Quit once the save-conflict dialog has been settled.
+    pub quit_pending: bool,
+    /// The failed file operation the error dialog reports.
+    pub io_error: Option<IoError>,
+
+    // Todo files opened lately, most recent first (`:e`, `gws open`)
+    pub recent_files: Vec<PathBuf>,
+    /// Where the recent files list is kept; None keeps it in memory only.
+    pub recent_path: Option<PathBuf>,
+    /// The file `:e` switches to once unsaved changes are dealt with.
+    pub pending_open: Option<PathBuf>,
+    /// The config profile the open file belongs to, shown in the header.
+    pub profile: Option<String>,
+    /// Agenda tasks of every profile, while the global agenda is open.
+    pub global_agenda: Vec<GlobalItem>,
+    /// Attached files of the focused task, checked when the list opened.
+    pub attachments: Vec<Attachment>,
+    /// Debug log being written, if any, for `:log`.
+    pub log_file: Option<PathBuf>,
+    /// Set when something on screen changed; the event loop draws a frame
+    /// only then, so an idle app costs next to nothing.
+    pub needs_redraw: bool,
+    /// The date the views were built for, to notice midnight passing.
+    day: Date,
 }
 
 impl App {
-    pub fn new(mut doc: Document, file_path: PathBuf) -> Self {
-        engine::auto_promote(&mut doc);
-        let agenda_items = engine::build_agenda(&doc);
+    pub fn new(doc: Document, file_path: PathBuf) -> Self {
         let mut app = Self {
             doc,
             file_path,
@@ -107,21 +526,63 @@ impl App {
             dirty: false,
             running: true,
             status_msg: String::new(),
-            agenda_items,
+            agenda_items: Vec::new(),
             agenda_cursor: 0,
             agenda_scroll: 0,
+            agenda_scope: AgendaScope::All,
+            hide_done: false,
+            show_todo: false,
+            hidden_todo_count: 0,
+            agenda_totals: Vec::new(),
+            last_review: None,
+            new_day_summary: Vec::new(),
+            buried: Vec::new(),
             tree_nodes: Vec::new(),
             backlog_cursor: 0,
             backlog_scroll: 0,
             collapse: CollapseState::new(),
+            backlog_filter: String::new(),
             settings_cursor: 0,
             settings_scroll: 0,
             theme_index: 0,
+            config: Config::default(),
+            config_path: None,
+            force_accessible: false,
             moving: None,
             visible_height: 0,
             input_buffer: String::new(),
             input_cursor: 0,
+            picker: Picker::default(),
+            completion: Completion::default(),
+            tag_target: None,
+            undo_stack: Vec::new(),
+            activity: ActivityLog::default(),
+            merge_mark: None,
+            triage: None,
+            day_plan: None,
+            paste_import: None,
+            active_filter: None,
+            checklist_reset: None,
+            pending_key: None,
+            count: None,
+            jump_input: None,
+            tutorial: None,
+            on_disk: None,
+            archive_on_disk: Archive::default(),
+            hold_autosave: false,
+            quit_pending: false,
+            io_error: None,
+            recent_files: Vec::new(),
+            recent_path: None,
+            pending_open: None,
+            profile: None,
+            global_agenda: Vec::new(),
+            attachments: Vec::new(),
+            log_file: None,
+            needs_redraw: true,
+            day: Date::today(),
         };
+        app.rebuild_agenda();
         app.rebuild_tree();
         app
     }
@@ -129,9 +590,40 @@ impl App {
     // --- Tree building ---
 
     pub fn rebuild_tree(&mut self) {
-        let mut nodes = Vec::new();
+        if !self.backlog_filter.is_empty() || self.active_filter.is_some() {
+            self.tree_nodes = self.filtered_tree();
+            self.clamp_backlog_cursor();
+            return;
+        }
+
+        // The previous tree is a good size hint; this runs after every edit
+        let mut nodes = Vec::with_capacity(self.tree_nodes.len().max(self.doc.categories.len()));
+
+        if self.doc.has_preamble() {
+            let collapsed = self.collapse.preamble_collapsed;
+            let indicator = if collapsed { "►" } else { "▼" };
+            nodes.push(TreeNode {
+                kind: TreeNodeKind::Preamble,
+                depth: 0,
+                display: format!("{} {}", indicator, self.doc.title().unwrap_or("Preamble")),
+            });
+
+            if !collapsed {
+                let title_idx = self.doc.title_idx();
+                for (line_idx, line) in self.doc.preamble.iter().enumerate() {
+                    if Some(line_idx) == title_idx || line.trim().is_empty() {
+                        continue;
+                    }
+                    nodes.push(TreeNode {
+                        kind: TreeNodeKind::PreambleLine { line_idx },
+                        depth: 1,
+                        display: line.trim_end().to_string(),
+                    });
+                }
+            }
+        }
 
-        for (cat_idx, category) in self.doc.categories.iter().enumerate() {
+        for (cat_idx, category) in self.doc.categories.iter().enumerate().filter(|(_, c)| !c.hidden) {
             let cat_collapsed = self.collapse.collapsed_categories.contains(&cat_idx);
             let indicator = if cat_collapsed { "►" } else { "▼" };
             nodes.push(TreeNode {
@@ -147,11 +639,10 @@ impl App {
             for (proj_idx, project) in category.projects.iter().enumerate() {
                 let proj_collapsed = self.collapse.collapsed_projects.contains(&(cat_idx, proj_idx));
                 let indicator = if proj_collapsed { "►" } else { "▼" };
-                let active_marker = if project.active { "🔶 " } else { "" };
                 nodes.push(TreeNode {
                     kind: TreeNodeKind::Project { cat_idx, proj_idx },
                     depth: 1,
-                    display: format!("{} {}{}", indicator, active_marker, project.name),
+                    display: format!("{} {}{}", indicator, project.markers(), project.name),
                 });
 
                 if proj_collapsed {
@@ -159,6 +650,9 @@ impl App {
                 }
 
                 for (task_idx, task) in project.tasks.iter().enumerate() {
+                    if self.hide_done && task.state == TaskState::Done {
+                        continue;
+                    }
                     let has_notes = !task.notes.is_empty();
                     let task_collapsed = self.collapse.collapsed_tasks.contains(&(cat_idx, proj_idx, task_idx));
 
@@ -182,8 +676,10 @@ impl App {
         }
 
         self.tree_nodes = nodes;
+        self.clamp_backlog_cursor();
+    }
 
-        // Clamp cursor
+    fn clamp_backlog_cursor(&mut self) {
         if !self.tree_nodes.is_empty() {
             if self.backlog_cursor >= self.tree_nodes.len() {
                 self.backlog_cursor = self.tree_nodes.len() - 1;
@@ -193,16 +689,233 @@ impl App {
         }
     }
 
+    /// Build the tree narrowed to nodes matching `backlog_filter` (case-insensitive),
+    /// keeping the ancestors of every match for context. Collapse state is ignored.
+    fn filtered_tree(&self) -> Vec<TreeNode> {
+        let query = self.backlog_filter.to_lowercase();
+        // "@name" and "#tag" match that exact mention or tag rather than any
+        // text containing it
+        let exact = |sigil| query.strip_prefix(sigil).filter(|p: &&str| !p.is_empty() && !p.contains(' '));
+        let (person, tag) = (exact('@'), exact('#'));
+        // With only a saved filter, names and notes don't match by themselves
+        let hit = |text: &str| {
+            !query.is_empty()
+                && match (person, tag) {
+                    (Some(person), _) => mentions(text).any(|name| name.to_lowercase() == person),
+                    (_, Some(tag)) => tags(text).any(|name| name.to_lowercase() == tag),
+                    _ => text.to_lowercase().contains(&query),
+                }
+        };
+        let saved = self.active_filter.as_ref().map(|f| &f.query);
+        let mut nodes = Vec::new();
+
+        let title_idx = self.doc.title_idx();
+        let title_hit = self.doc.title().is_some_and(hit);
+        let preamble_hits: Vec<TreeNode> = self
+            .doc
+            .preamble
+            .iter()
+            .enumerate()
+            .filter(|(i, line)| Some(*i) != title_idx && !line.trim().is_empty() && hit(line))
+            .map(|(line_idx, line)| TreeNode {
+                kind: TreeNodeKind::PreambleLine { line_idx },
+                depth: 1,
+                display: line.trim_end().to_string(),
+            })
+            .collect();
+        if title_hit || !preamble_hits.is_empty() {
+            nodes.push(TreeNode {
+                kind: TreeNodeKind::Preamble,
+                depth: 0,
+                display: format!("▼ {}", self.doc.title().unwrap_or("Preamble")),
+            });
+            nodes.extend(preamble_hits);
+        }
+
+        for (cat_idx, category) in self.doc.categories.iter().enumerate().filter(|(_, c)| !c.hidden) {
+            let cat_hit = hit(&category.name);
+            let mut cat_nodes = Vec::new();
+
+            for (proj_idx, project) in category.projects.iter().enumerate() {
+                let proj_hit = cat_hit || hit(&project.name);
+                let mut proj_nodes = Vec::new();
+
+                for (task_idx, task) in project.tasks.iter().enumerate() {
+                    if self.hide_done && task.state == TaskState::Done {
+                        continue;
+                    }
+                    let note_hits: Vec<usize> = (0..task.notes.len()).filter(|&i| hit(&task.notes[i])).collect();
+                    let text_hit = query.is_empty() || proj_hit || hit(&task.text) || !note_hits.is_empty();
+                    if !text_hit || saved.is_some_and(|q| !q.matches(category, project, task)) {
+                        continue;
+                    }
+                    proj_nodes.push(TreeNode {
+                        kind: TreeNodeKind::Task { cat_idx, proj_idx, task_idx },
+                        depth: 2,
+                        display: task.text.clone(),
+                    });
+                    for note_idx in note_hits {
+                        proj_nodes.push(TreeNode {
+                            kind: TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx },
+                            depth: 3,
+                            display: task.notes[note_idx].trim().to_string(),
+                        });
+                    }
+                }
+
+                // A saved filter is about tasks, so only projects holding matches show
+                if (proj_hit && saved.is_none()) || !proj_nodes.is_empty() {
+                    cat_nodes.push(TreeNode {
+                        kind: TreeNodeKind::Project { cat_idx, proj_idx },
+                        depth: 1,
+                        display: format!("▼ {}{}", project.markers(), project.name),
+                    });
+                    cat_nodes.append(&mut proj_nodes);
+                }
+            }
+
+            if (cat_hit && saved.is_none()) || !cat_nodes.is_empty() {
+                nodes.push(TreeNode {
+                    kind: TreeNodeKind::Category { cat_idx },
+                    depth: 0,
+                    display: format!("▼ {}", category.name),
+                });
+                nodes.append(&mut cat_nodes);
+            }
+        }
+
+        nodes
+    }
+
+    // --- Backlog: filter ---
+
+    /// Open the live filter box, seeded with the current filter.
+    pub fn start_filter(&mut self) {
+        let filter = self.backlog_filter.clone();
+        self.open_dialog_with_text(Dialog::Filter, &filter);
+    }
+
+    /// Re-filter the tree from the filter box contents, keeping the cursor on
+    /// the same node when it still matches.
+    pub fn update_filter(&mut self) {
+        let saved_kind = self.current_tree_node().map(|n| n.kind.clone());
+        self.backlog_filter = self.input_buffer.clone();
+        self.rebuild_tree();
+        if let Some(kind) = saved_kind {
+            self.restore_cursor_or_ancestor(&kind);
+        }
+    }
+
+    /// Drop the filter and restore the full tree, keeping the focused node.
+    pub fn clear_filter(&mut self) {
+        let saved_kind = self.current_tree_node().map(|n| n.kind.clone());
+        self.backlog_filter.clear();
+        self.rebuild_tree();
+        if let Some(kind) = saved_kind {
+            self.restore_cursor_or_ancestor(&kind);
+        }
+    }
+
     // --- Agenda ---
 
+    /// Rebuild the agenda after a change, auto-promoting first when the
+    /// config asks for it after every change.
     pub fn refresh_agenda(&mut self) {
-        engine::auto_promote(&mut self.doc);
+        if self.config.agenda.sink_done && engine::sink_done(&mut self.doc) {
+            self.dirty = true;
+        }
+        if self.config.agenda.auto_promote.on_change() {
+            let promoted = engine::auto_promote(&mut self.doc, self.config.agenda.promote);
+            if !promoted.is_empty() {
+                self.dirty = true;
+                let what = self.promoted_text(&promoted);
+                self.status_msg = if self.status_msg.is_empty() {
+                    format!("Promoted {}", what)
+                } else {
+                    format!("{} · promoted {}", self.status_msg, what)
+                };
+            }
+        }
         self.rebuild_agenda();
     }
 
+    /// `"Write copy" in Launch` for one promoted task, else `4 tasks in 3 projects`.
+    fn promoted_text(&self, promoted: &[(usize, usize, usize)]) -> String {
+        if let [(ci, pi, ti)] = *promoted {
+            let project = &self.doc.categories[ci].projects[pi];
+            return format!("\"{}\" in {}", project.tasks[ti].text, project.name);
+        }
+        let mut projects: Vec<_> = promoted.iter().map(|&(ci, pi, _)| (ci, pi)).collect();
+        projects.dedup();
+        format!(
+            "{} tasks in {} project{}",
+            promoted.len(),
+            projects.len(),
+            if projects.len() == 1 { "" } else { "s" }
+        )
+    }
+
+    /// `"Write copy" → In Progress`, after the task changed state.
+    fn state_change_text(&self, ci: usize, pi: usize, ti: usize) -> String {
+        let task = &self.doc.categories[ci].projects[pi].tasks[ti];
+        format!("\"{}\" → {}", task.text, task.state.label())
+    }
+
+    /// In single-tasking mode, a task that just went In Progress sends the
+    /// others back to On Deck; the swap is added to the status line.
+    fn enforce_single_task(&mut self, ci: usize, pi: usize, ti: usize) {
+        let started = self.doc.categories[ci].projects[pi].tasks[ti].state == TaskState::InProgress;
+        if !self.config.agenda.single_tasking || !started {
+            return;
+        }
+        let demoted = engine::demote_other_in_progress(&mut self.doc, (ci, pi, ti));
+        let what = match *demoted.as_slice() {
+            [] => return,
+            [(ci, pi, ti)] => format!("\"{}\"", self.doc.categories[ci].projects[pi].tasks[ti].text),
+            _ => tasks_text(demoted.len()),
+        };
+        self.status_msg = format!("{} · {} back to On Deck", self.status_msg, what);
+    }
+
+    /// `Launch activated` or `Launch deactivated`.
+    fn activation_text(&self, ci: usize, pi: usize) -> String {
+        let project = &self.doc.categories[ci].projects[pi];
+        format!("{} {}", project.name, if project.active { "activated" } else { "deactivated" })
+    }
+
     /// Rebuild agenda from doc without running auto_promote.
     fn rebuild_agenda(&mut self) {
-        self.agenda_items = engine::build_agenda(&self.doc);
+        let mut items = engine::build_agenda(&self.doc, &self.config.agenda.sections());
+        engine::boost_deadlines(&self.doc, &mut items, Date::today());
+        if !self.buried.is_empty() {
+            // Sections are contiguous, so a stable sort on (section, burial)
+            // sinks buried tasks without disturbing anything else
+            let sections = self.config.agenda.sections();
+            items.sort_by_key(|item| {
+                let section = sections.iter().position(|&s| s == item.task.state);
+                (section, self.buried_rank(item).map_or(0, |rank| rank + 1))
+            });
+        }
+        if self.agenda_scope != AgendaScope::All {
+            items.retain(|item| self.agenda_scope.includes(&self.doc, item));
+        }
+        if self.hide_done {
+            items.retain(|item| item.task.state != TaskState::Done);
+        }
+        if let Some(filter) = &self.active_filter {
+            items.retain(|item| {
+                let category = &self.doc.categories[item.category_idx];
+                let project = &category.projects[item.project_idx];
+                filter.query.matches(category, project, &project.tasks[item.task_idx])
+            });
+        }
+        let before = items.len();
+        if !self.show_todo {
+            items.retain(|item| item.task.state != TaskState::Todo);
+        }
+        self.hidden_todo_count = before - items.len();
+        self.agenda_totals = engine::section_totals(&items);
+        self.agenda_items = items;
         if !self.agenda_items.is_empty() {
             if self.agenda_cursor >= self.agenda_items.len() {
                 self.agenda_cursor = self.agenda_items.len() - 1;
@@ -213,563 +926,2996 @@ impl App {
     }
 
 
-    /// Total number of rows in the Settings view (1 theme row + categories).
-    pub fn settings_total(&self) -> usize {
-        1 + self.doc.categories.len()
+    /// Show or hide Done tasks in both the agenda and the backlog.
+    pub fn set_hide_done(&mut self, hide: bool) {
+        let kind = self.current_tree_node().map(|n| n.kind.clone());
+        self.hide_done = hide;
+        self.rebuild_agenda();
+        self.rebuild_tree();
+        if let Some(kind) = kind {
+            self.restore_cursor_or_ancestor(&kind);
+        }
     }
 
-    /// Index of the category in doc.categories for the current settings_cursor,
-    /// or None if the cursor is on the theme row.
-    pub fn settings_category_idx(&self) -> Option<usize> {
-        if self.settings_cursor == 0 {
-            None
-        } else {
-            Some(self.settings_cursor - 1)
+    pub fn toggle_hide_done(&mut self) {
+        self.set_hide_done(!self.hide_done);
+        self.status_msg = if self.hide_done { "Done tasks hidden" } else { "Done tasks shown" }.to_string();
+    }
+
+    /// Expand or collapse the agenda's Not Started section.
+    pub fn toggle_show_todo(&mut self) {
+        let current = self.agenda_items.get(self.agenda_cursor).map(|item| (item.category_idx, item.project_idx, item.task_idx));
+        self.show_todo = !self.show_todo;
+        self.rebuild_agenda();
+        if let Some(pos) = current.and_then(|key| {
+            self.agenda_items
+                .iter()
+                .position(|item| (item.category_idx, item.project_idx, item.task_idx) == key)
+        }) {
+            self.agenda_cursor = pos;
         }
+        self.status_msg = if self.show_todo { "Not Started shown" } else { "Not Started hidden" }.to_string();
     }
 
-    // --- Navigation ---
+    // --- View modifiers ---
 
-    pub fn move_down(&mut self) {
-        match self.view {
-            View::Agenda => {
-                if !self.agenda_items.is_empty() {
-                    if self.agenda_cursor < self.agenda_items.len() - 1 {
-                        self.agenda_cursor += 1;
-                    } else {
-                        self.agenda_cursor = 0;
-                    }
-                }
+    /// Filters and display modes narrowing the given view, for its title.
+    pub fn view_modifiers(&self, view: View) -> Vec<String> {
+        let mut modifiers = Vec::new();
+        if view == View::Agenda {
+            if self.agenda_scope != AgendaScope::All {
+                modifiers.push(self.agenda_scope.label());
             }
-            View::Backlog => {
-                if !self.tree_nodes.is_empty() {
-                    if self.backlog_cursor < self.tree_nodes.len() - 1 {
-                        self.backlog_cursor += 1;
-                    } else {
-                        self.backlog_cursor = 0;
-                    }
-                }
+            if self.show_todo {
+                modifiers.push("Not Started shown".to_string());
             }
-            View::Settings => {
-                let total = self.settings_total();
-                if total > 0 {
-                    if self.settings_cursor < total - 1 {
-                        self.settings_cursor += 1;
-                    } else {
-                        self.settings_cursor = 0;
-                    }
-                }
+        }
+        if matches!(view, View::Agenda | View::Backlog) {
+            if let Some(filter) = &self.active_filter {
+                modifiers.push(format!("Filter: {}", filter.name));
+            }
+            if self.hide_done {
+                modifiers.push("Done hidden".to_string());
             }
         }
+        modifiers
     }
 
-    pub fn move_up(&mut self) {
-        match self.view {
-            View::Agenda => {
-                if !self.agenda_items.is_empty() {
-                    if self.agenda_cursor > 0 {
-                        self.agenda_cursor -= 1;
-                    } else {
-                        self.agenda_cursor = self.agenda_items.len() - 1;
+    /// Drop every filter and display mode at once: scope, Not Started,
+    /// hidden Done tasks, the saved filter and the backlog filter.
+    pub fn clear_modifiers(&mut self) {
+        let any = self.agenda_scope != AgendaScope::All
+            || self.show_todo
+            || self.hide_done
+            || self.active_filter.is_some()
+            || !self.backlog_filter.is_empty();
+        if !any {
+            self.status_msg = "No filters to clear".to_string();
+            return;
+        }
+        self.agenda_scope = AgendaScope::All;
+        self.show_todo = false;
+        self.active_filter = None;
+        self.backlog_filter.clear();
+        // Rebuilds the agenda and tree, keeping the backlog cursor
+        self.set_hide_done(false);
+        self.agenda_cursor = 0;
+        self.agenda_scroll = 0;
+        self.status_msg = "Filters cleared".to_string();
+    }
+
+    // --- Saved filters ---
+
+    /// Pick one of the config's `[[filters]]`, or None to drop the current one.
+    pub fn open_filter_picker(&mut self) {
+        if self.config.filters.is_empty() {
+            self.status_msg = "No saved filters: add [[filters]] with a name and query to the config".to_string();
+            return;
+        }
+        let mut items = vec!["None".to_string()];
+        items.extend(self.config.filters.iter().map(|f| format!("{}  {}", f.name, f.query)));
+        let cursor = self
+            .active_filter
+            .as_ref()
+            .and_then(|active| self.config.filters.iter().position(|f| f.name == active.name))
+            .map_or(0, |i| i + 1);
+        self.picker = Picker { items, cursor };
+        self.open_dialog(Dialog::FilterPicker);
+    }
+
+    pub fn apply_filter_picker(&mut self) {
+        let filter = match self.picker.cursor.checked_sub(1) {
+            None => None,
+            Some(i) => {
+                let Some(saved) = self.config.filters.get(i) else {
+                    return;
+                };
+                match Query::parse(&saved.query) {
+                    Ok(query) => Some(ActiveFilter {
+                        name: saved.name.clone(),
+                        query,
+                    }),
+                    Err(e) => {
+                        self.status_msg = format!("Filter \"{}\": {}", saved.name, e);
+                        return;
                     }
                 }
             }
-            View::Backlog => {
-                if !self.tree_nodes.is_empty() {
-                    if self.backlog_cursor > 0 {
-                        self.backlog_cursor -= 1;
-                    } else {
-                        self.backlog_cursor = self.tree_nodes.len() - 1;
-                    }
+        };
+        self.status_msg = match &filter {
+            Some(filter) => format!("Filter: {}", filter.name),
+            None => "Filter cleared".to_string(),
+        };
+        self.active_filter = filter;
+        self.agenda_cursor = 0;
+        self.agenda_scroll = 0;
+        self.backlog_cursor = 0;
+        self.backlog_scroll = 0;
+        self.rebuild_agenda();
+        self.rebuild_tree();
+    }
+
+    // --- Agenda: scope ---
+
+    fn set_agenda_scope(&mut self, scope: AgendaScope) {
+        self.status_msg = format!("Agenda: {}", scope.label());
+        self.agenda_scope = scope;
+        self.agenda_cursor = 0;
+        self.agenda_scroll = 0;
+        self.rebuild_agenda();
+    }
+
+    /// Cycle the agenda scope through All and each category in document order.
+    pub fn cycle_agenda_category(&mut self) {
+        let names: Vec<&str> = self.doc.categories.iter().map(|c| c.name.as_str()).collect();
+        let next = match &self.agenda_scope {
+            AgendaScope::All => names.first(),
+            AgendaScope::Category { category } | AgendaScope::Project { category, .. } => {
+                match names.iter().position(|n| n == category) {
+                    Some(i) if matches!(self.agenda_scope, AgendaScope::Project { .. }) => names.get(i),
+                    Some(i) => names.get(i + 1),
+                    None => None,
                 }
             }
-            View::Settings => {
-                let total = self.settings_total();
-                if total > 0 {
-                    if self.settings_cursor > 0 {
-                        self.settings_cursor -= 1;
-                    } else {
-                        self.settings_cursor = total - 1;
-                    }
-                }
+        };
+        let scope = match next {
+            Some(name) => AgendaScope::Category { category: name.to_string() },
+            None => AgendaScope::All,
+        };
+        self.set_agenda_scope(scope);
+    }
+
+    /// Active projects offered by the scope picker, after the leading "All" row.
+    fn scope_picker_targets(&self) -> Vec<(usize, usize)> {
+        self.doc
+            .categories
+            .iter()
+            .enumerate()
+            .flat_map(|(ci, c)| {
+                c.projects
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.active)
+                    .map(move |(pi, _)| (ci, pi))
+            })
+            .collect()
+    }
+
+    /// Areas from the preamble, then from the config; an area defined in
+    /// both gets the categories of each.
+    pub fn areas(&self) -> Vec<Area> {
+        let mut areas: Vec<Area> = Vec::new();
+        for area in self.doc.preamble_areas().into_iter().chain(self.config.areas.iter().cloned()) {
+            match areas.iter_mut().find(|a| a.name == area.name) {
+                Some(existing) => existing.categories.extend(area.categories),
+                None => areas.push(area),
             }
         }
+        areas
     }
 
-    pub fn move_top(&mut self) {
-        match self.view {
-            View::Agenda => self.agenda_cursor = 0,
-            View::Backlog => self.backlog_cursor = 0,
-            View::Settings => self.settings_cursor = 0,
+    pub fn open_areas(&mut self) {
+        if self.areas().is_empty() {
+            self.status_msg = "No areas: add \"Area Name: Category, ...\" to the preamble or [[areas]] to the config".to_string();
+            return;
         }
+        self.open_dialog(Dialog::Areas);
     }
 
-    pub fn move_bottom(&mut self) {
-        match self.view {
-            View::Agenda => {
+    /// List everyone mentioned as `@Name`; picking one filters the Backlog
+    /// to the tasks involving them.
+    pub fn open_people(&mut self) {
+        let people = engine::people(&self.doc);
+        if people.is_empty() {
+            self.status_msg = "No one mentioned: write @Name in a task or note".to_string();
+            return;
+        }
+        let items = people
+            .iter()
+            .map(|p| {
+                let n = p.tasks.len();
+                format!("@{} · {} task{}, {} open", p.name, n, if n == 1 { "" } else { "s" }, p.open_tasks)
+            })
+            .collect();
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::People);
+    }
+
+    pub fn apply_people_picker(&mut self) {
+        let Some(person) = engine::people(&self.doc).into_iter().nth(self.picker.cursor) else {
+            return;
+        };
+        self.backlog_filter = format!("@{}", person.name);
+        self.view = View::Backlog;
+        self.rebuild_tree();
+        self.backlog_cursor = 0;
+        self.backlog_scroll = 0;
+    }
+
+    /// List the `#tag`s in use with their counts; picking one filters the
+    /// Backlog to the tagged tasks.
+    pub fn open_tags(&mut self) {
+        let index = engine::tag_index(&self.doc);
+        if index.is_empty() {
+            self.status_msg = "No tags: write #tag in a task or note".to_string();
+            return;
+        }
+        let items = index
+            .iter()
+            .map(|t| {
+                let n = t.tasks.len();
+                format!("#{} · {} task{}, {} open", t.name, n, if n == 1 { "" } else { "s" }, t.open_tasks)
+            })
+            .collect();
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::Tags);
+    }
+
+    fn picked_tag(&self) -> Option<String> {
+        engine::tag_index(&self.doc).into_iter().nth(self.picker.cursor).map(|t| t.name)
+    }
+
+    pub fn apply_tags_picker(&mut self) {
+        let Some(tag) = self.picked_tag() else {
+            return;
+        };
+        self.backlog_filter = format!("#{}", tag);
+        self.view = View::Backlog;
+        self.rebuild_tree();
+        self.backlog_cursor = 0;
+        self.backlog_scroll = 0;
+    }
+
+    /// Ask for a new name for the highlighted tag.
+    pub fn open_rename_tag(&mut self) {
+        let Some(tag) = self.picked_tag() else {
+            return;
+        };
+        self.open_dialog_with_text(Dialog::RenameTag, &tag);
+        self.tag_target = Some(tag);
+    }
+
+    /// Rename the tag everywhere; naming an existing tag merges the two.
+    pub fn rename_tag_from_input(&mut self) {
+        let Some(tag) = self.tag_target.take() else {
+            return;
+        };
+        let new_name = self.input_buffer.trim().trim_start_matches('#').to_string();
+        if !is_tag_name(&new_name) {
+            self.status_msg = format!("Not a tag name: {} (letters, digits, _ - and /)", new_name);
+            return;
+        }
+        if new_name == tag {
+            return;
+        }
+        let before = self.doc.clone();
+        let changed = engine::rename_tag(&mut self.doc, &tag, &new_name);
+        self.finish_tag_change(before, changed, format!("Renamed #{} to #{} on", tag, new_name));
+    }
+
+    /// Confirm removing the highlighted tag from every task.
+    pub fn open_delete_tag(&mut self) {
+        let Some(tag) = self.picked_tag() else {
+            return;
+        };
+        self.open_dialog(Dialog::ConfirmDeleteTag);
+        self.tag_target = Some(tag);
+    }
+
+    pub fn delete_tag(&mut self) {
+        let Some(tag) = self.tag_target.take() else {
+            return;
+        };
+        let before = self.doc.clone();
+        let changed = engine::delete_tag(&mut self.doc, &tag);
+        self.finish_tag_change(before, changed, format!("Removed #{} from", tag));
+    }
+
+    fn finish_tag_change(&mut self, before: Document, changed: usize, done: String) {
+        if changed == 0 {
+            self.status_msg = "No tasks changed".to_string();
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!("{} {} task{}", done, changed, if changed == 1 { "" } else { "s" });
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    pub fn open_scope_picker(&mut self) {
+        let mut items = vec!["All".to_string()];
+        for (ci, pi) in self.scope_picker_targets() {
+            let category = &self.doc.categories[ci];
+            items.push(format!("{} › {}", category.name, category.projects[pi].name));
+        }
+        let cursor = items
+            .iter()
+            .position(|label| *label == self.agenda_scope.label())
+            .unwrap_or(0);
+        self.picker = Picker { items, cursor };
+        self.open_dialog(Dialog::ScopePicker);
+    }
+
+    pub fn apply_scope_picker(&mut self) {
+        let scope = match self.picker.cursor.checked_sub(1) {
+            None => AgendaScope::All,
+            Some(i) => match self.scope_picker_targets().get(i) {
+                Some(&(ci, pi)) => {
+                    let category = &self.doc.categories[ci];
+                    AgendaScope::Project {
+                        category: category.name.clone(),
+                        project: category.projects[pi].name.clone(),
+                    }
+                }
+                None => return,
+            },
+        };
+        self.set_agenda_scope(scope);
+    }
+
+    pub fn picker_move(&mut self, direction: i32) {
+        let len = self.picker.items.len();
+        if len == 0 {
+            return;
+        }
+        self.picker.cursor = if direction > 0 {
+            (self.picker.cursor + 1) % len
+        } else {
+            (self.picker.cursor + len - 1) % len
+        };
+    }
+
+    /// Every row of the Settings view, section headings included.
+    pub fn settings_rows(&self) -> Vec<SettingsRow> {
+        let mut rows = vec![
+            SettingsRow::Section(SettingsSection::General),
+            SettingsRow::RestoreSession,
+            SettingsRow::Accessible,
+            SettingsRow::Autosave,
+            SettingsRow::KeyboardLayout,
+            SettingsRow::NewDay,
+            SettingsRow::Section(SettingsSection::Agenda),
+        ];
+        rows.extend((0..self.config.agenda.order.len()).map(SettingsRow::AgendaSection));
+        rows.extend([
+            SettingsRow::PromoteMode,
+            SettingsRow::PromoteStrategy,
+            SettingsRow::StalledAfter, SettingsRow::DemoteStalled,
+            SettingsRow::ShowCategory,
+            SettingsRow::SinkDone,
+            SettingsRow::SingleTasking]);
+        rows.push(SettingsRow::Section(SettingsSection::Keybindings));
+        rows.extend((0..KEYBINDINGS.len()).map(SettingsRow::Keybinding));
+        rows.extend([
+            SettingsRow::Section(SettingsSection::Themes),
+            SettingsRow::Theme,
+            SettingsRow::Section(SettingsSection::Files),
+            SettingsRow::DefaultFile,
+            SettingsRow::PreserveUnknown,
+            SettingsRow::RepoLocal,
+            SettingsRow::Section(SettingsSection::Categories),
+        ]);
+        rows.extend((0..self.doc.categories.len()).map(SettingsRow::Category));
+        rows
+    }
+
+    /// Total number of rows in the Settings view.
+    pub fn settings_total(&self) -> usize {
+        self.settings_rows().len()
+    }
+
+    pub fn settings_row(&self, idx: usize) -> SettingsRow {
+        self.settings_rows()
+            .get(idx)
+            .copied()
+            .unwrap_or(SettingsRow::Section(SettingsSection::General))
+    }
+
+    /// Position of `row` in the Settings view, or 0 if it is not shown.
+    pub fn settings_row_index(&self, row: SettingsRow) -> usize {
+        self.settings_rows().iter().position(|&r| r == row).unwrap_or(0)
+    }
+
+    /// Index of the category in doc.categories for the current settings_cursor,
+    /// or None if the cursor is on a preference row.
+    pub fn settings_category_idx(&self) -> Option<usize> {
+        match self.settings_row(self.settings_cursor) {
+            SettingsRow::Category(cat_idx) => Some(cat_idx),
+            _ => None,
+        }
+    }
+
+    // --- Settings: agenda sections ---
+
+    /// Show or hide the agenda section under the settings cursor.
+    pub fn toggle_agenda_section(&mut self) {
+        let SettingsRow::AgendaSection(idx) = self.settings_row(self.settings_cursor) else {
+            return;
+        };
+        let state = self.config.agenda.order[idx];
+        self.config.agenda.toggle_hidden(state);
+        self.rebuild_agenda();
+        self.save_config();
+    }
+
+    fn rerank_agenda_section(&mut self, direction: i32) {
+        if let SettingsRow::AgendaSection(idx) = self.settings_row(self.settings_cursor)
+            && let Some(new_idx) = self.config.agenda.move_section(idx, direction)
+        {
+            self.settings_cursor = self.settings_row_index(SettingsRow::AgendaSection(new_idx));
+            self.rebuild_agenda();
+        }
+    }
+
+    /// Step through when auto-promote runs.
+    pub fn next_promote_mode(&mut self) {
+        self.config.agenda.auto_promote = self.config.agenda.auto_promote.next();
+        self.save_config();
+    }
+
+    /// Switch auto-promote between top of list and highest priority. Takes
+    /// effect for projects that next need a task promoted.
+    pub fn toggle_promote_strategy(&mut self) {
+        self.config.agenda.promote = self.config.agenda.promote.toggle();
+        self.save_config();
+    }
+
+    /// Step through the stalled-task thresholds in `STALLED_AFTER_CHOICES`.
+    pub fn next_stalled_after(&mut self) {
+        let current = self.config.agenda.stalled_after_days;
+        let next = config::STALLED_AFTER_CHOICES
+            .iter()
+            .copied()
+            .find(|&days| days > current)
+            .unwrap_or(0);
+        self.config.agenda.stalled_after_days = next;
+        self.save_config();
+    }
+
+    pub fn toggle_demote_stalled(&mut self) {
+        self.config.agenda.demote_stalled = !self.config.agenda.demote_stalled;
+        self.save_config();
+    }
+
+    pub fn toggle_show_category(&mut self) {
+        self.config.agenda.show_category = !self.config.agenda.show_category;
+        self.save_config();
+    }
+
+    pub fn toggle_single_tasking(&mut self) {
+        self.config.agenda.single_tasking = !self.config.agenda.single_tasking;
+        self.save_config();
+    }
+
+    /// Sorting takes effect right away, and undoes like any other edit.
+    pub fn toggle_sink_done(&mut self) {
+        self.config.agenda.sink_done = !self.config.agenda.sink_done;
+        self.save_config();
+        let before = self.doc.clone();
+        let kind = self.current_tree_node().map(|n| n.kind.clone());
+        self.refresh_agenda();
+        if self.doc != before {
+            self.push_undo(before);
+            self.rebuild_tree();
+            if let Some(kind) = kind {
+                self.restore_cursor_or_ancestor(&kind);
+            }
+        }
+    }
+
+    /// Where an agenda row's task lives: its project, after its category
+    /// when `show_category` is set.
+    pub fn agenda_item_place(&self, item: &AgendaItem) -> String {
+        match self.doc.categories.get(item.category_idx) {
+            Some(category) if self.config.agenda.show_category => {
+                let separator = if self.accessible() { " > " } else { " › " };
+                format!("{}{}{}", category.name, separator, item.project_name)
+            }
+            _ => item.project_name.clone(),
+        }
+    }
+
+    /// Days the task has been In Progress, if that is past the configured limit.
+    pub fn stalled_days(&self, cat_idx: usize, proj_idx: usize, task_idx: usize) -> Option<i64> {
+        let days = self.config.agenda.stalled_after_days;
+        if days == 0 {
+            return None;
+        }
+        let category = self.doc.categories.get(cat_idx)?;
+        let project = category.projects.get(proj_idx)?;
+        let task = project.tasks.get(task_idx)?;
+        if task.state != TaskState::InProgress {
+            return None;
+        }
+        let since = self.activity.last_change(&category.name, &project.name, &task.text)?;
+        let age = Date::today().days_since(since);
+        (age >= days as i64).then_some(age)
+    }
+
+    /// Report tasks stalled In Progress, or demote them to On Deck when
+    /// `demote_stalled` is set. Run on startup, once the activity log is loaded.
+    /// Warn about categories or projects sharing a name and offer to rename
+    /// or merge them. `always` reports a clean document too (`:dedupe`).
+    pub fn offer_duplicate_fix(&mut self, always: bool) {
+        let found = engine::duplicates(&self.doc);
+        if found.is_empty() {
+            if always {
+                self.status_msg = "No duplicate names".to_string();
+            }
+            return;
+        }
+        if self.dialog != Dialog::None {
+            return;
+        }
+        let names: Vec<String> = found
+            .iter()
+            .map(|dup| {
+                let category = &self.doc.categories[dup.cat_idx];
+                match dup.proj_idx {
+                    Some(pi) => format!("{} › {}", category.name, category.projects[pi].name),
+                    None => category.name.clone(),
+                }
+            })
+            .collect();
+        self.status_msg = format!("Duplicate names: {}", names.join(", "));
+        self.open_dialog(Dialog::Duplicates);
+    }
+
+    /// Resolve duplicate names by merging them into the first of each name,
+    /// or by numbering the later ones.
+    pub fn fix_duplicates(&mut self, merge: bool) {
+        let before = self.doc.clone();
+        let n = if merge {
+            engine::merge_duplicates(&mut self.doc)
+        } else {
+            engine::rename_duplicates(&mut self.doc)
+        };
+        if n == 0 {
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!(
+            "{} {} duplicate{}",
+            if merge { "Merged" } else { "Renamed" },
+            n,
+            if n == 1 { "" } else { "s" }
+        );
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    pub fn check_stalled(&mut self) {
+        let days = self.config.agenda.stalled_after_days;
+        if days == 0 {
+            return;
+        }
+        let stalled = engine::stalled_tasks(&self.doc, &self.activity, Date::today(), days as i64);
+        if stalled.is_empty() {
+            return;
+        }
+        let n = stalled.len();
+        let tasks = if n == 1 { "task" } else { "tasks" };
+        if self.config.agenda.demote_stalled {
+            let before = self.doc.clone();
+            for ((ci, pi, ti), _) in stalled {
+                self.doc.categories[ci].projects[pi].tasks[ti].state = TaskState::OnDeck;
+            }
+            self.push_undo(before);
+            self.dirty = true;
+            self.status_msg = format!("Moved {} {} back to On Deck after {}+ days In Progress", n, tasks, days);
+            self.rebuild_agenda();
+            self.rebuild_tree();
+        } else {
+            self.status_msg = format!("{} {} In Progress for {}+ days", n, tasks, days);
+        }
+    }
+
+    /// Nudge toward the weekly review once `review_every_days` have passed
+    /// since the inbox was last triaged to the end.
+    pub fn check_review_due(&mut self) {
+        let every = self.config.general.review_every_days;
+        if every == 0 || self.doc.inbox().is_none() {
+            return;
+        }
+        let msg = match self.last_review {
+            Some(date) if Date::today().days_since(date) < every as i64 => return,
+            Some(date) => format!(
+                "Review due: inbox last triaged {} days ago (I to start)",
+                Date::today().days_since(date)
+            ),
+            None => "Review due: press I to triage the inbox".to_string(),
+        };
+        self.status_msg = if self.status_msg.is_empty() { msg } else { format!("{} · {}", self.status_msg, msg) };
+    }
+
+    pub fn toggle_restore_session(&mut self) {
+        self.config.general.restore_session = !self.config.general.restore_session;
+        self.save_config();
+    }
+
+    pub fn accessible(&self) -> bool {
+        self.config.general.accessible || self.force_accessible
+    }
+
+    pub fn next_keyboard_layout(&mut self) {
+        self.config.general.keyboard_layout = self.config.general.keyboard_layout.next();
+        self.save_config();
+    }
+
+    pub fn toggle_accessible(&mut self) {
+        self.config.general.accessible = !self.config.general.accessible;
+        self.save_config();
+    }
+
+    pub fn toggle_autosave(&mut self) {
+        self.config.general.autosave = !self.config.general.autosave;
+        self.save_config();
+    }
+
+    /// Takes effect from the next day's first launch.
+    pub fn toggle_new_day(&mut self) {
+        self.config.general.new_day = !self.config.general.new_day;
+        self.save_config();
+    }
+
+    /// Takes effect on the next load or reload.
+    pub fn toggle_preserve_unknown(&mut self) {
+        self.config.files.preserve_unknown = !self.config.files.preserve_unknown;
+        self.save_config();
+    }
+
+    /// Takes effect the next time gws starts.
+    pub fn toggle_repo_local(&mut self) {
+        self.config.files.repo_local = !self.config.files.repo_local;
+        self.save_config();
+    }
+
+    /// Set the default todo file from the input buffer; empty clears it.
+    pub fn set_default_file_from_input(&mut self) {
+        let path = self.input_buffer.trim();
+        self.config.files.default_file = (!path.is_empty()).then(|| PathBuf::from(path));
+        self.save_config();
+    }
+
+    /// Start the guided tutorial over the current document.
+    pub fn start_tutorial(&mut self) {
+        self.tutorial = Some(Tutorial::new(self));
+    }
+
+    /// Check the tutorial's current step against the app state.
+    pub fn advance_tutorial(&mut self) {
+        if let Some(mut tutorial) = self.tutorial.take() {
+            tutorial.update(self);
+            self.tutorial = Some(tutorial);
+        }
+    }
+
+    /// Install loaded preferences; later changes are saved back to `path`.
+    pub fn set_config(&mut self, config: Config, path: PathBuf) {
+        self.config = config;
+        self.config_path = Some(path);
+        self.rebuild_agenda();
+    }
+
+    fn save_config(&mut self) {
+        if let Some(path) = &self.config_path {
+            self.status_msg = match config::save(path, &self.config) {
+                Ok(()) => "Preferences saved".to_string(),
+                Err(e) => format!("Failed to save preferences: {}", e),
+            };
+        }
+    }
+
+    // --- Navigation ---
+
+    pub fn move_down(&mut self) {
+        match self.view {
+            View::Agenda => {
+                if !self.agenda_items.is_empty() {
+                    if self.agenda_cursor < self.agenda_items.len() - 1 {
+                        self.agenda_cursor += 1;
+                    } else {
+                        self.agenda_cursor = 0;
+                    }
+                }
+            }
+            View::Backlog => {
+                if !self.tree_nodes.is_empty() {
+                    if self.backlog_cursor < self.tree_nodes.len() - 1 {
+                        self.backlog_cursor += 1;
+                    } else {
+                        self.backlog_cursor = 0;
+                    }
+                }
+            }
+            View::Stats => {}
+            View::Settings => {
+                let total = self.settings_total();
+                if total > 0 {
+                    if self.settings_cursor < total - 1 {
+                        self.settings_cursor += 1;
+                    } else {
+                        self.settings_cursor = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        match self.view {
+            View::Agenda => {
+                if !self.agenda_items.is_empty() {
+                    if self.agenda_cursor > 0 {
+                        self.agenda_cursor -= 1;
+                    } else {
+                        self.agenda_cursor = self.agenda_items.len() - 1;
+                    }
+                }
+            }
+            View::Backlog => {
+                if !self.tree_nodes.is_empty() {
+                    if self.backlog_cursor > 0 {
+                        self.backlog_cursor -= 1;
+                    } else {
+                        self.backlog_cursor = self.tree_nodes.len() - 1;
+                    }
+                }
+            }
+            View::Stats => {}
+            View::Settings => {
+                let total = self.settings_total();
+                if total > 0 {
+                    if self.settings_cursor > 0 {
+                        self.settings_cursor -= 1;
+                    } else {
+                        self.settings_cursor = total - 1;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn move_top(&mut self) {
+        match self.view {
+            View::Agenda => self.agenda_cursor = 0,
+            View::Backlog => self.backlog_cursor = 0,
+            View::Stats => {}
+            View::Settings => self.settings_cursor = 0,
+        }
+    }
+
+    pub fn move_bottom(&mut self) {
+        match self.view {
+            View::Agenda => {
                 if !self.agenda_items.is_empty() {
                     self.agenda_cursor = self.agenda_items.len() - 1;
                 }
-            }
-            View::Backlog => {
-                if !self.tree_nodes.is_empty() {
-                    self.backlog_cursor = self.tree_nodes.len() - 1;
+            }
+            View::Backlog => {
+                if !self.tree_nodes.is_empty() {
+                    self.backlog_cursor = self.tree_nodes.len() - 1;
+                }
+            }
+            View::Stats => {}
+            View::Settings => {
+                let total = self.settings_total();
+                if total > 0 {
+                    self.settings_cursor = total - 1;
+                }
+            }
+        }
+    }
+
+    /// Number of rows the cursor can move over in the current view.
+    fn view_len(&self) -> usize {
+        match self.view {
+            View::Agenda => self.agenda_items.len(),
+            View::Backlog => self.tree_nodes.len(),
+            View::Stats => 0,
+            View::Settings => self.settings_total(),
+        }
+    }
+
+    /// Only called when `view_len` is non-zero, which rules out Stats.
+    fn cursor_mut(&mut self) -> &mut usize {
+        match self.view {
+            View::Agenda => &mut self.agenda_cursor,
+            View::Backlog => &mut self.backlog_cursor,
+            View::Stats => unreachable!("the Stats view has no cursor"),
+            View::Settings => &mut self.settings_cursor,
+        }
+    }
+
+    /// Move the cursor to a 1-based row, clamped to the list (`10G`).
+    pub fn move_to_line(&mut self, line: usize) {
+        let len = self.view_len();
+        if len == 0 {
+            return;
+        }
+        *self.cursor_mut() = line.saturating_sub(1).min(len - 1);
+    }
+
+    /// Move the cursor by `delta` rows, clamping at the ends (no wrap).
+    pub fn move_by(&mut self, delta: isize) {
+        let len = self.view_len();
+        if len == 0 {
+            return;
+        }
+        let cursor = self.cursor_mut();
+        *cursor = cursor.saturating_add_signed(delta).min(len - 1);
+    }
+
+    /// Move half a page down (positive) or up (negative).
+    pub fn half_page(&mut self, direction: isize) {
+        let step = (self.visible_height / 2).max(1) as isize;
+        self.move_by(step * direction);
+    }
+
+    /// Move a full page down (positive) or up (negative).
+    pub fn full_page(&mut self, direction: isize) {
+        let step = self.visible_height.max(1) as isize;
+        self.move_by(step * direction);
+    }
+
+    /// Index range of items currently shown in the viewport.
+    fn viewport_range(&self) -> (usize, usize) {
+        let len = self.view_len();
+        if len == 0 {
+            return (0, 0);
+        }
+        let height = self.visible_height.max(1);
+        match self.view {
+            View::Agenda => {
+                let first = self.agenda_scroll.min(len - 1);
+                let cursor = self.agenda_cursor;
+                let has_preview = self.agenda_note_preview().is_some();
+                let mut last = first;
+                while last + 1 < len {
+                    let headers = section_headers_between(&self.agenda_items, first, last + 1);
+                    let preview = (has_preview && (first..=last).contains(&cursor)) as usize;
+                    if (last + 1 - first) + headers + preview >= height {
+                        break;
+                    }
+                    last += 1;
+                }
+                (first, last)
+            }
+            View::Stats => (0, 0),
+            View::Backlog | View::Settings => {
+                let scroll = if self.view == View::Backlog { self.backlog_scroll } else { self.settings_scroll };
+                let first = scroll.min(len - 1);
+                (first, (first + height - 1).min(len - 1))
+            }
+        }
+    }
+
+    /// Jump to the top (`H`), middle (`M`) or bottom (`L`) row of the viewport.
+    pub fn move_to_viewport(&mut self, position: char) {
+        if self.view_len() == 0 {
+            return;
+        }
+        let (first, last) = self.viewport_range();
+        let target = match position {
+            'H' => first,
+            'M' => first + (last - first) / 2,
+            _ => last,
+        };
+        *self.cursor_mut() = target;
+    }
+
+    // --- Jump labels ---
+
+    /// Enter jump mode: every visible row gets a label that moves the cursor there.
+    pub fn start_jump(&mut self) {
+        if self.view_len() > 0 {
+            self.jump_input = Some(String::new());
+            self.status_msg = "Jump: type a label, Esc to cancel".to_string();
+        }
+    }
+
+    pub fn is_jumping(&self) -> bool {
+        self.jump_input.is_some()
+    }
+
+    /// Labels for the rows currently in the viewport, as (row index, label).
+    pub fn jump_labels(&self) -> Vec<(usize, String)> {
+        if self.view_len() == 0 {
+            return Vec::new();
+        }
+        let (first, last) = self.viewport_range();
+        (first..=last).zip(make_jump_labels(last - first + 1)).collect()
+    }
+
+    /// Label shown for a row while jumping, if it is still reachable with the input so far.
+    pub fn jump_label_for(&self, idx: usize) -> Option<String> {
+        let input = self.jump_input.as_ref()?;
+        self.jump_labels()
+            .into_iter()
+            .find(|(i, label)| *i == idx && label.starts_with(input.as_str()))
+            .map(|(_, label)| label)
+    }
+
+    /// Feed one typed character to jump mode; jumps on an exact match and
+    /// leaves jump mode on a match or when no label can match any more.
+    pub fn jump_key(&mut self, c: char) {
+        let Some(mut input) = self.jump_input.take() else { return };
+        input.push(c);
+        let labels = self.jump_labels();
+        if let Some((idx, _)) = labels.iter().find(|(_, label)| *label == input) {
+            *self.cursor_mut() = *idx;
+            self.status_msg.clear();
+        } else if labels.iter().any(|(_, label)| label.starts_with(&input)) {
+            self.jump_input = Some(input);
+        } else {
+            self.status_msg = "No such label".to_string();
+        }
+    }
+
+    pub fn cancel_jump(&mut self) {
+        self.jump_input = None;
+        self.status_msg.clear();
+    }
+
+    /// Update scroll offset to keep cursor visible for the given view height.
+    pub fn update_scroll(&mut self, visible_height: usize) {
+        self.visible_height = visible_height;
+
+        if self.view == View::Agenda {
+            // Agenda needs special handling to account for section header rows
+            let len = self.agenda_items.len();
+            if len == 0 || visible_height == 0 {
+                self.agenda_scroll = 0;
+                return;
+            }
+            let cursor = self.agenda_cursor;
+            if cursor < self.agenda_scroll {
+                self.agenda_scroll = cursor;
+            }
+            // Increase scroll until cursor fits within visible_height
+            // Keep the note preview under the cursor in view too
+            let preview = self.agenda_note_preview().is_some() as usize;
+            loop {
+                let headers = section_headers_between(&self.agenda_items, self.agenda_scroll, cursor);
+                let display_row = (cursor - self.agenda_scroll) + headers;
+                if display_row + preview < visible_height {
+                    break;
+                }
+                self.agenda_scroll += 1;
+                if self.agenda_scroll > cursor {
+                    self.agenda_scroll = cursor;
+                    break;
+                }
+            }
+            return;
+        }
+
+        let settings_total = self.settings_total();
+        let (cursor, scroll, len) = match self.view {
+            View::Agenda => unreachable!(),
+            View::Stats => return,
+            View::Backlog => (self.backlog_cursor, &mut self.backlog_scroll, self.tree_nodes.len()),
+            View::Settings => (self.settings_cursor, &mut self.settings_scroll, settings_total),
+        };
+        if len == 0 || visible_height == 0 {
+            *scroll = 0;
+            return;
+        }
+        if cursor >= *scroll + visible_height {
+            *scroll = cursor - visible_height + 1;
+        } else if cursor < *scroll {
+            *scroll = cursor;
+        }
+    }
+
+    /// Center the cursor vertically in the viewport.
+    pub fn center_cursor(&mut self, visible_height: usize) {
+        let (cursor, scroll) = match self.view {
+            View::Agenda => (self.agenda_cursor, &mut self.agenda_scroll),
+            View::Backlog => (self.backlog_cursor, &mut self.backlog_scroll),
+            View::Stats => return,
+            View::Settings => (self.settings_cursor, &mut self.settings_scroll),
+        };
+        *scroll = cursor.saturating_sub(visible_height / 2);
+    }
+
+    pub fn cycle_view(&mut self) {
+        self.view = match self.view {
+            View::Agenda => View::Backlog,
+            View::Backlog => View::Stats,
+            View::Stats => View::Settings,
+            View::Settings => View::Agenda,
+        };
+    }
+
+    /// Switch from Agenda to Backlog view, focusing on the same task.
+    pub fn jump_to_backlog_task(&mut self) {
+        let Some(item) = self.agenda_items.get(self.agenda_cursor) else {
+            return;
+        };
+        let target = TreeNodeKind::Task {
+            cat_idx: item.category_idx,
+            proj_idx: item.project_idx,
+            task_idx: item.task_idx,
+        };
+        self.reveal_in_backlog(&target);
+    }
+
+    /// Show the Backlog with the cursor on `target`, expanding the category
+    /// and project it sits in.
+    pub fn reveal_in_backlog(&mut self, target: &TreeNodeKind) {
+        match *target {
+            TreeNodeKind::Project { cat_idx, proj_idx } | TreeNodeKind::Task { cat_idx, proj_idx, .. } => {
+                self.collapse.collapsed_categories.remove(&cat_idx);
+                self.collapse.collapsed_projects.remove(&(cat_idx, proj_idx));
+            }
+            TreeNodeKind::Category { cat_idx } => {
+                self.collapse.collapsed_categories.remove(&cat_idx);
+            }
+            _ => {}
+        }
+        self.rebuild_tree();
+        self.restore_cursor(target);
+        self.view = View::Backlog;
+        self.update_scroll(self.visible_height);
+    }
+
+    /// Show the Agenda with the cursor on a task, if the agenda lists it.
+    pub fn reveal_in_agenda(&mut self, (cat_idx, proj_idx, task_idx): (usize, usize, usize)) -> bool {
+        let Some(i) = self
+            .agenda_items
+            .iter()
+            .position(|item| (item.category_idx, item.project_idx, item.task_idx) == (cat_idx, proj_idx, task_idx))
+        else {
+            return false;
+        };
+        self.agenda_cursor = i;
+        self.view = View::Agenda;
+        self.update_scroll(self.visible_height);
+        true
+    }
+
+    // --- Backlog: toggle collapse ---
+
+    pub fn toggle_collapse(&mut self) {
+        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            match &node.kind {
+                TreeNodeKind::Preamble => {
+                    self.collapse.preamble_collapsed = !self.collapse.preamble_collapsed;
+                }
+                TreeNodeKind::Category { cat_idx } => {
+                    let cat_idx = *cat_idx;
+                    if !self.collapse.collapsed_categories.remove(&cat_idx) {
+                        self.collapse.collapsed_categories.insert(cat_idx);
+                    }
+                }
+                TreeNodeKind::Project { cat_idx, proj_idx } => {
+                    let key = (*cat_idx, *proj_idx);
+                    if !self.collapse.collapsed_projects.remove(&key) {
+                        self.collapse.collapsed_projects.insert(key);
+                    }
+                }
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+                    let key = (*cat_idx, *proj_idx, *task_idx);
+                    if !self.collapse.collapsed_tasks.remove(&key) {
+                        self.collapse.collapsed_tasks.insert(key);
+                    }
+                }
+                TreeNodeKind::Note { .. } | TreeNodeKind::PreambleLine { .. } => {} // leaves can't collapse
+            }
+            // Save the kind before rebuild so we can restore cursor
+            let saved_kind = node.kind.clone();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+        }
+    }
+
+    // --- Backlog: bulk folding ---
+
+    /// Fold the tree so that only nodes shallower than `depth` have their children
+    /// shown: 1 = categories only, 2 = categories and projects, 3 = tasks without
+    /// notes, 4 or more = everything expanded.
+    pub fn fold_to_depth(&mut self, depth: u8) {
+        self.collapse.preamble_collapsed = depth <= 1;
+        self.collapse.collapsed_categories.clear();
+        self.collapse.collapsed_projects.clear();
+        self.collapse.collapsed_tasks.clear();
+
+        for (ci, category) in self.doc.categories.iter().enumerate() {
+            if depth <= 1 {
+                self.collapse.collapsed_categories.insert(ci);
+            }
+            for (pi, project) in category.projects.iter().enumerate() {
+                if depth == 2 {
+                    self.collapse.collapsed_projects.insert((ci, pi));
+                }
+                for (ti, task) in project.tasks.iter().enumerate() {
+                    if depth == 3 && !task.notes.is_empty() {
+                        self.collapse.collapsed_tasks.insert((ci, pi, ti));
+                    }
+                }
+            }
+        }
+
+        let saved_kind = self.current_tree_node().map(|n| n.kind.clone());
+        self.rebuild_tree();
+        if let Some(kind) = saved_kind {
+            self.restore_cursor_or_ancestor(&kind);
+        }
+        self.status_msg = match depth {
+            0 | 1 => "Folded to categories".to_string(),
+            2 => "Folded to projects".to_string(),
+            3 => "Folded to tasks".to_string(),
+            _ => "Expanded all".to_string(),
+        };
+    }
+
+    /// Restore cursor to `kind`, or to its closest visible ancestor if it is now hidden.
+    pub fn restore_cursor_or_ancestor(&mut self, kind: &TreeNodeKind) {
+        let mut candidates = vec![kind.clone()];
+        match *kind {
+            TreeNodeKind::Note { cat_idx, proj_idx, task_idx, .. } => {
+                candidates.push(TreeNodeKind::Task { cat_idx, proj_idx, task_idx });
+                candidates.push(TreeNodeKind::Project { cat_idx, proj_idx });
+                candidates.push(TreeNodeKind::Category { cat_idx });
+            }
+            TreeNodeKind::Task { cat_idx, proj_idx, .. } => {
+                candidates.push(TreeNodeKind::Project { cat_idx, proj_idx });
+                candidates.push(TreeNodeKind::Category { cat_idx });
+            }
+            TreeNodeKind::Project { cat_idx, .. } => {
+                candidates.push(TreeNodeKind::Category { cat_idx });
+            }
+            TreeNodeKind::PreambleLine { .. } => candidates.push(TreeNodeKind::Preamble),
+            TreeNodeKind::Category { .. } | TreeNodeKind::Preamble => {}
+        }
+        for candidate in &candidates {
+            if let Some(i) = self.tree_nodes.iter().position(|n| n.kind == *candidate) {
+                self.backlog_cursor = i;
+                return;
+            }
+        }
+    }
+
+    /// Restore cursor to the node matching the given kind after a rebuild.
+    fn restore_cursor(&mut self, kind: &TreeNodeKind) {
+        for (i, node) in self.tree_nodes.iter().enumerate() {
+            if node.kind == *kind {
+                self.backlog_cursor = i;
+                return;
+            }
+        }
+        // Fallback: clamp
+        if !self.tree_nodes.is_empty() && self.backlog_cursor >= self.tree_nodes.len() {
+            self.backlog_cursor = self.tree_nodes.len() - 1;
+        }
+    }
+
+    // --- Backlog: current node ---
+
+    pub fn current_tree_node(&self) -> Option<&TreeNode> {
+        self.tree_nodes.get(self.backlog_cursor)
+    }
+
+    /// Logged history of the focused agenda or backlog task, e.g.
+    /// "created 12d ago, on deck 3d, in progress 1d".
+    pub fn focused_task_history(&self) -> Option<String> {
+        let (ci, pi, ti) = self.focused_task_idx()?;
+        let category = self.doc.categories.get(ci)?;
+        let project = category.projects.get(pi)?;
+        let task = project.tasks.get(ti)?;
+        self.activity.task_history(&category.name, &project.name, &task.text, Date::today())
+    }
+
+    /// (category, project, task) of the selected agenda task, or of the
+    /// Backlog task or note under the cursor.
+    fn focused_task_idx(&self) -> Option<(usize, usize, usize)> {
+        match self.view {
+            View::Agenda => {
+                let item = self.agenda_items.get(self.agenda_cursor)?;
+                Some((item.category_idx, item.project_idx, item.task_idx))
+            }
+            View::Backlog => match self.current_tree_node()?.kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                | TreeNodeKind::Note { cat_idx, proj_idx, task_idx, .. } => Some((cat_idx, proj_idx, task_idx)),
+                _ => None,
+            },
+            View::Stats | View::Settings => None,
+        }
+    }
+
+    /// Links to title with `:titles`: those making up a whole task or note.
+    /// None, with the reason in the status bar, when there is nothing to do.
+    pub fn request_link_titles(&mut self) -> Option<Vec<String>> {
+        if !self.config.general.fetch_link_titles {
+            self.status_msg = "Fetching link titles is off (fetch_link_titles in the config)".to_string();
+            return None;
+        }
+        let urls = engine::bare_links(&self.doc);
+        if urls.is_empty() {
+            self.status_msg = "No bare links: a task or note that is only a URL gets titled".to_string();
+            return None;
+        }
+        let n = urls.len();
+        self.status_msg = format!("Fetching {} link title{}...", n, if n == 1 { "" } else { "s" });
+        Some(urls)
+    }
+
+    /// Write a fetched page title next to its link, or report why it failed.
+    pub fn apply_link_title(&mut self, url: &str, title: Result<String, String>) {
+        let title = match title {
+            Ok(title) => title,
+            Err(e) => {
+                self.status_msg = format!("No title for {}: {}", url, e);
+                return;
+            }
+        };
+        let before = self.doc.clone();
+        // The link may have been edited away while the page loaded
+        if engine::title_link(&mut self.doc, url, &title) == 0 {
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!("Titled link: {}", title);
+        let saved = self.current_tree_node().map(|n| n.kind.clone());
+        self.refresh_agenda();
+        self.rebuild_tree();
+        if let Some(kind) = saved {
+            self.restore_cursor(&kind);
+        }
+    }
+
+    /// Paths attached with `@file(...)` to the focused task.
+    pub fn focused_attachments(&self) -> Vec<&str> {
+        let Some((ci, pi, ti)) = self.focused_task_idx() else {
+            return Vec::new();
+        };
+        let task = self.doc.categories.get(ci).and_then(|c| c.projects.get(pi)).and_then(|p| p.tasks.get(ti));
+        task.map(Task::attachments).unwrap_or_default()
+    }
+
+    /// List the focused task's attached files, marking the missing ones.
+    pub fn open_attachments(&mut self, attachments: Vec<Attachment>) {
+        let items = attachments
+            .iter()
+            .map(|a| if a.exists { a.spec.clone() } else { format!("{}  (missing)", a.spec) })
+            .collect();
+        self.attachments = attachments;
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::Attachments);
+    }
+
+    /// Called on each tick of the event loop. At midnight the agenda is
+    /// rebuilt and the screen drawn again, as deadlines and ages count from
+    /// today.
+    pub fn tick(&mut self, today: Date) {
+        if today != self.day {
+            self.day = today;
+            self.refresh_agenda();
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Show the latest lines of the debug log, from the newest.
+    pub fn open_log(&mut self, lines: Vec<String>) {
+        if lines.is_empty() {
+            self.status_msg = "The debug log is empty".to_string();
+            return;
+        }
+        let cursor = lines.len() - 1;
+        self.picker = Picker { items: lines, cursor };
+        self.open_dialog(Dialog::Log);
+    }
+
+    /// The highlighted attached file, if it is there to open.
+    pub fn picked_attachment(&mut self) -> Option<PathBuf> {
+        let attachment = self.attachments.get(self.picker.cursor)?;
+        if !attachment.exists {
+            self.status_msg = format!("Not found: {}", attachment.path.display());
+            return None;
+        }
+        Some(attachment.path.clone())
+    }
+
+    /// First non-blank note line of the selected agenda task, shown dimmed
+    /// under it.
+    pub fn agenda_note_preview(&self) -> Option<&str> {
+        self.focused_task()?.notes.iter().map(|note| note.trim()).find(|note| !note.is_empty())
+    }
+
+    /// The selected agenda task as it is in the document, notes included.
+    /// The agenda or backlog task under the cursor, or the task of the
+    /// focused note.
+    pub fn focused_task(&self) -> Option<&Task> {
+        let (ci, pi, ti) = self.focused_task_idx()?;
+        self.doc.categories.get(ci)?.projects.get(pi)?.tasks.get(ti)
+    }
+
+    /// Where an agenda item sits in the bury list, if it was buried.
+    fn buried_rank(&self, item: &AgendaItem) -> Option<usize> {
+        let category = &self.doc.categories[item.category_idx];
+        let project = &category.projects[item.project_idx];
+        self.buried
+            .iter()
+            .position(|r| r.category == category.name && r.project == project.name && r.task == item.task.text)
+    }
+
+    /// `b`: "not now, but don't lose it". In the agenda, send the focused
+    /// task to the bottom of its section, or bring it back if it is already
+    /// buried; in the backlog, move it to the bottom of its project.
+    pub fn bury_focused(&mut self) {
+        match self.view {
+            View::Agenda => self.bury_agenda_item(),
+            View::Backlog => self.sink_focused_task(),
+            View::Stats | View::Settings => {}
+        }
+    }
+
+    fn bury_agenda_item(&mut self) {
+        let Some(item) = self.agenda_items.get(self.agenda_cursor) else { return };
+        let addr = (item.category_idx, item.project_idx, item.task_idx);
+        let state = item.task.state;
+        let category = &self.doc.categories[addr.0];
+        let project = &category.projects[addr.1];
+        let r = TaskRef { category: category.name.clone(), project: project.name.clone(), task: item.task.text.clone() };
+        let text = r.task.clone();
+        if let Some(pos) = self.buried.iter().position(|b| *b == r) {
+            self.buried.remove(pos);
+            self.status_msg = format!("Unburied \"{text}\"");
+        } else {
+            self.buried.push(r);
+            self.status_msg = format!("Buried \"{text}\" at the bottom of {}", state.label());
+        }
+        self.rebuild_agenda();
+        self.find_agenda_cursor(addr.0, addr.1, addr.2);
+    }
+
+    fn sink_focused_task(&mut self) {
+        let Some(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx }) = self.current_tree_node().map(|n| &n.kind) else {
+            return;
+        };
+        let before = self.doc.clone();
+        let Some(new_idx) = engine::sink_task(&mut self.doc, cat_idx, proj_idx, task_idx) else {
+            self.status_msg = "Already at the bottom".to_string();
+            return;
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        let project = &self.doc.categories[cat_idx].projects[proj_idx];
+        self.status_msg = format!(
+            "Moved \"{}\" to the bottom of {}",
+            project.tasks[new_idx].text,
+            project.name
+        );
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: new_idx });
+    }
+
+    /// Show the focused task's full text and all its notes in a popup
+    /// (`K`), for when the row cuts them short.
+    pub fn open_task_details(&mut self) {
+        if self.focused_task().is_some() {
+            self.open_dialog(Dialog::TaskDetails);
+        }
+    }
+
+    /// Category and project names of the focused agenda or backlog item,
+    /// outermost first; empty in the other views or on the preamble.
+    pub fn breadcrumb(&self) -> Vec<&str> {
+        let (ci, pi) = match self.view {
+            View::Agenda => match self.agenda_items.get(self.agenda_cursor) {
+                Some(item) => (item.category_idx, Some(item.project_idx)),
+                None => return Vec::new(),
+            },
+            View::Backlog => match self.current_tree_node().map(|n| &n.kind) {
+                Some(TreeNodeKind::Category { cat_idx }) => (*cat_idx, None),
+                Some(
+                    TreeNodeKind::Project { cat_idx, proj_idx }
+                    | TreeNodeKind::Task { cat_idx, proj_idx, .. }
+                    | TreeNodeKind::Note { cat_idx, proj_idx, .. },
+                ) => (*cat_idx, Some(*proj_idx)),
+                _ => return Vec::new(),
+            },
+            View::Stats | View::Settings => return Vec::new(),
+        };
+        let Some(category) = self.doc.categories.get(ci) else {
+            return Vec::new();
+        };
+        let mut crumbs = vec![category.name.as_str()];
+        crumbs.extend(pi.and_then(|pi| category.projects.get(pi)).map(|p| p.name.as_str()));
+        crumbs
+    }
+
+    // --- Mutations from Agenda view ---
+
+    /// Promote the focused task `times` steps (`3p`). Every step goes to the
+    /// same task, even once it has left the agenda.
+    pub fn promote_selected_agenda(&mut self, times: usize) {
+        if let Some(item) = self.agenda_items.get(self.agenda_cursor) {
+            let ci = item.category_idx;
+            let pi = item.project_idx;
+            let ti = item.task_idx;
+            if step_task(&mut self.doc, (ci, pi, ti), times, engine::promote_task) {
+                self.dirty = true;
+                self.status_msg = self.state_change_text(ci, pi, ti);
+                self.enforce_single_task(ci, pi, ti);
+                // Rebuild agenda without auto_promote to preserve the manual state change
+                self.rebuild_agenda();
+                self.rebuild_tree();
+                self.find_agenda_cursor(ci, pi, ti);
+                self.offer_checklist_reset(ci, pi);
+            }
+        }
+    }
+
+    /// Demote the focused task `times` steps (`3x`).
+    pub fn demote_selected_agenda(&mut self, times: usize) {
+        if let Some(item) = self.agenda_items.get(self.agenda_cursor) {
+            let ci = item.category_idx;
+            let pi = item.project_idx;
+            let ti = item.task_idx;
+            if step_task(&mut self.doc, (ci, pi, ti), times, engine::demote_task) {
+                self.dirty = true;
+                self.status_msg = self.state_change_text(ci, pi, ti);
+                self.enforce_single_task(ci, pi, ti);
+                // Rebuild agenda without auto_promote to preserve the manual state change
+                self.rebuild_agenda();
+                self.rebuild_tree();
+                self.find_agenda_cursor(ci, pi, ti);
+            }
+        }
+    }
+
+    /// Find an agenda item by its task address and move cursor to it.
+    fn find_agenda_cursor(&mut self, cat_idx: usize, proj_idx: usize, task_idx: usize) {
+        for (i, item) in self.agenda_items.iter().enumerate() {
+            if item.category_idx == cat_idx && item.project_idx == proj_idx && item.task_idx == task_idx {
+                self.agenda_cursor = i;
+                return;
+            }
+        }
+        if !self.agenda_items.is_empty() && self.agenda_cursor >= self.agenda_items.len() {
+            self.agenda_cursor = self.agenda_items.len() - 1;
+        }
+    }
+
+    // --- Mutations from Backlog view ---
+
+    /// Promote the focused task `times` steps (`3p`); on a project, toggle
+    /// whether it is active.
+    pub fn promote_selected_backlog(&mut self, times: usize) {
+        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            let saved_kind = node.kind.clone();
+            match &saved_kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                    if step_task(&mut self.doc, (*cat_idx, *proj_idx, *task_idx), times, engine::promote_task) =>
+                {
+                    self.dirty = true;
+                    self.status_msg = self.state_change_text(*cat_idx, *proj_idx, *task_idx);
+                    self.enforce_single_task(*cat_idx, *proj_idx, *task_idx);
+                    self.checklist_reset = Some((*cat_idx, *proj_idx));
+                }
+                TreeNodeKind::Project { cat_idx, proj_idx }
+                    if engine::toggle_project_active(&mut self.doc, *cat_idx, *proj_idx) =>
+                {
+                    self.dirty = true;
+                    self.status_msg = self.activation_text(*cat_idx, *proj_idx);
+                }
+                _ => {}
+            }
+            self.refresh_agenda();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+            if let Some((ci, pi)) = self.checklist_reset.take() {
+                self.offer_checklist_reset(ci, pi);
+            }
+        }
+    }
+
+    /// Demote the focused task `times` steps (`3x`); on a project, toggle
+    /// whether it is active.
+    pub fn demote_selected_backlog(&mut self, times: usize) {
+        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            let saved_kind = node.kind.clone();
+            match &saved_kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                    if step_task(&mut self.doc, (*cat_idx, *proj_idx, *task_idx), times, engine::demote_task) =>
+                {
+                    self.dirty = true;
+                    self.status_msg = self.state_change_text(*cat_idx, *proj_idx, *task_idx);
+                    self.enforce_single_task(*cat_idx, *proj_idx, *task_idx);
+                }
+                TreeNodeKind::Project { cat_idx, proj_idx }
+                    if engine::toggle_project_active(&mut self.doc, *cat_idx, *proj_idx) =>
+                {
+                    self.dirty = true;
+                    self.status_msg = self.activation_text(*cat_idx, *proj_idx);
+                }
+                _ => {}
+            }
+            self.refresh_agenda();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+        }
+    }
+
+    // --- Global mutations ---
+
+    pub fn run_auto_promote(&mut self) {
+        if !self.config.agenda.auto_promote.on_demand() {
+            self.status_msg = "Auto-promote is off (see Settings)".to_string();
+            return;
+        }
+        let promoted = engine::auto_promote(&mut self.doc, self.config.agenda.promote);
+        if promoted.is_empty() {
+            self.status_msg = "Nothing to promote".to_string();
+            return;
+        }
+        self.dirty = true;
+        self.status_msg = format!("Promoted {}", self.promoted_text(&promoted));
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    pub fn archive_done(&mut self) {
+        let before = self.doc.clone();
+        let archived = engine::archive_done(&mut self.doc, Date::today());
+        if archived == 0 {
+            self.status_msg = "Nothing to archive".to_string();
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!("Archived {} task{}", archived, if archived == 1 { "" } else { "s" });
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    /// The new-day routine, run on the first launch of a day: archive done
+    /// tasks, move deferred tasks that are due to On Deck, and auto-promote.
+    /// One undo step takes it all back.
+    pub fn start_new_day(&mut self) {
+        let today = Date::today();
+        let before = self.doc.clone();
+        let archived = engine::archive_done(&mut self.doc, today);
+        let resurfaced = engine::resurface_due(&mut self.doc, today);
+        let promoted = if self.config.agenda.auto_promote.on_demand() {
+            engine::auto_promote(&mut self.doc, self.config.agenda.promote)
+        } else {
+            Vec::new()
+        };
+
+        let mut summary = Vec::new();
+        if archived > 0 {
+            summary.push(format!("Archived {}", tasks_text(archived)));
+        }
+        for &(ci, pi, ti) in &resurfaced {
+            let project = &self.doc.categories[ci].projects[pi];
+            summary.push(format!("Due: \"{}\" in {} → On Deck", project.tasks[ti].text, project.name));
+        }
+        if !promoted.is_empty() {
+            summary.push(format!("Promoted {}", self.promoted_text(&promoted)));
+        }
+        if summary.is_empty() {
+            self.status_msg = "New day: nothing to tidy".to_string();
+            return;
+        }
+
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = "New day: u undoes the tidy-up".to_string();
+        self.rebuild_agenda();
+        self.rebuild_tree();
+        self.new_day_summary = summary;
+        if self.dialog == Dialog::None {
+            self.open_dialog(Dialog::NewDay);
+        }
+    }
+
+    /// `:retire`: move the focused project, every task in it, to
+    /// `## Done Projects`.
+    pub fn retire_focused_project(&mut self) {
+        let Some((ci, pi)) = self.focused_project_idx().filter(|_| self.view == View::Backlog) else {
+            self.status_msg = "Focus a project in the Backlog to retire it".to_string();
+            return;
+        };
+        let before = self.doc.clone();
+        let Some(project) = engine::retire_project(&mut self.doc, ci, pi, Date::today()) else {
+            return;
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!("Retired \"{}\" and its {} to {}", project.name, tasks_text(project.tasks.len()), DONE_PROJECTS_HEADING);
+        self.refresh_agenda();
+        self.rebuild_tree();
+        // The project after it, else the one before, else the category
+        let proj_idx = pi.min(self.doc.categories[ci].projects.len().saturating_sub(1));
+        self.restore_cursor_or_ancestor(&TreeNodeKind::Project { cat_idx: ci, proj_idx });
+    }
+
+    /// Pick an archived task to reopen (`U` or `:unarchive`).
+    pub fn open_unarchive_picker(&mut self) {
+        let items: Vec<String> = self
+            .doc
+            .archive
+            .iter()
+            .filter_map(|line| parser::archive_entry(line))
+            .map(|entry| {
+                let origin = entry.category.zip(entry.project).map(|(c, p)| format!("{} › {}", c, p));
+                let details: Vec<String> = origin.into_iter().chain(entry.date.map(|d| d.to_string())).collect();
+                if details.is_empty() {
+                    entry.text.to_string()
+                } else {
+                    format!("{}  ({})", entry.text, details.join(", "))
+                }
+            })
+            .collect();
+        if items.is_empty() {
+            self.status_msg = "Nothing archived".to_string();
+            return;
+        }
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::Unarchive);
+    }
+
+    /// Reopen the picked archive line in its project as a Todo.
+    pub fn apply_unarchive(&mut self) {
+        // The picker skips lines that aren't tasks, so map its row back to the archive
+        let Some(idx) = (0..self.doc.archive.len())
+            .filter(|&i| parser::archive_entry(&self.doc.archive[i]).is_some())
+            .nth(self.picker.cursor)
+        else {
+            return;
+        };
+        let before = self.doc.clone();
+        let Some((ci, pi)) = engine::unarchive(&mut self.doc, idx) else {
+            self.status_msg = "Nowhere to restore it: it doesn't say where it came from and there is no Inbox".to_string();
+            return;
+        };
+        let (category, project) = (&self.doc.categories[ci], &self.doc.categories[ci].projects[pi]);
+        self.status_msg = format!(
+            "Reopened \"{}\" in {} › {}",
+            project.tasks.last().map_or("", |t| t.text.as_str()),
+            category.name,
+            project.name
+        );
+        self.push_undo(before);
+        self.dirty = true;
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    // --- Undo ---
+
+    /// Record a snapshot of the document taken before an undoable mutation.
+    fn push_undo(&mut self, snapshot: Document) {
+        if self.undo_stack.len() == MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(snapshot);
+    }
+
+    /// Restore the document to the most recent snapshot.
+    pub fn undo(&mut self) {
+        let Some(doc) = self.undo_stack.pop() else {
+            self.status_msg = "Nothing to undo".to_string();
+            return;
+        };
+        let changes = diff::diff(&self.doc, &doc);
+        self.doc = doc;
+        self.dirty = true;
+        self.status_msg = if changes.is_empty() {
+            "Undone".to_string()
+        } else {
+            format!("Undone: {}", diff::summary(&changes))
+        };
+        self.rebuild_agenda();
+        self.rebuild_tree();
+    }
+
+    // --- Backlog: sort ---
+
+    /// Apply a one-shot sort chosen from the sort menu:
+    /// `n` projects by name, `a` active projects first, `s` tasks by state,
+    /// `p` tasks by priority, `d` tasks by due date.
+    pub fn apply_sort(&mut self, choice: char) {
+        let Some(node) = self.tree_nodes.get(self.backlog_cursor) else {
+            return;
+        };
+        let saved_kind = node.kind.clone();
+        let (cat_idx, proj_idx) = match node.kind {
+            TreeNodeKind::Category { cat_idx } => (cat_idx, None),
+            TreeNodeKind::Project { cat_idx, proj_idx }
+            | TreeNodeKind::Task { cat_idx, proj_idx, .. }
+            | TreeNodeKind::Note { cat_idx, proj_idx, .. } => (cat_idx, Some(proj_idx)),
+            TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => return,
+        };
+
+        let before = self.doc.clone();
+        let (changed, msg) = match (choice, proj_idx) {
+            ('n', _) => (engine::sort_projects_by_name(&mut self.doc, cat_idx), "Projects sorted by name"),
+            ('a', _) => (engine::sort_projects_active_first(&mut self.doc, cat_idx), "Active projects first"),
+            ('s', Some(pi)) => (engine::sort_tasks_by_state(&mut self.doc, cat_idx, pi), "Tasks sorted by state"),
+            ('p', Some(pi)) => (engine::sort_tasks_by_priority(&mut self.doc, cat_idx, pi), "Tasks sorted by priority"),
+            ('d', Some(pi)) => (engine::sort_tasks_by_due(&mut self.doc, cat_idx, pi), "Tasks sorted by due date"),
+            _ => return,
+        };
+
+        if changed {
+            self.push_undo(before);
+            self.dirty = true;
+            self.status_msg = msg.to_string();
+            self.rebuild_agenda();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+        } else {
+            self.status_msg = "Already sorted".to_string();
+        }
+    }
+
+    // --- Quick add ---
+
+    /// Every project name, in document order.
+    fn project_names(&self) -> Vec<String> {
+        self.doc.categories.iter().flat_map(|c| &c.projects).map(|p| p.name.clone()).collect()
+    }
+
+    /// Add the quick-add text as a Todo to the project named with
+    /// `+Project`, else to the inbox.
+    pub fn apply_quick_add(&mut self) {
+        let names = self.project_names();
+        let (text, project) = complete::split_sigil(&self.input_buffer, '+', names.iter().map(String::as_str));
+        if text.is_empty() {
+            return;
+        }
+        let target = match &project {
+            Some(name) => self.doc.find_project(name),
+            None => self.doc.inbox(),
+        };
+        let Some((ci, pi)) = target else {
+            self.status_msg = match project {
+                Some(name) => format!("No project named {}", name),
+                None => "No inbox: name a project with +Project or add one named Inbox".to_string(),
+            };
+            return;
+        };
+        let before = self.doc.clone();
+        if engine::add_task(&mut self.doc, ci, pi, text.clone()) {
+            self.push_undo(before);
+            self.dirty = true;
+            self.status_msg = format!("Added \"{}\" to {}", text, self.doc.categories[ci].projects[pi].name);
+            self.refresh_agenda();
+            self.rebuild_tree();
+        }
+    }
+
+    // --- Backlog: move task to project ---
+
+    /// Projects the focused task can move to, labelled `Category › Project`.
+    fn move_to_project_targets(&self) -> Vec<((usize, usize), String)> {
+        let Some(TreeNodeKind::Task { cat_idx, proj_idx, .. }) = self.current_tree_node().map(|n| &n.kind) else {
+            return Vec::new();
+        };
+        let current = (*cat_idx, *proj_idx);
+        self.doc
+            .categories
+            .iter()
+            .enumerate()
+            .flat_map(|(ci, c)| c.projects.iter().enumerate().map(move |(pi, p)| ((ci, pi), format!("{} › {}", c.name, p.name))))
+            .filter(|(target, _)| *target != current)
+            .collect()
+    }
+
+    pub fn open_move_to_project(&mut self) {
+        if !matches!(self.current_tree_node().map(|n| &n.kind), Some(TreeNodeKind::Task { .. })) {
+            return;
+        }
+        if self.move_to_project_targets().is_empty() {
+            self.status_msg = "No other projects to move to".to_string();
+            return;
+        }
+        self.open_dialog(Dialog::MoveToProject);
+    }
+
+    /// Move the focused task to the highlighted completion, or with the list
+    /// hidden, the project typed: one whose label matches exactly, else the
+    /// best match.
+    pub fn apply_move_to_project(&mut self) {
+        let Some(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx }) = self.current_tree_node().map(|n| &n.kind) else {
+            return;
+        };
+        let typed = match self.completion.matches.get(self.completion.cursor) {
+            Some(label) => label.clone(),
+            None => self.input_buffer.trim().to_string(),
+        };
+        let typed = typed.as_str();
+        if typed.is_empty() {
+            return;
+        }
+        let targets = self.move_to_project_targets();
+        let best = complete::rank(typed, targets.iter().map(|(_, label)| label.as_str()));
+        let target = targets
+            .iter()
+            .find(|(_, label)| label.eq_ignore_ascii_case(typed))
+            .or_else(|| targets.iter().find(|(_, label)| best.first() == Some(label)));
+        let Some(((ci, pi), label)) = target.cloned() else {
+            self.status_msg = format!("No project matches \"{}\"", typed);
+            return;
+        };
+        let before = self.doc.clone();
+        let text = self.doc.categories[cat_idx].projects[proj_idx].tasks[task_idx].text.clone();
+        let Some(ti) = engine::move_task_to_project(&mut self.doc, (cat_idx, proj_idx, task_idx), (ci, pi)) else {
+            return;
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!("Moved \"{}\" to {}", text, label);
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.reveal_in_backlog(&TreeNodeKind::Task { cat_idx: ci, proj_idx: pi, task_idx: ti });
+    }
+
+    // --- Backlog: add task ---
+
+    pub fn add_task_to_focused(&mut self) {
+        let text = self.input_buffer.trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+
+        // Figure out where to add based on current backlog focus
+        let (cat_idx, proj_idx) = if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            match &node.kind {
+                TreeNodeKind::Category { cat_idx: _ } => {
+                    // Adding a task to category doesn't make sense; this should be add project
+                    return;
+                }
+                TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => return,
+                TreeNodeKind::Project { cat_idx, proj_idx } => (*cat_idx, *proj_idx),
+                TreeNodeKind::Task { cat_idx, proj_idx, .. } => (*cat_idx, *proj_idx),
+                TreeNodeKind::Note { cat_idx, proj_idx, .. } => (*cat_idx, *proj_idx),
+            }
+        } else {
+            return;
+        };
+
+        if engine::add_task(&mut self.doc, cat_idx, proj_idx, text) {
+            self.dirty = true;
+            self.status_msg = "Task added".to_string();
+            self.refresh_agenda();
+            self.rebuild_tree();
+        }
+    }
+
+    pub fn add_project_to_focused(&mut self) {
+        let name = self.input_buffer.trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+
+        let cat_idx = if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            match &node.kind {
+                TreeNodeKind::Category { cat_idx } => *cat_idx,
+                TreeNodeKind::Project { cat_idx, .. } => *cat_idx,
+                TreeNodeKind::Task { cat_idx, .. } => *cat_idx,
+                TreeNodeKind::Note { cat_idx, .. } => *cat_idx,
+                TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => return,
+            }
+        } else {
+            return;
+        };
+
+        if engine::add_project(&mut self.doc, cat_idx, name, true) {
+            self.dirty = true;
+            self.status_msg = "Project added".to_string();
+            self.refresh_agenda();
+            self.rebuild_tree();
+        }
+    }
+
+    // --- Backlog: edit ---
+
+    pub fn apply_edit(&mut self) {
+        let new_text = self.input_buffer.trim().to_string();
+        if new_text.is_empty() {
+            return;
+        }
+
+        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            let saved_kind = node.kind.clone();
+            match &node.kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+                    if engine::rename_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx, new_text) {
+                        self.dirty = true;
+                        self.status_msg = "Task renamed".to_string();
+                    }
+                }
+                TreeNodeKind::Project { cat_idx, proj_idx } => {
+                    if engine::rename_project(&mut self.doc, *cat_idx, *proj_idx, new_text) {
+                        self.dirty = true;
+                        self.status_msg = "Project renamed".to_string();
+                    }
+                }
+                TreeNodeKind::Category { cat_idx } => {
+                    if engine::rename_category(&mut self.doc, *cat_idx, new_text) {
+                        self.dirty = true;
+                        self.status_msg = "Category renamed".to_string();
+                    }
+                }
+                TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
+                    if let Some(note) = self.doc.categories
+                        .get_mut(*cat_idx)
+                        .and_then(|c| c.projects.get_mut(*proj_idx))
+                        .and_then(|p| p.tasks.get_mut(*task_idx))
+                        .and_then(|t| t.notes.get_mut(*note_idx))
+                    {
+                        let text = format!("  {}", new_text);
+                        if *note != text {
+                            *note = text;
+                            self.dirty = true;
+                            self.status_msg = "Note updated".to_string();
+                        }
+                    }
+                }
+                TreeNodeKind::Preamble => {
+                    if engine::set_title(&mut self.doc, new_text) {
+                        self.dirty = true;
+                        self.status_msg = "Title updated".to_string();
+                    }
+                }
+                TreeNodeKind::PreambleLine { line_idx } => {
+                    if engine::set_preamble_line(&mut self.doc, *line_idx, new_text) {
+                        self.dirty = true;
+                        self.status_msg = "Preamble updated".to_string();
+                    }
+                }
+            }
+            self.refresh_agenda();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+        }
+    }
+
+    // --- Backlog: delete ---
+
+    pub fn delete_focused(&mut self) {
+        if self.block_range().is_some() {
+            return self.delete_block();
+        }
+        let Some(node) = self.tree_nodes.get(self.backlog_cursor) else {
+            return;
+        };
+        let before = self.doc.clone();
+        let (deleted, msg) = match node.kind {
+            TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+                (engine::delete_task(&mut self.doc, cat_idx, proj_idx, task_idx), "Task deleted")
+            }
+            TreeNodeKind::Project { cat_idx, proj_idx } => {
+                (engine::delete_project(&mut self.doc, cat_idx, proj_idx), "Project deleted")
+            }
+            TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
+                (engine::delete_task_note(&mut self.doc, cat_idx, proj_idx, task_idx, note_idx), "Note deleted")
+            }
+            TreeNodeKind::PreambleLine { line_idx } => {
+                (engine::delete_preamble_line(&mut self.doc, line_idx), "Preamble line deleted")
+            }
+            _ => return,
+        };
+        if !deleted {
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = msg.to_string();
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    // --- Backlog: rerank ---
+
+    pub fn rerank_focused(&mut self, direction: i32) {
+        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
+            let new_kind = match &node.kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+                    if let Some(new_idx) = engine::rerank_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx, direction) {
+                        self.dirty = true;
+                        Some(TreeNodeKind::Task { cat_idx: *cat_idx, proj_idx: *proj_idx, task_idx: new_idx })
+                    } else {
+                        None
+                    }
+                }
+                &TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
+                    let new_idx = engine::rerank_note(&mut self.doc, cat_idx, proj_idx, task_idx, note_idx, direction);
+                    self.dirty |= new_idx.is_some();
+                    new_idx.map(|note_idx| TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx })
                 }
-            }
-            View::Settings => {
-                let total = self.settings_total();
-                if total > 0 {
-                    self.settings_cursor = total - 1;
+                TreeNodeKind::Project { cat_idx, proj_idx } => {
+                    if let Some(new_idx) = engine::rerank_project(&mut self.doc, *cat_idx, *proj_idx, direction) {
+                        self.dirty = true;
+                        Some(TreeNodeKind::Project { cat_idx: *cat_idx, proj_idx: new_idx })
+                    } else {
+                        // Move across categories
+                        let ci = *cat_idx;
+                        let pi = *proj_idx;
+                        if direction < 0 && ci > 0 {
+                            // Move to end of previous category
+                            let dest_len = self.doc.categories[ci - 1].projects.len();
+                            if let Some((new_ci, new_pi)) = engine::move_project_to_category(&mut self.doc, ci, pi, ci - 1, dest_len) {
+                                self.dirty = true;
+                                Some(TreeNodeKind::Project { cat_idx: new_ci, proj_idx: new_pi })
+                            } else {
+                                None
+                            }
+                        } else if direction > 0 && ci + 1 < self.doc.categories.len() {
+                            // Move to start of next category
+                            if let Some((new_ci, new_pi)) = engine::move_project_to_category(&mut self.doc, ci, pi, ci + 1, 0) {
+                                self.dirty = true;
+                                Some(TreeNodeKind::Project { cat_idx: new_ci, proj_idx: new_pi })
+                            } else {
+                                None
+                            }
+                        } else {
+                            None
+                        }
+                    }
                 }
+                _ => None,
+            };
+
+            if let Some(kind) = new_kind {
+                self.refresh_agenda();
+                self.rebuild_tree();
+                self.restore_cursor(&kind);
             }
         }
     }
 
-    /// Update scroll offset to keep cursor visible for the given view height.
-    pub fn update_scroll(&mut self, visible_height: usize) {
-        self.visible_height = visible_height;
+    // --- Checklists ---
 
-        if self.view == View::Agenda {
-            // Agenda needs special handling to account for section header rows
-            let len = self.agenda_items.len();
-            if len == 0 || visible_height == 0 {
-                self.agenda_scroll = 0;
-                return;
-            }
-            let cursor = self.agenda_cursor;
-            if cursor < self.agenda_scroll {
-                self.agenda_scroll = cursor;
-            }
-            // Increase scroll until cursor fits within visible_height
-            loop {
-                let headers = section_headers_between(&self.agenda_items, self.agenda_scroll, cursor);
-                let display_row = (cursor - self.agenda_scroll) + headers;
-                if display_row < visible_height {
-                    break;
-                }
-                self.agenda_scroll += 1;
-                if self.agenda_scroll > cursor {
-                    self.agenda_scroll = cursor;
-                    break;
+    /// Ask to reset a checklist once its last item is done.
+    fn offer_checklist_reset(&mut self, cat_idx: usize, proj_idx: usize) {
+        let finished = self
+            .doc
+            .categories
+            .get(cat_idx)
+            .and_then(|c| c.projects.get(proj_idx))
+            .is_some_and(|p| p.is_finished_checklist());
+        if finished && self.dialog == Dialog::None {
+            self.checklist_reset = Some((cat_idx, proj_idx));
+            self.open_dialog(Dialog::ConfirmResetChecklist);
+        }
+    }
+
+    pub fn reset_finished_checklist(&mut self) {
+        let Some((ci, pi)) = self.checklist_reset.take() else {
+            return;
+        };
+        let before = self.doc.clone();
+        if engine::reset_checklist(&mut self.doc, ci, pi) {
+            self.push_undo(before);
+            self.dirty = true;
+            self.status_msg = format!("Checklist \"{}\" reset", self.doc.categories[ci].projects[pi].name);
+            self.rebuild_agenda();
+            self.rebuild_tree();
+        }
+    }
+
+    /// `C` in the Backlog: mark or unmark the focused project as a checklist.
+    pub fn toggle_checklist_focused(&mut self) {
+        let Some(node) = self.current_tree_node() else {
+            return;
+        };
+        let saved_kind = node.kind.clone();
+        let (ci, pi) = match node.kind {
+            TreeNodeKind::Project { cat_idx, proj_idx }
+            | TreeNodeKind::Task { cat_idx, proj_idx, .. }
+            | TreeNodeKind::Note { cat_idx, proj_idx, .. } => (cat_idx, proj_idx),
+            _ => return,
+        };
+        if engine::toggle_project_checklist(&mut self.doc, ci, pi) {
+            let project = &self.doc.categories[ci].projects[pi];
+            self.dirty = true;
+            self.status_msg = if project.checklist {
+                format!("\"{}\" is now a checklist", project.name)
+            } else {
+                format!("\"{}\" is no longer a checklist", project.name)
+            };
+            self.refresh_agenda();
+            self.rebuild_tree();
+            self.restore_cursor(&saved_kind);
+        }
+    }
+
+    /// Project of the backlog row under the cursor, or of its task or note.
+    fn focused_project_idx(&self) -> Option<(usize, usize)> {
+        match self.current_tree_node()?.kind {
+            TreeNodeKind::Project { cat_idx, proj_idx }
+            | TreeNodeKind::Task { cat_idx, proj_idx, .. }
+            | TreeNodeKind::Note { cat_idx, proj_idx, .. } => Some((cat_idx, proj_idx)),
+            _ => None,
+        }
+    }
+
+    /// Edit the deadline of the focused project, starting from the current one.
+    pub fn open_deadline_dialog(&mut self) {
+        let Some((ci, pi)) = self.focused_project_idx() else {
+            return;
+        };
+        let current = self.doc.categories[ci].projects[pi].deadline.map(|d| d.to_string()).unwrap_or_default();
+        self.open_dialog_with_text(Dialog::ProjectDeadline, &current);
+    }
+
+    /// Set the focused project's deadline from the input (`YYYY-MM-DD`,
+    /// `tomorrow`, `+3`, `+2w`); empty clears it.
+    pub fn set_deadline_from_input(&mut self) {
+        let Some((ci, pi)) = self.focused_project_idx() else {
+            return;
+        };
+        let input = self.input_buffer.trim();
+        let deadline = if input.is_empty() {
+            None
+        } else {
+            match Date::parse_relative_in(input, Date::today(), &self.config.calendar.calendar()) {
+                Some(date) => Some(date),
+                None => {
+                    self.status_msg = format!("Not a date: {}", input);
+                    return;
                 }
             }
+        };
+        let saved_kind = self.current_tree_node().map(|n| n.kind.clone());
+        let before = self.doc.clone();
+        let project = &mut self.doc.categories[ci].projects[pi];
+        project.deadline = deadline;
+        self.status_msg = match deadline {
+            Some(date) => format!("\"{}\" due {}", project.name, date),
+            None => format!("\"{}\" has no deadline", project.name),
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        self.refresh_agenda();
+        self.rebuild_tree();
+        if let Some(kind) = saved_kind {
+            self.restore_cursor(&kind);
+        }
+    }
+
+    /// `:color NAME` tints the focused project's rows on the Agenda and
+    /// Backlog; `:color` alone clears it.
+    pub fn set_focused_accent(&mut self, name: &str) {
+        let focused = match self.view {
+            View::Agenda => self.agenda_items.get(self.agenda_cursor).map(|i| (i.category_idx, i.project_idx)),
+            View::Backlog => self.focused_project_idx(),
+            View::Stats | View::Settings => None,
+        };
+        let Some((ci, pi)) = focused else {
+            self.status_msg = "Focus a project or one of its tasks to color it".to_string();
+            return;
+        };
+        let name = name.trim().to_lowercase();
+        if !name.is_empty() && !is_accent(&name) {
+            self.status_msg = format!("Not a color: {} (a name such as magenta or lightblue, or #rrggbb)", name);
+            return;
+        }
+        let before = self.doc.clone();
+        let project = &mut self.doc.categories[ci].projects[pi];
+        project.accent = Some(name).filter(|n| !n.is_empty());
+        self.status_msg = match &project.accent {
+            Some(accent) => format!("\"{}\" is now {}", project.name, accent),
+            None => format!("\"{}\" has no color", project.name),
+        };
+        if before != self.doc {
+            self.push_undo(before);
+            self.dirty = true;
+        }
+        self.refresh_agenda();
+    }
+
+    // --- Paste import ---
+
+    /// Open the paste-import dialog for the focused backlog project, starting
+    /// with `text` (a paste, or empty to type lines).
+    pub fn open_paste_import(&mut self, text: &str) {
+        let Some((cat_idx, proj_idx)) = self.focused_project_idx().filter(|_| self.view == View::Backlog) else {
+            self.status_msg = "Select a project in the Backlog to paste tasks into".to_string();
             return;
+        };
+        self.paste_import = Some(PasteImport {
+            cat_idx,
+            proj_idx,
+            text: text.to_string(),
+        });
+        self.open_dialog(Dialog::PasteImport);
+    }
+
+    pub fn paste_import_push(&mut self, text: &str) {
+        if let Some(paste) = &mut self.paste_import {
+            paste.text.push_str(text);
         }
+    }
 
-        let settings_total = self.settings_total();
-        let (cursor, scroll, len) = match self.view {
-            View::Agenda => unreachable!(),
-            View::Backlog => (self.backlog_cursor, &mut self.backlog_scroll, self.tree_nodes.len()),
-            View::Settings => (self.settings_cursor, &mut self.settings_scroll, settings_total),
+    pub fn paste_import_backspace(&mut self) {
+        if let Some(paste) = &mut self.paste_import {
+            paste.text.pop();
+        }
+    }
+
+    /// The tasks the pasted text would add.
+    pub fn paste_import_preview(&self) -> Vec<String> {
+        self.paste_import.as_ref().map(|p| engine::pasted_tasks(&p.text)).unwrap_or_default()
+    }
+
+    /// Add every previewed line as a Todo, as one undo step.
+    pub fn accept_paste_import(&mut self) {
+        let Some(paste) = self.paste_import.take() else {
+            return;
         };
-        if len == 0 || visible_height == 0 {
-            *scroll = 0;
+        let tasks = engine::pasted_tasks(&paste.text);
+        if tasks.is_empty() {
+            self.status_msg = "Nothing to add".to_string();
             return;
         }
-        if cursor >= *scroll + visible_height {
-            *scroll = cursor - visible_height + 1;
-        } else if cursor < *scroll {
-            *scroll = cursor;
+        let saved_kind = self.current_tree_node().map(|n| n.kind.clone());
+        let before = self.doc.clone();
+        let count = tasks.len();
+        for text in tasks {
+            engine::add_task(&mut self.doc, paste.cat_idx, paste.proj_idx, text);
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!(
+            "Added {} task{} to {}",
+            count,
+            if count == 1 { "" } else { "s" },
+            self.doc.categories[paste.cat_idx].projects[paste.proj_idx].name
+        );
+        self.refresh_agenda();
+        self.rebuild_tree();
+        if let Some(kind) = saved_kind {
+            self.restore_cursor(&kind);
         }
     }
 
-    /// Center the cursor vertically in the viewport.
-    pub fn center_cursor(&mut self, visible_height: usize) {
-        let (cursor, scroll) = match self.view {
-            View::Agenda => (self.agenda_cursor, &mut self.agenda_scroll),
-            View::Backlog => (self.backlog_cursor, &mut self.backlog_scroll),
-            View::Settings => (self.settings_cursor, &mut self.settings_scroll),
+    pub fn cancel_paste_import(&mut self) {
+        self.paste_import = None;
+    }
+
+    // --- Export / import ---
+
+    /// Recently opened files other than the open one.
+    pub fn other_recent_files(&self) -> Vec<&PathBuf> {
+        self.recent_files.iter().filter(|p| **p != self.file_path).collect()
+    }
+
+    /// Pick a recent file to switch to (`:e`, `gws open`).
+    pub fn open_recent_picker(&mut self) {
+        let home = dirs::home_dir();
+        let items: Vec<String> = self
+            .other_recent_files()
+            .iter()
+            .map(|path| match home.as_ref().and_then(|home| path.strip_prefix(home).ok()) {
+                Some(rest) => format!("~/{}", rest.display()),
+                None => path.display().to_string(),
+            })
+            .collect();
+        if items.is_empty() {
+            self.status_msg = "No other recent files".to_string();
+            return;
+        }
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::RecentFiles);
+    }
+
+    pub fn picked_recent_file(&self) -> Option<PathBuf> {
+        self.other_recent_files().get(self.picker.cursor).map(|p| p.to_path_buf())
+    }
+
+    /// Note the profile the open file belongs to and switch to its theme.
+    pub fn set_profile(&mut self, profile: Option<&Profile>) {
+        self.profile = profile.map(|p| p.name.clone());
+        let theme = profile.and_then(|p| p.theme.as_deref());
+        if let Some(index) = theme.and_then(|name| Theme::all().iter().position(|t| t.name.eq_ignore_ascii_case(name))) {
+            self.theme_index = index;
+        }
+    }
+
+    /// Pick one of the config's `[[profiles]]` to switch to.
+    pub fn open_profile_picker(&mut self) {
+        if self.config.profiles.is_empty() {
+            self.status_msg = "No profiles: add [[profiles]] with a name and file to the config".to_string();
+            return;
+        }
+        let items = self
+            .config
+            .profiles
+            .iter()
+            .map(|p| format!("{}  {}", p.name, p.file.display()))
+            .collect();
+        let cursor = self
+            .profile
+            .as_deref()
+            .and_then(|name| self.config.profiles.iter().position(|p| p.name == name))
+            .unwrap_or(0);
+        self.picker = Picker { items, cursor };
+        self.open_dialog(Dialog::ProfilePicker);
+    }
+
+    pub fn picked_profile(&self) -> Option<&Profile> {
+        self.config.profiles.get(self.picker.cursor)
+    }
+
+    /// Sections of the global agenda: the agenda's own, with Not Started
+    /// only while it is expanded here.
+    pub fn global_agenda_sections(&self) -> Vec<TaskState> {
+        let mut sections = self.config.agenda.sections();
+        if !self.show_todo {
+            sections.retain(|&s| s != TaskState::Todo);
+        }
+        sections
+    }
+
+    /// Show the agenda of every profile (`E`), gathered by the caller.
+    pub fn open_global_agenda(&mut self, items: Vec<GlobalItem>) {
+        if items.is_empty() {
+            self.status_msg = "Nothing on the agenda of any profile".to_string();
+            return;
+        }
+        let items_text = items.iter().map(|item| format!("[{}] {}", item.source, item.task.text)).collect();
+        self.picker = Picker { items: items_text, cursor: 0 };
+        self.global_agenda = items;
+        self.open_dialog(Dialog::GlobalAgenda);
+    }
+
+    pub fn close_global_agenda(&mut self) {
+        self.global_agenda.clear();
+        self.close_dialog();
+    }
+
+    /// Start over on another file's document, keeping preferences and the
+    /// recent files list.
+    pub fn switch_document(&mut self, doc: Document, file_path: PathBuf) {
+        let mut next = App::new(doc, file_path);
+        next.config = self.config.clone();
+        next.config_path = self.config_path.take();
+        next.force_accessible = self.force_accessible;
+        next.theme_index = self.theme_index;
+        next.recent_files = std::mem::take(&mut self.recent_files);
+        next.recent_path = self.recent_path.take();
+        next.rebuild_agenda();
+        *self = next;
+    }
+
+    /// A path typed in `:export` or `:import`: `~/` is the home directory and
+    /// relative paths are next to the open file.
+    pub fn resolve_path(&self, input: &str) -> PathBuf {
+        let path = PathBuf::from(input);
+        if let (Ok(rest), Some(home)) = (path.strip_prefix("~"), dirs::home_dir()) {
+            return home.join(rest);
+        }
+        match self.file_path.parent() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path,
+        }
+    }
+
+    /// The focused backlog category, or the project of the focused row, as a
+    /// standalone document, with its name.
+    pub fn focused_subtree(&self) -> Option<(String, Document)> {
+        if self.view != View::Backlog {
+            return None;
+        }
+        let (ci, pi) = match self.current_tree_node()?.kind {
+            TreeNodeKind::Category { cat_idx } => (cat_idx, None),
+            _ => self.focused_project_idx().map(|(ci, pi)| (ci, Some(pi)))?,
         };
-        *scroll = cursor.saturating_sub(visible_height / 2);
+        let doc = subtree::extract(&self.doc, &self.activity, ci, pi)?;
+        let category = &doc.categories[0];
+        let name = match pi {
+            Some(_) => category.projects[0].name.clone(),
+            None => category.name.clone(),
+        };
+        Some((name, doc))
     }
 
-    pub fn cycle_view(&mut self) {
-        self.view = match self.view {
-            View::Agenda => View::Backlog,
-            View::Backlog => View::Settings,
-            View::Settings => View::Agenda,
+    /// The Backlog task (or the task of a note) or project under the cursor,
+    /// split off for `:move`.
+    pub fn focused_move(&self) -> Option<Move> {
+        if self.view != View::Backlog {
+            return None;
+        }
+        let mut remaining = self.doc.clone();
+        let (ci, pi, task_idx) = match self.current_tree_node()?.kind {
+            TreeNodeKind::Project { cat_idx, proj_idx } => (cat_idx, proj_idx, None),
+            TreeNodeKind::Task { cat_idx, proj_idx, task_idx } | TreeNodeKind::Note { cat_idx, proj_idx, task_idx, .. } => {
+                (cat_idx, proj_idx, Some(task_idx))
+            }
+            _ => return None,
+        };
+        let category = self.doc.categories.get(ci)?;
+        let mut project = category.projects.get(pi)?.clone();
+        let label = match task_idx {
+            Some(ti) => {
+                let task = project.tasks.get(ti)?.clone();
+                engine::delete_task(&mut remaining, ci, pi, ti);
+                let label = format!("task \"{}\"", task.text);
+                project.tasks = vec![task];
+                project.notes.clear();
+                label
+            }
+            None => {
+                engine::delete_project(&mut remaining, ci, pi);
+                format!("project \"{}\"", project.name)
+            }
+        };
+        let piece = Document {
+            categories: vec![Category {
+                name: category.name.clone(),
+                hidden: category.hidden,
+                notes: Vec::new(),
+                projects: vec![project],
+            }],
+            ..Document::new()
+        };
+        // Promote now so the file written is the document shown afterwards
+        if self.config.agenda.auto_promote.on_change() {
+            engine::auto_promote(&mut remaining, self.config.agenda.promote);
+        }
+        Some(Move { label, piece, remaining })
+    }
+
+    /// Files to offer as `:move` targets: the profiles' files, then recent
+    /// files, without the open one.
+    pub fn move_targets(&self) -> Vec<PathBuf> {
+        let mut targets: Vec<PathBuf> = Vec::new();
+        let candidates = self.config.profiles.iter().map(|p| p.file()).chain(self.recent_files.iter().cloned());
+        for path in candidates {
+            if path != self.file_path && !targets.contains(&path) {
+                targets.push(path);
+            }
+        }
+        targets
+    }
+
+    /// Pick the file to move the focused task or project to.
+    pub fn open_move_picker(&mut self) {
+        if self.focused_move().is_none() {
+            self.status_msg = "Select a task or project in the Backlog to move".to_string();
+            return;
+        }
+        let items: Vec<String> = self.move_targets().iter().map(|p| p.display().to_string()).collect();
+        if items.is_empty() {
+            self.status_msg = "No other files to move to: use :move <file>".to_string();
+            return;
+        }
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::MoveTarget);
+    }
+
+    pub fn picked_move_target(&self) -> Option<PathBuf> {
+        self.move_targets().get(self.picker.cursor).cloned()
+    }
+
+    /// The move was written to both files: carry on without the item. Undo
+    /// can't bring it back without duplicating it in the other file, so the
+    /// undo history goes.
+    pub fn finish_move(&mut self, moved: Move, target: &std::path::Path) {
+        self.doc = moved.remaining;
+        self.dirty = false;
+        self.undo_stack.clear();
+        self.status_msg = format!("Moved {} to {}", moved.label, target.display());
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    /// Merge a document read by `:import` into this one.
+    pub fn import_document(&mut self, other: Document, source: &str) {
+        let before = self.doc.clone();
+        let merged = subtree::merge(&mut self.doc, other);
+        if merged == subtree::Merged::default() {
+            self.status_msg = format!("Nothing new to import from {}", source);
+            return;
+        }
+        self.push_undo(before);
+        self.dirty = true;
+        self.status_msg = format!(
+            "Imported {} task{} ({} new project{}) from {}",
+            merged.tasks,
+            if merged.tasks == 1 { "" } else { "s" },
+            merged.projects,
+            if merged.projects == 1 { "" } else { "s" },
+            source
+        );
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
+    /// The file changed on disk since it was loaded or saved: ask whether to
+    /// merge, overwrite or reload before writing.
+    pub fn offer_save_conflict(&mut self, quitting: bool) {
+        self.quit_pending = quitting;
+        self.status_msg = "The file changed on disk since it was loaded".to_string();
+        self.open_dialog(Dialog::SaveConflict);
+    }
+
+    /// Report a failed file read or write; the session carries on with the
+    /// document as it is. After a failed save the document stays dirty and
+    /// autosave waits for a retry or an explicit `s` rather than failing
+    /// again. A dialog the user is busy in is left open, with the error in
+    /// the status bar instead.
+    pub fn show_io_error(&mut self, message: String, retry: Retry) {
+        if retry == Retry::Save {
+            self.hold_autosave = true;
+            self.quit_pending = false;
+        }
+        if !matches!(self.dialog, Dialog::None | Dialog::Error) {
+            self.status_msg = message;
+            return;
+        }
+        self.status_msg = match retry {
+            Retry::Save => "Not saved".to_string(),
+            Retry::Reload => "Not reloaded".to_string(),
         };
+        self.io_error = Some(IoError { message, retry });
+        self.open_dialog(Dialog::Error);
+    }
+
+    pub fn close_error(&mut self) {
+        self.io_error = None;
+        self.close_dialog();
+    }
+
+    pub fn cancel_save_conflict(&mut self) {
+        self.close_dialog();
+        self.quit_pending = false;
+        self.hold_autosave = true;
+        self.status_msg = "Not saved: the file changed on disk (s to decide)".to_string();
+    }
+
+    /// Fold the version on disk into the document before saving over it, so
+    /// tasks and projects added there are kept alongside the changes here.
+    pub fn merge_from_disk(&mut self, disk: Document) {
+        let before = self.doc.clone();
+        let merged = subtree::merge(&mut self.doc, disk);
+        if merged != subtree::Merged::default() {
+            self.push_undo(before);
+        }
+        self.status_msg = format!(
+            "Merged {} task{} from the file on disk",
+            merged.tasks,
+            if merged.tasks == 1 { "" } else { "s" }
+        );
+        self.refresh_agenda();
+        self.rebuild_tree();
     }
 
-    /// Switch from Agenda to Backlog view, focusing on the same task.
-    pub fn jump_to_backlog_task(&mut self) {
-        let Some(item) = self.agenda_items.get(self.agenda_cursor) else {
-            return;
-        };
-        let cat_idx = item.category_idx;
-        let proj_idx = item.project_idx;
-        let task_idx = item.task_idx;
+    // --- Plan my day ---
 
-        // Ensure parent category and project are expanded so the task is visible
-        self.collapse.collapsed_categories.remove(&cat_idx);
-        self.collapse.collapsed_projects.remove(&(cat_idx, proj_idx));
-        self.rebuild_tree();
+    /// Propose a Today list filling the configured hours.
+    pub fn open_day_plan(&mut self) {
+        let hours = self.config.planner.hours;
+        self.day_plan = Some(DayPlan {
+            plan: Plan::default(),
+            skipped: Vec::new(),
+            cursor: 0,
+            hours,
+        });
+        self.replan();
+        self.open_dialog(Dialog::PlanDay);
+    }
 
-        // Find the matching task node in the tree
-        let target = TreeNodeKind::Task { cat_idx, proj_idx, task_idx };
-        for (i, node) in self.tree_nodes.iter().enumerate() {
-            if node.kind == target {
-                self.backlog_cursor = i;
-                break;
+    fn replan(&mut self) {
+        if let Some(day) = &mut self.day_plan {
+            day.plan = planner::plan_day(&self.doc, day.hours * 60, &day.skipped, Date::today());
+            day.cursor = day.cursor.min(day.plan.items.len().saturating_sub(1));
+        }
+    }
+
+    pub fn plan_move_cursor(&mut self, direction: i32) {
+        if let Some(day) = &mut self.day_plan {
+            let len = day.plan.items.len();
+            if len > 0 {
+                day.cursor = (day.cursor as i32 + direction).rem_euclid(len as i32) as usize;
             }
         }
+    }
 
-        self.view = View::Backlog;
-        self.update_scroll(self.visible_height);
+    /// Take the highlighted task out and refill its time.
+    pub fn plan_drop(&mut self) {
+        if let Some(day) = &mut self.day_plan
+            && let Some(item) = day.plan.items.get(day.cursor)
+        {
+            day.skipped.push(item.task);
+        }
+        self.replan();
     }
 
-    // --- Backlog: toggle collapse ---
+    /// Start over from the document, bringing back dropped tasks.
+    pub fn plan_regenerate(&mut self) {
+        if let Some(day) = &mut self.day_plan {
+            day.skipped.clear();
+        }
+        self.replan();
+        self.status_msg = "Plan regenerated".to_string();
+    }
 
-    pub fn toggle_collapse(&mut self) {
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            match &node.kind {
-                TreeNodeKind::Category { cat_idx } => {
-                    let cat_idx = *cat_idx;
-                    if !self.collapse.collapsed_categories.remove(&cat_idx) {
-                        self.collapse.collapsed_categories.insert(cat_idx);
-                    }
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    let key = (*cat_idx, *proj_idx);
-                    if !self.collapse.collapsed_projects.remove(&key) {
-                        self.collapse.collapsed_projects.insert(key);
-                    }
-                }
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    let key = (*cat_idx, *proj_idx, *task_idx);
-                    if !self.collapse.collapsed_tasks.remove(&key) {
-                        self.collapse.collapsed_tasks.insert(key);
-                    }
-                }
-                TreeNodeKind::Note { .. } => {} // notes can't collapse
-            }
-            // Save the kind before rebuild so we can restore cursor
-            let saved_kind = node.kind.clone();
-            self.rebuild_tree();
-            self.restore_cursor(&saved_kind);
+    pub fn plan_adjust_hours(&mut self, delta: i32) {
+        if let Some(day) = &mut self.day_plan {
+            day.hours = (day.hours as i32 + delta).clamp(1, 24) as u32;
         }
+        self.replan();
     }
 
-    /// Restore cursor to the node matching the given kind after a rebuild.
-    fn restore_cursor(&mut self, kind: &TreeNodeKind) {
-        for (i, node) in self.tree_nodes.iter().enumerate() {
-            if node.kind == *kind {
-                self.backlog_cursor = i;
-                return;
+    /// Today's plan as `W` would first propose it, for `:ics`.
+    pub fn todays_plan(&self) -> Plan {
+        planner::plan_day(&self.doc, self.config.planner.hours * 60, &[], Date::today())
+    }
+
+    /// Accept the plan: its not-started tasks go On Deck so the agenda shows
+    /// the whole Today list.
+    pub fn accept_day_plan(&mut self) {
+        let Some(day) = self.day_plan.take() else {
+            return;
+        };
+        let before = self.doc.clone();
+        let mut promoted = 0;
+        for item in &day.plan.items {
+            let (ci, pi, ti) = item.task;
+            let task = &mut self.doc.categories[ci].projects[pi].tasks[ti];
+            if task.state == TaskState::Todo {
+                task.state = TaskState::OnDeck;
+                promoted += 1;
             }
         }
-        // Fallback: clamp
-        if !self.tree_nodes.is_empty() && self.backlog_cursor >= self.tree_nodes.len() {
-            self.backlog_cursor = self.tree_nodes.len() - 1;
+        if promoted > 0 {
+            self.push_undo(before);
+            self.dirty = true;
+            self.refresh_agenda();
+            self.rebuild_tree();
         }
+        self.status_msg = format!(
+            "Planned {} task{} ({} of {}h)",
+            day.plan.items.len(),
+            if day.plan.items.len() == 1 { "" } else { "s" },
+            planner::format_minutes(day.plan.total_minutes()),
+            day.hours
+        );
     }
 
-    // --- Backlog: current node ---
-
-    pub fn current_tree_node(&self) -> Option<&TreeNode> {
-        self.tree_nodes.get(self.backlog_cursor)
+    pub fn cancel_day_plan(&mut self) {
+        self.day_plan = None;
     }
 
-    // --- Mutations from Agenda view ---
+    // --- Inbox triage ---
 
-    pub fn promote_selected_agenda(&mut self) {
-        if let Some(item) = self.agenda_items.get(self.agenda_cursor) {
-            let ci = item.category_idx;
-            let pi = item.project_idx;
-            let ti = item.task_idx;
-            if engine::promote_task(&mut self.doc, ci, pi, ti) {
-                self.dirty = true;
-                self.status_msg = "Task promoted".to_string();
-                // Rebuild agenda without auto_promote to preserve the manual state change
-                self.rebuild_agenda();
-                self.rebuild_tree();
-                self.find_agenda_cursor(ci, pi, ti);
-            }
+    /// Step through the inbox's open tasks one at a time.
+    pub fn start_triage(&mut self) {
+        let Some((cat_idx, proj_idx)) = self.doc.inbox() else {
+            self.status_msg = "No inbox: add a project or category named Inbox".to_string();
+            return;
+        };
+        self.triage = Some(Triage { cat_idx, proj_idx, task_idx: 0, handled: 0, unsorted: false });
+        self.advance_triage();
+        if self.triage.is_some() {
+            self.open_dialog(Dialog::Triage);
         }
     }
 
-    pub fn demote_selected_agenda(&mut self) {
-        if let Some(item) = self.agenda_items.get(self.agenda_cursor) {
-            let ci = item.category_idx;
-            let pi = item.project_idx;
-            let ti = item.task_idx;
-            if engine::demote_task(&mut self.doc, ci, pi, ti) {
-                self.dirty = true;
-                self.status_msg = "Task demoted".to_string();
-                // Rebuild agenda without auto_promote to preserve the manual state change
-                self.rebuild_agenda();
-                self.rebuild_tree();
-                self.find_agenda_cursor(ci, pi, ti);
-            }
+    /// Triage the tasks the parser found outside any project.
+    pub fn start_unsorted_triage(&mut self) {
+        let Some(&(cat_idx, proj_idx)) = self.doc.unsorted_projects().first() else {
+            self.status_msg = "No stray tasks to triage".to_string();
+            return;
+        };
+        self.triage = Some(Triage { cat_idx, proj_idx, task_idx: 0, handled: 0, unsorted: true });
+        self.advance_triage();
+        if self.triage.is_some() {
+            self.open_dialog(Dialog::Triage);
         }
     }
 
-    /// Find an agenda item by its task address and move cursor to it.
-    fn find_agenda_cursor(&mut self, cat_idx: usize, proj_idx: usize, task_idx: usize) {
-        for (i, item) in self.agenda_items.iter().enumerate() {
-            if item.category_idx == cat_idx && item.project_idx == proj_idx && item.task_idx == task_idx {
-                self.agenda_cursor = i;
-                return;
-            }
+    /// After loading a file with task lines outside any project, say so and
+    /// offer to triage the Unsorted projects they were collected into.
+    pub fn offer_unsorted_triage(&mut self, stray_tasks: usize) {
+        if stray_tasks == 0 || self.doc.unsorted_projects().is_empty() || self.dialog != Dialog::None {
+            return;
         }
-        if !self.agenda_items.is_empty() && self.agenda_cursor >= self.agenda_items.len() {
-            self.agenda_cursor = self.agenda_items.len() - 1;
+        self.status_msg = format!(
+            "Found {} task{} outside any project; collected into Unsorted",
+            stray_tasks,
+            if stray_tasks == 1 { "" } else { "s" }
+        );
+        self.open_dialog(Dialog::ConfirmTriageUnsorted);
+    }
+
+    /// "Inbox", or "Unsorted" when triaging stray tasks.
+    pub fn triage_label(&self) -> &'static str {
+        match &self.triage {
+            Some(t) if t.unsorted => UNSORTED_PROJECT,
+            _ => "Inbox",
         }
     }
 
-    // --- Mutations from Backlog view ---
+    /// The inbox task under review.
+    pub fn triage_task(&self) -> Option<&Task> {
+        let t = self.triage.as_ref()?;
+        self.doc.categories.get(t.cat_idx)?.projects.get(t.proj_idx)?.tasks.get(t.task_idx)
+    }
 
-    pub fn promote_selected_backlog(&mut self) {
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            let saved_kind = node.kind.clone();
-            match &node.kind {
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    if engine::promote_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx) {
-                        self.dirty = true;
-                        self.status_msg = "Task promoted".to_string();
-                    }
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    if engine::toggle_project_active(&mut self.doc, *cat_idx, *proj_idx) {
-                        self.dirty = true;
-                        let active = self.doc.categories[*cat_idx].projects[*proj_idx].active;
-                        self.status_msg = if active { "Project activated".to_string() } else { "Project deactivated".to_string() };
-                    }
-                }
-                _ => {}
-            }
-            self.refresh_agenda();
-            self.rebuild_tree();
-            self.restore_cursor(&saved_kind);
-        }
+    /// Open inbox tasks from the one under review onwards.
+    pub fn triage_remaining(&self) -> usize {
+        let Some(t) = &self.triage else {
+            return 0;
+        };
+        let tasks = self.doc.categories.get(t.cat_idx).and_then(|c| c.projects.get(t.proj_idx)).map(|p| &p.tasks[..]);
+        tasks
+            .and_then(|tasks| tasks.get(t.task_idx..))
+            .map_or(0, |rest| rest.iter().filter(|task| task.state != TaskState::Done).count())
     }
 
-    pub fn demote_selected_backlog(&mut self) {
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            let saved_kind = node.kind.clone();
-            match &node.kind {
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    if engine::demote_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx) {
-                        self.dirty = true;
-                        self.status_msg = "Task demoted".to_string();
-                    }
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    if engine::toggle_project_active(&mut self.doc, *cat_idx, *proj_idx) {
-                        self.dirty = true;
-                        let active = self.doc.categories[*cat_idx].projects[*proj_idx].active;
-                        self.status_msg = if active { "Project activated".to_string() } else { "Project deactivated".to_string() };
-                    }
+    /// Skip Done tasks; finish when the inbox runs out. Unsorted triage
+    /// carries on with the next Unsorted project.
+    fn advance_triage(&mut self) {
+        let label = self.triage_label();
+        let unsorted = self.doc.unsorted_projects();
+        let Some(t) = &mut self.triage else {
+            return;
+        };
+        loop {
+            // The inbox may have gone away in a reload
+            let tasks = match self.doc.categories.get(t.cat_idx).and_then(|c| c.projects.get(t.proj_idx)) {
+                Some(project) => &project.tasks[..],
+                None => &[],
+            };
+            while tasks.get(t.task_idx).is_some_and(|task| task.state == TaskState::Done) {
+                t.task_idx += 1;
+            }
+            if t.task_idx < tasks.len() {
+                return;
+            }
+            let next = unsorted.iter().find(|&&project| project > (t.cat_idx, t.proj_idx));
+            match next {
+                Some(&(ci, pi)) if t.unsorted => {
+                    (t.cat_idx, t.proj_idx, t.task_idx) = (ci, pi, 0);
                 }
-                _ => {}
+                _ => break,
             }
-            self.refresh_agenda();
-            self.rebuild_tree();
-            self.restore_cursor(&saved_kind);
         }
+        let handled = t.handled;
+        if !t.unsorted {
+            self.last_review = Some(Date::today());
+        }
+        self.stop_triage();
+        self.status_msg = match handled {
+            0 => format!("{} is empty", label),
+            n => format!("{} triaged: {} task{} handled", label, n, if n == 1 { "" } else { "s" }),
+        };
     }
 
-    // --- Global mutations ---
-
-    pub fn run_auto_promote(&mut self) {
-        engine::auto_promote(&mut self.doc);
-        self.dirty = true;
-        self.status_msg = "Auto-promote complete".to_string();
-        self.refresh_agenda();
-        self.rebuild_tree();
+    pub fn stop_triage(&mut self) {
+        self.triage = None;
+        self.close_dialog();
     }
 
-    pub fn archive_done(&mut self) {
-        engine::archive_done(&mut self.doc);
+    /// Record a triage decision. `kept` means the task stayed in the inbox and
+    /// the review moves past it; otherwise the next task slid into its place.
+    fn finish_triage_step(&mut self, before: Document, kept: bool, msg: String) {
+        self.push_undo(before);
         self.dirty = true;
-        self.status_msg = "Done tasks archived".to_string();
+        self.status_msg = msg;
+        if let Some(t) = &mut self.triage {
+            t.handled += 1;
+            if kept {
+                t.task_idx += 1;
+            }
+        }
         self.refresh_agenda();
         self.rebuild_tree();
+        self.advance_triage();
     }
 
-    // --- Backlog: add task ---
-
-    pub fn add_task_to_focused(&mut self) {
-        let text = self.input_buffer.trim().to_string();
-        if text.is_empty() {
-            return;
-        }
+    fn triage_address(&self) -> Option<(usize, usize, usize)> {
+        self.triage.as_ref().map(|t| (t.cat_idx, t.proj_idx, t.task_idx))
+    }
 
-        // Figure out where to add based on current backlog focus
-        let (cat_idx, proj_idx) = if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            match &node.kind {
-                TreeNodeKind::Category { cat_idx: _ } => {
-                    // Adding a task to category doesn't make sense; this should be add project
-                    return;
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => (*cat_idx, *proj_idx),
-                TreeNodeKind::Task { cat_idx, proj_idx, .. } => (*cat_idx, *proj_idx),
-                TreeNodeKind::Note { cat_idx, proj_idx, .. } => (*cat_idx, *proj_idx),
-            }
-        } else {
+    /// Do it now: promote the task so it reaches the agenda.
+    pub fn triage_do_now(&mut self) {
+        let Some((ci, pi, ti)) = self.triage_address() else {
             return;
         };
+        let before = self.doc.clone();
+        if engine::promote_task(&mut self.doc, ci, pi, ti) {
+            self.finish_triage_step(before, true, "Promoted".to_string());
+        }
+    }
 
-        if engine::add_task(&mut self.doc, cat_idx, proj_idx, text) {
-            self.dirty = true;
-            self.status_msg = "Task added".to_string();
-            self.refresh_agenda();
-            self.rebuild_tree();
+    /// Leave the task in the inbox and look at the next one.
+    pub fn triage_skip(&mut self) {
+        if let Some(t) = &mut self.triage {
+            t.task_idx += 1;
         }
+        self.advance_triage();
     }
 
-    pub fn add_project_to_focused(&mut self) {
-        let name = self.input_buffer.trim().to_string();
-        if name.is_empty() {
+    /// Defer: set a due date from the input (`YYYY-MM-DD`, `tomorrow`, `+3`,
+    /// `+2w`, `friday`, `+3b` for business days).
+    pub fn triage_defer_from_input(&mut self) {
+        let Some((ci, pi, ti)) = self.triage_address() else {
             return;
-        }
+        };
+        let Some(date) = Date::parse_relative_in(&self.input_buffer, Date::today(), &self.config.calendar.calendar()) else {
+            self.status_msg = format!("Not a date: {}", self.input_buffer.trim());
+            return;
+        };
+        let before = self.doc.clone();
+        self.doc.categories[ci].projects[pi].tasks[ti].set_due(date);
+        self.finish_triage_step(before, true, format!("Deferred to {}", date));
+    }
 
-        let cat_idx = if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            match &node.kind {
-                TreeNodeKind::Category { cat_idx } => *cat_idx,
-                TreeNodeKind::Project { cat_idx, .. } => *cat_idx,
-                TreeNodeKind::Task { cat_idx, .. } => *cat_idx,
-                TreeNodeKind::Note { cat_idx, .. } => *cat_idx,
-            }
-        } else {
+    /// Delegate: mention the person the task is waiting on.
+    pub fn triage_delegate_from_input(&mut self) {
+        let Some((ci, pi, ti)) = self.triage_address() else {
             return;
         };
+        let person = self.input_buffer.trim().trim_start_matches('@').to_string();
+        if person.is_empty() {
+            return;
+        }
+        let before = self.doc.clone();
+        let task = &mut self.doc.categories[ci].projects[pi].tasks[ti];
+        if !task.mentions(&person) {
+            task.text = format!("{} @{}", task.text, person);
+        }
+        self.finish_triage_step(before, true, format!("Delegated to @{}", person));
+    }
 
-        if engine::add_project(&mut self.doc, cat_idx, name, true) {
-            self.dirty = true;
-            self.status_msg = "Project added".to_string();
-            self.refresh_agenda();
-            self.rebuild_tree();
+    /// Projects the task can be moved to: every project but the inbox.
+    fn triage_move_targets(&self) -> Vec<(usize, usize)> {
+        let inbox = self.triage.as_ref().map(|t| (t.cat_idx, t.proj_idx));
+        self.doc
+            .categories
+            .iter()
+            .enumerate()
+            .flat_map(|(ci, c)| (0..c.projects.len()).map(move |pi| (ci, pi)))
+            .filter(|&target| Some(target) != inbox)
+            .collect()
+    }
+
+    pub fn open_triage_move_picker(&mut self) {
+        let items: Vec<String> = self
+            .triage_move_targets()
+            .into_iter()
+            .map(|(ci, pi)| format!("{} › {}", self.doc.categories[ci].name, self.doc.categories[ci].projects[pi].name))
+            .collect();
+        if items.is_empty() {
+            self.status_msg = "No other projects to move to".to_string();
+            return;
         }
+        self.picker = Picker { items, cursor: 0 };
+        self.open_dialog(Dialog::TriageMove);
     }
 
-    // --- Backlog: edit ---
+    pub fn apply_triage_move(&mut self) {
+        let target = self.triage_move_targets().get(self.picker.cursor).copied();
+        if let Some(target) = target {
+            self.triage_move_to(self.doc.clone(), target);
+        }
+    }
 
-    pub fn apply_edit(&mut self) {
-        let new_text = self.input_buffer.trim().to_string();
-        if new_text.is_empty() {
+    fn triage_move_to(&mut self, before: Document, (ci, pi): (usize, usize)) {
+        let Some(from) = self.triage_address() else {
             return;
+        };
+        if engine::move_task_to_project(&mut self.doc, from, (ci, pi)).is_some() {
+            let msg = format!("Moved to {}", self.doc.categories[ci].projects[pi].name);
+            self.finish_triage_step(before, false, msg);
         }
+    }
 
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            let saved_kind = node.kind.clone();
-            match &node.kind {
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    if engine::rename_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx, new_text) {
-                        self.dirty = true;
-                        self.status_msg = "Task renamed".to_string();
-                    }
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    if engine::rename_project(&mut self.doc, *cat_idx, *proj_idx, new_text) {
-                        self.dirty = true;
-                        self.status_msg = "Project renamed".to_string();
-                    }
-                }
-                TreeNodeKind::Category { cat_idx } => {
-                    if engine::rename_category(&mut self.doc, *cat_idx, new_text) {
-                        self.dirty = true;
-                        self.status_msg = "Category renamed".to_string();
-                    }
-                }
-                TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
-                    if let Some(note) = self.doc.categories
-                        .get_mut(*cat_idx)
-                        .and_then(|c| c.projects.get_mut(*proj_idx))
-                        .and_then(|p| p.tasks.get_mut(*task_idx))
-                        .and_then(|t| t.notes.get_mut(*note_idx))
-                    {
-                        *note = format!("  {}", new_text);
-                        self.dirty = true;
-                        self.status_msg = "Note updated".to_string();
-                    }
-                }
+    /// Someday/maybe: move the task to a project whose name starts with
+    /// "Someday", creating an inactive "Someday" project next to the inbox.
+    pub fn triage_someday(&mut self) {
+        let Some(t) = self.triage.clone() else {
+            return;
+        };
+        let before = self.doc.clone();
+        let existing = self.doc.categories.iter().enumerate().find_map(|(ci, c)| {
+            c.projects
+                .iter()
+                .position(|p| p.name.to_lowercase().starts_with("someday"))
+                .map(|pi| (ci, pi))
+        });
+        let target = match existing {
+            Some(target) => target,
+            None => {
+                engine::add_project(&mut self.doc, t.cat_idx, "Someday".to_string(), false);
+                (t.cat_idx, self.doc.categories[t.cat_idx].projects.len() - 1)
             }
-            self.refresh_agenda();
-            self.rebuild_tree();
-            self.restore_cursor(&saved_kind);
+        };
+        self.triage_move_to(before, target);
+    }
+
+    pub fn triage_delete(&mut self) {
+        let Some((ci, pi, ti)) = self.triage_address() else {
+            return;
+        };
+        let before = self.doc.clone();
+        if engine::delete_task(&mut self.doc, ci, pi, ti) {
+            self.finish_triage_step(before, false, "Task deleted".to_string());
         }
     }
 
-    // --- Backlog: delete ---
+    // --- Backlog: merge tasks ---
 
-    pub fn delete_focused(&mut self) {
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            match &node.kind {
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    engine::delete_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx);
-                    self.dirty = true;
-                    self.status_msg = "Task deleted".to_string();
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    engine::delete_project(&mut self.doc, *cat_idx, *proj_idx);
-                    self.dirty = true;
-                    self.status_msg = "Project deleted".to_string();
-                }
-                TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
-                    engine::delete_task_note(&mut self.doc, *cat_idx, *proj_idx, *task_idx, *note_idx);
-                    self.dirty = true;
-                    self.status_msg = "Note deleted".to_string();
-                }
-                _ => {}
-            }
+    /// `J` on a task: mark it for merging, or merge the marked task into it.
+    pub fn mark_or_merge(&mut self) {
+        let Some(TreeNodeKind::Task { cat_idx, proj_idx, task_idx }) = self.current_tree_node().map(|n| n.kind.clone())
+        else {
+            self.status_msg = "Merge works on tasks".to_string();
+            return;
+        };
+        let text = self.doc.categories[cat_idx].projects[proj_idx].tasks[task_idx].text.clone();
+
+        let Some(mark) = self.merge_mark.take() else {
+            self.status_msg = format!("Merging \"{}\": press J on the task to merge it into (Esc cancels)", text);
+            self.merge_mark = Some(MergeMark { cat_idx, proj_idx, task_idx, text });
+            return;
+        };
+        let from = (mark.cat_idx, mark.proj_idx, mark.task_idx);
+        let into = (cat_idx, proj_idx, task_idx);
+        if from == into {
+            self.status_msg = "Merge cancelled".to_string();
+            return;
+        }
+        let still_there = self
+            .doc
+            .categories
+            .get(mark.cat_idx)
+            .and_then(|c| c.projects.get(mark.proj_idx))
+            .and_then(|p| p.tasks.get(mark.task_idx))
+            .is_some_and(|t| t.text == mark.text);
+        if !still_there {
+            self.status_msg = "Marked task has moved; merge cancelled".to_string();
+            return;
+        }
+
+        let before = self.doc.clone();
+        if let Some(merged_idx) = engine::merge_tasks(&mut self.doc, from, into) {
+            self.push_undo(before);
+            self.dirty = true;
+            self.status_msg = format!("Merged \"{}\" into \"{}\"", mark.text, text);
             self.refresh_agenda();
             self.rebuild_tree();
+            self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: merged_idx });
         }
     }
 
-    // --- Backlog: rerank ---
-
-    pub fn rerank_focused(&mut self, direction: i32) {
-        if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            let new_kind = match &node.kind {
-                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    if let Some(new_idx) = engine::rerank_task(&mut self.doc, *cat_idx, *proj_idx, *task_idx, direction) {
-                        self.dirty = true;
-                        Some(TreeNodeKind::Task { cat_idx: *cat_idx, proj_idx: *proj_idx, task_idx: new_idx })
-                    } else {
-                        None
-                    }
-                }
-                TreeNodeKind::Project { cat_idx, proj_idx } => {
-                    if let Some(new_idx) = engine::rerank_project(&mut self.doc, *cat_idx, *proj_idx, direction) {
-                        self.dirty = true;
-                        Some(TreeNodeKind::Project { cat_idx: *cat_idx, proj_idx: new_idx })
-                    } else {
-                        // Move across categories
-                        let ci = *cat_idx;
-                        let pi = *proj_idx;
-                        if direction < 0 && ci > 0 {
-                            // Move to end of previous category
-                            let dest_len = self.doc.categories[ci - 1].projects.len();
-                            if let Some((new_ci, new_pi)) = engine::move_project_to_category(&mut self.doc, ci, pi, ci - 1, dest_len) {
-                                self.dirty = true;
-                                Some(TreeNodeKind::Project { cat_idx: new_ci, proj_idx: new_pi })
-                            } else {
-                                None
-                            }
-                        } else if direction > 0 && ci + 1 < self.doc.categories.len() {
-                            // Move to start of next category
-                            if let Some((new_ci, new_pi)) = engine::move_project_to_category(&mut self.doc, ci, pi, ci + 1, 0) {
-                                self.dirty = true;
-                                Some(TreeNodeKind::Project { cat_idx: new_ci, proj_idx: new_pi })
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        }
-                    }
-                }
-                _ => None,
-            };
-
-            if let Some(kind) = new_kind {
-                self.refresh_agenda();
-                self.rebuild_tree();
-                self.restore_cursor(&kind);
-            }
-        }
+    pub fn cancel_merge(&mut self) {
+        self.merge_mark = None;
+        self.status_msg = "Merge cancelled".to_string();
     }
 
     // --- Move mode ---
@@ -793,6 +3939,12 @@ impl App {
                                 original_proj_idx: *proj_idx,
                             })
                         }
+                        TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => Some(MoveKind::Note {
+                            cat_idx: *cat_idx,
+                            proj_idx: *proj_idx,
+                            task_idx: *task_idx,
+                            original_note_idx: *note_idx,
+                        }),
                         _ => None,
                     };
                     if let Some(k) = kind {
@@ -802,13 +3954,16 @@ impl App {
                 }
             }
             View::Settings => {
-                if let Some(cat_idx) = self.settings_category_idx() {
-                    if cat_idx < self.doc.categories.len() {
-                        self.moving = Some(MoveKind::Category {
-                            original_cat_idx: cat_idx,
-                        });
-                        self.status_msg = "Moving... j/k to reorder, Enter to accept, Esc to cancel".to_string();
-                    }
+                if let SettingsRow::AgendaSection(idx) = self.settings_row(self.settings_cursor) {
+                    self.moving = Some(MoveKind::AgendaSection { original_idx: idx });
+                    self.status_msg = "Moving... j/k to reorder, Enter to accept, Esc to cancel".to_string();
+                } else if let Some(cat_idx) = self.settings_category_idx()
+                    && cat_idx < self.doc.categories.len()
+                {
+                    self.moving = Some(MoveKind::Category {
+                        original_cat_idx: cat_idx,
+                    });
+                    self.status_msg = "Moving... j/k to reorder, Enter to accept, Esc to cancel".to_string();
                 }
             }
             View::Agenda => {
@@ -819,6 +3974,7 @@ impl App {
                     self.status_msg = "Moving... j/k to reorder, Enter to accept, Esc to cancel".to_string();
                 }
             }
+            View::Stats => {}
         }
     }
 
@@ -826,7 +3982,12 @@ impl App {
     pub fn move_step(&mut self, direction: i32) {
         match self.view {
             View::Agenda => self.rerank_agenda(direction),
+            View::Backlog if self.block_range().is_some() => self.block_step(direction),
             View::Backlog => self.rerank_focused(direction),
+            View::Stats => {}
+            View::Settings if matches!(self.moving, Some(MoveKind::AgendaSection { .. })) => {
+                self.rerank_agenda_section(direction)
+            }
             View::Settings => self.rerank_category(direction),
         }
     }
@@ -852,10 +4013,26 @@ impl App {
 
     /// Accept the current move (just exit move mode, changes already applied).
     pub fn accept_move(&mut self) {
+        if let Some(MoveKind::Block { .. }) = self.moving {
+            let Some(MoveKind::Block { anchor, cursor, before, .. }) = self.moving.take() else { return };
+            if *before != self.doc {
+                self.push_undo(*before);
+            }
+            self.status_msg = format!("Moved {}", tasks_text(anchor.abs_diff(cursor) + 1));
+            self.refresh_agenda();
+            return;
+        }
+        if let Some(MoveKind::AgendaSection { .. }) = self.moving {
+            self.moving = None;
+            self.status_msg = "Moved".to_string();
+            self.save_config();
+            return;
+        }
         if let Some(ref kind) = self.moving {
             let is_agenda = matches!(kind, MoveKind::AgendaItem { .. });
+            // Each step already marked the document changed; an agenda
+            // reorder only changes the view
             self.moving = None;
-            self.dirty = true;
             self.status_msg = "Moved".to_string();
             if !is_agenda {
                 self.refresh_agenda();
@@ -866,70 +4043,275 @@ impl App {
     /// Cancel the move and revert to the original position.
     pub fn cancel_move(&mut self) {
         let Some(move_kind) = self.moving.take() else { return };
+        let mut refocus = None;
 
         match move_kind {
             MoveKind::Task { cat_idx, proj_idx, original_task_idx } => {
                 // Find current position of the task from the tree cursor
-                if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-                    if let TreeNodeKind::Task { task_idx: current_idx, .. } = &node.kind {
-                        let current = *current_idx;
-                        if current != original_task_idx {
-                            if let Some(project) = self.doc.categories
-                                .get_mut(cat_idx)
-                                .and_then(|c| c.projects.get_mut(proj_idx))
-                            {
-                                let task = project.tasks.remove(current);
-                                project.tasks.insert(original_task_idx, task);
-                            }
-                        }
+                if let Some(node) = self.tree_nodes.get(self.backlog_cursor)
+                    && let TreeNodeKind::Task { task_idx: current_idx, .. } = &node.kind
+                {
+                    let current = *current_idx;
+                    if current != original_task_idx
+                        && let Some(project) = self.doc.categories
+                            .get_mut(cat_idx)
+                            .and_then(|c| c.projects.get_mut(proj_idx))
+                    {
+                        let task = project.tasks.remove(current);
+                        project.tasks.insert(original_task_idx, task);
                     }
                 }
             }
+            MoveKind::Note { cat_idx, proj_idx, task_idx, original_note_idx } => {
+                if let Some(&TreeNodeKind::Note { note_idx: current, .. }) = self.current_tree_node().map(|n| &n.kind)
+                    && let Some(task) = self.doc.categories
+                        .get_mut(cat_idx)
+                        .and_then(|c| c.projects.get_mut(proj_idx))
+                        .and_then(|p| p.tasks.get_mut(task_idx))
+                {
+                    let note = task.notes.remove(current);
+                    task.notes.insert(original_note_idx, note);
+                }
+                refocus = Some(TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx: original_note_idx });
+            }
             MoveKind::Project { original_cat_idx, original_proj_idx } => {
-                if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-                    if let TreeNodeKind::Project { cat_idx: current_cat, proj_idx: current_proj, .. } = &node.kind {
-                        let cur_cat = *current_cat;
-                        let cur_proj = *current_proj;
-                        if cur_cat != original_cat_idx || cur_proj != original_proj_idx {
-                            // Remove from current position, insert at original
-                            if let Some(category) = self.doc.categories.get_mut(cur_cat) {
-                                let proj = category.projects.remove(cur_proj);
-                                let dest = self.doc.categories.get_mut(original_cat_idx);
-                                if let Some(dest_cat) = dest {
-                                    let idx = original_proj_idx.min(dest_cat.projects.len());
-                                    dest_cat.projects.insert(idx, proj);
-                                }
+                if let Some(node) = self.tree_nodes.get(self.backlog_cursor)
+                    && let TreeNodeKind::Project { cat_idx: current_cat, proj_idx: current_proj, .. } = &node.kind
+                {
+                    let cur_cat = *current_cat;
+                    let cur_proj = *current_proj;
+                    if cur_cat != original_cat_idx || cur_proj != original_proj_idx {
+                        // Remove from current position, insert at original
+                        if let Some(category) = self.doc.categories.get_mut(cur_cat) {
+                            let proj = category.projects.remove(cur_proj);
+                            let dest = self.doc.categories.get_mut(original_cat_idx);
+                            if let Some(dest_cat) = dest {
+                                let idx = original_proj_idx.min(dest_cat.projects.len());
+                                dest_cat.projects.insert(idx, proj);
                             }
                         }
                     }
                 }
             }
             MoveKind::Category { original_cat_idx } => {
-                if let Some(current) = self.settings_category_idx() {
-                    if current != original_cat_idx {
-                        let cat = self.doc.categories.remove(current);
-                        self.doc.categories.insert(original_cat_idx, cat);
-                    }
+                if let Some(current) = self.settings_category_idx()
+                    && current != original_cat_idx
+                {
+                    let cat = self.doc.categories.remove(current);
+                    self.doc.categories.insert(original_cat_idx, cat);
                 }
-                self.settings_cursor = original_cat_idx + 1; // +1 for theme row
+                self.settings_cursor = self.settings_row_index(SettingsRow::Category(original_cat_idx));
+            }
+            MoveKind::AgendaSection { original_idx } => {
+                if let SettingsRow::AgendaSection(current) = self.settings_row(self.settings_cursor) {
+                    let state = self.config.agenda.order.remove(current);
+                    self.config.agenda.order.insert(original_idx, state);
+                }
+                self.settings_cursor = self.settings_row_index(SettingsRow::AgendaSection(original_idx));
             }
             MoveKind::AgendaItem { original_idx } => {
                 // refresh_agenda() below rebuilds from doc, restoring original order
                 self.agenda_cursor = original_idx;
             }
+            MoveKind::Block { cat_idx, proj_idx, origin, before, .. } => {
+                self.doc = *before;
+                refocus = Some(TreeNodeKind::Task { cat_idx, proj_idx, task_idx: origin });
+            }
         }
 
         self.status_msg = "Move cancelled".to_string();
         self.refresh_agenda();
         self.rebuild_tree();
+        if let Some(kind) = refocus {
+            self.restore_cursor(&kind);
+        }
     }
 
     pub fn is_moving(&self) -> bool {
         self.moving.is_some()
     }
 
+    // --- Move mode: block selection ---
+
+    /// `V` while moving a task: select from it to the tasks j/k go to, to
+    /// move, promote or delete them together. Steps already taken are undone.
+    pub fn start_block_selection(&mut self) {
+        let Some(MoveKind::Task { cat_idx, proj_idx, original_task_idx }) = self.moving else {
+            return;
+        };
+        self.cancel_move();
+        self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: original_task_idx });
+        self.moving = Some(MoveKind::Block {
+            cat_idx,
+            proj_idx,
+            anchor: original_task_idx,
+            cursor: original_task_idx,
+            origin: original_task_idx,
+            grabbed: false,
+            before: Box::new(self.doc.clone()),
+        });
+        self.status_msg = "Selecting... j/k to extend, m to move, p/x to cycle, d to delete, Esc to cancel".to_string();
+    }
+
+    /// The selected block as (category, project, first task, last task).
+    pub fn block_range(&self) -> Option<(usize, usize, usize, usize)> {
+        match self.moving {
+            Some(MoveKind::Block { cat_idx, proj_idx, anchor, cursor, .. }) => {
+                Some((cat_idx, proj_idx, anchor.min(cursor), anchor.max(cursor)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the block has been picked up with `m`.
+    pub fn block_grabbed(&self) -> bool {
+        matches!(self.moving, Some(MoveKind::Block { grabbed: true, .. }))
+    }
+
+    /// The tasks of the block shown in the tree; tasks the filter hides are
+    /// moved along with the block but not promoted or deleted.
+    fn block_tasks(&self) -> Vec<usize> {
+        let Some((ci, pi, start, end)) = self.block_range() else {
+            return Vec::new();
+        };
+        self.tree_nodes
+            .iter()
+            .filter_map(|n| match n.kind {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                    if (cat_idx, proj_idx) == (ci, pi) && (start..=end).contains(&task_idx) =>
+                {
+                    Some(task_idx)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// j/k with a block: extend the selection to the next task of the
+    /// project, or move the whole block once it is grabbed.
+    fn block_step(&mut self, direction: i32) {
+        let Some(MoveKind::Block { cat_idx, proj_idx, anchor, cursor, grabbed, .. }) = self.moving else {
+            return;
+        };
+        if !grabbed {
+            let next = if direction < 0 {
+                self.tree_nodes[..self.backlog_cursor.min(self.tree_nodes.len())]
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find_map(|(i, n)| block_task(&n.kind, cat_idx, proj_idx).map(|ti| (i, ti)))
+            } else {
+                self.tree_nodes
+                    .iter()
+                    .enumerate()
+                    .skip(self.backlog_cursor + 1)
+                    .find_map(|(i, n)| block_task(&n.kind, cat_idx, proj_idx).map(|ti| (i, ti)))
+            };
+            if let (Some((i, task_idx)), Some(MoveKind::Block { cursor, .. })) = (next, self.moving.as_mut()) {
+                *cursor = task_idx;
+                self.backlog_cursor = i;
+            }
+            return;
+        }
+        let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+        if engine::move_task_block(&mut self.doc, cat_idx, proj_idx, start, end, direction).is_none() {
+            return;
+        }
+        let shift = |i: usize| if direction < 0 { i - 1 } else { i + 1 };
+        if let Some(MoveKind::Block { anchor, cursor, .. }) = self.moving.as_mut() {
+            *anchor = shift(*anchor);
+            *cursor = shift(*cursor);
+        }
+        self.dirty = true;
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: shift(cursor) });
+    }
+
+    /// `m` with a block selected: pick it up so j/k move it.
+    pub fn grab_block(&mut self) {
+        let Some(count) = self.block_range().map(|(_, _, start, end)| end - start + 1) else {
+            return;
+        };
+        if let Some(MoveKind::Block { grabbed, .. }) = self.moving.as_mut() {
+            *grabbed = true;
+        }
+        self.status_msg = format!("Moving {}... j/k to move, Enter to accept, Esc to cancel", tasks_text(count));
+    }
+
+    /// `p`/`x` with a block: promote or demote each of its tasks, as one
+    /// undo step together with any move, and leave move mode. Single-tasking
+    /// keeps only the cursor's task In Progress (or the block's first, if
+    /// the cursor's isn't).
+    pub fn cycle_block(&mut self, promote: bool) {
+        if self.block_range().is_none() {
+            return;
+        }
+        let tasks = self.block_tasks();
+        let Some(MoveKind::Block { cat_idx, proj_idx, cursor, before, .. }) = self.moving.take() else {
+            return;
+        };
+        let changed = tasks
+            .iter()
+            .filter(|&&ti| {
+                if promote {
+                    engine::promote_task(&mut self.doc, cat_idx, proj_idx, ti)
+                } else {
+                    engine::demote_task(&mut self.doc, cat_idx, proj_idx, ti)
+                }
+            })
+            .count();
+        self.status_msg = format!("{} {}", if promote { "Promoted" } else { "Demoted" }, tasks_text(changed));
+        let started = |ti: usize| self.doc.categories[cat_idx].projects[proj_idx].tasks[ti].state == TaskState::InProgress;
+        let keep = if tasks.contains(&cursor) && started(cursor) {
+            Some(cursor)
+        } else {
+            tasks.iter().copied().find(|&ti| started(ti))
+        };
+        if let Some(ti) = keep {
+            self.enforce_single_task(cat_idx, proj_idx, ti);
+        }
+        if *before != self.doc {
+            self.push_undo(*before);
+            self.dirty = true;
+        }
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: cursor });
+        self.offer_checklist_reset(cat_idx, proj_idx);
+    }
+
+    /// Delete the block's tasks once `d` is confirmed, as one undo step.
+    fn delete_block(&mut self) {
+        let mut tasks = self.block_tasks();
+        let Some(MoveKind::Block { cat_idx, proj_idx, before, .. }) = self.moving.take() else {
+            return;
+        };
+        let Some(&first) = tasks.first() else {
+            return;
+        };
+        // Last first, so the earlier indices stay put
+        tasks.sort_unstable_by(|a, b| b.cmp(a));
+        for &ti in &tasks {
+            engine::delete_task(&mut self.doc, cat_idx, proj_idx, ti);
+        }
+        self.push_undo(*before);
+        self.dirty = true;
+        self.status_msg = format!("Deleted {}", tasks_text(tasks.len()));
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.restore_cursor_or_ancestor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: first });
+    }
+
+    /// How many tasks `d` would delete from the block.
+    pub fn block_delete_count(&self) -> Option<usize> {
+        self.block_range().map(|_| self.block_tasks().len())
+    }
+
     // --- Backlog: add note ---
 
+    // Both arms hand `note` over, which a match guard can't do
+    #[allow(clippy::collapsible_match)]
     pub fn add_note_to_focused(&mut self) {
         let note = self.input_buffer.trim().to_string();
         if note.is_empty() {
@@ -937,9 +4319,9 @@ impl App {
         }
 
         if let Some(node) = self.tree_nodes.get(self.backlog_cursor) {
-            match &node.kind {
+            match node.kind {
                 TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
-                    if engine::add_task_note(&mut self.doc, *cat_idx, *proj_idx, *task_idx, note) {
+                    if engine::add_task_note(&mut self.doc, cat_idx, proj_idx, task_idx, note) {
                         self.dirty = true;
                         self.status_msg = "Note added".to_string();
                         let saved = node.kind.clone();
@@ -947,11 +4329,61 @@ impl App {
                         self.restore_cursor(&saved);
                     }
                 }
+                // On a note, the new one goes right below it
+                TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx } => {
+                    if let Some(note_idx) = engine::insert_task_note(&mut self.doc, cat_idx, proj_idx, task_idx, note_idx + 1, note) {
+                        self.dirty = true;
+                        self.status_msg = "Note added".to_string();
+                        self.rebuild_tree();
+                        self.restore_cursor(&TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx });
+                    }
+                }
                 _ => {}
             }
         }
     }
 
+    /// Make the focused note a task of its own, right after its task.
+    pub fn note_to_task_focused(&mut self) {
+        let Some(&TreeNodeKind::Note { cat_idx, proj_idx, task_idx, note_idx }) = self.current_tree_node().map(|n| &n.kind) else {
+            return;
+        };
+        let before = self.doc.clone();
+        let Some(new_idx) = engine::note_to_task(&mut self.doc, cat_idx, proj_idx, task_idx, note_idx) else {
+            self.status_msg = "Nothing to make a task of: the note is blank".to_string();
+            return;
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        let text = &self.doc.categories[cat_idx].projects[proj_idx].tasks[new_idx].text;
+        self.status_msg = format!("Note made a task: \"{}\"", text);
+        self.refresh_agenda();
+        self.rebuild_tree();
+        self.restore_cursor(&TreeNodeKind::Task { cat_idx, proj_idx, task_idx: new_idx });
+    }
+
+    // --- Backlog: preamble ---
+
+    /// Insert a preamble line after the focused preamble node.
+    pub fn add_preamble_line_to_focused(&mut self) {
+        let text = self.input_buffer.trim_end().to_string();
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let insert_idx = match self.tree_nodes.get(self.backlog_cursor).map(|n| &n.kind) {
+            Some(TreeNodeKind::Preamble) => self.doc.title_idx().map_or(0, |i| i + 1),
+            Some(TreeNodeKind::PreambleLine { line_idx }) => line_idx + 1,
+            _ => return,
+        };
+
+        let line_idx = engine::insert_preamble_line(&mut self.doc, insert_idx, text);
+        self.dirty = true;
+        self.status_msg = "Preamble line added".to_string();
+        self.rebuild_tree();
+        self.restore_cursor(&TreeNodeKind::PreambleLine { line_idx });
+    }
+
     // --- Settings: category operations ---
 
     pub fn add_category_from_input(&mut self) {
@@ -970,19 +4402,43 @@ impl App {
         if new_name.is_empty() {
             return;
         }
-        if let Some(cat_idx) = self.settings_category_idx() {
-            if engine::rename_category(&mut self.doc, cat_idx, new_name) {
-                self.dirty = true;
-                self.status_msg = "Category renamed".to_string();
-                self.rebuild_tree();
-            }
+        if let Some(cat_idx) = self.settings_category_idx()
+            && engine::rename_category(&mut self.doc, cat_idx, new_name)
+        {
+            self.dirty = true;
+            self.status_msg = "Category renamed".to_string();
+            self.rebuild_tree();
         }
     }
 
+    /// Space/Enter on a category in Settings: hide it from the Agenda and
+    /// Backlog, or show it again.
+    pub fn toggle_category_hidden(&mut self) {
+        let Some(cat_idx) = self.settings_category_idx() else {
+            return;
+        };
+        let before = self.doc.clone();
+        let Some(category) = self.doc.categories.get_mut(cat_idx) else {
+            return;
+        };
+        category.hidden = !category.hidden;
+        self.status_msg = if category.hidden {
+            format!("\"{}\" hidden from the Agenda and Backlog", category.name)
+        } else {
+            format!("\"{}\" shown again", category.name)
+        };
+        self.push_undo(before);
+        self.dirty = true;
+        self.refresh_agenda();
+        self.rebuild_tree();
+    }
+
     pub fn delete_selected_category(&mut self) {
         if let Some(cat_idx) = self.settings_category_idx() {
+            let before = self.doc.clone();
             if engine::remove_category(&mut self.doc, cat_idx) {
                 self.dirty = true;
+                self.push_undo(before);
                 self.status_msg = "Category deleted".to_string();
                 self.refresh_agenda();
                 self.rebuild_tree();
@@ -996,28 +4452,73 @@ impl App {
     }
 
     pub fn rerank_category(&mut self, direction: i32) {
-        if let Some(cat_idx) = self.settings_category_idx() {
-            if let Some(new_idx) = engine::rerank_category(&mut self.doc, cat_idx, direction) {
-                self.settings_cursor = new_idx + 1; // +1 for theme row
-                self.dirty = true;
-                self.refresh_agenda();
-                self.rebuild_tree();
-            }
+        if let Some(cat_idx) = self.settings_category_idx()
+            && let Some(new_idx) = engine::rerank_category(&mut self.doc, cat_idx, direction)
+        {
+            self.settings_cursor = self.settings_row_index(SettingsRow::Category(new_idx));
+            self.dirty = true;
+            self.refresh_agenda();
+            self.rebuild_tree();
         }
     }
 
     // --- Serialization / Reload ---
 
+    /// The todo file's content, without the archive when `[files]
+    /// archive_file` keeps it apart.
     pub fn serialize(&self) -> String {
-        serializer::serialize(&self.doc)
+        if self.archive_apart() {
+            serializer::serialize_without_archive(&self.doc)
+        } else {
+            serializer::serialize(&self.doc)
+        }
+    }
+
+    /// With `[files] archive_file`, that file and its content.
+    pub fn serialize_archive(&self) -> Option<(PathBuf, String)> {
+        let path = self.config.files.archive_file(&self.file_path)?;
+        Some((path, serializer::serialize_archive(&self.doc)))
+    }
+
+    fn archive_apart(&self) -> bool {
+        self.config.files.archive_file.is_some()
     }
 
     pub fn reload(&mut self, content: &str) {
-        self.doc = parser::parse(content);
+        // Watcher events often carry no real change (touch, editor swap
+        // files); a borrowed parse is enough to tell and keeps cursors put.
+        let outline = parser::parse_outline_with(content, self.config.parse_options());
+        let unchanged = if self.archive_apart() { outline.matches_apart_from_archive(&self.doc) } else { outline.matches(&self.doc) };
+        if unchanged {
+            self.dirty = false;
+            self.status_msg = "Reloaded from disk (no changes)".to_string();
+            return;
+        }
+        let mut doc = outline.to_document();
+        if self.archive_apart() {
+            // The archive file isn't watched: what it held when last read or
+            // written goes after any lines still in the todo file's own
+            // archive section
+            doc.archive = std::mem::take(&mut doc.archive).followed_by(&self.archive_on_disk);
+        }
+        let changes = diff::diff(&self.doc, &doc);
+        self.doc = doc;
         self.dirty = false;
-        self.status_msg = "Reloaded from disk".to_string();
+        // Undoing to a snapshot from before the edit on disk would save
+        // over that edit
+        self.undo_stack.clear();
+        self.status_msg = if changes.is_empty() {
+            "Reloaded from disk".to_string()
+        } else {
+            format!("Reloaded from disk: {}", diff::summary(&changes))
+        };
+        if self.config.agenda.auto_promote.on_load() {
+            engine::auto_promote(&mut self.doc, self.config.agenda.promote);
+        }
         self.refresh_agenda();
         self.rebuild_tree();
+        self.offer_duplicate_fix(false);
+        self.offer_unsorted_triage(outline.stray_tasks);
     }
 
     // --- Dialog management ---
@@ -1026,18 +4527,91 @@ impl App {
         self.dialog = dialog;
         self.input_buffer.clear();
         self.input_cursor = 0;
+        self.update_completion();
     }
 
     pub fn open_dialog_with_text(&mut self, dialog: Dialog, text: &str) {
         self.dialog = dialog;
         self.input_buffer = text.to_string();
         self.input_cursor = text.len();
+        self.update_completion();
     }
 
     pub fn close_dialog(&mut self) {
         self.dialog = Dialog::None;
         self.input_buffer.clear();
         self.input_cursor = 0;
+        self.completion = Completion::default();
+    }
+
+    // --- Completion ---
+
+    /// Names the open dialog completes, and where in the input the word
+    /// being completed starts; None when nothing is being completed.
+    fn completion_source(&self) -> Option<(usize, Vec<String>)> {
+        match self.dialog {
+            Dialog::QuickAdd => {
+                let project = complete::sigil_word(&self.input_buffer, self.input_cursor, '+');
+                // Whichever of +Project and #tag was typed last
+                match (project, self.tag_word()) {
+                    (Some(project), Some(tag)) if tag > project => Some((tag, self.tag_names())),
+                    (Some(project), _) => Some((project, self.project_names())),
+                    (None, tag) => Some((tag?, self.tag_names())),
+                }
+            }
+            Dialog::MoveToProject => {
+                Some((0, self.move_to_project_targets().into_iter().map(|(_, label)| label).collect()))
+            }
+            Dialog::AddTask | Dialog::EditTask | Dialog::EditNote | Dialog::EditExistingNote => {
+                Some((self.tag_word()?, self.tag_names()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Where the `#tag` being typed starts, if the cursor is on one.
+    fn tag_word(&self) -> Option<usize> {
+        let start = complete::sigil_word(&self.input_buffer, self.input_cursor, '#')?;
+        let word = &self.input_buffer[start..self.input_cursor];
+        (!word.contains(char::is_whitespace)).then_some(start)
+    }
+
+    fn tag_names(&self) -> Vec<String> {
+        engine::tag_index(&self.doc).into_iter().map(|t| t.name).collect()
+    }
+
+    /// Match the word being completed again; call after the input changes.
+    pub fn update_completion(&mut self) {
+        self.completion = match self.completion_source() {
+            Some((start, names)) => Completion {
+                start,
+                matches: complete::rank(&self.input_buffer[start..self.input_cursor], names.iter().map(String::as_str)),
+                cursor: 0,
+            },
+            None => Completion::default(),
+        };
+    }
+
+    /// Highlight the next (1) or previous (-1) match, wrapping around.
+    pub fn completion_step(&mut self, direction: i32) {
+        let len = self.completion.matches.len();
+        if len > 0 {
+            self.completion.cursor = (self.completion.cursor as i32 + direction).rem_euclid(len as i32) as usize;
+        }
+    }
+
+    /// Put the highlighted match in place of the word being completed and
+    /// hide the list until something else is typed. False with no match.
+    pub fn accept_completion(&mut self) -> bool {
+        let Some(name) = self.completion.matches.get(self.completion.cursor) else {
+            return false;
+        };
+        // More of the task text usually follows a +Project or #tag
+        let name = if self.dialog == Dialog::MoveToProject { name.clone() } else { format!("{} ", name) };
+        self.input_buffer.replace_range(self.completion.start..self.input_cursor, &name);
+        self.input_cursor = self.completion.start + name.len();
+        self.completion = Completion::default();
+        true
     }
 
     // --- Theme ---
@@ -1057,14 +4631,15 @@ impl App {
     }
 
     // Input buffer for dialogs
+    // `input_cursor` is a byte offset that always sits on a char boundary
     pub fn input_char(&mut self, c: char) {
         self.input_buffer.insert(self.input_cursor, c);
-        self.input_cursor += 1;
+        self.input_cursor += c.len_utf8();
     }
 
     pub fn input_backspace(&mut self) {
-        if self.input_cursor > 0 {
-            self.input_cursor -= 1;
+        if let Some(c) = self.input_buffer[..self.input_cursor].chars().next_back() {
+            self.input_cursor -= c.len_utf8();
             self.input_buffer.remove(self.input_cursor);
         }
     }
@@ -1076,14 +4651,14 @@ impl App {
     }
 
     pub fn input_move_left(&mut self) {
-        if self.input_cursor > 0 {
-            self.input_cursor -= 1;
+        if let Some(c) = self.input_buffer[..self.input_cursor].chars().next_back() {
+            self.input_cursor -= c.len_utf8();
         }
     }
 
     pub fn input_move_right(&mut self) {
-        if self.input_cursor < self.input_buffer.len() {
-            self.input_cursor += 1;
+        if let Some(c) = self.input_buffer[self.input_cursor..].chars().next() {
+            self.input_cursor += c.len_utf8();
         }
     }
 
@@ -1117,6 +4692,10 @@ impl App {
                         .map(|n| n.trim().to_string())
                         .unwrap_or_default()
                 }
+                TreeNodeKind::Preamble => self.doc.title().unwrap_or_default().to_string(),
+                TreeNodeKind::PreambleLine { line_idx } => {
+                    self.doc.preamble.get(*line_idx).cloned().unwrap_or_default()
+                }
             }
         } else {
             String::new()
@@ -0,0 +1,88 @@
+//! Files attached to tasks with `@file(path)`: where a path points and
+//! opening it with the system's default application.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+/// A task's attached file, resolved for opening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// The path as written in the task.
+    pub spec: String,
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+/// Where `spec` points: `~/` is the home directory and relative paths are
+/// taken from `base`, the directory of the todo file.
+pub fn resolve(spec: &str, base: &Path) -> PathBuf {
+    let path = Path::new(spec);
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Resolve each of `specs` and check whether the file is there.
+pub fn check(specs: &[&str], base: &Path) -> Vec<Attachment> {
+    specs
+        .iter()
+        .map(|spec| {
+            let path = resolve(spec, base);
+            Attachment { spec: spec.to_string(), exists: path.exists(), path }
+        })
+        .collect()
+}
+
+/// Open `path` with the system's default application without waiting for it.
+pub fn open(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Could not open {}", path.display()))?;
+    // Reap the opener once it hands the file over
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{attachments, mentions};
+
+    #[test]
+    fn test_attachments() {
+        let line = "Sign @file(~/docs/contract v2.pdf) with @Sam, see @file(notes.txt) @file()";
+        assert_eq!(attachments(line).collect::<Vec<_>>(), ["~/docs/contract v2.pdf", "notes.txt"]);
+        assert_eq!(mentions(line).collect::<Vec<_>>(), ["Sam"]);
+
+        let base = Path::new("/work");
+        assert_eq!(resolve("notes.txt", base), PathBuf::from("/work/notes.txt"));
+        assert_eq!(resolve("/etc/hosts", base), PathBuf::from("/etc/hosts"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(resolve("~/docs/a.pdf", base), home.join("docs/a.pdf"));
+        }
+
+        let dir = std::env::temp_dir().join(format!("gws-attach-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        let checked = check(&["notes.txt", "gone.pdf"], &dir);
+        assert_eq!(checked.iter().map(|a| a.exists).collect::<Vec<_>>(), [true, false]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
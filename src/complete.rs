@@ -0,0 +1,119 @@
+//! Fuzzy completion of names typed into dialogs: how well a name matches
+//! what was typed, and which part of the input is being completed.
+
+/// How well `query` matches `name`, lower is better; None when the letters of
+/// `query` don't all appear in `name` in order. Case is ignored. Letters that
+/// start the name or a word cost nothing, others cost the gap since the last
+/// match, so prefixes beat initials and initials beat scattered letters.
+pub fn score(query: &str, name: &str) -> Option<usize> {
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    let mut cost = 0;
+    let mut pos = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q == ' ' {
+            continue;
+        }
+        let found = pos + name[pos..].iter().position(|&c| c == q)?;
+        let word_start = found == 0 || !name[found - 1].is_alphanumeric();
+        if found != pos && !word_start {
+            cost += found - pos;
+        } else if found != pos {
+            cost += 1;
+        }
+        pos = found + 1;
+    }
+    Some(cost)
+}
+
+/// The `names` matching `query`, best first; equal scores keep their order
+/// and a name given twice is listed once.
+pub fn rank<'a>(query: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut scored: Vec<(usize, &str)> = Vec::new();
+    for name in names {
+        if scored.iter().any(|(_, seen)| *seen == name) {
+            continue;
+        }
+        if let Some(cost) = score(query, name) {
+            scored.push((cost, name));
+        }
+    }
+    scored.sort_by_key(|(cost, _)| *cost);
+    scored.into_iter().map(|(_, name)| name.to_string()).collect()
+}
+
+/// Where the word after `sigil` starts, when the text before `cursor` ends in
+/// one being typed: `+Lau` in `Call Sam +Lau`. The sigil must start the text
+/// or follow a space, so `C++` is left alone.
+pub fn sigil_word(text: &str, cursor: usize, sigil: char) -> Option<usize> {
+    let before = &text[..cursor];
+    let at = before.rfind(sigil)?;
+    let starts_word = before[..at].chars().next_back().is_none_or(char::is_whitespace);
+    starts_word.then_some(at + sigil.len_utf8())
+}
+
+/// Split a `+Project` token out of quick-add text, the last one if there are
+/// several. The longest of `names` the text after the sigil starts with
+/// (ignoring case) is taken, so names with spaces work; failing that, the
+/// word after it as typed. Returns the task text and the name, if any.
+pub fn split_sigil<'a>(text: &str, sigil: char, names: impl IntoIterator<Item = &'a str>) -> (String, Option<String>) {
+    let Some(start) = text
+        .match_indices(sigil)
+        .map(|(i, _)| i)
+        .rfind(|&i| text[..i].chars().next_back().is_none_or(char::is_whitespace))
+        .map(|i| i + sigil.len_utf8())
+    else {
+        return (text.split_whitespace().collect::<Vec<_>>().join(" "), None);
+    };
+    let rest = &text[start..];
+    let lower = rest.to_lowercase();
+    let named = names
+        .into_iter()
+        .filter(|name| {
+            let name = name.to_lowercase();
+            lower.starts_with(&name) && lower[name.len()..].chars().next().is_none_or(char::is_whitespace)
+        })
+        .max_by_key(|name| name.len());
+    let len = match named {
+        Some(name) => name.len(),
+        None => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+    };
+    // Lowercasing can change a name's length; fall back to the word typed
+    let len = if rest.is_char_boundary(len) { len } else { rest.find(char::is_whitespace).unwrap_or(rest.len()) };
+    let project = rest[..len].to_string();
+    let task = format!("{} {}", &text[..start - sigil.len_utf8()], &rest[len..]);
+    let task = task.split_whitespace().collect::<Vec<_>>().join(" ");
+    (task, (!project.is_empty()).then_some(project))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let names = ["Launch", "Hiring", "Home Lab", "Learn piano", "Launch"];
+        assert_eq!(rank("la", names), ["Launch", "Home Lab", "Learn piano"]);
+        assert_eq!(rank("hl", names), ["Home Lab"]);
+        assert_eq!(rank("LP", names), ["Learn piano"]);
+        assert_eq!(rank("", names), ["Launch", "Hiring", "Home Lab", "Learn piano"]);
+        assert!(rank("xyz", names).is_empty());
+    }
+
+    #[test]
+    fn test_sigil_word() {
+        assert_eq!(sigil_word("Call Sam +Lau", 13, '+'), Some(10));
+        assert_eq!(sigil_word("+", 1, '+'), Some(1));
+        assert_eq!(sigil_word("Learn C++", 9, '+'), None);
+        assert_eq!(sigil_word("Call Sam", 8, '+'), None);
+    }
+
+    #[test]
+    fn test_split_sigil() {
+        let names = ["Launch", "Launch Plan", "Hiring"];
+        assert_eq!(split_sigil("Call Sam +launch plan", '+', names), ("Call Sam".to_string(), Some("launch plan".to_string())));
+        assert_eq!(split_sigil("+Hiring Post job", '+', names), ("Post job".to_string(), Some("Hiring".to_string())));
+        assert_eq!(split_sigil("Call Sam +Press today", '+', names), ("Call Sam today".to_string(), Some("Press".to_string())));
+        assert_eq!(split_sigil("Learn C++", '+', names), ("Learn C++".to_string(), None));
+        assert_eq!(split_sigil(" Call Sam ", '+', names), ("Call Sam".to_string(), None));
+    }
+}
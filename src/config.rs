@@ -0,0 +1,462 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::date::{Calendar, Date};
+use crate::engine::{self, PromoteMode, PromoteStrategy};
+use crate::keymap::KeyboardLayout;
+use crate::model::{Area, DEFAULT_ARCHIVE_HEADING, HeadingLevels, TaskState};
+use crate::parser::ParseOptions;
+use crate::paths;
+
+/// User preferences shared by every todo file, stored in `~/.config/gws/config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub general: GeneralConfig,
+    pub agenda: AgendaConfig,
+    pub files: FilesConfig,
+    pub format: FormatConfig,
+    pub planner: PlannerConfig,
+    pub calendar: CalendarConfig,
+    /// Areas of focus shared by every file, as `[[areas]]` tables.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub areas: Vec<Area>,
+    /// Named filter expressions (see `query`), as `[[filters]]` tables.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<SavedFilter>,
+    /// Named todo files to switch between, as `[[profiles]]` tables.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+    /// Chat webhooks told about finished tasks, as `[[webhooks]]` tables.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub query: String,
+}
+
+/// A todo file with a name (`work`, `personal`) and optionally its own theme.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub file: PathBuf,
+    /// Theme name as listed in Settings; the session's theme when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+}
+
+/// Where to announce state changes: every task that reaches one of `on` and
+/// matches `filter` (all tasks when empty) is posted to `url` as `template`
+/// with `{text}`, `{project}`, `{category}`, `{state}` and `{icon}` filled in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub filter: String,
+    #[serde(default = "default_webhook_states")]
+    pub on: Vec<TaskState>,
+    #[serde(default = "default_webhook_template")]
+    pub template: String,
+}
+
+fn default_webhook_states() -> Vec<TaskState> {
+    vec![TaskState::Done]
+}
+
+fn default_webhook_template() -> String {
+    "{icon} {text} ({project})".to_string()
+}
+
+impl Profile {
+    /// The profile's file with a leading `~/` expanded.
+    pub fn file(&self) -> PathBuf {
+        expand_home(&self.file)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneralConfig {
+    /// Reopen each file with its saved view, cursors and folds.
+    pub restore_session: bool,
+    /// Plain-text rendering for screen readers: no emoji or box drawing,
+    /// descriptive rows, and a fixed status line.
+    pub accessible: bool,
+    /// Save shortly after every change instead of waiting for `s`.
+    pub autosave: bool,
+    /// Let `:titles` fetch the page titles of bare links; off for offline use.
+    pub fetch_link_titles: bool,
+    /// Read command keys by their place on this layout, as if on QWERTY.
+    pub keyboard_layout: KeyboardLayout,
+    /// Remind at startup when the inbox was last triaged to the end more
+    /// than this many days ago; 0 is off.
+    pub review_every_days: u32,
+    /// On the first launch of a day, archive done tasks, bring back
+    /// deferred ones that are due and auto-promote, then show what changed.
+    pub new_day: bool,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            restore_session: true,
+            accessible: false,
+            autosave: false,
+            fetch_link_titles: true,
+            keyboard_layout: KeyboardLayout::Qwerty,
+            review_every_days: 0,
+            new_day: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilesConfig {
+    /// Todo file opened when `--file` is not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_file: Option<PathBuf>,
+    /// Keep code blocks, tables and other unrecognized markdown verbatim.
+    pub preserve_unknown: bool,
+    /// Inside a git repository, open its `TODO.md` or `.gws/todo.md` when
+    /// there is one.
+    pub repo_local: bool,
+    /// Keep the archive in this file rather than at the end of the todo
+    /// file. Relative to the todo file; `{name}` is the todo file's name
+    /// without its extension.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_file: Option<PathBuf>,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            default_file: None,
+            preserve_unknown: true,
+            repo_local: true,
+            archive_file: None,
+        }
+    }
+}
+
+impl FilesConfig {
+    /// The configured default file with a leading `~/` expanded.
+    pub fn default_file(&self) -> Option<PathBuf> {
+        self.default_file.as_deref().map(expand_home)
+    }
+
+    /// Where the archive of `todo_file` is kept, when it has a file of its own.
+    pub fn archive_file(&self, todo_file: &Path) -> Option<PathBuf> {
+        let template = self.archive_file.as_deref()?.to_string_lossy();
+        let name = todo_file.file_stem().unwrap_or_default().to_string_lossy();
+        let path = expand_home(Path::new(&template.replace("{name}", &name)));
+        // An absolute path replaces the directory
+        Some(match todo_file.parent() {
+            Some(dir) => dir.join(path),
+            None => path,
+        })
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Working days for due dates typed as offsets (see `Date::parse_relative_in`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Also move `tomorrow`, `+3` and `+2w` off weekends and holidays.
+    pub workdays_only: bool,
+    /// Days off as `YYYY-MM-DD`; business-day counts skip them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<String>,
+}
+
+impl CalendarConfig {
+    /// Unreadable holiday dates are ignored.
+    pub fn calendar(&self) -> Calendar {
+        Calendar {
+            holidays: self.holidays.iter().filter_map(|s| Date::parse(s.trim())).collect(),
+            workdays_only: self.workdays_only,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlannerConfig {
+    /// Hours of task time "Plan my day" fills.
+    pub hours: u32,
+    /// When the first task of the day starts in `:ics` exports, `HH:MM`.
+    pub day_start: String,
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self { hours: 6, day_start: "09:00".to_string() }
+    }
+}
+
+/// How todo files map onto markdown headings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Heading level of categories (2 means `##`).
+    pub category_level: usize,
+    /// Heading level of projects (3 means `###`).
+    pub project_level: usize,
+    /// Names accepted for the archive section, e.g. `["Done", "Archive",
+    /// "Erledigt"]`. The first names the section in files without one.
+    pub archive_headings: Vec<String>,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        let levels = HeadingLevels::default();
+        Self {
+            category_level: levels.category,
+            project_level: levels.project,
+            archive_headings: vec![DEFAULT_ARCHIVE_HEADING.to_string()],
+        }
+    }
+}
+
+impl FormatConfig {
+    /// The configured levels, or the defaults if they don't make sense.
+    pub fn levels(&self) -> HeadingLevels {
+        let levels = HeadingLevels {
+            category: self.category_level,
+            project: self.project_level,
+        };
+        if levels.is_valid() { levels } else { HeadingLevels::default() }
+    }
+}
+
+/// Which task states get an agenda section, and in what order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgendaConfig {
+    /// Every state, in display order.
+    pub order: Vec<TaskState>,
+    /// States left off the agenda entirely.
+    pub hidden: Vec<TaskState>,
+    /// Which Todo task auto-promote picks.
+    pub promote: PromoteStrategy,
+    /// When auto-promote runs.
+    pub auto_promote: PromoteMode,
+    /// Days a task may sit In Progress before it counts as stalled; 0 is off.
+    pub stalled_after_days: u32,
+    /// Move stalled tasks back to On Deck on startup instead of flagging them.
+    pub demote_stalled: bool,
+    /// Label rows `(Category › Project)` rather than `(Project)`, for files
+    /// where several categories have a project of the same name.
+    pub show_category: bool,
+    /// Keep ✅ tasks below the open ones in each project.
+    pub sink_done: bool,
+    /// One task In Progress at a time: starting one moves the others back
+    /// to On Deck.
+    pub single_tasking: bool,
+}
+
+impl Default for AgendaConfig {
+    fn default() -> Self {
+        Self {
+            order: engine::DEFAULT_SECTIONS.to_vec(),
+            hidden: Vec::new(),
+            promote: PromoteStrategy::default(),
+            auto_promote: PromoteMode::default(),
+            stalled_after_days: 0,
+            demote_stalled: false,
+            show_category: false,
+            sink_done: false,
+            single_tasking: false,
+        }
+    }
+}
+
+/// Choices offered for `stalled_after_days` in Settings.
+pub const STALLED_AFTER_CHOICES: [u32; 5] = [0, 3, 7, 14, 30];
+
+impl AgendaConfig {
+    /// Visible sections in display order.
+    pub fn sections(&self) -> Vec<TaskState> {
+        self.order.iter().copied().filter(|s| !self.hidden.contains(s)).collect()
+    }
+
+    pub fn is_hidden(&self, state: TaskState) -> bool {
+        self.hidden.contains(&state)
+    }
+
+    pub fn toggle_hidden(&mut self, state: TaskState) {
+        if let Some(pos) = self.hidden.iter().position(|&s| s == state) {
+            self.hidden.remove(pos);
+        } else {
+            self.hidden.push(state);
+        }
+    }
+
+    /// Swap the section at `idx` with its neighbour; returns the new index.
+    pub fn move_section(&mut self, idx: usize, direction: i32) -> Option<usize> {
+        let new_idx = idx as i32 + direction;
+        if idx >= self.order.len() || new_idx < 0 || new_idx >= self.order.len() as i32 {
+            return None;
+        }
+        self.order.swap(idx, new_idx as usize);
+        Some(new_idx as usize)
+    }
+
+    /// Repair hand-edited configs: drop duplicates and append missing states.
+    fn normalize(&mut self) {
+        let mut order = Vec::new();
+        for state in self.order.iter().chain(Self::default().order.iter()) {
+            if !order.contains(state) {
+                order.push(*state);
+            }
+        }
+        self.order = order;
+        let mut seen = Vec::new();
+        self.hidden.retain(|s| {
+            let first = !seen.contains(s);
+            seen.push(*s);
+            first
+        });
+    }
+}
+
+impl Config {
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut config: Self = toml::from_str(content).context("Failed to parse config file")?;
+        config.agenda.normalize();
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// The profile called `name`, ignoring case.
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// How to read todo files under this config.
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            fidelity: self.files.preserve_unknown,
+            levels: self.format.levels(),
+            archive_headings: if self.format.archive_headings.is_empty() {
+                FormatConfig::default().archive_headings
+            } else {
+                self.format.archive_headings.clone()
+            },
+        }
+    }
+}
+
+/// `config.toml` under `$XDG_CONFIG_HOME/gws`, or the legacy `~/.gws`.
+pub fn config_path() -> PathBuf {
+    paths::Dirs::from_env().config_file()
+}
+
+/// Load the config, falling back to defaults if it is missing or unreadable.
+pub fn load(path: &Path) -> Config {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| Config::from_toml(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, config: &Config) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create config directory")?;
+    }
+    fs::write(path, config.to_toml()).context("Failed to write config file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_toml_roundtrip() {
+        let mut config = Config::default();
+        config.agenda.toggle_hidden(TaskState::Done);
+        config.general.restore_session = false;
+        config.general.accessible = true;
+        config.general.autosave = true;
+        config.general.fetch_link_titles = false;
+        config.files.preserve_unknown = false;
+        config.files.repo_local = false;
+        config.format.category_level = 1;
+        config.format.project_level = 2;
+        config.format.archive_headings = vec!["Erledigt".to_string(), "Done".to_string()];
+        config.areas = vec![Area {
+            name: "Health".to_string(),
+            categories: vec!["Medical".to_string(), "Fitness".to_string()],
+        }];
+        config.files.default_file = Some(PathBuf::from("~/notes/todo.md"));
+        config.filters = vec![SavedFilter {
+            name: "Errands".to_string(),
+            query: "state:ondeck AND @home".to_string(),
+        }];
+        config.profiles = vec![Profile {
+            name: "work".to_string(),
+            file: PathBuf::from("~/work/todo.md"),
+            theme: Some("Nord".to_string()),
+        }];
+        config.agenda.move_section(1, -1);
+        config.agenda.promote = PromoteStrategy::HighestPriority;
+        config.agenda.auto_promote = PromoteMode::OnDemand;
+        config.agenda.stalled_after_days = 7;
+        config.agenda.demote_stalled = true;
+        config.planner.hours = 4;
+        config.calendar.workdays_only = true;
+        config.calendar.holidays = vec!["2026-12-25".to_string(), "someday".to_string()];
+        assert_eq!(config.calendar.calendar().holidays.len(), 1);
+        assert!(config.to_toml().contains("promote = \"highest-priority\""));
+        assert!(config.to_toml().contains("auto_promote = \"on-demand\""));
+        let restored = Config::from_toml(&config.to_toml()).unwrap();
+        assert_eq!(restored, config);
+        assert_eq!(restored.profile("Work"), config.profiles.first());
+        assert!(restored.profile("home").is_none());
+        assert_eq!(restored.agenda.sections(), [TaskState::OnDeck, TaskState::InProgress, TaskState::Todo]);
+    }
+
+    #[test]
+    fn test_config_normalizes_order() {
+        let config = Config::from_toml("[agenda]\norder = [\"todo\", \"todo\", \"done\"]\n").unwrap();
+        assert_eq!(config.agenda.order, [TaskState::Todo, TaskState::Done, TaskState::InProgress, TaskState::OnDeck]);
+
+        // Empty file means defaults
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(config, Config::default());
+        assert!(config.general.restore_session);
+        assert_eq!(config.files.default_file(), None);
+
+        // Projects above categories fall back to the defaults
+        let config = Config::from_toml("[format]\ncategory_level = 3\nproject_level = 2\n").unwrap();
+        assert_eq!(config.format.levels(), HeadingLevels::default());
+
+        // The archive file sits next to the todo file unless it says otherwise
+        let config = Config::from_toml("[files]\narchive_file = \"{name}.archive.md\"\n").unwrap();
+        assert_eq!(config.files.archive_file(Path::new("/notes/work.md")), Some(PathBuf::from("/notes/work.archive.md")));
+        let config = Config::from_toml("[files]\narchive_file = \"/archive/done.md\"\n").unwrap();
+        assert_eq!(config.files.archive_file(Path::new("/notes/todo.md")), Some(PathBuf::from("/archive/done.md")));
+        assert_eq!(Config::default().files.archive_file(Path::new("/notes/todo.md")), None);
+
+        // A webhook needs only its URL
+        let config = Config::from_toml("[[webhooks]]\nurl = \"https://hooks.example.com/x\"\n").unwrap();
+        assert_eq!(config.webhooks[0].on, [TaskState::Done]);
+        assert_eq!(config.webhooks[0].template, "{icon} {text} ({project})");
+    }
+}
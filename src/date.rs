@@ -0,0 +1,262 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar day (UTC), written as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    /// Days since 1970-01-01.
+    days: i64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        Self { days: secs.div_euclid(86_400) }
+    }
+
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        // Days from civil (Howard Hinnant's algorithm)
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        Some(Self { days: era * 146_097 + doe - 719_468 })
+    }
+
+    /// Parse `YYYY-MM-DD`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.splitn(3, '-');
+        let year = parts.next()?;
+        let month = parts.next()?;
+        let day = parts.next()?;
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+        Self::from_ymd(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+    }
+
+    /// A date typed by the user: `YYYY-MM-DD`, `today`, `tomorrow`, or an
+    /// offset from `today` such as `+3` (days) or `+2w` (weeks). See
+    /// `parse_relative_in` for the rest.
+    pub fn parse_relative(s: &str, today: Date) -> Option<Self> {
+        Self::parse_relative_in(s, today, &Calendar::default())
+    }
+
+    /// `parse_relative`, plus weekday names (`friday`, `next fri`: the next
+    /// one after today), business days (`+3b`, `in 3 business days`) counted
+    /// on `calendar`, and `in 3 days` or `in 2 weeks`. With
+    /// `calendar.workdays_only`, an offset that lands on a weekend or holiday
+    /// moves on to the next working day; dates and weekdays typed by name are
+    /// taken as they are.
+    pub fn parse_relative_in(s: &str, today: Date, calendar: &Calendar) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        if s == "today" {
+            return Some(today);
+        }
+        if let Some(weekday) = weekday_named(s.strip_prefix("next ").unwrap_or(&s)) {
+            return Some(today.plus_days((weekday as i64 - today.weekday() as i64 - 1).rem_euclid(7) + 1));
+        }
+        let (n, unit) = match offset(&s) {
+            Some(offset) => offset,
+            None => return Self::parse(&s),
+        };
+        let date = match unit {
+            'b' => return Some(calendar.add_workdays(today, n)),
+            'w' => today.plus_days(n.checked_mul(7)?),
+            _ => today.plus_days(n),
+        };
+        Some(if calendar.workdays_only { calendar.next_workday(date) } else { date })
+    }
+
+    /// Day of the week, Monday = 0 through Sunday = 6.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (self.days + 3).rem_euclid(7) as u32
+    }
+
+    /// (year, month, day)
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let z = self.days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+
+    pub fn minus_days(self, days: i64) -> Self {
+        Self { days: self.days - days }
+    }
+
+    pub fn plus_days(self, days: i64) -> Self {
+        Self { days: self.days + days }
+    }
+
+    /// Whole days from `earlier` to this date (negative if `earlier` is later).
+    pub fn days_since(self, earlier: Date) -> i64 {
+        self.days - earlier.days
+    }
+}
+
+/// Offsets further out than this many days (ten years) are taken as typos;
+/// business days are counted one at a time.
+const MAX_OFFSET_DAYS: i64 = 3653;
+
+/// `tomorrow`, `+3`, `+3d`, `+2w`, `+3b`, `in 3 days`, `in 2 weeks` or
+/// `in 3 business days`, as a count and unit (`d`, `w` or `b`).
+fn offset(s: &str) -> Option<(i64, char)> {
+    if s == "tomorrow" {
+        return Some((1, 'd'));
+    }
+    let (n, unit) = if let Some(offset) = s.strip_prefix('+') {
+        match offset.char_indices().last() {
+            Some((i, unit @ ('d' | 'w' | 'b'))) => (&offset[..i], unit),
+            _ => (offset, 'd'),
+        }
+    } else {
+        let rest = s.strip_prefix("in ")?;
+        let (n, unit) = rest.split_once(' ')?;
+        let unit = match unit.trim_end_matches('s') {
+            "day" => 'd',
+            "week" => 'w',
+            "business day" | "workday" | "working day" => 'b',
+            _ => return None,
+        };
+        (n, unit)
+    };
+    let n: i64 = n.parse().ok()?;
+    let days = if unit == 'w' { n.checked_mul(7)? } else { n };
+    (days.abs() <= MAX_OFFSET_DAYS).then_some((n, unit))
+}
+
+const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// `friday` or `fri` as 4 (Monday = 0).
+fn weekday_named(s: &str) -> Option<u32> {
+    if s.len() < 3 {
+        return None;
+    }
+    WEEKDAYS.iter().position(|day| day.starts_with(s)).map(|i| i as u32)
+}
+
+/// Which days count as working days: Monday to Friday, less `holidays`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calendar {
+    pub holidays: Vec<Date>,
+    /// Move day and week offsets off weekends and holidays too, not only
+    /// business-day counts.
+    pub workdays_only: bool,
+}
+
+impl Calendar {
+    pub fn is_workday(&self, date: Date) -> bool {
+        date.weekday() < 5 && !self.holidays.contains(&date)
+    }
+
+    /// `date` if it is a working day, else the first one after it.
+    pub fn next_workday(&self, date: Date) -> Date {
+        let mut date = date;
+        // Capped so a holiday list covering every weekday can't hang
+        for _ in 0..366 {
+            if self.is_workday(date) {
+                break;
+            }
+            date = date.plus_days(1);
+        }
+        date
+    }
+
+    /// The working day `n` working days after `date`.
+    pub fn add_workdays(&self, date: Date, n: i64) -> Date {
+        let mut date = date;
+        for _ in 0..n.max(0) {
+            date = self.next_workday(date.plus_days(1));
+        }
+        date
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (y, m, d) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", y, m, d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_roundtrip() {
+        let date = Date::from_ymd(2024, 2, 29).unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(Date::parse("2024-02-29"), Some(date));
+        assert_eq!(date.minus_days(60).to_string(), "2023-12-31");
+        assert_eq!(Date::from_ymd(1970, 1, 1).unwrap().days, 0);
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("2023-2-28"), None);
+
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        let relative = |s| Date::parse_relative(s, today).map(|d| d.to_string());
+        assert_eq!(relative("Tomorrow").as_deref(), Some("2026-03-11"));
+        assert_eq!(relative("+3").as_deref(), Some("2026-03-13"));
+        assert_eq!(relative("+2w").as_deref(), Some("2026-03-24"));
+        assert_eq!(relative("2026-04-01").as_deref(), Some("2026-04-01"));
+        assert_eq!(relative("soon"), None);
+    }
+
+    #[test]
+    fn test_workdays() {
+        // Tuesday
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        assert_eq!(today.weekday(), 1);
+        let holiday = Date::from_ymd(2026, 3, 16).unwrap();
+        let calendar = Calendar { holidays: vec![holiday], workdays_only: false };
+        let relative = |s, calendar: &Calendar| Date::parse_relative_in(s, today, calendar).map(|d| d.to_string());
+
+        assert_eq!(relative("friday", &calendar).as_deref(), Some("2026-03-13"));
+        assert_eq!(relative("next Tue", &calendar).as_deref(), Some("2026-03-17"));
+        assert_eq!(relative("sat", &calendar).as_deref(), Some("2026-03-14"));
+        assert_eq!(relative("fr", &calendar), None);
+
+        // Three working days on is Friday; four skips the weekend and Monday's holiday
+        assert_eq!(relative("+3b", &calendar).as_deref(), Some("2026-03-13"));
+        assert_eq!(relative("in 4 business days", &calendar).as_deref(), Some("2026-03-17"));
+        assert_eq!(relative("in 2 weeks", &calendar).as_deref(), Some("2026-03-24"));
+
+        // Plain offsets land on the weekend unless asked not to
+        assert_eq!(relative("+4", &calendar).as_deref(), Some("2026-03-14"));
+        let strict = Calendar { workdays_only: true, ..calendar };
+        assert_eq!(relative("+4", &strict).as_deref(), Some("2026-03-17"));
+        assert_eq!(relative("in 1 day", &strict).as_deref(), Some("2026-03-11"));
+        assert_eq!(relative("sat", &strict).as_deref(), Some("2026-03-14"));
+
+        // Offsets past ten years are refused rather than counted out
+        assert_eq!(relative("+3653", &strict).as_deref(), Some("2036-03-10"));
+        assert_eq!(relative("+100000000b", &strict), None);
+        assert_eq!(relative("in 99999999 business days", &strict), None);
+        assert_eq!(relative("+2000000000000000000w", &strict), None);
+    }
+}
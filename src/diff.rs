@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use crate::model::{Document, Task, TaskState};
+
+/// Where a task lives, by name rather than index so addresses from two
+/// versions of a document can be compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskAddress {
+    pub category: String,
+    pub project: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(TaskAddress),
+    Removed(TaskAddress),
+    /// Same text, now under a different category or project.
+    Moved { from: TaskAddress, to: TaskAddress },
+    StateChanged { address: TaskAddress, from: TaskState, to: TaskState },
+    NotesChanged(TaskAddress),
+}
+
+type Key<'a> = (&'a str, &'a str, &'a str);
+
+fn tasks(doc: &Document) -> impl Iterator<Item = (Key<'_>, &Task)> {
+    doc.categories.iter().flat_map(|c| {
+        c.projects
+            .iter()
+            .flat_map(move |p| p.tasks.iter().map(move |t| ((c.name.as_str(), p.name.as_str(), t.text.as_str()), t)))
+    })
+}
+
+fn address((category, project, text): Key) -> TaskAddress {
+    TaskAddress {
+        category: category.to_string(),
+        project: project.to_string(),
+        text: text.to_string(),
+    }
+}
+
+/// Task-level changes from `old` to `new`, in `new`'s document order with
+/// removals last. Tasks are matched by category, project and text; a task
+/// whose text changed shows up as removed and added.
+pub fn diff(old: &Document, new: &Document) -> Vec<Change> {
+    // Old tasks by address; duplicates pair up in document order
+    let mut unmatched: HashMap<Key, Vec<&Task>> = HashMap::new();
+    for (key, task) in tasks(old) {
+        unmatched.entry(key).or_default().push(task);
+    }
+    for list in unmatched.values_mut() {
+        list.reverse();
+    }
+
+    let mut changes = Vec::new();
+    let mut added = Vec::new();
+    for (key, task) in tasks(new) {
+        let Some(before) = unmatched.get_mut(&key).and_then(|l| l.pop()) else {
+            added.push(key);
+            continue;
+        };
+        if before.state != task.state {
+            changes.push(Change::StateChanged {
+                address: address(key),
+                from: before.state,
+                to: task.state,
+            });
+        }
+        if before.notes != task.notes {
+            changes.push(Change::NotesChanged(address(key)));
+        }
+    }
+
+    // Leftover old tasks, in old document order
+    let mut removed: Vec<Key> = Vec::new();
+    for (key, _) in tasks(old) {
+        if unmatched.get_mut(&key).and_then(|l| l.pop()).is_some() {
+            removed.push(key);
+        }
+    }
+
+    // An addition whose text matches a removal elsewhere is a move
+    for key in added {
+        match removed.iter().position(|r| r.2 == key.2) {
+            Some(pos) => {
+                let from = removed.remove(pos);
+                changes.push(Change::Moved {
+                    from: address(from),
+                    to: address(key),
+                });
+            }
+            None => changes.push(Change::Added(address(key))),
+        }
+    }
+    changes.extend(removed.into_iter().map(|key| Change::Removed(address(key))));
+    changes
+}
+
+/// One-line description such as "3 tasks added, 1 completed".
+pub fn summary(changes: &[Change]) -> String {
+    let count = |f: fn(&Change) -> bool| changes.iter().filter(|c| f(c)).count();
+    let completed = count(|c| matches!(c, Change::StateChanged { to: TaskState::Done, .. }));
+    let parts = [
+        (count(|c| matches!(c, Change::Added(_))), "added"),
+        (count(|c| matches!(c, Change::Removed(_))), "removed"),
+        (count(|c| matches!(c, Change::Moved { .. })), "moved"),
+        (completed, "completed"),
+        (count(|c| matches!(c, Change::StateChanged { .. })) - completed, "changed state"),
+        (count(|c| matches!(c, Change::NotesChanged(_))), "with edited notes"),
+    ];
+
+    let mut out = Vec::new();
+    for (n, what) in parts {
+        if n == 0 {
+            continue;
+        }
+        if out.is_empty() {
+            out.push(format!("{} task{} {}", n, if n == 1 { "" } else { "s" }, what));
+        } else {
+            out.push(format!("{} {}", n, what));
+        }
+    }
+    out.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const BEFORE: &str = "\
+## Work
+
+### 🔶 Launch
+- 🔴 Write copy
+- 🔵 Pick a domain
+- 🔴 Write copy
+
+### Later
+- 🔴 Learn piano
+- 🔴 Drop me
+";
+
+    #[test]
+    fn test_diff_changes() {
+        let after = "\
+## Work
+
+### 🔶 Launch
+- 🔴 Write copy
+- ✅ Pick a domain
+  Bought gws.dev
+- 🔴 Write copy
+- 🔴 Learn piano
+- 🔴 Book venue
+
+### Later
+";
+        let changes = diff(&parse(BEFORE), &parse(after));
+        let addr = |project: &str, text: &str| TaskAddress {
+            category: "Work".to_string(),
+            project: project.to_string(),
+            text: text.to_string(),
+        };
+        assert_eq!(
+            changes,
+            vec![
+                Change::StateChanged {
+                    address: addr("Launch", "Pick a domain"),
+                    from: TaskState::OnDeck,
+                    to: TaskState::Done,
+                },
+                Change::NotesChanged(addr("Launch", "Pick a domain")),
+                Change::Moved {
+                    from: addr("Later", "Learn piano"),
+                    to: addr("Launch", "Learn piano"),
+                },
+                Change::Added(addr("Launch", "Book venue")),
+                Change::Removed(addr("Later", "Drop me")),
+            ]
+        );
+        assert_eq!(
+            summary(&changes),
+            "1 task added, 1 removed, 1 moved, 1 completed, 1 with edited notes"
+        );
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let doc = parse(BEFORE);
+        assert!(diff(&doc, &doc).is_empty());
+        assert_eq!(summary(&[]), "");
+    }
+}
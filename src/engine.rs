@@ -1,37 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activity::ActivityLog;
+use crate::date::Date;
+use crate::links;
 use crate::model::*;
+use crate::parser;
+
+/// Which Todo task auto-promote picks in each active project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromoteStrategy {
+    /// The first Todo in the list.
+    #[default]
+    TopOfList,
+    /// The Todo with the highest `!1`..`!9` priority; the first one on a tie
+    /// or when none has a priority.
+    HighestPriority,
+}
 
-/// Auto-promote: For each active project, scan tasks top-down.
-/// Skip ✅. If first 🔴 found, promote to 🔵, stop. If 🔵 or 🔶 already exists, stop.
-pub fn auto_promote(doc: &mut Document) {
-    for category in &mut doc.categories {
-        for project in &mut category.projects {
-            if !project.is_active() {
+impl PromoteStrategy {
+    pub fn label(self) -> &'static str {
+        match self {
+            PromoteStrategy::TopOfList => "Top of list",
+            PromoteStrategy::HighestPriority => "Highest priority",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            PromoteStrategy::TopOfList => PromoteStrategy::HighestPriority,
+            PromoteStrategy::HighestPriority => PromoteStrategy::TopOfList,
+        }
+    }
+}
+
+/// When auto-promote runs on its own; `r` runs it on demand in every mode
+/// but `Off`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromoteMode {
+    /// Never, not even on `r`.
+    Off,
+    /// Only when asked with `r`.
+    OnDemand,
+    /// When the file is opened or reloaded.
+    OnLoad,
+    /// On load and after every change.
+    #[default]
+    Continuous,
+}
+
+impl PromoteMode {
+    pub const ALL: [PromoteMode; 4] = [PromoteMode::Off, PromoteMode::OnDemand, PromoteMode::OnLoad, PromoteMode::Continuous];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PromoteMode::Off => "Off",
+            PromoteMode::OnDemand => "On demand (r)",
+            PromoteMode::OnLoad => "When the file loads",
+            PromoteMode::Continuous => "After every change",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Whether `r` promotes.
+    pub fn on_demand(self) -> bool {
+        self != PromoteMode::Off
+    }
+
+    /// Whether opening or reloading the file promotes.
+    pub fn on_load(self) -> bool {
+        matches!(self, PromoteMode::OnLoad | PromoteMode::Continuous)
+    }
+
+    /// Whether every change to the document promotes.
+    pub fn on_change(self) -> bool {
+        self == PromoteMode::Continuous
+    }
+}
+
+/// Auto-promote: For each active project with no 🔵 or 🔶 task, promote one
+/// 🔴 to 🔵, chosen by `strategy`. ✅ tasks are skipped. Returns where the
+/// promoted tasks are; empty when nothing changed.
+pub fn auto_promote(doc: &mut Document, strategy: PromoteStrategy) -> Vec<(usize, usize, usize)> {
+    let mut promoted = Vec::new();
+    for (ci, category) in doc.categories.iter_mut().enumerate().filter(|(_, c)| !c.hidden) {
+        for (pi, project) in category.projects.iter_mut().enumerate() {
+            // Checklists are worked through in any order
+            if !project.is_active() || project.checklist {
                 continue;
             }
-            for task in &mut project.tasks {
-                match task.state {
-                    TaskState::Done => continue,
-                    TaskState::OnDeck => break,
-                    TaskState::InProgress => break,
-                    TaskState::Todo => {
-                        task.state = TaskState::OnDeck;
-                        break;
+            if project.tasks.iter().any(|t| matches!(t.state, TaskState::OnDeck | TaskState::InProgress)) {
+                continue;
+            }
+            let todos = project.tasks.iter().enumerate().filter(|(_, t)| t.state == TaskState::Todo);
+            let pick = match strategy {
+                PromoteStrategy::TopOfList => todos.map(|(i, _)| i).next(),
+                // min_by_key keeps the first of equal keys; no priority sorts last
+                PromoteStrategy::HighestPriority => {
+                    todos.min_by_key(|(_, t)| t.priority().unwrap_or(u8::MAX)).map(|(i, _)| i)
+                }
+            };
+            if let Some(i) = pick {
+                project.tasks[i].state = TaskState::OnDeck;
+                promoted.push((ci, pi, i));
+            }
+        }
+    }
+    promoted
+}
+
+/// In Progress tasks that, per the activity log, went In Progress at least
+/// `days` days before `today`, with how many days ago that was. Tasks with no
+/// logged change are never stalled.
+pub fn stalled_tasks(doc: &Document, log: &ActivityLog, today: Date, days: i64) -> Vec<((usize, usize, usize), i64)> {
+    let mut stalled = Vec::new();
+    for (ci, category) in doc.categories.iter().enumerate().filter(|(_, c)| !c.hidden) {
+        for (pi, project) in category.projects.iter().enumerate() {
+            for (ti, task) in project.tasks.iter().enumerate() {
+                if task.state != TaskState::InProgress {
+                    continue;
+                }
+                if let Some(since) = log.last_change(&category.name, &project.name, &task.text) {
+                    let age = today.days_since(since);
+                    if age >= days {
+                        stalled.push(((ci, pi, ti), age));
                     }
                 }
             }
         }
     }
+    stalled
 }
 
 /// Archive: Collect all ✅ tasks from all projects, prepend to `## Done` section.
-pub fn archive_done(doc: &mut Document) {
+/// Each line records where the task lived and is stamped with `today`, e.g.
+/// `- ✅ [Work/Launch] Ship it (2026-03-10)`.
+/// Checklists keep their items; they are reset rather than archived.
+/// Returns how many tasks were archived.
+pub fn archive_done(doc: &mut Document, today: Date) -> usize {
     let mut archived: Vec<String> = Vec::new();
 
     for category in &mut doc.categories {
-        for project in &mut category.projects {
+        for project in category.projects.iter_mut().filter(|p| !p.checklist) {
             project.tasks.retain(|task| {
                 if task.state == TaskState::Done {
-                    archived.push(format!("- ✅ {}", task.text));
+                    archived.push(format!("- ✅ [{}/{}] {} ({})", category.name, project.name, task.text, today));
                     return false;
                 }
                 true
@@ -39,8 +157,123 @@ pub fn archive_done(doc: &mut Document) {
         }
     }
 
-    archived.append(&mut doc.archive);
-    doc.archive = archived;
+    let count = archived.len();
+    if count > 0 {
+        doc.archive.splice(0..0, archived);
+    }
+    count
+}
+
+/// Deferred tasks whose day has come: Todo tasks of active projects due
+/// on or before `today` move to On Deck. Returns where they are.
+pub fn resurface_due(doc: &mut Document, today: Date) -> Vec<(usize, usize, usize)> {
+    let mut resurfaced = Vec::new();
+    for (ci, category) in doc.categories.iter_mut().enumerate().filter(|(_, c)| !c.hidden) {
+        for (pi, project) in category.projects.iter_mut().enumerate().filter(|(_, p)| p.is_active()) {
+            for (ti, task) in project.tasks.iter_mut().enumerate() {
+                if task.state == TaskState::Todo && task.due().is_some_and(|due| due <= today) {
+                    task.state = TaskState::OnDeck;
+                    resurfaced.push((ci, pi, ti));
+                }
+            }
+        }
+    }
+    resurfaced
+}
+
+/// Retire a project: remove it from its category and record it whole, with
+/// every task as it stands and the date, at the top of `## Done Projects`.
+/// Returns the project.
+pub fn retire_project(doc: &mut Document, cat_idx: usize, proj_idx: usize, today: Date) -> Option<Project> {
+    let category = doc.categories.get_mut(cat_idx)?;
+    if proj_idx >= category.projects.len() {
+        return None;
+    }
+    let project = category.projects.remove(proj_idx);
+    let mut block = vec![
+        format!("{}{}", doc.levels.project_prefix(), project.name),
+        format!("Retired {} from {}", today, category.name),
+    ];
+    block.extend(project.notes.iter().cloned());
+    for task in &project.tasks {
+        block.push(format!("- {} {}", task.state.symbol(), task.text));
+        block.extend(task.notes.iter().cloned());
+    }
+    if !doc.done_projects.is_empty() {
+        block.push(String::new());
+    }
+    block.append(&mut doc.done_projects);
+    doc.done_projects = block;
+    Some(project)
+}
+
+/// Text and date of an archive line written by `archive_done`, e.g.
+/// `- ✅ [Work/Launch] Ship it (2026-03-10)`. Undated lines yield None.
+pub fn archived(line: &str) -> Option<(&str, Date)> {
+    let entry = parser::archive_entry(line)?;
+    Some((entry.text, entry.date?))
+}
+
+/// Reopen archive line `idx` as a Todo in the project it was archived from,
+/// for tasks that turn out not to be finished after all, re-creating the
+/// category or (inactive) project if it has gone since. Lines that don't
+/// record where they came from go to the inbox. Returns the project it
+/// landed in, or None if there was nowhere to put it.
+pub fn unarchive(doc: &mut Document, idx: usize) -> Option<(usize, usize)> {
+    let entry = parser::archive_entry(doc.archive.get(idx)?)?;
+    let text = entry.text.to_string();
+    let origin = entry.category.zip(entry.project).map(|(c, p)| (c.to_string(), p.to_string()));
+    let (ci, pi) = match origin {
+        Some((category, project)) => {
+            let ci = match doc.categories.iter().position(|c| c.name.eq_ignore_ascii_case(&category)) {
+                Some(ci) => ci,
+                None => {
+                    doc.categories.push(Category::new(category));
+                    doc.categories.len() - 1
+                }
+            };
+            let projects = &mut doc.categories[ci].projects;
+            let pi = match projects.iter().position(|p| p.name.eq_ignore_ascii_case(&project)) {
+                Some(pi) => pi,
+                None => {
+                    projects.push(Project::new(project, false));
+                    projects.len() - 1
+                }
+            };
+            (ci, pi)
+        }
+        None => doc.inbox()?,
+    };
+    doc.archive.remove(idx);
+    doc.categories[ci].projects[pi].tasks.push(Task::new(TaskState::Todo, text));
+    Some((ci, pi))
+}
+
+/// Set every item of a checklist project back to Todo.
+pub fn reset_checklist(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
+    let Some(project) = doc.categories.get_mut(cat_idx).and_then(|c| c.projects.get_mut(proj_idx)) else {
+        return false;
+    };
+    let mut changed = false;
+    for task in &mut project.tasks {
+        changed |= task.state != TaskState::Todo;
+        task.state = TaskState::Todo;
+    }
+    changed
+}
+
+/// Toggle whether a project is a reusable checklist.
+pub fn toggle_project_checklist(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
+    if let Some(project) = doc
+        .categories
+        .get_mut(cat_idx)
+        .and_then(|c| c.projects.get_mut(proj_idx))
+    {
+        project.checklist = !project.checklist;
+        true
+    } else {
+        false
+    }
 }
 
 /// Promote a specific task by 3-index address.
@@ -77,19 +310,46 @@ pub fn demote_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx
     false
 }
 
+/// Single-tasking: move every 🔶 task other than `keep` back to 🔵.
+/// Returns where the demoted tasks are.
+pub fn demote_other_in_progress(doc: &mut Document, keep: (usize, usize, usize)) -> Vec<(usize, usize, usize)> {
+    let mut demoted = Vec::new();
+    for (ci, category) in doc.categories.iter_mut().enumerate() {
+        for (pi, project) in category.projects.iter_mut().enumerate() {
+            for (ti, task) in project.tasks.iter_mut().enumerate() {
+                if task.state == TaskState::InProgress && (ci, pi, ti) != keep {
+                    task.state = TaskState::OnDeck;
+                    demoted.push((ci, pi, ti));
+                }
+            }
+        }
+    }
+    demoted
+}
+
+/// Default agenda section order.
+pub const DEFAULT_SECTIONS: [TaskState; 4] = [TaskState::InProgress, TaskState::OnDeck, TaskState::Done, TaskState::Todo];
+
 /// Build flat agenda: all tasks from active projects, sorted by section.
-pub fn build_agenda(doc: &Document) -> Vec<AgendaItem> {
-    let mut items: Vec<AgendaItem> = Vec::new();
+/// Only `sections` are shown, grouped in that order. Hidden categories are
+/// left out.
+pub fn build_agenda(doc: &Document, sections: &[TaskState]) -> Vec<AgendaItem> {
+    // One bucket per section, filled in document order, so no sort is needed
+    let mut buckets: Vec<Vec<AgendaItem>> = vec![Vec::new(); sections.len()];
 
-    for (cat_idx, category) in doc.categories.iter().enumerate() {
+    for (cat_idx, category) in doc.categories.iter().enumerate().filter(|(_, c)| !c.hidden) {
         for (proj_idx, project) in category.projects.iter().enumerate() {
             if !project.is_active() {
                 continue;
             }
             for (task_idx, task) in project.tasks.iter().enumerate() {
-                items.push(AgendaItem {
+                let Some(section) = sections.iter().position(|&s| s == task.state) else {
+                    continue;
+                };
+                buckets[section].push(AgendaItem {
                     project_name: project.name.clone(),
-                    task: task.clone(),
+                    // Rows show only state and text; notes stay in the document
+                    task: Task::new(task.state, task.text.clone()),
                     category_idx: cat_idx,
                     project_idx: proj_idx,
                     task_idx,
@@ -98,20 +358,243 @@ pub fn build_agenda(doc: &Document) -> Vec<AgendaItem> {
         }
     }
 
-    // Stable sort by section order: Todo=0, InProgress=1, OnDeck=2, Done=3
-    items.sort_by_key(|item| section_order(item.task.state));
+    buckets.into_iter().flatten().collect()
+}
 
-    items
+/// Count and estimated time of each section of a built agenda, in order.
+/// Tasks without an estimate count but add no time.
+pub fn section_totals(items: &[AgendaItem]) -> Vec<SectionTotal> {
+    let mut totals: Vec<SectionTotal> = Vec::new();
+    for item in items {
+        let minutes = item.task.estimate().unwrap_or(0);
+        match totals.last_mut() {
+            Some(total) if total.state == item.task.state => {
+                total.count += 1;
+                total.minutes += minutes;
+            }
+            _ => totals.push(SectionTotal { state: item.task.state, count: 1, minutes }),
+        }
+    }
+    totals
 }
 
-/// Section display order for agenda grouping.
-pub fn section_order(state: TaskState) -> u8 {
-    match state {
-        TaskState::InProgress => 0,
-        TaskState::OnDeck => 1,
-        TaskState::Done => 2,
-        TaskState::Todo => 3,
+/// Projects this close to their deadline (or past it) have their tasks
+/// moved up the agenda.
+pub const DEADLINE_BOOST_DAYS: i64 = 14;
+
+/// Within each agenda section, put tasks of projects due within
+/// `DEADLINE_BOOST_DAYS` first, nearest deadline first. Everything else keeps
+/// document order.
+pub fn boost_deadlines(doc: &Document, items: &mut [AgendaItem], today: Date) {
+    let urgency = |item: &AgendaItem| {
+        doc.categories[item.category_idx].projects[item.project_idx]
+            .days_left(today)
+            .filter(|&days| days <= DEADLINE_BOOST_DAYS)
+            .unwrap_or(i64::MAX)
+    };
+    for section in items.chunk_by_mut(|a, b| a.task.state == b.task.state) {
+        section.sort_by_key(urgency);
+    }
+}
+
+/// Project and task counts for one area of focus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AreaSummary {
+    pub name: String,
+    pub categories: usize,
+    pub projects: usize,
+    pub active_projects: usize,
+    pub open_tasks: usize,
+    /// Done tasks not yet archived, i.e. recent completions.
+    pub done_tasks: usize,
+}
+
+/// Roll categories up into areas, matching category names case-insensitively.
+/// Categories outside every area are gathered under "Unassigned".
+pub fn area_summaries(doc: &Document, areas: &[Area]) -> Vec<AreaSummary> {
+    let summarize = |name: &str, categories: Vec<&Category>| {
+        let projects = categories.iter().flat_map(|c| c.projects.iter());
+        let tasks = projects.clone().flat_map(|p| p.tasks.iter());
+        AreaSummary {
+            name: name.to_string(),
+            categories: categories.len(),
+            projects: projects.clone().count(),
+            active_projects: projects.filter(|p| p.active).count(),
+            open_tasks: tasks.clone().filter(|t| t.state != TaskState::Done).count(),
+            done_tasks: tasks.filter(|t| t.state == TaskState::Done).count(),
+        }
+    };
+    let in_area = |area: &Area, category: &Category| area.categories.iter().any(|n| n.eq_ignore_ascii_case(&category.name));
+
+    let mut summaries: Vec<AreaSummary> = areas
+        .iter()
+        .map(|area| summarize(&area.name, doc.categories.iter().filter(|c| in_area(area, c)).collect()))
+        .collect();
+    let unassigned: Vec<&Category> = doc
+        .categories
+        .iter()
+        .filter(|c| !areas.iter().any(|a| in_area(a, c)))
+        .collect();
+    if !unassigned.is_empty() {
+        summaries.push(summarize("Unassigned", unassigned));
+    }
+    summaries
+}
+
+/// Someone mentioned as `@Name`, with the tasks that mention them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Person {
+    /// Spelling of the first mention in document order.
+    pub name: String,
+    /// Mentioning tasks as (category, project, task) indices.
+    pub tasks: Vec<(usize, usize, usize)>,
+    pub open_tasks: usize,
+}
+
+/// Index `@Name` mentions in task text and notes, matching names
+/// case-insensitively. People with the most open tasks come first.
+pub fn people(doc: &Document) -> Vec<Person> {
+    let mut people: Vec<Person> = Vec::new();
+    for (ci, category) in doc.categories.iter().enumerate() {
+        for (pi, project) in category.projects.iter().enumerate() {
+            for (ti, task) in project.tasks.iter().enumerate() {
+                let lines = std::iter::once(&task.text).chain(&task.notes);
+                for name in lines.flat_map(|line| mentions(line)) {
+                    let idx = match people.iter().position(|p| p.name.eq_ignore_ascii_case(name)) {
+                        Some(idx) => idx,
+                        None => {
+                            people.push(Person {
+                                name: name.to_string(),
+                                tasks: Vec::new(),
+                                open_tasks: 0,
+                            });
+                            people.len() - 1
+                        }
+                    };
+                    let person = &mut people[idx];
+                    if person.tasks.last() != Some(&(ci, pi, ti)) {
+                        person.tasks.push((ci, pi, ti));
+                        if task.state != TaskState::Done {
+                            person.open_tasks += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    people.sort_by(|a, b| b.open_tasks.cmp(&a.open_tasks).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    people
+}
+
+/// A `#tag` in use, with the tasks carrying it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    /// Spelling of the first use in document order.
+    pub name: String,
+    /// Tagged tasks as (category, project, task) indices.
+    pub tasks: Vec<(usize, usize, usize)>,
+    pub open_tasks: usize,
+}
+
+/// Index `#tag`s in task text and notes, matching case-insensitively. The
+/// most used tags come first.
+pub fn tag_index(doc: &Document) -> Vec<Tag> {
+    let mut index: Vec<Tag> = Vec::new();
+    for (ci, category) in doc.categories.iter().enumerate() {
+        for (pi, project) in category.projects.iter().enumerate() {
+            for (ti, task) in project.tasks.iter().enumerate() {
+                let lines = std::iter::once(&task.text).chain(&task.notes);
+                for name in lines.flat_map(|line| tags(line)) {
+                    let idx = match index.iter().position(|t| t.name.eq_ignore_ascii_case(name)) {
+                        Some(idx) => idx,
+                        None => {
+                            index.push(Tag {
+                                name: name.to_string(),
+                                tasks: Vec::new(),
+                                open_tasks: 0,
+                            });
+                            index.len() - 1
+                        }
+                    };
+                    let tag = &mut index[idx];
+                    if tag.tasks.last() != Some(&(ci, pi, ti)) {
+                        tag.tasks.push((ci, pi, ti));
+                        if task.state != TaskState::Done {
+                            tag.open_tasks += 1;
+                        }
+                    }
+                }
+            }
+        }
     }
+    index.sort_by(|a, b| b.tasks.len().cmp(&a.tasks.len()).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    index
+}
+
+/// Rewrite `#tag` as `#new_name` in every task and note. Returns how many
+/// tasks changed.
+pub fn rename_tag(doc: &mut Document, tag: &str, new_name: &str) -> usize {
+    replace_tag(doc, tag, Some(new_name))
+}
+
+/// Remove `#tag` from every task and note, with the space before it. A task
+/// whose text is nothing but the tag keeps it there. Returns how many tasks
+/// changed.
+pub fn delete_tag(doc: &mut Document, tag: &str) -> usize {
+    replace_tag(doc, tag, None)
+}
+
+fn replace_tag(doc: &mut Document, tag: &str, new_name: Option<&str>) -> usize {
+    let mut changed = 0;
+    for task in doc.categories.iter_mut().flat_map(|c| &mut c.projects).flat_map(|p| &mut p.tasks) {
+        let mut touched = false;
+        for (i, line) in std::iter::once(&mut task.text).chain(&mut task.notes).enumerate() {
+            let Some(replaced) = replace_tag_in(line, tag, new_name) else {
+                continue;
+            };
+            if i == 0 && replaced.trim().is_empty() {
+                continue;
+            }
+            *line = replaced;
+            touched = true;
+        }
+        changed += touched as usize;
+    }
+    changed
+}
+
+/// `line` with each `#tag` renamed, or removed when `new_name` is None;
+/// None when the tag isn't there.
+fn replace_tag_in(line: &str, tag: &str, new_name: Option<&str>) -> Option<String> {
+    let spans: Vec<(usize, usize)> = tag_spans(line).filter(|&(start, end)| line[start..end].eq_ignore_ascii_case(tag)).collect();
+    if spans.is_empty() {
+        return None;
+    }
+    let mut out = String::new();
+    let mut pos = 0;
+    for (start, end) in spans {
+        let hash = start - 1;
+        match new_name {
+            Some(name) => {
+                out.push_str(&line[pos..start]);
+                out.push_str(name);
+                pos = end;
+            }
+            // Take one neighbouring space along so no double space is left
+            None => match line[pos..hash].strip_suffix(' ') {
+                Some(before) => {
+                    out.push_str(before);
+                    pos = end;
+                }
+                None => {
+                    out.push_str(&line[pos..hash]);
+                    pos = end + line[end..].starts_with(' ') as usize;
+                }
+            },
+        }
+    }
+    out.push_str(&line[pos..]);
+    Some(out)
 }
 
 /// Add a new Todo task to a project.
@@ -128,6 +611,36 @@ pub fn add_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, text: Strin
     }
 }
 
+/// Task texts from a pasted list: one per non-empty line, with bullets
+/// (`-`, `*`, `+`, `•`), numbering (`1.`, `1)`), checkboxes (`[ ]`, `[x]`)
+/// and task symbols stripped from the front.
+pub fn pasted_tasks(text: &str) -> Vec<String> {
+    let strip_marker = |line: &str| -> Option<usize> {
+        for marker in ["- ", "* ", "+ ", "• ", "[ ] ", "[x] ", "[X] "] {
+            if line.starts_with(marker) {
+                return Some(marker.len());
+            }
+        }
+        if let Some(symbol) = DEFAULT_SECTIONS.iter().map(|s| s.symbol()).find(|sym| line.starts_with(sym)) {
+            return Some(symbol.len());
+        }
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let after = &line[digits..];
+        (digits > 0 && (after.starts_with(". ") || after.starts_with(") "))).then_some(digits + 2)
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut rest = line.trim();
+            // A bullet can be followed by a checkbox, a symbol, or both
+            while let Some(len) = strip_marker(rest) {
+                rest = rest[len..].trim_start();
+            }
+            // An empty bullet is a blank line too
+            (!rest.is_empty() && !["-", "*", "+", "•"].contains(&rest)).then(|| rest.to_string())
+        })
+        .collect()
+}
+
 /// Toggle project active/inactive.
 pub fn toggle_project_active(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
     if let Some(project) = doc
@@ -158,22 +671,35 @@ pub fn delete_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx
         .categories
         .get_mut(cat_idx)
         .and_then(|c| c.projects.get_mut(proj_idx))
+        && task_idx < project.tasks.len()
     {
-        if task_idx < project.tasks.len() {
-            project.tasks.remove(task_idx);
-            return true;
-        }
+        project.tasks.remove(task_idx);
+        return true;
     }
     false
 }
 
+/// Move a task to the end of another project. Returns its new index.
+pub fn move_task_to_project(doc: &mut Document, from: (usize, usize, usize), to: (usize, usize)) -> Option<usize> {
+    let (ci, pi, ti) = from;
+    doc.categories.get(to.0)?.projects.get(to.1)?;
+    let project = doc.categories.get_mut(ci)?.projects.get_mut(pi)?;
+    if ti >= project.tasks.len() || (ci, pi) == to {
+        return None;
+    }
+    let task = project.tasks.remove(ti);
+    let target = &mut doc.categories[to.0].projects[to.1].tasks;
+    target.push(task);
+    Some(target.len() - 1)
+}
+
 /// Delete a project.
 pub fn delete_project(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
-    if let Some(category) = doc.categories.get_mut(cat_idx) {
-        if proj_idx < category.projects.len() {
-            category.projects.remove(proj_idx);
-            return true;
-        }
+    if let Some(category) = doc.categories.get_mut(cat_idx)
+        && proj_idx < category.projects.len()
+    {
+        category.projects.remove(proj_idx);
+        return true;
     }
     false
 }
@@ -194,6 +720,56 @@ pub fn rerank_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx
     Some(new_idx)
 }
 
+/// Sink ✅ tasks below the rest of their project, keeping both groups in
+/// order. Checklists are left alone so their steps stay in sequence.
+/// Returns whether anything moved.
+pub fn sink_done(doc: &mut Document) -> bool {
+    let mut moved = false;
+    for project in doc.categories.iter_mut().flat_map(|c| c.projects.iter_mut()) {
+        let unsorted = project
+            .tasks
+            .windows(2)
+            .any(|w| w[0].state == TaskState::Done && w[1].state != TaskState::Done);
+        if project.checklist || !unsorted {
+            continue;
+        }
+        let (open, done): (Vec<_>, Vec<_>) = project.tasks.drain(..).partition(|t| t.state != TaskState::Done);
+        project.tasks = open;
+        project.tasks.extend(done);
+        moved = true;
+    }
+    moved
+}
+
+/// Move a task to the end of its project. Returns its new index, or `None`
+/// if it is already last.
+pub fn sink_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx: usize) -> Option<usize> {
+    let tasks = &mut doc.categories.get_mut(cat_idx)?.projects.get_mut(proj_idx)?.tasks;
+    if task_idx + 1 >= tasks.len() {
+        return None;
+    }
+    tasks[task_idx..].rotate_left(1);
+    Some(tasks.len() - 1)
+}
+
+/// Move the tasks `start..=end` of a project one place up or down as a
+/// block. Returns the block's new start.
+pub fn move_task_block(doc: &mut Document, cat_idx: usize, proj_idx: usize, start: usize, end: usize, direction: i32) -> Option<usize> {
+    let tasks = &mut doc.categories.get_mut(cat_idx)?.projects.get_mut(proj_idx)?.tasks;
+    if start > end || end >= tasks.len() {
+        return None;
+    }
+    if direction < 0 && start > 0 {
+        tasks[start - 1..=end].rotate_left(1);
+        Some(start - 1)
+    } else if direction > 0 && end + 1 < tasks.len() {
+        tasks[start..=end + 1].rotate_right(1);
+        Some(start + 1)
+    } else {
+        None
+    }
+}
+
 /// Rerank a project within its category (direction: -1 = up, 1 = down).
 pub fn rerank_project(doc: &mut Document, cat_idx: usize, proj_idx: usize, direction: i32) -> Option<usize> {
     let category = doc.categories.get_mut(cat_idx)?;
@@ -221,32 +797,34 @@ pub fn move_project_to_category(doc: &mut Document, from_cat: usize, proj_idx: u
     Some((to_cat, idx))
 }
 
-/// Rename a task.
+/// Rename a task. False if it is missing or already has that text.
 pub fn rename_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx: usize, new_text: String) -> bool {
-    if let Some(task) = doc
+    match doc
         .categories
         .get_mut(cat_idx)
         .and_then(|c| c.projects.get_mut(proj_idx))
         .and_then(|p| p.tasks.get_mut(task_idx))
     {
-        task.text = new_text;
-        true
-    } else {
-        false
+        Some(task) if task.text != new_text => {
+            task.text = new_text;
+            true
+        }
+        _ => false,
     }
 }
 
-/// Rename a project.
+/// Rename a project. False if it is missing or already has that name.
 pub fn rename_project(doc: &mut Document, cat_idx: usize, proj_idx: usize, new_name: String) -> bool {
-    if let Some(project) = doc
+    match doc
         .categories
         .get_mut(cat_idx)
         .and_then(|c| c.projects.get_mut(proj_idx))
     {
-        project.name = new_name;
-        true
-    } else {
-        false
+        Some(project) if project.name != new_name => {
+            project.name = new_name;
+            true
+        }
+        _ => false,
     }
 }
 
@@ -265,13 +843,14 @@ pub fn remove_category(doc: &mut Document, cat_idx: usize) -> bool {
     }
 }
 
-/// Rename a category.
+/// Rename a category. False if it is missing or already has that name.
 pub fn rename_category(doc: &mut Document, cat_idx: usize, new_name: String) -> bool {
-    if let Some(category) = doc.categories.get_mut(cat_idx) {
-        category.name = new_name;
-        true
-    } else {
-        false
+    match doc.categories.get_mut(cat_idx) {
+        Some(category) if category.name != new_name => {
+            category.name = new_name;
+            true
+        }
+        _ => false,
     }
 }
 
@@ -308,19 +887,370 @@ pub fn delete_task_note(doc: &mut Document, cat_idx: usize, proj_idx: usize, tas
         .get_mut(cat_idx)
         .and_then(|c| c.projects.get_mut(proj_idx))
         .and_then(|p| p.tasks.get_mut(task_idx))
+        && note_idx < task.notes.len()
     {
-        if note_idx < task.notes.len() {
-            task.notes.remove(note_idx);
-            return true;
-        }
+        task.notes.remove(note_idx);
+        return true;
     }
     false
 }
 
+/// Insert a note on a task at `note_idx` (at most the end). Returns where
+/// it went.
+pub fn insert_task_note(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx: usize, note_idx: usize, note: String) -> Option<usize> {
+    let task = doc.categories.get_mut(cat_idx)?.projects.get_mut(proj_idx)?.tasks.get_mut(task_idx)?;
+    let note_idx = note_idx.min(task.notes.len());
+    task.notes.insert(note_idx, format!("  {}", note));
+    Some(note_idx)
+}
+
+/// Rerank a note within its task (direction: -1 = up, 1 = down).
+pub fn rerank_note(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx: usize, note_idx: usize, direction: i32) -> Option<usize> {
+    let task = doc.categories.get_mut(cat_idx)?.projects.get_mut(proj_idx)?.tasks.get_mut(task_idx)?;
+    let new_idx = note_idx as i32 + direction;
+    if note_idx >= task.notes.len() || new_idx < 0 || new_idx >= task.notes.len() as i32 {
+        return None;
+    }
+    let new_idx = new_idx as usize;
+    task.notes.swap(note_idx, new_idx);
+    Some(new_idx)
+}
+
+/// Turn a note into a Todo task placed right after its task, dropping a
+/// leading `- ` or `* ` bullet. Returns the new task's index; None for a
+/// blank note.
+pub fn note_to_task(doc: &mut Document, cat_idx: usize, proj_idx: usize, task_idx: usize, note_idx: usize) -> Option<usize> {
+    let project = doc.categories.get_mut(cat_idx)?.projects.get_mut(proj_idx)?;
+    let note = project.tasks.get(task_idx)?.notes.get(note_idx)?.trim();
+    let text = note.strip_prefix("- ").or_else(|| note.strip_prefix("* ")).unwrap_or(note).trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    project.tasks[task_idx].notes.remove(note_idx);
+    project.tasks.insert(task_idx + 1, Task::new(TaskState::Todo, text));
+    Some(task_idx + 1)
+}
+
+/// Tag that keeps a task, project or category out of a shared copy.
+pub const PRIVATE_TAG: &str = "private";
+
+/// A copy of `doc` to show others (`gws export --share`): no notes, no
+/// preamble beyond the title, no retired projects (kept with their notes),
+/// nothing after the archive, and nothing tagged `#private`: such tasks,
+/// projects and categories, and archived lines, are left out.
+pub fn share_copy(doc: &Document) -> Document {
+    let private = |text: &str| tags(text).any(|tag| tag.eq_ignore_ascii_case(PRIVATE_TAG));
+    let mut shared = doc.clone();
+    shared.preamble = doc.title_idx().map(|i| vec![doc.preamble[i].clone(), String::new()]).unwrap_or_default();
+    shared.trailing.clear();
+    shared.done_projects.clear();
+    shared.archive.retain(|line| !private(line));
+    shared.categories.retain(|c| !private(&c.name));
+    for category in &mut shared.categories {
+        category.notes.clear();
+        category.projects.retain(|p| !private(&p.name));
+        for project in &mut category.projects {
+            project.notes.clear();
+            project.tasks.retain(|t| !t.has_tag(PRIVATE_TAG));
+            for task in &mut project.tasks {
+                task.notes.clear();
+            }
+        }
+    }
+    shared
+}
+
+/// The links that make up a whole task or note, each once, in document
+/// order.
+pub fn bare_links(doc: &Document) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    let tasks = doc.categories.iter().flat_map(|c| &c.projects).flat_map(|p| &p.tasks);
+    for line in tasks.flat_map(|t| std::iter::once(&t.text).chain(&t.notes)) {
+        if let Some(url) = links::bare_url(line).filter(|url| !urls.iter().any(|u| u == url)) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// Rewrite every task or note that is just `url` as `Title (url)`, keeping a
+/// note's indent. Returns how many changed.
+pub fn title_link(doc: &mut Document, url: &str, title: &str) -> usize {
+    let mut changed = 0;
+    let tasks = doc.categories.iter_mut().flat_map(|c| &mut c.projects).flat_map(|p| &mut p.tasks);
+    for line in tasks.flat_map(|t| std::iter::once(&mut t.text).chain(&mut t.notes)) {
+        if links::bare_url(line) == Some(url) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            *line = format!("{}{} ({})", indent, title, url);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Merge the task at `from` into the task at `into`: `into` keeps its text
+/// and position, takes the more advanced of the two states, and gains
+/// `from`'s notes (led by `from`'s text when it differs); `from` is removed.
+/// Returns the merged task's index, which shifts if `from` sat above it.
+pub fn merge_tasks(doc: &mut Document, from: (usize, usize, usize), into: (usize, usize, usize)) -> Option<usize> {
+    let task = |doc: &Document, (ci, pi, ti): (usize, usize, usize)| {
+        doc.categories.get(ci)?.projects.get(pi)?.tasks.get(ti).cloned()
+    };
+    if from == into {
+        return None;
+    }
+    let source = task(doc, from)?;
+    let target = task(doc, into)?;
+
+    let merged = &mut doc.categories[into.0].projects[into.1].tasks[into.2];
+    merged.state = merged.state.max(source.state);
+    if source.text != target.text {
+        merged.notes.push(format!("  {}", source.text));
+    }
+    merged.notes.extend(source.notes);
+
+    doc.categories[from.0].projects[from.1].tasks.remove(from.2);
+    let same_project = (from.0, from.1) == (into.0, into.1);
+    Some(if same_project && from.2 < into.2 { into.2 - 1 } else { into.2 })
+}
+
+/// A category, or a project within one, whose name repeats an earlier one
+/// (ignoring case). `original` indexes the earlier category, or the earlier
+/// project of the same category; `proj_idx` is None for a category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Duplicate {
+    pub cat_idx: usize,
+    pub proj_idx: Option<usize>,
+    pub original: usize,
+}
+
+fn first_match<'a>(mut names: impl Iterator<Item = &'a str>, name: &str) -> Option<usize> {
+    names.position(|n| n.eq_ignore_ascii_case(name))
+}
+
+/// Categories and projects sharing a name, in document order.
+pub fn duplicates(doc: &Document) -> Vec<Duplicate> {
+    let mut found = Vec::new();
+    for (ci, category) in doc.categories.iter().enumerate() {
+        let earlier = doc.categories[..ci].iter().map(|c| c.name.as_str());
+        if let Some(original) = first_match(earlier, &category.name) {
+            found.push(Duplicate { cat_idx: ci, proj_idx: None, original });
+        }
+        for (pi, project) in category.projects.iter().enumerate() {
+            let earlier = category.projects[..pi].iter().map(|p| p.name.as_str());
+            if let Some(original) = first_match(earlier, &project.name) {
+                found.push(Duplicate { cat_idx: ci, proj_idx: Some(pi), original });
+            }
+        }
+    }
+    found
+}
+
+/// `name (2)`, `name (3)`, ...: the first not in `taken`.
+fn unique_name(name: &str, taken: &[&str]) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| first_match(taken.iter().copied(), candidate).is_none())
+        .expect("unbounded range")
+}
+
+/// Give each duplicate a numbered name, `Launch (2)`. Returns how many were renamed.
+pub fn rename_duplicates(doc: &mut Document) -> usize {
+    let found = duplicates(doc);
+    for dup in &found {
+        match dup.proj_idx {
+            None => {
+                let taken: Vec<&str> = doc.categories.iter().map(|c| c.name.as_str()).collect();
+                let name = unique_name(&doc.categories[dup.cat_idx].name, &taken);
+                doc.categories[dup.cat_idx].name = name;
+            }
+            Some(pi) => {
+                let category = &doc.categories[dup.cat_idx];
+                let taken: Vec<&str> = category.projects.iter().map(|p| p.name.as_str()).collect();
+                let name = unique_name(&category.projects[pi].name, &taken);
+                doc.categories[dup.cat_idx].projects[pi].name = name;
+            }
+        }
+    }
+    found.len()
+}
+
+/// Fold each duplicate into the earlier one of the same name: a category's
+/// notes and projects move over, then projects sharing a name are combined.
+/// A task whose text the earlier project already has is merged into it
+/// (more advanced state, notes appended); the rest are appended. The merged
+/// project is active, a checklist, or has a deadline if either was.
+/// Returns how many duplicates were folded in.
+pub fn merge_duplicates(doc: &mut Document) -> usize {
+    let mut merged = 0;
+    for dup in duplicates(doc).iter().rev().filter(|d| d.proj_idx.is_none()) {
+        let category = doc.categories.remove(dup.cat_idx);
+        let target = &mut doc.categories[dup.original];
+        target.notes.extend(category.notes);
+        target.projects.extend(category.projects);
+        merged += 1;
+    }
+    // Category merges can line up new project duplicates; list them afresh
+    for dup in duplicates(doc).iter().rev() {
+        let Some(pi) = dup.proj_idx else { continue };
+        let projects = &mut doc.categories[dup.cat_idx].projects;
+        let project = projects.remove(pi);
+        let target = &mut projects[dup.original];
+        target.active |= project.active;
+        target.checklist |= project.checklist;
+        target.deadline = match (target.deadline, project.deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        target.notes.extend(project.notes);
+        for task in project.tasks {
+            match target.tasks.iter_mut().find(|t| t.text == task.text) {
+                Some(existing) => {
+                    existing.state = existing.state.max(task.state);
+                    existing.notes.extend(task.notes);
+                }
+                None => target.tasks.push(task),
+            }
+        }
+        merged += 1;
+    }
+    merged
+}
+
+/// Sort the projects of a category alphabetically (case-insensitive).
+/// Returns true if the order changed.
+pub fn sort_projects_by_name(doc: &mut Document, cat_idx: usize) -> bool {
+    let Some(category) = doc.categories.get_mut(cat_idx) else {
+        return false;
+    };
+    let before: Vec<String> = category.projects.iter().map(|p| p.name.clone()).collect();
+    category.projects.sort_by_key(|p| p.name.to_lowercase());
+    category.projects.iter().map(|p| &p.name).ne(before.iter())
+}
+
+/// Move active projects ahead of inactive ones, keeping relative order.
+/// Returns true if the order changed.
+pub fn sort_projects_active_first(doc: &mut Document, cat_idx: usize) -> bool {
+    let Some(category) = doc.categories.get_mut(cat_idx) else {
+        return false;
+    };
+    let before: Vec<String> = category.projects.iter().map(|p| p.name.clone()).collect();
+    category.projects.sort_by_key(|p| !p.active);
+    category.projects.iter().map(|p| &p.name).ne(before.iter())
+}
+
+/// Rank used when sorting a project's tasks by state.
+fn state_sort_rank(state: TaskState) -> u8 {
+    match state {
+        TaskState::InProgress => 0,
+        TaskState::OnDeck => 1,
+        TaskState::Todo => 2,
+        TaskState::Done => 3,
+    }
+}
+
+/// Sort a project's tasks by state (In Progress, On Deck, Todo, Done), keeping
+/// relative order within each state. Returns true if the order changed.
+pub fn sort_tasks_by_state(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
+    let Some(project) = doc
+        .categories
+        .get_mut(cat_idx)
+        .and_then(|c| c.projects.get_mut(proj_idx))
+    else {
+        return false;
+    };
+    let before = project.tasks.clone();
+    project.tasks.sort_by_key(|t| state_sort_rank(t.state));
+    project.tasks != before
+}
+
+/// Sort a project's tasks by priority (`!1` first), tasks without one last,
+/// keeping relative order among equals. Returns true if the order changed.
+pub fn sort_tasks_by_priority(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
+    let Some(project) = doc
+        .categories
+        .get_mut(cat_idx)
+        .and_then(|c| c.projects.get_mut(proj_idx))
+    else {
+        return false;
+    };
+    let before = project.tasks.clone();
+    project.tasks.sort_by_key(|t| {
+        let priority = t.priority();
+        (priority.is_none(), priority)
+    });
+    project.tasks != before
+}
+
+/// Sort a project's tasks by due date, soonest first and undated tasks last,
+/// keeping relative order among equals. Returns true if the order changed.
+pub fn sort_tasks_by_due(doc: &mut Document, cat_idx: usize, proj_idx: usize) -> bool {
+    let Some(project) = doc
+        .categories
+        .get_mut(cat_idx)
+        .and_then(|c| c.projects.get_mut(proj_idx))
+    else {
+        return false;
+    };
+    let before = project.tasks.clone();
+    project.tasks.sort_by_key(|t| {
+        let due = t.due();
+        (due.is_none(), due)
+    });
+    project.tasks != before
+}
+
+/// Set the document title (`# Title` line), inserting it at the top of the preamble if absent.
+/// False if the title was already that.
+pub fn set_title(doc: &mut Document, title: String) -> bool {
+    let line = format!("# {}", title);
+    match doc.title_idx() {
+        Some(idx) if doc.preamble[idx] == line => return false,
+        Some(idx) => doc.preamble[idx] = line,
+        None => {
+            let needs_gap = doc.preamble.first().is_some_and(|l| !l.trim().is_empty());
+            doc.preamble.insert(0, line);
+            if needs_gap {
+                doc.preamble.insert(1, String::new());
+            }
+        }
+    }
+    true
+}
+
+/// Insert a preamble line at `line_idx` (clamped to the end). Returns the index used.
+pub fn insert_preamble_line(doc: &mut Document, line_idx: usize, text: String) -> usize {
+    let idx = line_idx.min(doc.preamble.len());
+    doc.preamble.insert(idx, text);
+    idx
+}
+
+/// Replace the text of a preamble line. False if it is missing or unchanged.
+pub fn set_preamble_line(doc: &mut Document, line_idx: usize, text: String) -> bool {
+    match doc.preamble.get_mut(line_idx) {
+        Some(line) if *line != text => {
+            *line = text;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Delete a preamble line.
+pub fn delete_preamble_line(doc: &mut Document, line_idx: usize) -> bool {
+    if line_idx < doc.preamble.len() {
+        doc.preamble.remove(line_idx);
+        true
+    } else {
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::parse;
+    use crate::serializer::serialize;
 
     fn sample_doc() -> Document {
         parse(
@@ -344,7 +1274,7 @@ mod tests {
     #[test]
     fn test_auto_promote_basic() {
         let mut doc = sample_doc();
-        auto_promote(&mut doc);
+        auto_promote(&mut doc, PromoteStrategy::TopOfList);
 
         let alpha = &doc.categories[0].projects[0];
         assert_eq!(alpha.tasks[0].state, TaskState::OnDeck); // 🔴 → 🔵
@@ -358,15 +1288,91 @@ mod tests {
         assert_eq!(inactive.tasks[0].state, TaskState::Todo); // not touched
     }
 
+    #[test]
+    fn test_auto_promote_strategies() {
+        let doc = parse(
+            "\
+## Work
+
+### 🔶 Launch
+- ✅ Pick a domain !1
+- 🔴 Write copy
+- 🔴 Book venue !3
+- 🔴 Sign contract !2
+- 🔴 Order swag !2
+
+### 🔶 Hiring
+- 🔴 Post job
+- 🔴 Screen resumes
+",
+        );
+        let promoted = |strategy| {
+            let mut doc = doc.clone();
+            auto_promote(&mut doc, strategy);
+            doc.categories[0]
+                .projects
+                .iter()
+                .flat_map(|p| &p.tasks)
+                .filter(|t| t.state == TaskState::OnDeck)
+                .map(|t| t.text.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(promoted(PromoteStrategy::TopOfList), ["Write copy", "Post job"]);
+        // Ties go to the first; without priorities it is the top of the list
+        assert_eq!(promoted(PromoteStrategy::HighestPriority), ["Sign contract !2", "Post job"]);
+    }
+
+    #[test]
+    fn test_stalled_tasks() {
+        let v1 = parse("## Work\n\n### 🔶 Launch\n- 🔵 Write copy\n- 🔴 Book venue\n- 🔴 Untracked\n");
+        let v2 = parse("## Work\n\n### 🔶 Launch\n- 🔶 Write copy\n- 🔶 Book venue\n- 🔶 Untracked\n");
+        let day = |d| Date::from_ymd(2026, 3, d).unwrap();
+        let mut log = ActivityLog::default();
+        log.record(&v1, &v2, day(1));
+        // Book venue went back to On Deck and then In Progress again on the 8th
+        log.events.retain(|e| e.task.as_deref() != Some("Untracked"));
+        log.events.push(crate::activity::Event { date: "2026-03-08".to_string(), ..log.events[1].clone() });
+
+        assert_eq!(stalled_tasks(&v2, &log, day(10), 7), [((0, 0, 0), 9)]);
+        assert_eq!(stalled_tasks(&v2, &log, day(10), 2).len(), 2);
+        assert!(stalled_tasks(&v1, &log, day(10), 2).is_empty());
+    }
+
     #[test]
     fn test_auto_promote_idempotent() {
         let mut doc = sample_doc();
-        auto_promote(&mut doc);
+        assert!(!auto_promote(&mut doc, PromoteStrategy::TopOfList).is_empty());
         let after_first = doc.clone();
-        auto_promote(&mut doc);
+        assert!(auto_promote(&mut doc, PromoteStrategy::TopOfList).is_empty());
         assert_eq!(doc, after_first);
     }
 
+    #[test]
+    fn test_unarchive() {
+        let mut doc = parse(
+            "## Work\n\n### 🔶 Launch\n- 🔴 Book venue\n\n### Inbox\n\n## Done\n- ✅ [Work/Launch] Ship it (2026-03-10)\n- ✅ [Home/Chores] Fix sink (2026-03-09)\n- ✅ Old archive\n",
+        );
+        assert_eq!(unarchive(&mut doc, 0), Some((0, 0)));
+        assert_eq!(doc.categories[0].projects[0].tasks[1], Task::new(TaskState::Todo, "Ship it".to_string()));
+        // The category and project are gone, so they come back
+        assert_eq!(unarchive(&mut doc, 0), Some((1, 0)));
+        assert_eq!(doc.categories[1].name, "Home");
+        assert!(!doc.categories[1].projects[0].active);
+        assert_eq!(unarchive(&mut doc, 0), Some((0, 1)));
+        assert!(doc.archive.is_empty());
+        assert_eq!(unarchive(&mut doc, 0), None);
+    }
+
+    #[test]
+    fn test_pasted_tasks() {
+        let pasted = "- Call plumber\n\n  * [ ] Renew passport\n3. Book flights\n4) 🔴 Pack\n• [x] Water plants\nPlain line  \n-\n";
+        assert_eq!(
+            pasted_tasks(pasted),
+            ["Call plumber", "Renew passport", "Book flights", "Pack", "Water plants", "Plain line"]
+        );
+        assert_eq!(pasted_tasks("2024 budget review"), ["2024 budget review"]);
+    }
+
     #[test]
     fn test_archive_done() {
         let mut doc = parse(
@@ -382,10 +1388,12 @@ mod tests {
 ",
         );
 
-        archive_done(&mut doc);
+        assert_eq!(archive_done(&mut doc, Date::from_ymd(2026, 3, 10).unwrap()), 1);
         assert_eq!(doc.categories[0].projects[0].tasks.len(), 1);
-        assert!(doc.archive.iter().any(|l| l.contains("Already done")));
+        assert_eq!(doc.archive[0], "- ✅ [Work/Project] Already done (2026-03-10)");
+        assert_eq!(archived(&doc.archive[0]), Some(("Already done", Date::from_ymd(2026, 3, 10).unwrap())));
         assert!(doc.archive.iter().any(|l| l.contains("Old archive")));
+        assert_eq!(archive_done(&mut doc, Date::from_ymd(2026, 3, 11).unwrap()), 0);
     }
 
     #[test]
@@ -405,8 +1413,8 @@ mod tests {
     #[test]
     fn test_build_agenda() {
         let mut doc = sample_doc();
-        auto_promote(&mut doc);
-        let agenda = build_agenda(&doc);
+        auto_promote(&mut doc, PromoteStrategy::TopOfList);
+        let agenda = build_agenda(&doc, &DEFAULT_SECTIONS);
 
         // Alpha: [OnDeck, Todo], Beta: [OnDeck, Todo] — inactive project excluded
         // Sorted by section: OnDeck(1), Todo(3)
@@ -417,6 +1425,35 @@ mod tests {
         assert_eq!(agenda[3].task.state, TaskState::Todo);
     }
 
+    #[test]
+    fn test_boost_deadlines() {
+        let doc = parse(
+            "\
+## Work
+
+### 🔶 Someday
+- 🔵 Tidy up
+- 🔴 Plan
+
+### 🔶 Launch due:2026-03-20
+- 🔵 Write copy
+- 🔴 Book venue
+
+### 🔶 Audit due:2026-03-12
+- 🔴 Collect receipts
+",
+        );
+        let order = |day| {
+            let mut agenda = build_agenda(&doc, &DEFAULT_SECTIONS);
+            boost_deadlines(&doc, &mut agenda, Date::from_ymd(2026, 3, day).unwrap());
+            agenda.into_iter().map(|item| item.task.text).collect::<Vec<_>>()
+        };
+        // Only the Audit is due within two weeks
+        assert_eq!(order(1), ["Tidy up", "Write copy", "Collect receipts", "Plan", "Book venue"]);
+        // Both are; the nearer deadline goes first
+        assert_eq!(order(10), ["Write copy", "Tidy up", "Collect receipts", "Book venue", "Plan"]);
+    }
+
     #[test]
     fn test_add_task() {
         let mut doc = sample_doc();
@@ -465,6 +1502,99 @@ mod tests {
         assert_eq!(doc.categories[0].projects[0].tasks[1].text, "First todo");
     }
 
+    #[test]
+    fn test_retire_project() {
+        let mut doc = parse("## Work\n\n### Launch\nKickoff in March\n- ✅ Ship it\n- 🔴 Write copy\n  Draft in docs\n\n### Hiring\n- 🔴 Post ad\n");
+        let today = Date::parse("2026-10-17").unwrap();
+        assert_eq!(retire_project(&mut doc, 0, 0, today).map(|p| p.tasks.len()), Some(2));
+        retire_project(&mut doc, 0, 0, today);
+        assert!(doc.categories[0].projects.is_empty());
+        assert_eq!(retire_project(&mut doc, 0, 0, today), None);
+
+        let output = serialize(&doc);
+        assert_eq!(
+            output,
+            "## Work\n\n## Done Projects\n### Hiring\nRetired 2026-10-17 from Work\n- 🔴 Post ad\n\n\
+             ### Launch\nRetired 2026-10-17 from Work\nKickoff in March\n- ✅ Ship it\n- 🔴 Write copy\n  Draft in docs\n"
+        );
+        // Read back as a section of its own, not as a category
+        let reread = parse(&output);
+        assert_eq!(reread, doc);
+        assert_eq!(reread.categories.len(), 1);
+        assert_eq!(serialize(&parse(&format!("{}\n## Done\n- ✅ Old\n", output))), format!("{}\n## Done\n- ✅ Old\n", output));
+    }
+
+    #[test]
+    fn test_move_task_block() {
+        let mut doc = parse("## Work\n### Launch\n- 🔴 A\n- 🔴 B\n  Note on B\n- 🔴 C\n- 🔴 D\n");
+        let texts = |doc: &Document| doc.categories[0].projects[0].tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+        assert_eq!(move_task_block(&mut doc, 0, 0, 1, 2, 1), Some(2));
+        assert_eq!(texts(&doc), ["A", "D", "B", "C"]);
+        assert_eq!(move_task_block(&mut doc, 0, 0, 2, 3, 1), None);
+        assert_eq!(move_task_block(&mut doc, 0, 0, 2, 3, -1), Some(1));
+        assert_eq!(move_task_block(&mut doc, 0, 0, 1, 2, -1), Some(0));
+        assert_eq!(texts(&doc), ["B", "C", "A", "D"]);
+        assert_eq!(move_task_block(&mut doc, 0, 0, 0, 1, -1), None);
+        assert_eq!(move_task_block(&mut doc, 0, 0, 3, 4, 1), None);
+        // Notes travel with their task
+        assert_eq!(serialize(&doc), "## Work\n\n### Launch\n- 🔴 B\n  Note on B\n- 🔴 C\n- 🔴 A\n- 🔴 D\n");
+    }
+
+    #[test]
+    fn test_sink_done() {
+        let mut doc = parse("## Work\n### Launch\n- ✅ A\n- 🔴 B\n- ✅ C\n- 🔵 D\n### 📋 Steps\n- ✅ One\n- 🔴 Two\n");
+        let texts = |p: &Project| p.tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+        assert!(sink_done(&mut doc));
+        assert_eq!(texts(&doc.categories[0].projects[0]), ["B", "D", "A", "C"]);
+        assert!(!sink_done(&mut doc));
+        assert_eq!(texts(&doc.categories[0].projects[1]), ["One", "Two"]);
+    }
+
+    #[test]
+    fn test_demote_other_in_progress() {
+        let mut doc = parse("## Work\n### Launch\n- 🔶 A\n- 🔶 B\n## Home\n### House\n- 🔶 C\n- 🔵 D\n");
+        assert_eq!(demote_other_in_progress(&mut doc, (0, 0, 1)), [(0, 0, 0), (1, 0, 0)]);
+        let states: Vec<_> = doc.categories.iter().flat_map(|c| &c.projects).flat_map(|p| &p.tasks).map(|t| t.state).collect();
+        assert_eq!(states, [TaskState::OnDeck, TaskState::InProgress, TaskState::OnDeck, TaskState::OnDeck]);
+    }
+
+    #[test]
+    fn test_section_totals() {
+        let doc = parse("## Work\n### 🔶 Launch\n- 🔶 Draft ~1h\n- 🔶 Review ~30m\n- 🔵 Call\n- 🔵 Email ~15m\n");
+        let items = build_agenda(&doc, &DEFAULT_SECTIONS);
+        assert_eq!(
+            section_totals(&items),
+            [
+                SectionTotal { state: TaskState::InProgress, count: 2, minutes: 90 },
+                SectionTotal { state: TaskState::OnDeck, count: 2, minutes: 15 },
+            ]
+        );
+        assert!(section_totals(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_resurface_due() {
+        let today = Date::parse("2026-03-10").unwrap();
+        let mut doc = parse(
+            "## Work\n### 🔶 Launch\n- 🔴 Renew due:2026-03-10\n- 🔴 Later due:2026-03-11\n- 🔴 Late due:2026-03-01\n- 🔶 Started due:2026-03-01\n### Idle\n- 🔴 Parked due:2026-03-01\n",
+        );
+        assert_eq!(resurface_due(&mut doc, today), [(0, 0, 0), (0, 0, 2)]);
+        assert_eq!(doc.categories[0].projects[0].tasks[0].state, TaskState::OnDeck);
+        assert_eq!(doc.categories[0].projects[0].tasks[1].state, TaskState::Todo);
+        assert_eq!(doc.categories[0].projects[1].tasks[0].state, TaskState::Todo);
+        assert!(resurface_due(&mut doc, today).is_empty());
+    }
+
+    #[test]
+    fn test_sink_task() {
+        let mut doc = parse("## Work\n### Launch\n- 🔴 A\n- 🔴 B\n- 🔴 C\n");
+        let texts = |doc: &Document| doc.categories[0].projects[0].tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+        assert_eq!(sink_task(&mut doc, 0, 0, 0), Some(2));
+        assert_eq!(texts(&doc), ["B", "C", "A"]);
+        assert_eq!(sink_task(&mut doc, 0, 0, 2), None);
+        assert_eq!(sink_task(&mut doc, 0, 1, 0), None);
+    }
+
     #[test]
     fn test_rerank_project() {
         let mut doc = sample_doc();
@@ -479,6 +1609,7 @@ mod tests {
         let mut doc = sample_doc();
         assert!(rename_task(&mut doc, 0, 0, 0, "Renamed".to_string()));
         assert_eq!(doc.categories[0].projects[0].tasks[0].text, "Renamed");
+        assert!(!rename_task(&mut doc, 0, 0, 0, "Renamed".to_string()));
     }
 
     #[test]
@@ -486,6 +1617,7 @@ mod tests {
         let mut doc = sample_doc();
         assert!(rename_project(&mut doc, 0, 0, "Renamed Proj".to_string()));
         assert_eq!(doc.categories[0].projects[0].name, "Renamed Proj");
+        assert!(!rename_project(&mut doc, 0, 0, "Renamed Proj".to_string()));
     }
 
     #[test]
@@ -515,6 +1647,61 @@ mod tests {
 
         assert!(delete_task_note(&mut doc, 0, 0, 0, 0));
         assert_eq!(doc.categories[0].projects[0].tasks[0].notes.len(), 0);
+
+        assert_eq!(insert_task_note(&mut doc, 0, 0, 0, 0, "- Call back".to_string()), Some(0));
+        assert_eq!(insert_task_note(&mut doc, 0, 0, 0, 9, "Last".to_string()), Some(1));
+        assert_eq!(insert_task_note(&mut doc, 0, 0, 0, 1, "Middle".to_string()), Some(1));
+        assert_eq!(doc.categories[0].projects[0].tasks[0].notes, ["  - Call back", "  Middle", "  Last"]);
+
+        assert_eq!(rerank_note(&mut doc, 0, 0, 0, 0, 1), Some(1));
+        assert_eq!(rerank_note(&mut doc, 0, 0, 0, 2, 1), None);
+        assert_eq!(doc.categories[0].projects[0].tasks[0].notes, ["  Middle", "  - Call back", "  Last"]);
+
+        assert_eq!(note_to_task(&mut doc, 0, 0, 0, 1), Some(1));
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks[0].notes, ["  Middle", "  Last"]);
+        assert_eq!((tasks[1].state, tasks[1].text.as_str()), (TaskState::Todo, "Call back"));
+        assert_eq!(tasks[2].text, "Second todo");
+    }
+
+    #[test]
+    fn test_title_links() {
+        let mut doc = parse(
+            "## Work\n\n### Reading\n- 🔴 https://example.com/a\n  https://example.com/b\n  see https://example.com/c\n- 🔴 Read https://example.com/b\n- ✅ https://example.com/a\n",
+        );
+        assert_eq!(bare_links(&doc), ["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(title_link(&mut doc, "https://example.com/a", "Page A"), 2);
+        assert_eq!(title_link(&mut doc, "https://example.com/b", "Page B"), 1);
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks[0].text, "Page A (https://example.com/a)");
+        assert_eq!(tasks[0].notes, ["  Page B (https://example.com/b)", "  see https://example.com/c"]);
+        assert_eq!(tasks[1].text, "Read https://example.com/b");
+        assert_eq!(tasks[2].text, "Page A (https://example.com/a)");
+        assert!(bare_links(&doc).is_empty());
+    }
+
+    #[test]
+    fn test_share_copy() {
+        let doc = parse(
+            "# Plans\nMy pin is 1234\n\n## Work\n\n### 🔶 Launch\nBudget: $5k\n- 🔶 Book venue\n  Call 555-0100\n- 🔴 Ask for a raise #Private\n- 🔴 Tell Sam\n  #private for now\n\n### Job hunt #private\nSalary: 90k\n- 🔴 Update CV\n  Recruiter 555-0199\n\n## Health #private\n\n### Doctor\n- 🔴 Book checkup\n\n## Done\n- ✅ [Work/Launch] Pick a domain (2026-03-01)\n- ✅ [Work/Launch] Interview #private (2026-03-02)\n",
+        );
+        let shared = share_copy(&doc);
+        assert_eq!(shared.title(), Some("Plans"));
+        assert!(!shared.preamble.iter().any(|line| line.contains("1234")));
+        assert_eq!(shared.categories.len(), 1);
+        let work = &shared.categories[0];
+        assert_eq!(work.projects.len(), 1);
+        assert!(work.projects[0].notes.is_empty());
+        let tasks: Vec<_> = work.projects[0].tasks.iter().map(|t| (t.text.as_str(), t.notes.len())).collect();
+        assert_eq!(tasks, [("Book venue", 0)]);
+        assert_eq!(shared.archive.len(), 1);
+        assert!(shared.archive[0].contains("Pick a domain"));
+
+        // Retired projects are kept whole, notes and all, so none are shared
+        let mut doc = doc;
+        retire_project(&mut doc, 0, 1, Date::from_ymd(2026, 3, 10).unwrap()).unwrap();
+        assert!(doc.done_projects.iter().any(|line| line.contains("Salary")));
+        assert!(share_copy(&doc).done_projects.is_empty());
     }
 
     #[test]
@@ -524,4 +1711,275 @@ mod tests {
         assert!(delete_task(&mut doc, 0, 0, 0));
         assert_eq!(doc.categories[0].projects[0].tasks.len(), count - 1);
     }
+
+    #[test]
+    fn test_set_title() {
+        let mut doc = parse("Intro text\n\n## Work\n");
+        set_title(&mut doc, "My GTD".to_string());
+        assert_eq!(doc.title(), Some("My GTD"));
+        assert_eq!(doc.preamble[1], "");
+        assert_eq!(doc.preamble[2], "Intro text");
+
+        set_title(&mut doc, "Renamed".to_string());
+        assert_eq!(doc.title(), Some("Renamed"));
+        assert_eq!(doc.preamble.len(), 4);
+    }
+
+    #[test]
+    fn test_preamble_lines() {
+        let mut doc = parse("# Title\nFirst\n\n## Work\n");
+        let idx = insert_preamble_line(&mut doc, 2, "Second".to_string());
+        assert_eq!(idx, 2);
+        assert_eq!(doc.preamble[2], "Second");
+
+        assert!(set_preamble_line(&mut doc, 1, "Changed".to_string()));
+        assert_eq!(doc.preamble[1], "Changed");
+
+        assert!(delete_preamble_line(&mut doc, 1));
+        assert_eq!(doc.preamble[1], "Second");
+        assert!(!delete_preamble_line(&mut doc, 99));
+    }
+
+    #[test]
+    fn test_sort_projects() {
+        let mut doc = parse(
+            "\
+## Work
+
+### beta
+### 🔶 Gamma
+### 🔶 alpha
+",
+        );
+        assert!(sort_projects_by_name(&mut doc, 0));
+        let names: Vec<&str> = doc.categories[0].projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "beta", "Gamma"]);
+        assert!(!sort_projects_by_name(&mut doc, 0));
+
+        assert!(sort_projects_active_first(&mut doc, 0));
+        let names: Vec<&str> = doc.categories[0].projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "Gamma", "beta"]);
+    }
+
+    #[test]
+    fn test_sort_tasks_by_state() {
+        let mut doc = parse(
+            "\
+## Work
+
+### 🔶 Project
+- ✅ Done one
+- 🔴 Todo one
+- 🔶 Working
+- 🔴 Todo two
+- 🔵 Next
+",
+        );
+        assert!(sort_tasks_by_state(&mut doc, 0, 0));
+        let texts: Vec<&str> = doc.categories[0].projects[0].tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["Working", "Next", "Todo one", "Todo two", "Done one"]);
+        assert!(!sort_tasks_by_state(&mut doc, 0, 0));
+    }
+
+    #[test]
+    fn test_sort_tasks_by_priority_and_due() {
+        let mut doc = parse(
+            "\
+## Work
+
+### 🔶 Project
+- 🔴 Plain one
+- 🔴 Later !3 due:2026-05-01
+- 🔴 Urgent !1
+- 🔴 Plain two due:2026-04-01
+- 🔴 Also later !3
+",
+        );
+        assert!(sort_tasks_by_priority(&mut doc, 0, 0));
+        let texts: Vec<&str> = doc.categories[0].projects[0].tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["Urgent !1", "Later !3 due:2026-05-01", "Also later !3", "Plain one", "Plain two due:2026-04-01"]);
+        assert!(!sort_tasks_by_priority(&mut doc, 0, 0));
+
+        assert!(sort_tasks_by_due(&mut doc, 0, 0));
+        let texts: Vec<&str> = doc.categories[0].projects[0].tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["Plain two due:2026-04-01", "Later !3 due:2026-05-01", "Urgent !1", "Also later !3", "Plain one"]);
+        assert!(!sort_tasks_by_due(&mut doc, 0, 0));
+    }
+
+    #[test]
+    fn test_merge_tasks() {
+        let mut doc = parse(
+            "## Work\n\n### Launch\n- 🔵 Book venue\n  Call Friday\n- 🔴 Write copy\n- 🔴 book venue\n  Two quotes in\n",
+        );
+        // Later duplicate into the earlier one: state and notes carry over
+        assert_eq!(merge_tasks(&mut doc, (0, 0, 2), (0, 0, 0)), Some(0));
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].state, TaskState::OnDeck);
+        assert_eq!(tasks[0].notes, ["  Call Friday", "  book venue", "  Two quotes in"]);
+
+        // Merging downward shifts the target up; the advanced state wins
+        assert_eq!(merge_tasks(&mut doc, (0, 0, 0), (0, 0, 1)), Some(0));
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text, "Write copy");
+        assert_eq!(tasks[0].state, TaskState::OnDeck);
+
+        assert_eq!(merge_tasks(&mut doc, (0, 0, 0), (0, 0, 0)), None);
+        assert_eq!(merge_tasks(&mut doc, (0, 0, 5), (0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_area_summaries() {
+        let doc = parse(
+            "# Life\nArea Health: medical, Fitness\n- Area Work: Business\n\n## Medical\n\n### 🔶 Checkups\n- ✅ Dentist\n- 🔴 Eye exam\n\n## Fitness\n\n### Running\n- 🔴 10k\n\n## Business\n\n### 🔶 Launch\n- 🔵 Copy\n\n## Errands\n",
+        );
+        let areas = doc.preamble_areas();
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0].categories, ["medical", "Fitness"]);
+
+        let summaries = area_summaries(&doc, &areas);
+        let names: Vec<&str> = summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Health", "Work", "Unassigned"]);
+        assert_eq!(
+            summaries[0],
+            AreaSummary {
+                name: "Health".to_string(),
+                categories: 2,
+                projects: 2,
+                active_projects: 1,
+                open_tasks: 2,
+                done_tasks: 1,
+            }
+        );
+        assert_eq!(summaries[2].categories, 1);
+    }
+
+    #[test]
+    fn test_checklist_projects() {
+        let mut doc = parse("## Home\n\n### 🔶 📋 Packing\n- ✅ Passport\n- 🔴 Charger\n");
+        let packing = &doc.categories[0].projects[0];
+        assert!(packing.active && packing.checklist);
+        assert_eq!(packing.name, "Packing");
+        assert!(crate::serializer::serialize(&doc).contains("### 🔶 📋 Packing\n"));
+
+        // Neither auto-promoted nor archived
+        auto_promote(&mut doc, PromoteStrategy::TopOfList);
+        archive_done(&mut doc, Date::today());
+        let packing = &doc.categories[0].projects[0];
+        assert_eq!(packing.tasks[1].state, TaskState::Todo);
+        assert_eq!(packing.tasks.len(), 2);
+        assert!(doc.archive.is_empty());
+
+        doc.categories[0].projects[0].tasks[1].state = TaskState::Done;
+        assert!(doc.categories[0].projects[0].is_finished_checklist());
+        assert!(reset_checklist(&mut doc, 0, 0));
+        assert!(doc.categories[0].projects[0].tasks.iter().all(|t| t.state == TaskState::Todo));
+    }
+
+    #[test]
+    fn test_people_index() {
+        let line = "Ask @Sarah and @dev-team. Mail sarah@example.com, cc @sarah";
+        assert_eq!(mentions(line).collect::<Vec<_>>(), ["Sarah", "dev-team", "sarah"]);
+
+        let doc = parse(
+            "## Work\n\n### Launch\n- 🔴 Review with @Sarah\n  @Sarah prefers mornings\n- ✅ Sync with @bob\n- 🔴 Draft agenda\n  Invite @bob and @sarah\n",
+        );
+        let people = people(&doc);
+        let summary: Vec<_> = people.iter().map(|p| (p.name.as_str(), p.tasks.len(), p.open_tasks)).collect();
+        assert_eq!(summary, [("Sarah", 2, 2), ("bob", 2, 1)]);
+        assert_eq!(people[0].tasks, [(0, 0, 0), (0, 0, 2)]);
+        assert!(doc.categories[0].projects[0].tasks[2].mentions("SARAH"));
+    }
+
+    #[test]
+    fn test_tags() {
+        let line = "#urgent Fix C# build for #12, see #home/lab- and #Urgent";
+        assert_eq!(tags(line).collect::<Vec<_>>(), ["urgent", "home/lab", "Urgent"]);
+        assert!(is_tag_name("deep-work") && !is_tag_name("two words") && !is_tag_name("42"));
+
+        let mut doc = parse(
+            "## Work\n\n### Launch\n- 🔴 Call printer #urgent today\n  Ask about #errands\n- ✅ Book venue #Urgent\n- 🔴 #errands\n",
+        );
+        let index = tag_index(&doc);
+        let summary: Vec<_> = index.iter().map(|t| (t.name.as_str(), t.tasks.len(), t.open_tasks)).collect();
+        assert_eq!(summary, [("errands", 2, 2), ("urgent", 2, 1)]);
+        assert!(doc.categories[0].projects[0].tasks[1].has_tag("URGENT"));
+
+        assert_eq!(rename_tag(&mut doc, "urgent", "now"), 2);
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks[0].text, "Call printer #now today");
+        assert_eq!(tasks[1].text, "Book venue #now");
+
+        assert_eq!(delete_tag(&mut doc, "errands"), 1);
+        let tasks = &doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks[0].notes, ["  Ask about"]);
+        assert_eq!(tasks[2].text, "#errands");
+        assert_eq!(delete_tag(&mut doc, "now"), 2);
+        assert_eq!(doc.categories[0].projects[0].tasks[0].text, "Call printer today");
+        assert_eq!(delete_tag(&mut doc, "missing"), 0);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let input = "\
+## Work
+
+### 🔶 Launch due:2026-05-01
+- 🔴 Book venue
+- 🔴 Write copy
+
+### Hiring
+- 🔴 Post job
+
+### launch due:2026-04-20
+- 🔶 Write copy
+  Pricing first
+- 🔴 Print flyers
+
+## Home
+
+### Chores
+- 🔴 Fix sink
+
+## work
+Shared notes
+
+### Hiring
+- 🔴 Call recruiter
+";
+        let doc = parse(input);
+        assert_eq!(
+            duplicates(&doc),
+            [
+                Duplicate { cat_idx: 0, proj_idx: Some(2), original: 0 },
+                Duplicate { cat_idx: 2, proj_idx: None, original: 0 },
+            ]
+        );
+
+        let mut renamed = doc.clone();
+        assert_eq!(rename_duplicates(&mut renamed), 2);
+        assert_eq!(renamed.categories[0].projects[2].name, "launch (2)");
+        assert_eq!(renamed.categories[2].name, "work (2)");
+        assert!(duplicates(&renamed).is_empty());
+
+        let mut merged = doc.clone();
+        assert_eq!(merge_duplicates(&mut merged), 3);
+        assert!(duplicates(&merged).is_empty());
+        assert_eq!(merged.categories.len(), 2);
+        let work = &merged.categories[0];
+        assert_eq!(work.notes, ["Shared notes"]);
+        let names: Vec<&str> = work.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Launch", "Hiring"]);
+        let launch = &work.projects[0];
+        assert!(launch.active);
+        assert_eq!(launch.deadline, Date::parse("2026-04-20"));
+        let tasks: Vec<(&str, TaskState)> = launch.tasks.iter().map(|t| (t.text.as_str(), t.state)).collect();
+        assert_eq!(
+            tasks,
+            [("Book venue", TaskState::Todo), ("Write copy", TaskState::InProgress), ("Print flyers", TaskState::Todo)]
+        );
+        assert_eq!(launch.tasks[1].notes, ["  Pricing first"]);
+        assert_eq!(work.projects[1].tasks.len(), 2);
+    }
 }
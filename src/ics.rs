@@ -0,0 +1,138 @@
+//! `:ics`: today's plan as calendar events, back to back from the start of
+//! the working day, in an iCalendar file any calendar app can import.
+
+use crate::date::Date;
+use crate::model::Document;
+use crate::planner::Plan;
+
+/// Where the working day starts when `[planner] day_start` can't be read.
+pub const DEFAULT_DAY_START: u32 = 9 * 60;
+
+/// Minutes after midnight from `HH:MM`.
+pub fn parse_time(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+/// `YYYYMMDDTHHMMSSZ` for a Unix time, as DTSTAMP wants it.
+pub fn utc_stamp(secs: i64) -> String {
+    let day = Date::from_ymd(1970, 1, 1).map_or(String::new(), |epoch| compact(epoch.plus_days(secs.div_euclid(86_400))));
+    let secs = secs.rem_euclid(86_400);
+    format!("{}T{:02}{:02}{:02}Z", day, secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// One event per plan item on `day`, the first at `start` minutes after
+/// midnight. Times are floating, so calendars read them as local time.
+/// `stamp` is the DTSTAMP of every event (see `utc_stamp`).
+pub fn plan_calendar(doc: &Document, plan: &Plan, day: Date, start: u32, stamp: &str) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//gws//Plan my day//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    let mut at = start;
+    for item in &plan.items {
+        let (ci, pi, ti) = item.task;
+        let category = &doc.categories[ci];
+        let project = &category.projects[pi];
+        let task = &project.tasks[ti];
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            // Same task, same day, same event: re-importing updates it
+            format!("UID:{}-{}@gws", task.id(), compact(day)),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", local_time(day, at)),
+            format!("DTEND:{}", local_time(day, at + item.minutes)),
+            format!("SUMMARY:{}", escape(&task.text)),
+            format!("DESCRIPTION:{}", escape(&format!("{} › {}", category.name, project.name))),
+            format!("CATEGORIES:{}", escape(&category.name)),
+            "END:VEVENT".to_string(),
+        ]);
+        at += item.minutes;
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// `20260310`
+fn compact(day: Date) -> String {
+    let (y, m, d) = day.ymd();
+    format!("{:04}{:02}{:02}", y, m, d)
+}
+
+/// `20260310T093000`, carrying past midnight into the next day.
+fn local_time(day: Date, minutes: u32) -> String {
+    let day = day.plus_days((minutes / 1440) as i64);
+    let minutes = minutes % 1440;
+    format!("{}T{:02}{:02}00", compact(day), minutes / 60, minutes % 60)
+}
+
+/// Escape text values: backslash, comma, semicolon and newlines.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Fold a content line at 75 bytes, continuation lines starting with a
+/// space, without splitting a character.
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::planner::plan_day;
+
+    #[test]
+    fn test_plan_calendar() {
+        let doc = parse("## Work\n### 🔶 Launch\n- 🔶 Write copy, final ~1h30m\n- 🔵 Call Sam ~45m\n- 🔴 Later\n");
+        let day = Date::from_ymd(2026, 3, 10).unwrap();
+        let plan = plan_day(&doc, 135, &[], day);
+        let ics = plan_calendar(&doc, &plan, day, 9 * 60, "20260310T070000Z");
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:20260310T090000\r\nDTEND:20260310T103000\r\nSUMMARY:Write copy\\, final ~1h30m\r\n"));
+        assert!(ics.contains("DTSTART:20260310T103000\r\nDTEND:20260310T111500\r\nSUMMARY:Call Sam ~45m\r\n"));
+        assert!(ics.contains("DESCRIPTION:Work › Launch\r\n"));
+        assert!(ics.contains(&format!("UID:{}-20260310@gws\r\n", doc.categories[0].projects[0].tasks[1].id())));
+    }
+
+    #[test]
+    fn test_times_and_folding() {
+        assert_eq!(parse_time("08:30"), Some(510));
+        assert_eq!(parse_time("24:00"), None);
+        assert_eq!(parse_time("9am"), None);
+        assert_eq!(utc_stamp(86_400 + 3_723), "19700102T010203Z");
+        assert_eq!(local_time(Date::from_ymd(2026, 3, 10).unwrap(), 1440 + 30), "20260311T003000");
+
+        let folded = fold(&format!("SUMMARY:{}", "é".repeat(40)));
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("SUMMARY:{}", "é".repeat(40)));
+    }
+}
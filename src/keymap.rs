@@ -0,0 +1,185 @@
+//! Keyboard layouts. With a layout other than QWERTY, command keys are read
+//! by where they sit on the keyboard, so `j`/`k`, `h`/`l` and the rest stay
+//! under the same fingers as on QWERTY. Text typed into dialogs is never
+//! translated.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    /// Keys are taken as typed.
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+    /// French AZERTY.
+    Azerty,
+}
+
+/// US QWERTY, key by key: the number row, then the three letter rows.
+const QWERTY: [&str; 2] = [
+    "`1234567890-=qwertyuiop[]\\asdfghjkl;'zxcvbnm,./",
+    "~!@#$%^&*()_+QWERTYUIOP{}|ASDFGHJKL:\"ZXCVBNM<>?",
+];
+const DVORAK: [&str; 2] = [
+    "`1234567890[]',.pyfgcrl/=\\aoeuidhtns-;qjkxbmwvz",
+    "~!@#$%^&*(){}\"<>PYFGCRL?+|AOEUIDHTNS_:QJKXBMWVZ",
+];
+const COLEMAK: [&str; 2] = [
+    "`1234567890-=qwfpgjluy;[]\\arstdhneio'zxcvbkm,./",
+    "~!@#$%^&*()_+QWFPGJLUY:{}|ARSTDHNEIO\"ZXCVBKM<>?",
+];
+/// The digits are shifted on AZERTY; they are kept as digits below, so a
+/// count can be typed either way. `@` and `#` are typed with AltGr, off the
+/// table, so they are taken as typed.
+const AZERTY: [&str; 2] = [
+    "²&é\"'(-è_çà)=azertyuiop^$*qsdfghjklmùwxcvbn,;:!",
+    "³1234567890°+AZERTYUIOP¨£µQSDFGHJKLM%WXCVBN?./§",
+];
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 4] =
+        [KeyboardLayout::Qwerty, KeyboardLayout::Dvorak, KeyboardLayout::Colemak, KeyboardLayout::Azerty];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyboardLayout::Qwerty => "QWERTY",
+            KeyboardLayout::Dvorak => "Dvorak",
+            KeyboardLayout::Colemak => "Colemak",
+            KeyboardLayout::Azerty => "AZERTY",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&l| l == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    fn keys(self) -> [&'static str; 2] {
+        match self {
+            KeyboardLayout::Qwerty => QWERTY,
+            KeyboardLayout::Dvorak => DVORAK,
+            KeyboardLayout::Colemak => COLEMAK,
+            KeyboardLayout::Azerty => AZERTY,
+        }
+    }
+
+    /// The QWERTY key at the place of `c` on this layout. Digits are always
+    /// digits, and characters off the main block are left alone.
+    pub fn to_qwerty(self, c: char) -> char {
+        if c.is_ascii_digit() {
+            return c;
+        }
+        map_char(c, self.keys(), QWERTY)
+    }
+
+    /// The key to press on this layout for QWERTY's `c`.
+    pub fn from_qwerty(self, c: char) -> char {
+        match map_char(c, QWERTY, self.keys()) {
+            // A symbol over a digit on QWERTY that this layout types elsewhere
+            key if key.is_ascii_digit() => c,
+            key => key,
+        }
+    }
+
+    /// `key` as the QWERTY key in its place; keys with Ctrl or Alt are
+    /// taken as typed, as terminals send them by letter.
+    pub fn translate(self, key: KeyEvent) -> KeyEvent {
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                KeyEvent { code: KeyCode::Char(self.to_qwerty(c)), ..key }
+            }
+            _ => key,
+        }
+    }
+
+    /// A key hint such as `j/k:Nav  Enter:Open` with its single-character
+    /// keys given as they are on this layout.
+    pub fn relabel(self, hint: &str) -> String {
+        if self == KeyboardLayout::Qwerty {
+            return hint.to_string();
+        }
+        hint.split("  ")
+            .map(|part| match part.split_once(':') {
+                Some((keys, what)) if !keys.is_empty() && !keys.contains(' ') => {
+                    format!("{}:{}", self.relabel_keys(keys, "/"), what)
+                }
+                _ => part.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    /// Keys such as `j / k` (with `separator` " / ") with each
+    /// single-character key relabelled.
+    pub fn relabel_keys(self, keys: &str, separator: &str) -> String {
+        if keys.chars().count() == 1 {
+            return keys.chars().map(|c| self.from_qwerty(c)).collect();
+        }
+        keys.split(separator)
+            .map(|key| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => self.from_qwerty(c).to_string(),
+                    _ => key.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+fn map_char(c: char, from: [&str; 2], to: [&str; 2]) -> char {
+    from.iter()
+        .zip(to)
+        .find_map(|(from, to)| {
+            let i = from.chars().position(|k| k == c)?;
+            to.chars().nth(i)
+        })
+        .unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts() {
+        for layout in KeyboardLayout::ALL {
+            let [lower, upper] = layout.keys();
+            assert_eq!(lower.chars().count(), QWERTY[0].chars().count(), "{:?}", layout);
+            assert_eq!(upper.chars().count(), QWERTY[1].chars().count(), "{:?}", layout);
+        }
+
+        // Dvorak's h and t sit where QWERTY has j and k
+        assert_eq!(KeyboardLayout::Dvorak.to_qwerty('h'), 'j');
+        assert_eq!(KeyboardLayout::Dvorak.to_qwerty('t'), 'k');
+        assert_eq!(KeyboardLayout::Dvorak.to_qwerty('G'), 'U');
+        assert_eq!(KeyboardLayout::Colemak.to_qwerty('n'), 'j');
+        assert_eq!(KeyboardLayout::Azerty.to_qwerty('a'), 'q');
+        assert_eq!(KeyboardLayout::Azerty.to_qwerty('&'), '1');
+        assert_eq!(KeyboardLayout::Azerty.to_qwerty('1'), '1');
+        assert_eq!(KeyboardLayout::Qwerty.to_qwerty('j'), 'j');
+        assert_eq!(KeyboardLayout::Dvorak.to_qwerty('é'), 'é');
+        assert_eq!(KeyboardLayout::Azerty.from_qwerty('@'), '@');
+        assert_eq!(KeyboardLayout::Azerty.to_qwerty('@'), '@');
+        for c in ('a'..='z').chain('A'..='Z') {
+            for layout in KeyboardLayout::ALL {
+                assert_eq!(layout.to_qwerty(layout.from_qwerty(c)), c, "{:?} {}", layout, c);
+            }
+        }
+
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(KeyboardLayout::Dvorak.translate(ctrl_d), ctrl_d);
+        assert_eq!(
+            KeyboardLayout::Dvorak.translate(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE)).code,
+            KeyCode::Char('j')
+        );
+
+        assert_eq!(KeyboardLayout::Dvorak.relabel("j/k:Nav  Enter:Open  /:Filter  Type to filter"), "h/t:Nav  Enter:Open  z:Filter  Type to filter");
+        assert_eq!(KeyboardLayout::Dvorak.relabel_keys("j / k", " / "), "h / t");
+        assert_eq!(KeyboardLayout::Dvorak.relabel_keys("Ctrl+D / Ctrl+U", " / "), "Ctrl+D / Ctrl+U");
+    }
+}
@@ -0,0 +1,33 @@
+//! Library half of gws: the document model, parser/serializer, engine and
+//! TUI, shared by the binary and the benchmarks.
+
+pub mod activity;
+pub mod app;
+pub mod attach;
+pub mod complete;
+pub mod config;
+pub mod date;
+pub mod diff;
+pub mod engine;
+pub mod ics;
+pub mod keymap;
+pub mod links;
+pub mod logging;
+pub mod model;
+pub mod parser;
+pub mod paths;
+pub mod planner;
+pub mod print;
+pub mod prompt;
+pub mod query;
+pub mod report;
+pub mod serializer;
+pub mod session;
+pub mod stats;
+pub mod storage;
+pub mod subtree;
+pub mod theme;
+pub mod tui;
+pub mod tutorial;
+pub mod watcher;
+pub mod webhook;
@@ -0,0 +1,158 @@
+//! Readable links: spotting a task or note that is nothing but a URL, and
+//! fetching the page's title to write next to it.
+
+use std::process::Command;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+/// How long one page may take before its title is given up on.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pages are read up to this size; the title is near the top.
+const MAX_PAGE_BYTES: &str = "1048576";
+
+/// How many titles are fetched at once, so a file full of links doesn't
+/// start a `curl` for every one of them together.
+const FETCH_WORKERS: usize = 4;
+
+/// The URL when `text` is only an `http://` or `https://` link.
+pub fn bare_url(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let is_url = (text.starts_with("http://") || text.starts_with("https://")) && !text.contains(char::is_whitespace);
+    is_url.then_some(text)
+}
+
+/// The `<title>` of an HTML page, with entities decoded and whitespace
+/// collapsed; None when it is missing or empty.
+pub fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = decode_entities(&html[start..end]);
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                code => match code.strip_prefix("#x").or_else(|| code.strip_prefix("#X")) {
+                    Some(hex) => char::from_u32(u32::from_str_radix(hex, 16).ok()?)?,
+                    None => char::from_u32(code.strip_prefix('#')?.parse().ok()?)?,
+                },
+            };
+            Some((c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Fetch `url` with `curl` and read its title.
+pub fn fetch_title(url: &str, timeout: Duration) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--location", "--fail", "--max-filesize", MAX_PAGE_BYTES])
+        .args(["--max-time", &timeout.as_secs().max(1).to_string(), "--user-agent", "gws", "--", url])
+        .output()
+        .context("Could not run curl")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    match page_title(&String::from_utf8_lossy(&output.stdout)) {
+        Some(title) => Ok(title),
+        None => bail!("No title at {}", url),
+    }
+}
+
+/// Fetch the titles of `urls` in the background, a few at a time, each
+/// handed to `done` with its URL as it arrives.
+pub fn spawn_fetches<F>(urls: Vec<String>, timeout: Duration, done: F)
+where
+    F: Fn(String, Result<String>) + Clone + Send + 'static,
+{
+    let workers = urls.len().min(FETCH_WORKERS);
+    let (tx, rx) = mpsc::channel();
+    for url in urls {
+        let _ = tx.send(url);
+    }
+    drop(tx);
+    // Each worker takes the next URL until the queue runs dry
+    let rx = Arc::new(Mutex::new(rx));
+    for _ in 0..workers {
+        let rx = Arc::clone(&rx);
+        let done = done.clone();
+        std::thread::spawn(move || {
+            loop {
+                let next = match rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => return,
+                };
+                let Ok(url) = next else {
+                    return;
+                };
+                let title = fetch_title(&url, timeout);
+                done(url, title);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bare_url() {
+        assert_eq!(bare_url("  https://example.com/a?b=1 "), Some("https://example.com/a?b=1"));
+        assert_eq!(bare_url("http://example.com"), Some("http://example.com"));
+        assert_eq!(bare_url("Read https://example.com"), None);
+        assert_eq!(bare_url("ftp://example.com"), None);
+    }
+
+    #[test]
+    fn test_page_title() {
+        let html = "<html><head><meta charset=utf-8><TITLE lang=en>\n  Rust &amp; Cargo &#8212; the&nbsp;Book &#x27;22&copy\n</TITLE>";
+        assert_eq!(page_title(html).as_deref(), Some("Rust & Cargo — the Book '22&copy"));
+        assert_eq!(page_title("<title>  </title>"), None);
+        assert_eq!(page_title("<p>No title</p>"), None);
+    }
+
+    #[test]
+    fn test_spawn_fetches() {
+        // Nothing listens on port 9, so each fetch fails at once; every URL
+        // is still answered, though only a few are fetched at a time
+        let urls: Vec<String> = (0..10).map(|i| format!("http://127.0.0.1:9/{}", i)).collect();
+        let (tx, rx) = mpsc::channel();
+        spawn_fetches(urls.clone(), Duration::from_secs(1), move |url, title| {
+            let _ = tx.send((url, title.is_ok()));
+        });
+        let mut answered: Vec<(String, bool)> = rx.iter().take(urls.len()).collect();
+        answered.sort();
+        assert_eq!(answered, urls.into_iter().map(|url| (url, false)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,59 @@
+//! Debug logging to a file, to attach to bug reports: turned on with
+//! `--log-file` or `GWS_LOG`, and read back in the app with `:log`.
+
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+use tracing::Level;
+
+/// Environment variable naming the log file when `--log-file` isn't given.
+pub const LOG_ENV: &str = "GWS_LOG";
+
+/// How many of the latest lines `:log` shows.
+pub const VIEW_LINES: usize = 500;
+
+/// The log file asked for: `--log-file`, else `$GWS_LOG` when set and not
+/// empty.
+pub fn log_path(flag: Option<PathBuf>) -> Option<PathBuf> {
+    flag.or_else(|| std::env::var_os(LOG_ENV).filter(|v| !v.is_empty()).map(PathBuf::from))
+}
+
+/// Append debug-level events to `path` for the rest of the run.
+pub fn init(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Could not open the log file {}", path.display()))?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(Level::DEBUG)
+        .try_init()
+        .map_err(|e| anyhow!(e))
+}
+
+/// The last `count` lines of the log file.
+pub fn tail(path: &Path, count: usize) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    Ok(lines[lines.len().saturating_sub(count)..].iter().map(|l| l.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail() {
+        let path = std::env::temp_dir().join(format!("gws-log-{}.log", std::process::id()));
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+        assert_eq!(tail(&path, 2).unwrap(), ["two", "three"]);
+        assert_eq!(tail(&path, 10).unwrap(), ["one", "two", "three"]);
+        fs::remove_file(&path).unwrap();
+        assert!(tail(&path, 2).is_err());
+        assert_eq!(log_path(Some(PathBuf::from("gws.log"))), Some(PathBuf::from("gws.log")));
+    }
+}
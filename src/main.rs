@@ -1,20 +1,11 @@
-mod app;
-mod engine;
-mod model;
-mod parser;
-mod serializer;
-mod theme;
-mod tui;
-mod watcher;
-
 use std::fs;
-use std::io;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use clap::Parser as ClapParser;
-use crossterm::event::{self, Event};
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste, Event};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
@@ -22,7 +13,10 @@ use crossterm::ExecutableCommand;
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 
-use app::App;
+use gws::{activity, app, attach, config, date, engine, ics, links, logging, model, parser, paths, planner, print, prompt, query, report, serializer, session, storage, subtree, tui, tutorial, watcher, webhook};
+
+use app::{App, Retry};
+use tui::events::{self, AppEvent, Events};
 use tui::input::{self, Action};
 
 #[derive(ClapParser)]
@@ -31,16 +25,213 @@ struct Cli {
     /// Path to the todo markdown file
     #[arg(short, long)]
     file: Option<PathBuf>,
+
+    /// Open the global todo file even inside a directory or git repository
+    /// with its own
+    #[arg(short, long, conflicts_with = "file")]
+    global: bool,
+
+    /// Open the file of a `[[profiles]]` entry in the config, e.g. `work`
+    #[arg(short, long, conflicts_with_all = ["file", "global"])]
+    profile: Option<String>,
+
+    /// Open at this view
+    #[arg(long, value_enum)]
+    view: Option<StartView>,
+
+    /// Open the Backlog at this project, expanded
+    #[arg(long)]
+    project: Option<String>,
+
+    /// Open at the task with this id (`id` in `gws list --format json`) or
+    /// the start of it: in the Agenda with `--view agenda`, else the Backlog
+    #[arg(long, conflicts_with = "project")]
+    task_id: Option<String>,
+
+    /// Plain-text rendering for screen readers (also `accessible` in config)
+    #[arg(long)]
+    accessible: bool,
+
+    /// Append a debug log (parsing, file watching, saves, keys) to this file,
+    /// to attach to a bug report; also `GWS_LOG`. Read it in the app with `:log`
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn default_file_path() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not determine home directory")
-        .join(".gws")
-        .join("todo.md")
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Learn the basics in a sandbox document
+    Tutorial,
+    /// Print the todo file in use, how it was found, and where the config,
+    /// session and activity log live
+    Which,
+    /// Pick one of the recently opened todo files to work on
+    Open,
+    /// Print the agenda: In Progress and On Deck tasks of active projects
+    Agenda {
+        /// Merge the agendas of every `[[profiles]]` file, tagging each task
+        /// with its profile
+        #[arg(long)]
+        all_profiles: bool,
+    },
+    /// Print the Agenda or Backlog as text, for pipes and quick glances
+    Print {
+        #[arg(long, value_enum, default_value = "agenda")]
+        view: PrintView,
+        /// Only tasks matching a saved filter, by name, or a filter expression
+        #[arg(long)]
+        filter: Option<String>,
+        /// Color the output: auto (on a terminal, unless $NO_COLOR is set),
+        /// always or never
+        #[arg(long, value_enum, default_value = "auto")]
+        color: ColorChoice,
+    },
+    /// Print the Agenda (or Backlog) and print it again whenever the file
+    /// changes, for a dashboard terminal; Ctrl+C stops
+    Watch {
+        #[arg(long, value_enum, default_value = "agenda")]
+        view: PrintView,
+        /// Only tasks matching a saved filter, by name, or a filter expression
+        #[arg(long)]
+        filter: Option<String>,
+        /// Color the output: auto (on a terminal, unless $NO_COLOR is set),
+        /// always or never
+        #[arg(long, value_enum, default_value = "auto")]
+        color: ColorChoice,
+    },
+    /// Print open, waiting and recently completed tasks for a person or
+    /// project, or a summary of the last seven days
+    #[command(group(clap::ArgGroup::new("scope").required(true).args(["person", "project", "week"])))]
+    Report {
+        /// Tasks mentioning this person, e.g. `@Sarah`
+        #[arg(long)]
+        person: Option<String>,
+        /// Tasks of this project
+        #[arg(long)]
+        project: Option<String>,
+        /// Tasks completed and added, and projects (de)activated, this week
+        #[arg(long, conflicts_with = "plain")]
+        week: bool,
+        /// Plain text instead of markdown
+        #[arg(long)]
+        plain: bool,
+    },
+    /// Search the tasks, notes and archive of the todo file and every
+    /// profile's file, printing each match with its file, category, project
+    /// and state; exits 1 when nothing matches
+    Grep {
+        /// Case-insensitive regular expression
+        pattern: String,
+    },
+    /// Print the todo file, or with `--share` a copy fit to show others
+    Export {
+        /// Leave out notes, the preamble and anything tagged `#private`
+        #[arg(long)]
+        share: bool,
+        /// Write to this file instead of printing
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Print the task in progress, fast enough for a shell prompt or tmux
+    /// status bar; prints nothing and exits 1 when no task is in progress
+    Current {
+        /// Template with {state_icon}, {state}, {text}, {project} and {category}
+        #[arg(long, default_value = prompt::DEFAULT_FORMAT)]
+        format: String,
+    },
+    /// Print the tasks matching a filter, e.g.
+    /// `gws list 'project:"Website*" state:todo due<2024-06-01'`
+    List {
+        /// Filter in the saved-filter language; every task when omitted
+        query: Option<String>,
+        #[arg(long, value_enum, default_value = "table")]
+        format: ListFormat,
+    },
 }
 
-fn ensure_file(path: &PathBuf) -> Result<String> {
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum StartView {
+    Agenda,
+    Backlog,
+    Stats,
+    Settings,
+}
+
+impl From<StartView> for app::View {
+    fn from(view: StartView) -> Self {
+        match view {
+            StartView::Agenda => app::View::Agenda,
+            StartView::Backlog => app::View::Backlog,
+            StartView::Stats => app::View::Stats,
+            StartView::Settings => app::View::Settings,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum PrintView {
+    Agenda,
+    Backlog,
+}
+
+impl From<PrintView> for print::PrintView {
+    fn from(view: PrintView) -> Self {
+        match view {
+            PrintView::Agenda => print::PrintView::Agenda,
+            PrintView::Backlog => print::PrintView::Backlog,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ListFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+impl From<ListFormat> for report::ListFormat {
+    fn from(format: ListFormat) -> Self {
+        match format {
+            ListFormat::Table => report::ListFormat::Table,
+            ListFormat::Json => report::ListFormat::Json,
+            ListFormat::Markdown => report::ListFormat::Markdown,
+        }
+    }
+}
+
+/// The todo file as an absolute path, with `..` and symlinked directories
+/// resolved. A symlinked file stays the link: reads follow it, saves write
+/// through it, and the watcher notices when it is repointed.
+fn resolve_file_path(path: PathBuf) -> PathBuf {
+    let path = std::path::absolute(&path).unwrap_or(path);
+    match (path.parent().and_then(|dir| fs::canonicalize(dir).ok()), path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path,
+    }
+}
+
+fn ensure_file(path: &Path) -> Result<String> {
     if path.exists() {
         fs::read_to_string(path).context("Failed to read todo file")
     } else {
@@ -55,126 +246,891 @@ fn ensure_file(path: &PathBuf) -> Result<String> {
     }
 }
 
-fn save_atomic(path: &PathBuf, content: &str) -> Result<()> {
-    let tmp_path = path.with_extension("md.tmp");
-    fs::write(&tmp_path, content).context("Failed to write temp file")?;
-    fs::rename(&tmp_path, path).context("Failed to rename temp file")?;
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let log_file = logging::log_path(cli.log_file);
+    if let Some(path) = &log_file {
+        logging::init(path)?;
+    }
+    let dirs = paths::Dirs::from_env();
+    let config_path = dirs.config_file();
+    let config = config::load(&config_path);
+    let mut candidates = paths::Candidates::new(cli.file, config.files.default_file());
+    if let Some(name) = &cli.profile {
+        let Some(profile) = config.profile(name) else {
+            let names: Vec<&str> = config.profiles.iter().map(|p| p.name.as_str()).collect();
+            let known = if names.is_empty() { "none defined".to_string() } else { names.join(", ") };
+            anyhow::bail!("No profile named \"{}\" in {} (profiles: {})", name, config_path.display(), known);
+        };
+        candidates.profile = Some(profile.file());
+    }
+    if cli.global {
+        candidates = candidates.global();
+    } else if !config.files.repo_local {
+        candidates.repo_root = None;
+    }
+    let (file_path, source) = candidates.find(&dirs);
+    let file_path = resolve_file_path(file_path);
+    tracing::debug!(version = env!("CARGO_PKG_VERSION"), file = %file_path.display(), source = source.label(), "Starting");
+
+    let pick_recent = matches!(cli.command, Some(Command::Open));
+    match cli.command {
+        Some(Command::Which) => {
+            println!("todo file: {} ({})", file_path.display(), source.label());
+            println!("config:    {}", config_path.display());
+            println!("session:   {}", session::session_path(&file_path).display());
+            println!("activity:  {}", activity::activity_path(&file_path).display());
+            println!("data dir:  {}", dirs.data_dir().display());
+            return Ok(());
+        }
+        Some(Command::Tutorial) => return run_tutorial(cli.accessible),
+        Some(Command::Report { person, project, week, plain }) => {
+            let content = fs::read_to_string(&file_path)
+                .with_context(|| format!("Failed to read {}", file_path.display()))?;
+            let mut doc = parser::parse_with(&content, config.parse_options());
+            read_archive(&mut doc, &file_path, &config)?;
+            if week {
+                print!("{}", report::weekly(&doc, &activity::load(&file_path), date::Date::today()));
+                return Ok(());
+            }
+            let scope = match (person, project) {
+                (Some(person), _) => report::Scope::Person(person.trim_start_matches('@').to_string()),
+                (None, Some(project)) => report::Scope::Project(project),
+                (None, None) => unreachable!("clap requires --person, --project or --week"),
+            };
+            print!("{}", report::report(&doc, &scope, plain)?);
+            return Ok(());
+        }
+        Some(Command::List { query, format }) => {
+            let query = query.as_deref().map(query::Query::parse).transpose()?;
+            let content = fs::read_to_string(&file_path)
+                .with_context(|| format!("Failed to read {}", file_path.display()))?;
+            let doc = parser::parse_with(&content, config.parse_options());
+            print!("{}", report::list(&doc, query.as_ref(), format.into()));
+            return Ok(());
+        }
+        Some(Command::Current { format }) => {
+            match prompt::load_current(&file_path, &config_path, &config, date::Date::today())? {
+                Some(current) => println!("{}", prompt::render(&format, &current)),
+                None => std::process::exit(1),
+            }
+            return Ok(());
+        }
+        Some(Command::Grep { pattern }) => {
+            let pattern = regex::RegexBuilder::new(&pattern)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("Not a valid pattern: {}", pattern))?;
+            let (doc, _) = read_document(&file_path, &config)?;
+            let (sources, skipped) = profile_documents(&config, Some((&file_path, &doc)));
+            for problem in skipped {
+                eprintln!("Skipping profile {}", problem);
+            }
+            let out = report::grep(&agenda_sources(&sources), &pattern);
+            if out.is_empty() {
+                std::process::exit(1);
+            }
+            print!("{}", out);
+            return Ok(());
+        }
+        Some(Command::Export { share, output }) => {
+            let content = fs::read_to_string(&file_path)
+                .with_context(|| format!("Failed to read {}", file_path.display()))?;
+            let mut doc = parser::parse_with(&content, config.parse_options());
+            read_archive(&mut doc, &file_path, &config)?;
+            let doc = if share { engine::share_copy(&doc) } else { doc };
+            let text = serializer::serialize(&doc);
+            match output {
+                Some(path) => fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?,
+                None => print!("{}", text),
+            }
+            return Ok(());
+        }
+        Some(Command::Print { view, filter, color }) => {
+            let query = print_filter(&config, filter.as_deref())?;
+            let (doc, _) = read_document(&file_path, &config)?;
+            print!("{}", print::print(&doc, view.into(), &print_sections(&config), query.as_ref(), color.enabled()));
+            return Ok(());
+        }
+        Some(Command::Watch { view, filter, color }) => {
+            let query = print_filter(&config, filter.as_deref())?;
+            return watch_view(&file_path, &config, view.into(), query.as_ref(), color.enabled());
+        }
+        Some(Command::Agenda { all_profiles }) => {
+            let sources = if all_profiles {
+                if config.profiles.is_empty() {
+                    anyhow::bail!("No [[profiles]] in {}", config_path.display());
+                }
+                let (sources, skipped) = profile_documents(&config, None);
+                for problem in skipped {
+                    eprintln!("Skipping profile {}", problem);
+                }
+                sources
+            } else {
+                let (doc, _) = read_document(&file_path, &config)?;
+                vec![(file_label(&file_path), file_path, doc)]
+            };
+            let mut sections = config.agenda.sections();
+            sections.retain(|&s| s != model::TaskState::Todo);
+            let items = report::global_agenda(&agenda_sources(&sources), &sections, date::Date::today());
+            print!("{}", report::global_agenda_text(&items, &sections));
+            return Ok(());
+        }
+        Some(Command::Open) | None => {}
+    }
+
+    let (doc, stray_tasks, archive_on_disk) = load_document(&file_path, &config)?;
+    let mut app = App::new(doc, file_path);
+    app.archive_on_disk = archive_on_disk;
+    app.set_config(config, config_path);
+    app.force_accessible = cli.accessible;
+    app.recent_path = Some(dirs.recent_file());
+    app.log_file = log_file;
+    prepare(&mut app, stray_tasks);
+    deep_link(&mut app, cli.view, cli.project.as_deref(), cli.task_id.as_deref())?;
+    if pick_recent {
+        app.open_recent_picker();
+    }
+
+    run_app(&mut app, true)
+}
+
+/// Read (or create) a todo file, returning the document, how many tasks
+/// sat outside any project, and the lines of its archive file (see
+/// `read_archive`), which a reload puts back.
+fn load_document(path: &Path, config: &config::Config) -> Result<(model::Document, usize, model::Archive)> {
+    ensure_file(path)?;
+    read_todo_file(path, config)
+}
+
+/// Read a todo file as `load_document` does, without creating it.
+fn read_document(path: &Path, config: &config::Config) -> Result<(model::Document, usize)> {
+    let (doc, stray_tasks, _) = read_todo_file(path, config)?;
+    Ok((doc, stray_tasks))
+}
+
+fn read_todo_file(path: &Path, config: &config::Config) -> Result<(model::Document, usize, model::Archive)> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let outline = parser::parse_outline_with(&content, config.parse_options());
+    let stray_tasks = outline.stray_tasks;
+    tracing::debug!(file = %path.display(), bytes = content.len(), stray_tasks, "Read");
+    let mut doc = outline.to_document();
+    let archive_file = read_archive(&mut doc, path, config)?;
+    if config.agenda.sink_done {
+        engine::sink_done(&mut doc);
+    }
+    if config.agenda.auto_promote.on_load() {
+        engine::auto_promote(&mut doc, config.agenda.promote);
+    }
+    Ok((doc, stray_tasks, archive_file))
+}
+
+/// With `[files] archive_file`, add the archive kept there after any lines
+/// still in the todo file's own archive section; those move to the archive
+/// file on the next save. Returns the archive file's lines.
+fn read_archive(doc: &mut model::Document, path: &Path, config: &config::Config) -> Result<model::Archive> {
+    let Some(archive_path) = config.files.archive_file(path) else {
+        return Ok(model::Archive::default());
+    };
+    let content = match fs::read_to_string(&archive_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(model::Archive::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", archive_path.display())),
+    };
+    let archive = parser::parse_with(&content, config.parse_options()).archive;
+    tracing::debug!(file = %archive_path.display(), lines = archive.len(), "Read archive");
+    doc.archive = std::mem::take(&mut doc.archive).followed_by(&archive);
+    Ok(archive)
+}
+
+/// Write the archive file of `[files] archive_file`, when the archive
+/// differs from what it holds.
+fn write_archive(app: &mut App) -> Result<()> {
+    let Some((path, content)) = app.serialize_archive() else {
+        return Ok(());
+    };
+    if fs::read_to_string(&path).unwrap_or_default() != content {
+        storage::save_atomic(&path, &content).with_context(|| format!("Failed to write {}", path.display()))?;
+        tracing::debug!(file = %path.display(), bytes = content.len(), "Saved archive");
+    }
+    app.archive_on_disk = app.doc.archive.clone();
     Ok(())
 }
 
-fn state_file_path(file_path: &PathBuf) -> PathBuf {
-    file_path.with_extension("state")
+/// `--filter` of `gws print` and `gws watch`: a saved filter by name, else
+/// the text is the expression itself.
+fn print_filter(config: &config::Config, filter: Option<&str>) -> Result<Option<query::Query>> {
+    filter
+        .map(|text| match config.filters.iter().find(|f| f.name.eq_ignore_ascii_case(text)) {
+            Some(saved) => query::Query::parse(&saved.query),
+            None => query::Query::parse(text),
+        })
+        .transpose()
 }
 
-fn load_collapse_state(file_path: &PathBuf) -> model::CollapseState {
-    let state_path = state_file_path(file_path);
-    if let Ok(content) = fs::read_to_string(&state_path) {
-        model::CollapseState::deserialize(&content)
-    } else {
-        model::CollapseState::new()
+/// Agenda sections printed outside the TUI: the configured ones, with Not
+/// Started folded away as the Agenda starts out.
+fn print_sections(config: &config::Config) -> Vec<model::TaskState> {
+    let mut sections = config.agenda.sections();
+    sections.retain(|&s| s != model::TaskState::Todo);
+    sections
+}
+
+/// `gws watch`: print the view, then again whenever the file changes or the
+/// day turns over (deadlines move tasks up), until interrupted. On a
+/// terminal each update replaces the last; in a pipe they follow each other.
+fn watch_view(
+    file_path: &Path,
+    config: &config::Config,
+    view: print::PrintView,
+    query: Option<&query::Query>,
+    color: bool,
+) -> Result<()> {
+    let mut watch = watcher::FileWatch::new(file_path)?;
+    let terminal = io::stdout().is_terminal();
+    let sections = print_sections(config);
+    let mut shown = String::new();
+    let mut day = None;
+    let mut changed = true;
+    loop {
+        if changed || day != Some(date::Date::today()) {
+            day = Some(date::Date::today());
+            // A file missing mid-sync is reported and waited out
+            let text = match read_document(file_path, config) {
+                Ok((doc, _)) => print::print(&doc, view, &sections, query, color),
+                Err(e) => format!("{:#}\n", e),
+            };
+            if text != shown {
+                if terminal {
+                    print!("\x1b[2J\x1b[H");
+                } else if !shown.is_empty() {
+                    println!();
+                }
+                print!("{}", text);
+                io::stdout().flush()?;
+                shown = text;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(250));
+        changed = false;
+        while watch.poll().is_some() {
+            changed = true;
+        }
     }
 }
 
-fn save_collapse_state(file_path: &PathBuf, state: &model::CollapseState) {
-    let state_path = state_file_path(file_path);
-    let _ = fs::write(&state_path, state.serialize());
+/// A file's name, for tagging its tasks when it has no profile.
+fn file_label(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let file_path = cli.file.unwrap_or_else(default_file_path);
-    let content = ensure_file(&file_path)?;
-    let doc = parser::parse(&content);
+/// Every profile's document, for the global agenda. The open file, given as
+/// `current`, is used as it is in memory (and listed first if it has no
+/// profile); the others are read from disk. Profiles that can't be read are
+/// skipped and returned with the reason.
+fn profile_documents(
+    config: &config::Config,
+    current: Option<(&Path, &model::Document)>,
+) -> (Vec<(String, PathBuf, model::Document)>, Vec<String>) {
+    let mut sources = Vec::new();
+    let mut skipped = Vec::new();
+    if let Some((path, doc)) = current
+        && !config.profiles.iter().any(|p| resolve_file_path(p.file()) == path)
+    {
+        sources.push((file_label(path), path.to_path_buf(), doc.clone()));
+    }
+    for profile in &config.profiles {
+        let path = resolve_file_path(profile.file());
+        let doc = match current {
+            Some((current_path, doc)) if current_path == path => doc.clone(),
+            _ => match read_document(&path, config) {
+                Ok((doc, _)) => doc,
+                Err(e) => {
+                    skipped.push(format!("{}: {:#}", profile.name, e));
+                    continue;
+                }
+            },
+        };
+        sources.push((profile.name.clone(), path, doc));
+    }
+    (sources, skipped)
+}
 
-    let mut app = App::new(doc, file_path.clone());
+fn agenda_sources(sources: &[(String, PathBuf, model::Document)]) -> Vec<report::AgendaSource<'_>> {
+    sources
+        .iter()
+        .map(|(name, file, doc)| report::AgendaSource { name, file, doc })
+        .collect()
+}
 
-    // Restore collapse state and theme
-    let state = load_collapse_state(&file_path);
-    app.theme_index = theme::Theme::by_name(&state.theme_name);
-    app.collapse = state;
-    app.rebuild_tree();
+/// `E`: gather the agenda of every profile into a dialog.
+fn show_global_agenda(app: &mut App) {
+    if app.config.profiles.is_empty() {
+        app.status_msg = "No profiles: add [[profiles]] with a name and file to the config".to_string();
+        return;
+    }
+    let (sources, skipped) = profile_documents(&app.config, Some((&app.file_path, &app.doc)));
+    let items = report::global_agenda(&agenda_sources(&sources), &app.global_agenda_sections(), date::Date::today());
+    app.open_global_agenda(items);
+    if !skipped.is_empty() {
+        app.status_msg = format!("Skipped profile {}", skipped.join("; "));
+    }
+}
 
-    // Set up file watcher
-    let (watcher_rx, _watcher_handle) = match watcher::watch_file(file_path.clone()) {
-        Ok((rx, w)) => (Some(rx), Some(w)),
-        Err(_) => (None, None),
+/// List the focused task's attached files; relative paths are taken from
+/// the todo file's directory.
+fn show_attachments(app: &mut App) {
+    let base = app.file_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let attachments = attach::check(&app.focused_attachments(), &base);
+    app.open_attachments(attachments);
+}
+
+fn open_attachment(app: &mut App, path: &Path) {
+    app.status_msg = match attach::open(path) {
+        Ok(()) => format!("Opened {}", path.display()),
+        Err(e) => format!("{:#}", e),
     };
+}
+
+/// `:log`: the latest lines of the debug log.
+fn show_log(app: &mut App) {
+    let Some(path) = app.log_file.clone() else {
+        return;
+    };
+    match logging::tail(&path, logging::VIEW_LINES) {
+        Ok(lines) => app.open_log(lines),
+        Err(e) => app.status_msg = format!("{:#}", e),
+    }
+}
+
+/// Set up a freshly loaded file: its activity log and session, the startup
+/// checks, and its place at the top of the recent files.
+fn prepare(app: &mut App, stray_tasks: usize) {
+    app.activity = activity::load(&app.file_path);
+    app.on_disk = watcher::Fingerprint::of_file(&app.file_path);
+    app.check_stalled();
+    app.offer_duplicate_fix(false);
+    app.offer_unsorted_triage(stray_tasks);
+
+    // Restore collapse state, theme, view and cursors. The review date is
+    // kept either way, or the reminder would never let up
+    let session = session::load(&app.file_path, &app.doc);
+    app.last_review = session.last_review.as_deref().and_then(date::Date::parse);
+    if app.config.general.restore_session {
+        session.apply(app);
+    }
+    let last_opened = session.last_opened.as_deref().and_then(date::Date::parse);
+    if app.config.general.new_day && last_opened != Some(date::Date::today()) {
+        app.start_new_day();
+    }
+    app.check_review_due();
+    // A profile's theme wins over the session's
+    let profile = app
+        .config
+        .profiles
+        .iter()
+        .find(|p| resolve_file_path(p.file()) == app.file_path)
+        .cloned();
+    app.set_profile(profile.as_ref());
+
+    if let Some(path) = &app.recent_path {
+        let mut recent = session::RecentFiles::load(path);
+        recent.touch(&app.file_path);
+        let _ = recent.save(path);
+        app.recent_files = recent.files;
+    }
+}
+
+/// Open at the place `--view`, `--project` or `--task-id` names, over the
+/// view the session restored.
+fn deep_link(app: &mut App, view: Option<StartView>, project: Option<&str>, task_id: Option<&str>) -> Result<()> {
+    if let Some(id) = task_id {
+        let Some(task) = app.doc.find_task_id(id) else {
+            anyhow::bail!("No task with id {} in {}", id, app.file_path.display());
+        };
+        if view != Some(StartView::Agenda) || !app.reveal_in_agenda(task) {
+            let (cat_idx, proj_idx, task_idx) = task;
+            app.reveal_in_backlog(&model::TreeNodeKind::Task { cat_idx, proj_idx, task_idx });
+        }
+    } else if let Some(name) = project {
+        let Some((cat_idx, proj_idx)) = app.doc.find_project(name) else {
+            anyhow::bail!("No project named \"{}\" in {}", name, app.file_path.display());
+        };
+        app.reveal_in_backlog(&model::TreeNodeKind::Project { cat_idx, proj_idx });
+    } else if let Some(view) = view {
+        app.view = view.into();
+    }
+    Ok(())
+}
+
+/// `:e`: switch to another todo file. The new file is read before the current
+/// one is let go, so a file that can't be opened leaves everything as it was.
+/// Unsaved changes must have been saved or discarded by now.
+fn open_file(app: &mut App, path: PathBuf) -> Result<()> {
+    let path = resolve_file_path(path);
+    let (doc, stray_tasks, archive_on_disk) = load_document(&path, &app.config)?;
+    let _ = session::save(&app.file_path, &session::Session::capture(app));
+    app.switch_document(doc, path);
+    app.archive_on_disk = archive_on_disk;
+    prepare(app, stray_tasks);
+    app.status_msg = format!("Opened {}", app.file_path.display());
+    Ok(())
+}
+
+/// Run the guided tutorial on an in-memory sandbox; the user's files are not touched.
+fn run_tutorial(accessible: bool) -> Result<()> {
+    let mut doc = parser::parse(tutorial::SANDBOX);
+    engine::auto_promote(&mut doc, engine::PromoteStrategy::default());
+    let mut app = App::new(doc, std::env::temp_dir().join("gws-tutorial.md"));
+    app.force_accessible = accessible;
+    app.start_tutorial();
+    run_app(&mut app, false)
+}
+
+/// Run the TUI until quit. With `watch`, the open file is watched for
+/// external edits and keeps a session; without, it is a sandbox.
+fn run_app(app: &mut App, watch: bool) -> Result<()> {
+    // Set up file watcher
+    let mut file_watch = watch.then(|| watcher::FileWatch::new(&app.file_path).ok()).flatten();
+
+    // Terminal setup; the terminal is restored even if setup or the loop fails
+    install_panic_hook();
+    let result = setup_terminal().and_then(|mut terminal| run_loop(&mut terminal, app, file_watch.as_mut()));
+    restore_terminal()?;
 
-    // Terminal setup
+    // Auto-save on quit if dirty
+    if app.dirty {
+        write_document(app, watch)?;
+    }
+
+    // Save collapse state and theme
+    if watch {
+        let _ = session::save(&app.file_path, &session::Session::capture(app));
+    }
+
+    result
+}
+
+fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     io::stdout().execute(EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(io::stdout());
-    let mut terminal = Terminal::new(backend)?;
+    // Pastes arrive whole, for the paste-import dialog, instead of as key presses
+    io::stdout().execute(EnableBracketedPaste)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
     terminal.clear()?;
+    Ok(terminal)
+}
 
-    // Main event loop
-    let result = run_loop(&mut terminal, &mut app, watcher_rx.as_ref());
-
-    // Cleanup
+fn restore_terminal() -> Result<()> {
     disable_raw_mode()?;
+    io::stdout().execute(DisableBracketedPaste)?;
     io::stdout().execute(LeaveAlternateScreen)?;
+    io::stdout().execute(crossterm::cursor::Show)?;
+    Ok(())
+}
 
-    // Auto-save on quit if dirty
-    if app.dirty {
-        let content = app.serialize();
-        save_atomic(&app.file_path, &content)?;
+/// Raw mode turns Ctrl-Z into a key press, so stop the process ourselves:
+/// restore the terminal, raise SIGTSTP, and take the screen back on `fg`.
+#[cfg(unix)]
+fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, _app: &mut App) -> Result<()> {
+    restore_terminal()?;
+    // SAFETY: raise() only signals the current process; execution resumes
+    // here once the shell sends SIGCONT.
+    unsafe {
+        libc::raise(libc::SIGTSTP);
     }
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    io::stdout().execute(EnableBracketedPaste)?;
+    terminal.clear()?;
+    Ok(())
+}
 
-    // Save collapse state and theme
-    app.collapse.theme_name = app.theme().name.to_string();
-    save_collapse_state(&app.file_path, &app.collapse);
+#[cfg(not(unix))]
+fn suspend(_terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
+    app.status_msg = "Suspend is not supported on this platform".to_string();
+    Ok(())
+}
+
+/// Restore the terminal before the default hook prints the panic, so the
+/// message is readable and the shell is not left in raw mode.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+}
+
+/// Write the document, and its archive file if it has one, logging tasks
+/// added and projects (de)activated since the version on disk when
+/// `log_activity` is set. A log that fails to save never blocks saving the
+/// document.
+fn write_document(app: &mut App, log_activity: bool) -> Result<()> {
+    let previous = log_activity.then(|| fs::read_to_string(&app.file_path).ok()).flatten();
+    // The archive first: if the todo file then fails to save, archived tasks
+    // are in both files rather than in neither
+    write_archive(app)?;
+    let content = app.serialize();
+    storage::save_atomic(&app.file_path, &content)?;
+    tracing::debug!(file = %app.file_path.display(), bytes = content.len(), "Saved");
+    if let Some(previous) = previous {
+        let old = parser::parse_with(&previous, app.config.parse_options());
+        app.activity.record(&old, &app.doc, date::Date::today());
+        let _ = activity::save(&app.file_path, &app.activity);
+        webhook::spawn_posts(webhook::announcements(&app.config.webhooks, &old, &app.doc));
+    }
+    Ok(())
+}
 
+/// `:move`: move the focused Backlog task or project to another file. Both
+/// files are written, taking any unsaved changes along, or neither is.
+fn move_to_file(app: &mut App, path: &Path) {
+    let Some(moved) = app.focused_move() else {
+        app.status_msg = "Select a task or project in the Backlog to move".to_string();
+        return;
+    };
+    let path = resolve_file_path(path.to_path_buf());
+    if path == app.file_path {
+        app.status_msg = format!("The {} is already in {}", moved.label, path.display());
+        return;
+    }
+    if changed_on_disk(app) {
+        app.status_msg = "The file changed on disk: save or reload before moving".to_string();
+        return;
+    }
+    match write_move(app, &path, &moved) {
+        Ok(()) => {
+            app.finish_move(moved, &path);
+            app.on_disk = watcher::Fingerprint::of_file(&app.file_path);
+        }
+        Err(e) => app.status_msg = format!("Could not move to {}: {:#}", path.display(), e),
+    }
+}
+
+/// Add the moved item to the target file and write both files (and the
+/// archive file, when there is one).
+fn write_move(app: &mut App, path: &Path, moved: &app::Move) -> Result<()> {
+    // A target that doesn't exist yet is created with just the item
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).context("Failed to read the target file"),
+    };
+    let mut target = parser::parse_with(&content, app.config.parse_options());
+    if subtree::merge(&mut target, moved.piece.clone()) == subtree::Merged::default() {
+        anyhow::bail!("it already has the {}", moved.label);
+    }
+    let remaining = if app.config.files.archive_file.is_some() {
+        // Archive lines still in the todo file must reach the archive file
+        // before the todo file is written without them
+        write_archive(app)?;
+        serializer::serialize_without_archive(&moved.remaining)
+    } else {
+        serializer::serialize(&moved.remaining)
+    };
+    storage::save_both((path, &serializer::serialize(&target)), (&app.file_path, &remaining))
+}
+
+/// `:export`: write the focused category or project to a new file. Never
+/// overwrites; failures are reported in the status bar.
+fn export_subtree(app: &mut App, path: &Path) {
+    if path.exists() {
+        app.status_msg = format!("{} already exists", path.display());
+        return;
+    }
+    let Some((name, doc)) = app.focused_subtree() else {
+        app.status_msg = "Select a category or project in the Backlog to export".to_string();
+        return;
+    };
+    app.status_msg = match fs::write(path, serializer::serialize(&doc)) {
+        Ok(()) => format!("Exported \"{}\" to {}", name, path.display()),
+        Err(e) => format!("Export failed: {}", e),
+    };
+}
+
+/// `:ics`: write today's plan to a new iCalendar file, one event per task
+/// from `[planner] day_start`. Never overwrites.
+fn export_ics(app: &mut App, path: &Path) {
+    if path.exists() {
+        app.status_msg = format!("{} already exists", path.display());
+        return;
+    }
+    let plan = app.todays_plan();
+    if plan.items.is_empty() {
+        app.status_msg = "Nothing to plan today".to_string();
+        return;
+    }
+    let start = ics::parse_time(&app.config.planner.day_start).unwrap_or(ics::DEFAULT_DAY_START);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    let content = ics::plan_calendar(&app.doc, &plan, date::Date::today(), start, &ics::utc_stamp(now));
+    app.status_msg = match fs::write(path, content) {
+        Ok(()) => format!(
+            "Exported {} task{} ({}) to {}",
+            plan.items.len(),
+            if plan.items.len() == 1 { "" } else { "s" },
+            planner::format_minutes(plan.total_minutes()),
+            path.display()
+        ),
+        Err(e) => format!("Export failed: {}", e),
+    };
+}
+
+/// `:import`: merge another file's categories, projects and archive into the document.
+fn import_file(app: &mut App, path: &Path) {
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            let doc = parser::parse_with(&content, app.config.parse_options());
+            app.import_document(doc, &path.display().to_string());
+        }
+        Err(e) => app.status_msg = format!("Could not read {}: {}", path.display(), e),
+    }
+}
+
+/// Idle time after the last key press before an autosave.
+const AUTOSAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Save the document, noting the version written so the watcher events it
+/// causes can be ignored.
+fn save(app: &mut App) -> Result<()> {
+    write_document(app, true)?;
+    app.dirty = false;
+    app.hold_autosave = false;
+    app.on_disk = watcher::Fingerprint::of_file(&app.file_path);
+    Ok(())
+}
+
+/// Keep the TUI running through a failed read or write (a file briefly
+/// missing mid-sync, permission denied): the error is shown in a retryable
+/// dialog and the document is left as it is, so no work is lost.
+fn report_io<T>(app: &mut App, result: Result<T>, retry: Retry) -> Option<T> {
     result
+        .map_err(|e| {
+            let verb = match retry {
+                Retry::Save => "save",
+                Retry::Reload => "reload",
+            };
+            tracing::error!(file = %app.file_path.display(), "Could not {}: {:#}", verb, e);
+            app.show_io_error(format!("Could not {} {}: {:#}", verb, app.file_path.display(), e), retry)
+        })
+        .ok()
+}
+
+/// Whether the file changed on disk since it was loaded or last saved.
+fn changed_on_disk(app: &App) -> bool {
+    app.on_disk.is_some_and(|fp| !fp.matches(&app.file_path))
+}
+
+/// Save unless that would overwrite changes made on disk since, in which
+/// case ask first. Returns whether the document was written.
+fn save_checked(app: &mut App, quitting: bool) -> Result<bool> {
+    if changed_on_disk(app) {
+        app.offer_save_conflict(quitting);
+        return Ok(false);
+    }
+    save(app)?;
+    Ok(true)
+}
+
+/// Merge the version on disk into the document, then save over it.
+fn save_merged(app: &mut App) -> Result<()> {
+    // A file removed since is nothing to merge
+    let content = fs::read_to_string(&app.file_path).unwrap_or_default();
+    app.merge_from_disk(parser::parse_with(&content, app.config.parse_options()));
+    save(app)
+}
+
+/// Re-read the file. The version is noted before reading, so a write in
+/// between is caught by the next save rather than overwritten.
+fn reload(app: &mut App) -> Result<()> {
+    let version = watcher::Fingerprint::of_file(&app.file_path);
+    let content = fs::read_to_string(&app.file_path)?;
+    tracing::debug!(file = %app.file_path.display(), bytes = content.len(), "Reloaded");
+    app.reload(&content);
+    app.on_disk = version;
+    // The file is back; a failed reload no longer needs reporting
+    if app.io_error.as_ref().is_some_and(|e| e.retry == Retry::Reload) {
+        app.close_error();
+    }
+    Ok(())
 }
 
 fn run_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    watcher_rx: Option<&std::sync::mpsc::Receiver<watcher::FileEvent>>,
+    mut file_watch: Option<&mut watcher::FileWatch>,
 ) -> Result<()> {
-    loop {
-        terminal.draw(|frame| tui::ui::draw(frame, &mut *app))?;
-
-        // Check for file changes
-        if let Some(rx) = watcher_rx {
-            if watcher::poll_file_events(rx).is_some() {
-                if !app.dirty {
-                    let content = fs::read_to_string(&app.file_path)?;
-                    app.reload(&content);
-                } else {
-                    app.status_msg = "External change detected (unsaved changes)".to_string();
-                }
+    let events = Events::new(events::TICK_RATE);
+    let mut last_input = Instant::now();
+    app.needs_redraw = true;
+    while app.running {
+        if app.needs_redraw {
+            terminal.draw(|frame| tui::ui::draw(frame, &mut *app))?;
+            app.needs_redraw = false;
+        }
+        // Take in everything already waiting before drawing again, so a
+        // burst of keys costs one frame
+        let mut next = Some(events.next());
+        while let Some(event) = next {
+            handle_event(terminal, app, &mut file_watch, &events, event, &mut last_input)?;
+            next = if app.running { events.try_next() } else { None };
+        }
+    }
+    Ok(())
+}
+
+/// Apply one event, flagging a redraw when the screen may have changed.
+fn handle_event(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    file_watch: &mut Option<&mut watcher::FileWatch>,
+    events: &Events,
+    event: AppEvent,
+    last_input: &mut Instant,
+) -> Result<()> {
+    match event {
+        AppEvent::Terminal(Event::Paste(text)) => {
+            *last_input = Instant::now();
+            input::handle_paste(app, &text);
+        }
+        AppEvent::Terminal(Event::Key(key)) => {
+            *last_input = Instant::now();
+            let action = input::handle_key(app, key);
+            tracing::debug!(code = ?key.code, modifiers = ?key.modifiers, dialog = ?app.dialog, ?action, "Key");
+            run_action(terminal, app, file_watch, events, action)?;
+            if app.quit_pending && !app.dirty {
+                app.running = false;
             }
         }
+        // A resize or focus change: draw again
+        AppEvent::Terminal(_) => {}
+        AppEvent::InputError(e) => return Err(e.into()),
+        AppEvent::LinkTitle(url, title) => app.apply_link_title(&url, title.map_err(|e| format!("{:#}", e))),
+        AppEvent::Tick => {
+            tick(app, file_watch.as_deref_mut(), *last_input);
+            return Ok(());
+        }
+    }
+    app.needs_redraw = true;
+    Ok(())
+}
 
-        // Poll for keyboard events with a timeout to allow watcher checks
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match input::handle_key(app, key) {
-                    Action::Quit => {
-                        app.running = false;
-                        break;
-                    }
-                    Action::Save => {
-                        let content = app.serialize();
-                        save_atomic(&app.file_path, &content)?;
-                        app.dirty = false;
-                        app.status_msg = "Saved".to_string();
-                    }
-                    Action::Reload => {
-                        let content = fs::read_to_string(&app.file_path)?;
-                        app.reload(&content);
+/// Carry out what a key asked of the event loop.
+fn run_action(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    file_watch: &mut Option<&mut watcher::FileWatch>,
+    events: &Events,
+    action: Action,
+) -> Result<()> {
+    match action {
+        Action::Quit => {
+            let saved = !app.dirty || {
+                let result = save_checked(app, true);
+                report_io(app, result, Retry::Save) == Some(true)
+            };
+            if saved {
+                app.running = false;
+            }
+        }
+        Action::QuitDiscard => {
+            app.dirty = false;
+            app.running = false;
+        }
+        Action::Save => {
+            let result = save_checked(app, false);
+            if report_io(app, result, Retry::Save) == Some(true) {
+                app.status_msg = "Saved".to_string();
+            }
+        }
+        Action::SaveMerged => {
+            let result = save_merged(app);
+            report_io(app, result, Retry::Save);
+        }
+        Action::SaveOverwrite => {
+            let result = save(app);
+            if report_io(app, result, Retry::Save).is_some() {
+                app.status_msg = "Saved over the changes on disk".to_string();
+            }
+        }
+        Action::Reload => {
+            let result = reload(app);
+            report_io(app, result, Retry::Reload);
+        }
+        Action::Suspend => suspend(terminal, app)?,
+        Action::Export(path) => export_subtree(app, &path),
+        Action::Import(path) => import_file(app, &path),
+        Action::ExportIcs(path) => export_ics(app, &path),
+        Action::Move(path) => move_to_file(app, &path),
+        Action::GlobalAgenda => show_global_agenda(app),
+        Action::Attachments => show_attachments(app),
+        Action::OpenAttachment(path) => open_attachment(app, &path),
+        Action::ShowLog => show_log(app),
+        Action::FetchTitles(urls) => {
+            let sender = events.sender();
+            links::spawn_fetches(urls, links::FETCH_TIMEOUT, move |url, title| {
+                // The loop may have ended meanwhile
+                let _ = sender.send(AppEvent::LinkTitle(url, title));
+            });
+        }
+        Action::Open(path) => match file_watch.as_deref_mut() {
+            // The tutorial sandbox has no file to switch from
+            None => app.status_msg = "Opening files is not available here".to_string(),
+            Some(watch) => {
+                let saved = !app.dirty || {
+                    let result = save_checked(app, false);
+                    report_io(app, result, Retry::Save) == Some(true)
+                };
+                if saved {
+                    match open_file(app, path) {
+                        Ok(()) => {
+                            if let Ok(next) = watcher::FileWatch::new(&app.file_path) {
+                                *watch = next;
+                            }
+                        }
+                        Err(e) => app.status_msg = format!("Could not open: {:#}", e),
                     }
-                    Action::None => {}
                 }
             }
-        }
+        },
+        Action::None => {}
+    }
+    Ok(())
+}
 
-        if !app.running {
-            break;
+/// The timers: the date, changes to the file on disk, and autosave. An idle
+/// tick leaves the screen alone.
+fn tick(app: &mut App, file_watch: Option<&mut watcher::FileWatch>, last_input: Instant) {
+    app.tick(date::Date::today());
+    if let Some(watch) = file_watch
+        && let Some(event) = watch.poll()
+    {
+        // Events from our own saves find the file as we wrote it
+        let ours = app.on_disk.is_some_and(|fp| fp.matches(&app.file_path));
+        tracing::debug!(?event, ours, dirty = app.dirty, "File watcher");
+        app.needs_redraw |= !ours;
+        match (ours, app.dirty) {
+            (true, _) => {}
+            (false, false) => {
+                let result = reload(app);
+                report_io(app, result, Retry::Reload);
+            }
+            (false, true) => app.status_msg = "External change detected (unsaved changes)".to_string(),
         }
     }
-    Ok(())
+
+    // Debounced autosave: wait for a pause in typing, and never mid-dialog
+    if app.dirty
+        && app.config.general.autosave
+        && app.dialog == app::Dialog::None
+        && !app.is_moving()
+        && !app.hold_autosave
+        && last_input.elapsed() >= AUTOSAVE_DELAY
+    {
+        let result = save_checked(app, false);
+        report_io(app, result, Retry::Save);
+        app.needs_redraw = true;
+    }
 }
 
 #[cfg(test)]
@@ -183,6 +1139,7 @@ mod integration_tests {
     use crate::engine;
     use crate::parser;
     use crate::serializer;
+    use crate::tutorial;
     use std::path::PathBuf;
 
     fn sample_content() -> &'static str {
@@ -240,7 +1197,7 @@ mod integration_tests {
         assert_eq!(app.doc.archive.len(), 3);
 
         // 2. Build agenda (before auto-promote)
-        let agenda = engine::build_agenda(&app.doc);
+        let agenda = engine::build_agenda(&app.doc, &engine::DEFAULT_SECTIONS);
         // Active projects: Website Redesign (🔶+🔵), Essential (🔵+🔶), Q1 Tax (no 🔵/🔶),
         // Kitchen (🔵), Inbox (no 🔵/🔶)
         let total: usize = agenda.len();
@@ -248,7 +1205,7 @@ mod integration_tests {
 
         // 3. Run auto-promote
         app.run_auto_promote();
-        let agenda = engine::build_agenda(&app.doc);
+        let agenda = engine::build_agenda(&app.doc, &engine::DEFAULT_SECTIONS);
         assert!(agenda.len() >= total); // should have more or equal
 
         // 4. Promote a specific task (cat 0, proj 0, task 2 = "Set up staging")
@@ -314,7 +1271,7 @@ mod integration_tests {
         // Verify categories exist
         assert!(doc.categories.len() >= 2);
 
-        let agenda = engine::build_agenda(&doc);
+        let agenda = engine::build_agenda(&doc, &engine::DEFAULT_SECTIONS);
         assert!(!agenda.is_empty());
     }
 
@@ -341,7 +1298,7 @@ mod integration_tests {
         assert!(!doc.categories[0].projects[1].active);
 
         // Agenda should work — Alpha has one 🔵 and one 🔴
-        let agenda = engine::build_agenda(&doc);
+        let agenda = engine::build_agenda(&doc, &engine::DEFAULT_SECTIONS);
         assert_eq!(agenda.len(), 2);
     }
 
@@ -400,7 +1357,7 @@ mod integration_tests {
 
         // --- Agenda before auto-promote ---
         println!("\n=== AGENDA (before auto-promote) ===");
-        let agenda = engine::build_agenda(&doc);
+        let agenda = engine::build_agenda(&doc, &engine::DEFAULT_SECTIONS);
         for item in &agenda {
             println!("  ({}) {} {}", item.project_name, item.task.state.symbol(), item.task.text);
         }
@@ -408,9 +1365,9 @@ mod integration_tests {
 
         // --- Auto-promote ---
         let mut doc = doc;
-        engine::auto_promote(&mut doc);
+        engine::auto_promote(&mut doc, engine::PromoteStrategy::TopOfList);
         println!("\n=== AGENDA (after auto-promote) ===");
-        let agenda = engine::build_agenda(&doc);
+        let agenda = engine::build_agenda(&doc, &engine::DEFAULT_SECTIONS);
         for item in &agenda {
             println!("  ({}) {} {}", item.project_name, item.task.state.symbol(), item.task.text);
         }
@@ -458,7 +1415,7 @@ mod integration_tests {
 
         // Promote the InProgress task to Done
         app.agenda_cursor = 0;
-        app.promote_selected_agenda();
+        app.promote_selected_agenda(1);
 
         // Task should now be Done
         assert_eq!(
@@ -474,4 +1431,1083 @@ mod integration_tests {
         app.refresh_agenda();
         assert_eq!(app.agenda_items.len(), 1, "Archived task should be gone from agenda");
     }
+
+    #[test]
+    fn test_preamble_tree_nodes() {
+        let content = "\
+# My GTD
+
+Intro text
+
+## Work
+
+### 🔶 Project
+- 🔴 Task
+";
+        let doc = parser::parse(content);
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+
+        // Header node carries the title; blank lines and the title line are hidden
+        assert_eq!(app.tree_nodes[0].kind, crate::model::TreeNodeKind::Preamble);
+        assert_eq!(app.tree_nodes[0].display, "▼ My GTD");
+        assert_eq!(app.tree_nodes[1].kind, crate::model::TreeNodeKind::PreambleLine { line_idx: 2 });
+        assert!(matches!(app.tree_nodes[2].kind, crate::model::TreeNodeKind::Category { .. }));
+
+        // Collapsing hides the preamble lines
+        app.backlog_cursor = 0;
+        app.toggle_collapse();
+        assert!(matches!(app.tree_nodes[1].kind, crate::model::TreeNodeKind::Category { .. }));
+
+        // Editing the header renames the title and survives a round-trip
+        app.input_buffer = "Renamed".to_string();
+        app.apply_edit();
+        let doc2 = parser::parse(&app.serialize());
+        assert_eq!(doc2.title(), Some("Renamed"));
+    }
+
+    #[test]
+    fn test_sort_and_undo() {
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        let original = app.doc.clone();
+
+        // Finance category node: sort its projects by name
+        app.backlog_cursor = app.tree_nodes.iter()
+            .position(|n| n.kind == crate::model::TreeNodeKind::Category { cat_idx: 1 })
+            .unwrap();
+        app.apply_sort('n');
+        let names: Vec<&str> = app.doc.categories[1].projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Inbox", "Kitchen Renovation", "Learn Rust", "Q1 Tax Filing"]);
+        assert!(app.dirty);
+
+        app.undo();
+        assert_eq!(app.doc, original);
+        assert!(app.undo_stack.is_empty());
+
+        // A reload of an edit on disk leaves nothing older to undo to
+        app.apply_sort('n');
+        let edited = app.serialize().replace("Learn Rust", "Learn Go");
+        app.reload(&edited);
+        app.undo();
+        assert_eq!(app.status_msg, "Nothing to undo");
+        assert!(app.doc.categories[1].projects.iter().any(|p| p.name == "Learn Go"));
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn test_fold_to_depth() {
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        let categories = app.doc.categories.len();
+        let projects: usize = app.doc.categories.iter().map(|c| c.projects.len()).sum();
+
+        // Cursor on a task folds back to its category
+        app.backlog_cursor = 2;
+        app.fold_to_depth(1);
+        assert_eq!(app.tree_nodes.len(), categories);
+        assert_eq!(app.current_tree_node().unwrap().kind, crate::model::TreeNodeKind::Category { cat_idx: 0 });
+
+        app.fold_to_depth(2);
+        assert_eq!(app.tree_nodes.len(), categories + projects);
+
+        app.fold_to_depth(u8::MAX);
+        assert!(app.collapse.collapsed_categories.is_empty());
+        assert!(app.collapse.collapsed_projects.is_empty());
+        assert!(app.tree_nodes.len() > categories + projects);
+    }
+
+    #[test]
+    fn test_page_navigation() {
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Backlog;
+        app.visible_height = 6;
+        let len = app.tree_nodes.len();
+
+        app.half_page(1);
+        assert_eq!(app.backlog_cursor, 3);
+        app.full_page(1);
+        assert_eq!(app.backlog_cursor, 9);
+        app.full_page(10);
+        assert_eq!(app.backlog_cursor, len - 1, "paging clamps instead of wrapping");
+        app.half_page(-100);
+        assert_eq!(app.backlog_cursor, 0);
+
+        app.backlog_scroll = 4;
+        app.move_to_viewport('H');
+        assert_eq!(app.backlog_cursor, 4);
+        app.move_to_viewport('M');
+        assert_eq!(app.backlog_cursor, 6);
+        app.move_to_viewport('L');
+        assert_eq!(app.backlog_cursor, 9);
+
+        // Agenda viewport accounts for section header rows:
+        // "In Progress" header + 2 items, "On Deck" header + 2 items
+        app.view = crate::app::View::Agenda;
+        app.agenda_scroll = 0;
+        app.move_to_viewport('L');
+        assert_eq!(app.agenda_cursor, 3);
+    }
+
+    #[test]
+    fn test_count_prefixed_motions() {
+        use crate::tui::input::handle_key;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let press = |app: &mut App, c: char| {
+            handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        };
+
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Backlog;
+
+        press(&mut app, '5');
+        assert_eq!(app.count, Some(5));
+        press(&mut app, 'j');
+        assert_eq!(app.backlog_cursor, 5);
+        assert_eq!(app.count, None);
+
+        press(&mut app, '1');
+        press(&mut app, '0');
+        press(&mut app, 'G');
+        assert_eq!(app.backlog_cursor, 9);
+
+        // 3p on a Todo task: Todo → OnDeck → InProgress → Done
+        let (ci, pi, ti) = (0, 0, 2);
+        app.backlog_cursor = app.tree_nodes.iter()
+            .position(|n| n.kind == crate::model::TreeNodeKind::Task { cat_idx: ci, proj_idx: pi, task_idx: ti })
+            .unwrap();
+        press(&mut app, '3');
+        press(&mut app, 'p');
+        assert_eq!(app.doc.categories[ci].projects[pi].tasks[ti].state, crate::model::TaskState::Done);
+
+        // A huge count is capped, and on the agenda every step goes to the
+        // task that was under the cursor: 999 steps round the states
+        // leave it three on, and no other task changes
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        for c in "999999999j".chars() {
+            press(&mut app, c);
+        }
+        assert_eq!(app.agenda_cursor, app.agenda_items.len() - 1);
+        app.agenda_cursor = 0;
+        let item = &app.agenda_items[0];
+        let (ci, pi, ti) = (item.category_idx, item.project_idx, item.task_idx);
+        let mut expected = app.doc.clone();
+        let task = &mut expected.categories[ci].projects[pi].tasks[ti];
+        task.state = task.state.promote().promote().promote();
+        for c in "999999999p".chars() {
+            press(&mut app, c);
+        }
+        assert_eq!(app.doc, expected);
+    }
+
+    #[test]
+    fn test_jump_labels() {
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Backlog;
+        app.visible_height = 10;
+
+        app.start_jump();
+        let labels = app.jump_labels();
+        assert_eq!(labels.len(), 10);
+        assert_eq!(labels[0], (0, "a".to_string()));
+        assert_eq!(app.jump_label_for(3).as_deref(), Some("f"));
+
+        app.jump_key('f');
+        assert_eq!(app.backlog_cursor, 3);
+        assert!(!app.is_jumping());
+
+        // More rows than letters switch every label to two letters
+        let content = std::fs::read_to_string("sample_todo.md").unwrap();
+        let mut app = App::new(parser::parse(&content), PathBuf::from("sample_todo.md"));
+        app.view = crate::app::View::Backlog;
+        app.visible_height = 30;
+        assert!(app.tree_nodes.len() > 26);
+        app.start_jump();
+        assert!(app.jump_labels().iter().all(|(_, l)| l.len() == 2));
+        app.jump_key('a');
+        assert!(app.is_jumping());
+        assert_eq!(app.jump_label_for(0).as_deref(), Some("aa"));
+        app.jump_key('d');
+        assert_eq!(app.backlog_cursor, 2);
+    }
+
+    #[test]
+    fn test_backlog_filter() {
+        use crate::model::TreeNodeKind;
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Backlog;
+        let full_len = app.tree_nodes.len();
+
+        app.start_filter();
+        for c in "nvidia".chars() {
+            app.input_char(c);
+            app.update_filter();
+        }
+        // Business › Essential › two NVIDIA tasks
+        let kinds: Vec<&TreeNodeKind> = app.tree_nodes.iter().map(|n| &n.kind).collect();
+        assert_eq!(kinds, [
+            &TreeNodeKind::Category { cat_idx: 0 },
+            &TreeNodeKind::Project { cat_idx: 0, proj_idx: 1 },
+            &TreeNodeKind::Task { cat_idx: 0, proj_idx: 1, task_idx: 0 },
+            &TreeNodeKind::Task { cat_idx: 0, proj_idx: 1, task_idx: 2 },
+        ]);
+
+        // Project name matches keep all of the project's tasks
+        app.input_buffer = "kitchen".to_string();
+        app.update_filter();
+        assert_eq!(app.tree_nodes.len(), 1 + 1 + 3);
+
+        // Clearing restores the full tree with the cursor on the same node
+        app.backlog_cursor = 3;
+        app.clear_filter();
+        assert_eq!(app.tree_nodes.len(), full_len);
+        assert_eq!(app.current_tree_node().unwrap().kind, TreeNodeKind::Task { cat_idx: 1, proj_idx: 1, task_idx: 1 });
+    }
+
+    #[test]
+    fn test_agenda_scope() {
+        use crate::app::AgendaScope;
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        let all_len = app.agenda_items.len();
+
+        // c cycles All → Business → Finance → All
+        app.cycle_agenda_category();
+        assert_eq!(app.agenda_scope, AgendaScope::Category { category: "Business".to_string() });
+        assert!(app.agenda_items.iter().all(|i| i.category_idx == 0));
+        app.cycle_agenda_category();
+        assert!(app.agenda_items.iter().all(|i| i.category_idx == 1));
+        app.cycle_agenda_category();
+        assert_eq!(app.agenda_scope, AgendaScope::All);
+        assert_eq!(app.agenda_items.len(), all_len);
+
+        // Picker lists All plus active projects only
+        app.open_scope_picker();
+        assert_eq!(app.picker.items.len(), 1 + 5);
+        assert_eq!(app.picker.items[2], "Business › Essential");
+        app.picker_move(1);
+        app.picker_move(1);
+        app.apply_scope_picker();
+        assert_eq!(app.agenda_scope.label(), "Business › Essential");
+        assert!(app.agenda_items.iter().all(|i| i.category_idx == 0 && i.project_idx == 1));
+        assert!(!app.agenda_items.is_empty());
+
+        // Cycling from a project scope widens to its category first
+        app.cycle_agenda_category();
+        assert_eq!(app.agenda_scope, AgendaScope::Category { category: "Business".to_string() });
+    }
+
+    #[test]
+    fn test_hide_done() {
+        use crate::model::{TaskState, TreeNodeKind};
+        let content = "## Work\n\n### 🔶 Alpha\n- ✅ Shipped\n- 🔶 Building\n- 🔴 Later\n";
+        let mut app = App::new(parser::parse(content), PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Backlog;
+        assert!(app.agenda_items.iter().any(|i| i.task.state == TaskState::Done));
+
+        // Cursor on the hidden task falls back to its project
+        app.backlog_cursor = 2;
+        app.toggle_hide_done();
+        assert!(app.agenda_items.iter().all(|i| i.task.state != TaskState::Done));
+        assert_eq!(app.tree_nodes.len(), 1 + 1 + 2);
+        assert_eq!(app.current_tree_node().unwrap().kind, TreeNodeKind::Project { cat_idx: 0, proj_idx: 0 });
+
+        app.toggle_hide_done();
+        assert_eq!(app.tree_nodes.len(), 1 + 1 + 3);
+    }
+
+    #[test]
+    fn test_agenda_hides_not_started_by_default() {
+        use crate::model::TaskState;
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        assert!(app.agenda_items.iter().all(|i| i.task.state != TaskState::Todo));
+        let hidden = app.hidden_todo_count;
+        assert!(hidden > 0);
+
+        // Revealing keeps the cursor on the same task
+        app.agenda_cursor = 1;
+        let key = (app.agenda_items[1].category_idx, app.agenda_items[1].task_idx);
+        let shown_before = app.agenda_items.len();
+        app.toggle_show_todo();
+        assert_eq!(app.agenda_items.len(), shown_before + hidden);
+        assert_eq!(app.hidden_todo_count, 0);
+        let item = &app.agenda_items[app.agenda_cursor];
+        assert_eq!((item.category_idx, item.task_idx), key);
+    }
+
+    #[test]
+    fn test_agenda_section_settings() {
+        use crate::app::SettingsRow;
+        use crate::model::TaskState;
+        use crate::tui::input::handle_key;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let press = |app: &mut App, code: KeyCode| {
+            handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+        };
+
+        let mut doc = parser::parse(sample_content());
+        engine::auto_promote(&mut doc, engine::PromoteStrategy::default());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Settings;
+
+        // First agenda row is In Progress; hide it from the agenda
+        app.settings_cursor = app.settings_row_index(SettingsRow::AgendaSection(0));
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.agenda_items.iter().all(|i| i.task.state != TaskState::InProgress));
+
+        // Move On Deck above In Progress, then accept
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.agenda.order[..2], [TaskState::OnDeck, TaskState::InProgress]);
+        assert_eq!(app.settings_row(app.settings_cursor), SettingsRow::AgendaSection(0));
+
+        // Cancelling a move restores the order
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.config.agenda.order[..2], [TaskState::OnDeck, TaskState::InProgress]);
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn test_settings_preferences() {
+        use crate::app::{SettingsRow, SettingsSection};
+        use crate::tui::input::handle_key;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let press = |app: &mut App, code: KeyCode| {
+            handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+        };
+
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+        app.view = crate::app::View::Settings;
+        assert_eq!(app.settings_row(0), SettingsRow::Section(SettingsSection::General));
+
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.settings_row(app.settings_cursor), SettingsRow::RestoreSession);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.config.general.restore_session);
+
+        app.settings_cursor = app.settings_row_index(SettingsRow::DefaultFile);
+        press(&mut app, KeyCode::Char('e'));
+        for c in "~/work.md".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.files.default_file, Some(PathBuf::from("~/work.md")));
+
+        // Category rows follow the preferences
+        let last = app.settings_total() - 1;
+        assert_eq!(app.settings_row(last), SettingsRow::Category(1));
+        app.settings_cursor = last;
+        assert_eq!(app.settings_category_idx(), Some(1));
+    }
+
+    #[test]
+    fn test_tutorial_walkthrough() {
+        use crate::model::TreeNodeKind;
+        use crate::tui::input::handle_key;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let press = |app: &mut App, code: KeyCode| {
+            handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+        };
+        let progress = |app: &App| app.tutorial.as_ref().unwrap().progress();
+
+        let mut doc = parser::parse(tutorial::SANDBOX);
+        engine::auto_promote(&mut doc, engine::PromoteStrategy::default());
+        let mut app = App::new(doc, PathBuf::from("/tmp/gws-tutorial.md"));
+        app.start_tutorial();
+        assert_eq!(progress(&app), "1/6");
+
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(progress(&app), "2/6");
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(progress(&app), "3/6");
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(progress(&app), "4/6");
+
+        // Adding only counts once the dialog is confirmed
+        app.backlog_cursor = app
+            .tree_nodes
+            .iter()
+            .position(|n| matches!(n.kind, TreeNodeKind::Project { .. }))
+            .unwrap();
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(progress(&app), "4/6");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(progress(&app), "5/6");
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(progress(&app), "5/6");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(progress(&app), "6/6");
+
+        // Wrong view gets a nudge instead of the instructions
+        assert!(app.tutorial.as_ref().unwrap().hint(&app).starts_with("Press Tab"));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('A'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(app.tutorial.as_ref().unwrap().is_finished());
+    }
+
+    #[test]
+    fn test_accessible_descriptions() {
+        use crate::tui::widgets;
+        let doc = parser::parse(sample_content());
+        let mut app = App::new(doc, PathBuf::from("/tmp/test.md"));
+
+        let item = app.agenda_items.iter().find(|i| i.task.text == "NVIDIA Conference expense").unwrap();
+        assert_eq!(widgets::describe_agenda_item(item, &app.agenda_item_place(item)), "Task, On Deck, NVIDIA Conference expense, project Essential");
+
+        let describe = |app: &App, idx: usize| widgets::describe_tree_node(app, &app.tree_nodes[idx]);
+        assert_eq!(describe(&app, 0), "Category, Business, expanded");
+        assert_eq!(describe(&app, 1), "Project, Website Redesign, active, expanded");
+        assert_eq!(describe(&app, 2), "Task, In Progress, Finalize color palette with design team");
+
+        assert!(!app.accessible());
+        assert_eq!(widgets::glyphs(&app).cursor, "▸");
+        app.force_accessible = true;
+        assert_eq!(widgets::glyphs(&app).cursor, ">");
+        assert!(!app.config.general.accessible);
+    }
+
+    #[test]
+    fn test_ctrl_z_suspends() {
+        use crate::tui::input::{handle_key, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        let action = handle_key(&mut app, KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert!(matches!(action, Action::Suspend));
+        assert!(app.pending_key.is_none());
+    }
+
+    #[test]
+    fn test_quit_confirmation() {
+        use crate::app::Dialog;
+        use crate::tui::input::{handle_key, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let press = |app: &mut App, code: KeyCode| handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        assert!(matches!(press(&mut app, KeyCode::Char('q')), Action::Quit));
+
+        app.dirty = true;
+        assert!(matches!(press(&mut app, KeyCode::Char('q')), Action::None));
+        assert_eq!(app.dialog, Dialog::ConfirmQuit);
+        assert!(matches!(press(&mut app, KeyCode::Esc), Action::None));
+        assert_eq!(app.dialog, Dialog::None);
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(matches!(press(&mut app, KeyCode::Char('d')), Action::QuitDiscard));
+        press(&mut app, KeyCode::Char('q'));
+        assert!(matches!(press(&mut app, KeyCode::Char('s')), Action::Quit));
+
+        // Command line
+        let run = |app: &mut App, command: &str| {
+            press(app, KeyCode::Char(':'));
+            for c in command.chars() {
+                press(app, KeyCode::Char(c));
+            }
+            press(app, KeyCode::Enter)
+        };
+        assert!(matches!(run(&mut app, "q!"), Action::QuitDiscard));
+        assert!(matches!(run(&mut app, "w"), Action::Save));
+        assert!(matches!(run(&mut app, "q"), Action::None));
+        assert_eq!(app.dialog, Dialog::ConfirmQuit);
+        app.close_dialog();
+        assert!(matches!(run(&mut app, "nope"), Action::None));
+        assert_eq!(app.status_msg, "Unknown command: nope");
+    }
+
+    #[test]
+    fn test_promote_modes() {
+        use engine::PromoteMode;
+        use crate::model::TaskState;
+        let content = "## Work\n\n### 🔶 Launch\n- 🔴 Pick a domain\n- 🔴 Write copy\n";
+        let first = |app: &App| app.doc.categories[0].projects[0].tasks[0].state;
+        let mut app = App::new(parser::parse(content), PathBuf::from("/tmp/test.md"));
+        app.config.agenda.auto_promote = PromoteMode::OnDemand;
+        app.refresh_agenda();
+        assert_eq!(first(&app), TaskState::Todo);
+        app.reload("## Work\n\n### 🔶 Launch\n- 🔴 Pick a domain\n");
+        assert_eq!(first(&app), TaskState::Todo);
+
+        // Off ignores r as well
+        app.next_promote_mode();
+        app.next_promote_mode();
+        app.next_promote_mode();
+        assert_eq!(app.config.agenda.auto_promote, PromoteMode::Off);
+        app.run_auto_promote();
+        assert_eq!(app.status_msg, "Auto-promote is off (see Settings)");
+        assert_eq!(first(&app), TaskState::Todo);
+
+        app.config.agenda.auto_promote = PromoteMode::OnDemand;
+        app.run_auto_promote();
+        assert_eq!(first(&app), TaskState::OnDeck);
+        assert_eq!(app.status_msg, "Promoted \"Pick a domain\" in Launch");
+
+        app.config.agenda.auto_promote = PromoteMode::OnLoad;
+        app.reload(content);
+        assert_eq!(app.status_msg, "Reloaded from disk: 1 task added, 1 changed state");
+        assert_eq!(first(&app), TaskState::OnDeck);
+    }
+
+    #[test]
+    fn test_no_op_changes_stay_clean() {
+        use crate::app::View;
+        use crate::tui::input::handle_key;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        let content = "# Plans\n\n## Work\n\n### 🔶 Launch\n- 🔵 Pick a domain\n- 🔴 Write copy\n";
+        let mut app = App::new(parser::parse(content), PathBuf::from("/tmp/test.md"));
+        app.run_auto_promote();
+        assert_eq!(app.status_msg, "Nothing to promote");
+        app.archive_done();
+        assert_eq!(app.status_msg, "Nothing to archive");
+        assert!(app.undo_stack.is_empty());
+
+        // Saving an edit without changing the text
+        app.view = View::Backlog;
+        app.rebuild_tree();
+        for row in 0..app.tree_nodes.len() {
+            app.backlog_cursor = row;
+            handle_key(&mut app, KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE));
+            handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        }
+        // Deleting the category row does nothing
+        app.backlog_cursor = 1;
+        app.delete_focused();
+        assert!(!app.dirty);
+        assert!(app.undo_stack.is_empty());
+        assert_eq!(app.serialize(), content);
+    }
+
+    #[test]
+    fn test_reload_unchanged_keeps_state() {
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        let content = app.serialize();
+        app.agenda_cursor = 2;
+        app.reload(&content);
+        assert_eq!(app.status_msg, "Reloaded from disk (no changes)");
+        assert_eq!(app.agenda_cursor, 2);
+
+        let edited = content.replacen("NVIDIA plan", "NVIDIA roadmap", 1);
+        app.reload(&edited);
+        assert_eq!(app.status_msg, "Reloaded from disk: 1 task added, 1 removed");
+        assert!(app.serialize().contains("NVIDIA roadmap"));
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        use crate::app::View;
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let dir = std::env::temp_dir().join(format!("gws-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut app = App::new(parser::parse(sample_content()), dir.join("todo.md"));
+        app.view = View::Backlog;
+        app.rebuild_tree();
+        // The second row is the Website Redesign project
+        app.backlog_cursor = 1;
+        let command = |app: &mut App, command: &str| {
+            input::handle_key(app, KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE));
+            for c in command.chars() {
+                input::handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            match input::handle_key(app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)) {
+                Action::Export(path) => super::export_subtree(app, &path),
+                Action::Import(path) => super::import_file(app, &path),
+                Action::ExportIcs(path) => super::export_ics(app, &path),
+                _ => {}
+            }
+        };
+
+        command(&mut app, "export website.md");
+        let path = dir.join("website.md");
+        assert_eq!(app.status_msg, format!("Exported \"Website Redesign\" to {}", path.display()));
+        let exported = std::fs::read_to_string(&path).unwrap();
+        assert!(exported.starts_with("## Business\n\n### 🔶 Website Redesign\n- 🔶 Finalize color palette"));
+        assert!(!exported.contains("Essential"));
+
+        command(&mut app, "export website.md");
+        assert_eq!(app.status_msg, format!("{} already exists", path.display()));
+
+        // Importing into a document without the project brings it back whole
+        let mut other = App::new(parser::parse("## Home\n\n### Chores\n- 🔴 Fix sink\n"), dir.join("home.md"));
+        command(&mut other, "import website.md");
+        assert_eq!(other.status_msg, format!("Imported 4 tasks (1 new project) from {}", path.display()));
+        assert_eq!(other.doc.categories[1].projects[0].name, "Website Redesign");
+        assert!(other.dirty);
+        other.undo();
+        assert_eq!(other.doc.categories.len(), 1);
+
+        command(&mut app, "import website.md");
+        assert_eq!(app.status_msg, format!("Nothing new to import from {}", path.display()));
+        command(&mut app, "import missing.md");
+        assert!(app.status_msg.starts_with("Could not read"));
+
+        // Today's plan as calendar events, from 09:00
+        command(&mut app, "ics today.ics");
+        let ics_path = dir.join("today.ics");
+        assert!(app.status_msg.starts_with("Exported "), "{}", app.status_msg);
+        let ics = std::fs::read_to_string(&ics_path).unwrap();
+        assert!(ics.contains("SUMMARY:Finalize color palette with design team\r\n"));
+        assert!(ics.contains("T090000\r\n"));
+        command(&mut app, "ics today.ics");
+        assert_eq!(app.status_msg, format!("{} already exists", ics_path.display()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_over_external_changes() {
+        use crate::app::Dialog;
+        use crate::tui::input::{self, Action};
+        use crate::watcher::Fingerprint;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let dir = std::env::temp_dir().join(format!("gws-conflict-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.md");
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔴 Fix sink\n").unwrap();
+        let mut app = App::new(parser::parse(&std::fs::read_to_string(&path).unwrap()), path.clone());
+        app.on_disk = Fingerprint::of_file(&path);
+        let key = |app: &mut App, c: char| input::handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+        engine::add_task(&mut app.doc, 0, 0, "Mow lawn".to_string());
+        app.dirty = true;
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔴 Fix sink\n- 🔴 Water plants\n").unwrap();
+
+        assert!(!super::save_checked(&mut app, false).unwrap());
+        assert_eq!(app.dialog, Dialog::SaveConflict);
+        assert!(matches!(key(&mut app, 'c'), Action::None));
+        assert!(app.hold_autosave && app.dirty);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("Water plants"));
+
+        assert!(!super::save_checked(&mut app, true).unwrap());
+        assert!(app.quit_pending);
+        assert!(matches!(key(&mut app, 'm'), Action::SaveMerged));
+        super::save_merged(&mut app).unwrap();
+        assert_eq!(app.status_msg, "Merged 1 task from the file on disk");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "## Home\n\n### Chores\n- 🔴 Fix sink\n- 🔴 Mow lawn\n- 🔴 Water plants\n"
+        );
+        assert!(!app.dirty && !app.hold_autosave);
+        assert!(!super::changed_on_disk(&app));
+
+        // Reloading notes the new version, so the next save goes ahead
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔴 Fix sink\n").unwrap();
+        super::reload(&mut app).unwrap();
+        app.dirty = true;
+        assert!(super::save_checked(&mut app, false).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_archive_file() {
+        let dir = std::env::temp_dir().join(format!("gws-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.md");
+        let archive = dir.join("todo.archive.md");
+        let mut config = crate::config::Config::default();
+        config.files.archive_file = Some(PathBuf::from("{name}.archive.md"));
+        std::fs::write(&path, "## Home\n\n### Chores\n- ✅ Fix sink\n- 🔴 Mow lawn\n\n## Done\n- ✅ [Home/Chores] Paint fence (2026-03-01)\n").unwrap();
+
+        // Lines still in the todo file are kept, and move out on the first save
+        let (doc, _, archive_on_disk) = super::load_document(&path, &config).unwrap();
+        let mut app = App::new(doc, path.clone());
+        app.config = config.clone();
+        app.archive_on_disk = archive_on_disk;
+        app.archive_done();
+        super::save(&mut app).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "## Home\n\n### Chores\n- 🔴 Mow lawn\n");
+        let written = std::fs::read_to_string(&archive).unwrap();
+        assert!(written.starts_with("## Done\n- ✅ [Home/Chores] Fix sink ("));
+        assert!(written.ends_with("- ✅ [Home/Chores] Paint fence (2026-03-01)\n"));
+
+        // Reading the todo file brings the archive back
+        let (doc, _) = super::read_document(&path, &config).unwrap();
+        assert_eq!(doc.archive, app.doc.archive);
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.status_msg, "Reloaded from disk (no changes)");
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔴 Mow lawn\n- 🔴 Rake leaves\n").unwrap();
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.doc.archive.len(), 2);
+
+        // A line in both the todo file's archive section and the archive
+        // file, such as a task finished twice in a day, keeps both copies
+        let twice = app.doc.archive[0].clone();
+        std::fs::write(&path, format!("## Home\n\n### Chores\n- 🔴 Mow lawn\n\n## Done\n{}\n", twice)).unwrap();
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.doc.archive.len(), 3);
+        assert_eq!(app.doc.archive[0], twice);
+        assert_eq!(app.doc.archive[1], twice);
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔴 Mow lawn\n- 🔴 Rake leaves\n").unwrap();
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.doc.archive.len(), 2);
+
+        // A reload that only changes a task leaves the archive unsplit
+        let (doc, _, archive_on_disk) = super::load_document(&path, &config).unwrap();
+        app.switch_document(doc, path.clone());
+        app.archive_on_disk = archive_on_disk;
+        assert!(!app.doc.archive.is_split());
+        std::fs::write(&path, "## Home\n\n### Chores\n- 🔵 Mow lawn\n- 🔴 Rake leaves\n").unwrap();
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.doc.categories[0].projects[0].tasks[0].state, crate::model::TaskState::OnDeck);
+        assert!(!app.doc.archive.is_split());
+        assert!(app.doc.archive.text().is_some());
+
+        // Reopening the last archived task empties the archive file
+        app.doc.archive.truncate(1);
+        app.open_unarchive_picker();
+        app.apply_unarchive();
+        super::save(&mut app).unwrap();
+        assert_eq!(std::fs::read_to_string(&archive).unwrap(), "");
+        assert!(std::fs::read_to_string(&path).unwrap().contains("- 🔴 Fix sink"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_io_failures_keep_session() {
+        use crate::app::{Dialog, Retry};
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let path = std::env::temp_dir().join(format!("gws-missing-{}", std::process::id())).join("todo.md");
+        let mut app = App::new(parser::parse(sample_content()), path.clone());
+        engine::add_task(&mut app.doc, 0, 0, "Ship it".to_string());
+        app.dirty = true;
+
+        let result = super::save_checked(&mut app, false);
+        assert!(super::report_io(&mut app, result, Retry::Save).is_none());
+        assert_eq!(app.dialog, Dialog::Error);
+        let error = app.io_error.clone().unwrap();
+        assert_eq!(error.retry, Retry::Save);
+        assert!(error.message.starts_with(&format!("Could not save {}: Failed to write temp file", path.display())));
+        assert!(app.dirty && app.hold_autosave);
+        app.close_error();
+        assert_eq!(app.dialog, Dialog::None);
+        assert_eq!(app.doc.categories[0].projects[0].tasks.last().unwrap().text, "Ship it");
+
+        // A file gone mid-sync fails the reload, and reappearing clears the error
+        app.dirty = false;
+        let result = super::reload(&mut app);
+        assert!(super::report_io(&mut app, result, Retry::Reload).is_none());
+        assert_eq!(app.io_error.as_ref().map(|e| e.retry), Some(Retry::Reload));
+        assert_eq!(app.status_msg, "Not reloaded");
+        let retry = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert!(matches!(input::handle_key(&mut app, retry), Action::Reload));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "## Home\n").unwrap();
+        super::reload(&mut app).unwrap();
+        assert_eq!(app.doc.categories[0].name, "Home");
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_switch_files() {
+        use crate::app::Dialog;
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let dir = super::resolve_file_path(std::env::temp_dir().join(format!("gws-open-{}", std::process::id())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (work, home) = (dir.join("work.md"), dir.join("home.md"));
+        std::fs::write(&work, sample_content()).unwrap();
+        std::fs::write(&home, "## Home\n\n### Chores\n- 🔴 Fix sink\n").unwrap();
+        let mut app = App::new(parser::parse(sample_content()), work.clone());
+        app.recent_path = Some(dir.join("recent.toml"));
+        super::prepare(&mut app, 0);
+        let key = |app: &mut App, code: KeyCode| input::handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+        let command = |app: &mut App, command: &str| {
+            key(app, KeyCode::Char(':'));
+            for c in command.chars() {
+                key(app, KeyCode::Char(c));
+            }
+            key(app, KeyCode::Enter)
+        };
+
+        command(&mut app, "e");
+        assert_eq!(app.status_msg, "No other recent files");
+        assert!(matches!(command(&mut app, "e work.md"), Action::None));
+        assert_eq!(app.status_msg, format!("{} is already open", work.display()));
+
+        // Unsaved changes are dealt with first
+        engine::add_task(&mut app.doc, 0, 0, "Ship it".to_string());
+        app.dirty = true;
+        assert!(matches!(command(&mut app, "e home.md"), Action::None));
+        assert_eq!(app.dialog, Dialog::ConfirmOpen);
+        assert!(matches!(key(&mut app, KeyCode::Esc), Action::None));
+        assert!(app.dirty && app.pending_open.is_none());
+        command(&mut app, "e home.md");
+        assert!(matches!(key(&mut app, KeyCode::Char('d')), Action::Open(ref path) if *path == home));
+        assert!(!app.dirty);
+
+        super::open_file(&mut app, home.clone()).unwrap();
+        assert_eq!(app.file_path, home);
+        assert_eq!(app.doc.categories[0].name, "Home");
+        assert_eq!(app.recent_files, vec![home.clone(), work.clone()]);
+        assert!(!std::fs::read_to_string(&work).unwrap().contains("Ship it"));
+
+        // The picker lists the other recent files
+        command(&mut app, "e");
+        assert_eq!(app.dialog, Dialog::RecentFiles);
+        assert_eq!(app.picker.items, vec![work.display().to_string()]);
+        assert!(matches!(key(&mut app, KeyCode::Enter), Action::Open(ref path) if *path == work));
+
+        // A file that can't be read leaves the open one alone
+        assert!(super::open_file(&mut app, dir.clone()).is_err());
+        assert_eq!(app.file_path, home);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_profiles() {
+        use crate::app::Dialog;
+        use crate::config::Profile;
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let dir = super::resolve_file_path(std::env::temp_dir().join(format!("gws-profiles-{}", std::process::id())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (work, home) = (dir.join("work.md"), dir.join("home.md"));
+        std::fs::write(&work, sample_content()).unwrap();
+        let mut app = App::new(parser::parse(sample_content()), work.clone());
+        let key = |app: &mut App, c: char| input::handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+
+        key(&mut app, 'B');
+        assert_eq!(app.status_msg, "No profiles: add [[profiles]] with a name and file to the config");
+        app.config.general.restore_session = false;
+        app.config.profiles = vec![
+            Profile { name: "work".to_string(), file: work.clone(), theme: Some("nord".to_string()) },
+            Profile { name: "home".to_string(), file: home.clone(), theme: None },
+        ];
+        super::prepare(&mut app, 0);
+        assert_eq!(app.profile.as_deref(), Some("work"));
+        assert_eq!(app.theme().name, "Nord");
+
+        key(&mut app, 'B');
+        assert_eq!(app.dialog, Dialog::ProfilePicker);
+        assert_eq!(app.picker.cursor, 0);
+        assert!(matches!(input::handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)), Action::None));
+        assert_eq!(app.status_msg, "Already on profile work");
+        key(&mut app, 'B');
+        key(&mut app, 'j');
+        let action = input::handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(matches!(action, Action::Open(ref path) if *path == home));
+
+        // A profile without a theme keeps the current one
+        super::open_file(&mut app, home.clone()).unwrap();
+        assert_eq!(app.profile.as_deref(), Some("home"));
+        assert_eq!(app.theme().name, "Nord");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_move_to_file() {
+        use crate::app::View;
+        use crate::watcher::Fingerprint;
+
+        let dir = super::resolve_file_path(std::env::temp_dir().join(format!("gws-move-{}", std::process::id())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (work, home) = (dir.join("work.md"), dir.join("home.md"));
+        std::fs::write(&work, "## Home\n\n### 🔶 Chores\n- 🔶 Fix sink\n  Leaks under the basin\n- 🔴 Mow lawn\n\n### Garden\n- 🔴 Plant bulbs\n").unwrap();
+        std::fs::write(&home, "# Home\n\n## Home\n\n### 🔶 Chores\n- 🔴 Water plants\n").unwrap();
+        let mut app = App::new(parser::parse(&std::fs::read_to_string(&work).unwrap()), work.clone());
+        app.on_disk = Fingerprint::of_file(&work);
+        app.view = View::Backlog;
+        app.rebuild_tree();
+
+        super::move_to_file(&mut app, &home);
+        assert_eq!(app.status_msg, "Select a task or project in the Backlog to move");
+        // Rows: category, project, task
+        app.backlog_cursor = 2;
+        super::move_to_file(&mut app, &home);
+        assert_eq!(app.status_msg, format!("Moved task \"Fix sink\" to {}", home.display()));
+        assert_eq!(
+            std::fs::read_to_string(&home).unwrap(),
+            "# Home\n\n## Home\n\n### 🔶 Chores\n- 🔴 Water plants\n- 🔶 Fix sink\n  Leaks under the basin\n"
+        );
+        assert!(!std::fs::read_to_string(&work).unwrap().contains("Fix sink"));
+        assert!(!app.dirty && !super::changed_on_disk(&app));
+
+        // A project goes whole, into a file created for it
+        let garden = dir.join("garden.md");
+        app.backlog_cursor = 3;
+        super::move_to_file(&mut app, &garden);
+        assert_eq!(std::fs::read_to_string(&garden).unwrap(), "## Home\n\n### Garden\n- 🔴 Plant bulbs\n");
+        assert_eq!(app.doc.categories[0].projects.len(), 1);
+
+        // Nothing is written when the target can't take it
+        app.backlog_cursor = 1;
+        super::move_to_file(&mut app, &dir.join("missing/todo.md"));
+        assert!(app.status_msg.starts_with("Could not move to"));
+        assert!(std::fs::read_to_string(&work).unwrap().contains("Mow lawn"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_global_agenda_across_profiles() {
+        use crate::app::Dialog;
+        use crate::config::Profile;
+
+        let dir = super::resolve_file_path(std::env::temp_dir().join(format!("gws-global-{}", std::process::id())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (work, home) = (dir.join("work.md"), dir.join("home.md"));
+        std::fs::write(&home, "## Home\n\n### 🔶 Chores\n- 🔶 Fix sink\n").unwrap();
+        let mut app = App::new(parser::parse("## Work\n\n### 🔶 Launch\n- 🔶 Review copy\n"), work.clone());
+        let profile = |name: &str, file: &PathBuf| Profile { name: name.to_string(), file: file.clone(), theme: None };
+
+        // The open file takes part even without a profile, unsaved changes included
+        app.config.profiles = vec![profile("home", &home), profile("gone", &dir.join("gone.md"))];
+        engine::add_task(&mut app.doc, 0, 0, "Book venue".to_string());
+        app.doc.categories[0].projects[0].tasks[1].state = crate::model::TaskState::OnDeck;
+        super::show_global_agenda(&mut app);
+        assert_eq!(app.dialog, Dialog::GlobalAgenda);
+        let rows: Vec<(&str, &str)> = app.global_agenda.iter().map(|i| (i.source.as_str(), i.task.text.as_str())).collect();
+        assert_eq!(rows, [("work.md", "Review copy"), ("home", "Fix sink"), ("work.md", "Book venue")]);
+        assert_eq!(app.global_agenda[1].file, home);
+        assert!(app.status_msg.starts_with("Skipped profile gone: Failed to read"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_attached_files() {
+        use crate::app::Dialog;
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let dir = super::resolve_file_path(std::env::temp_dir().join(format!("gws-attach-{}", std::process::id())));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("quote.txt"), "").unwrap();
+        let doc = "## Work\n\n### 🔶 Launch\n- 🔶 Sign contract @file(quote.txt)\n  Scan: @file(gone.pdf)\n- 🔶 Book venue\n";
+        let mut app = App::new(parser::parse(doc), dir.join("todo.md"));
+        engine::auto_promote(&mut app.doc, app.config.agenda.promote);
+        app.refresh_agenda();
+        let key = |app: &mut App, code| input::handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+
+        assert!(matches!(key(&mut app, KeyCode::Char('v')), Action::Attachments));
+        super::show_attachments(&mut app);
+        assert_eq!(app.dialog, Dialog::Attachments);
+        assert_eq!(app.picker.items, ["quote.txt", "gone.pdf  (missing)"]);
+        assert!(app.attachments[0].path.starts_with(&dir));
+
+        key(&mut app, KeyCode::Char('j'));
+        assert!(matches!(key(&mut app, KeyCode::Enter), Action::None));
+        assert!(app.status_msg.starts_with("Not found:"));
+        super::show_attachments(&mut app);
+        assert!(matches!(key(&mut app, KeyCode::Enter), Action::OpenAttachment(path) if path == dir.join("quote.txt")));
+
+        key(&mut app, KeyCode::Char('j'));
+        assert!(matches!(key(&mut app, KeyCode::Char('v')), Action::None));
+        assert!(app.status_msg.starts_with("No attached files"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_viewer() {
+        use crate::app::Dialog;
+        use crate::tui::input::{self, Action};
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        let command = |app: &mut App, text: &str| {
+            input::handle_key(app, KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE));
+            for c in text.chars() {
+                input::handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+            input::handle_key(app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))
+        };
+        assert!(matches!(command(&mut app, "log"), Action::None));
+        assert!(app.status_msg.starts_with("No debug log"));
+
+        let path = std::env::temp_dir().join(format!("gws-viewer-{}.log", std::process::id()));
+        std::fs::write(&path, "DEBUG gws: Starting\nDEBUG gws: Saved\n").unwrap();
+        app.log_file = Some(path.clone());
+        assert!(matches!(command(&mut app, "log"), Action::ShowLog));
+        super::show_log(&mut app);
+        assert_eq!(app.dialog, Dialog::Log);
+        assert_eq!(app.picker.items, ["DEBUG gws: Starting", "DEBUG gws: Saved"]);
+        assert_eq!(app.picker.cursor, 1);
+        input::handle_key(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.dialog, Dialog::None);
+
+        std::fs::write(&path, "").unwrap();
+        super::show_log(&mut app);
+        assert_eq!(app.status_msg, "The debug log is empty");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_redraw_only_on_change() {
+        use crate::date::Date;
+
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        assert!(app.needs_redraw);
+        app.needs_redraw = false;
+
+        // An idle tick leaves the screen alone
+        let today = Date::today();
+        app.tick(today);
+        assert!(!app.needs_redraw);
+
+        // Midnight: the agenda is rebuilt for the new day and drawn again
+        let tomorrow = today.plus_days(1);
+        app.tick(tomorrow);
+        assert!(app.needs_redraw);
+        app.needs_redraw = false;
+        app.tick(tomorrow);
+        assert!(!app.needs_redraw);
+    }
+
+    #[test]
+    fn test_deep_link() {
+        use super::StartView;
+        use crate::app::View;
+
+        let mut app = App::new(parser::parse(sample_content()), PathBuf::from("/tmp/test.md"));
+        app.fold_to_depth(1);
+        super::deep_link(&mut app, Some(StartView::Stats), None, None).unwrap();
+        assert_eq!(app.view, View::Stats);
+
+        super::deep_link(&mut app, None, Some("kitchen renovation"), None).unwrap();
+        assert_eq!(app.view, View::Backlog);
+        assert!(app.current_tree_node().unwrap().display.ends_with("Kitchen Renovation"));
+        assert!(app.tree_nodes.iter().any(|n| n.display.ends_with("Choose cabinet hardware")));
+        assert!(super::deep_link(&mut app, None, Some("Nope"), None).is_err());
+
+        // Ids are hashed from the text; a prefix is enough
+        let task = &app.doc.categories[1].projects[0].tasks[2];
+        assert_eq!(task.id().len(), 6);
+        let id = task.id()[..4].to_string();
+        super::deep_link(&mut app, None, None, Some(&id)).unwrap();
+        assert!(app.current_tree_node().unwrap().display.ends_with("Schedule appointment with accountant"));
+
+        // The agenda when asked for and the task is on it
+        let id = app.doc.categories[0].projects[0].tasks[0].id();
+        super::deep_link(&mut app, Some(StartView::Agenda), None, Some(&id)).unwrap();
+        assert_eq!(app.view, View::Agenda);
+        assert_eq!(app.agenda_items[app.agenda_cursor].task.text, "Finalize color palette with design team");
+        assert!(super::deep_link(&mut app, None, None, Some("zzzzzz")).is_err());
+    }
 }
@@ -1,7 +1,15 @@
 use std::collections::HashSet;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, OnceLock};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+use crate::date::Date;
+
+/// Ordered from least to most advanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskState {
     Todo,
     OnDeck,
@@ -88,12 +96,164 @@ impl Task {
         }
     }
 
+    /// Due date from a `due:YYYY-MM-DD` token in the text.
+    pub fn due(&self) -> Option<Date> {
+        self.text.split_whitespace().find_map(|word| Date::parse(word.strip_prefix("due:")?))
+    }
+
+    /// Set or replace the `due:` token, keeping the rest of the text.
+    pub fn set_due(&mut self, date: Date) {
+        let mut words: Vec<String> = self
+            .text
+            .split_whitespace()
+            .filter(|w| !w.starts_with("due:"))
+            .map(str::to_string)
+            .collect();
+        words.push(format!("due:{}", date));
+        self.text = words.join(" ");
+    }
+
+    /// Priority from a `!1`..`!9` token in the text; `!1` is the highest.
+    pub fn priority(&self) -> Option<u8> {
+        self.text.split_whitespace().find_map(|word| match word.strip_prefix('!')?.as_bytes() {
+            [d @ b'1'..=b'9'] => Some(d - b'0'),
+            _ => None,
+        })
+    }
+
+    /// Time estimate in minutes from a `~45m`, `~2h` or `~1h30m` token.
+    pub fn estimate(&self) -> Option<u32> {
+        self.text.split_whitespace().find_map(|word| {
+            let rest = word.strip_prefix('~')?;
+            let (hours, minutes) = match rest.split_once('h') {
+                Some((h, m)) => (h.parse::<u32>().ok()?, m),
+                None => (0, rest),
+            };
+            let minutes = match minutes {
+                "" if rest.contains('h') => 0,
+                m => m.strip_suffix('m')?.parse::<u32>().ok()?,
+            };
+            Some(hours * 60 + minutes).filter(|&total| total > 0)
+        })
+    }
+
+    /// A short id for linking to the task from outside (`gws list`,
+    /// `--task-id`): six hex digits hashed from the text, so it stays the same
+    /// when the task changes state or moves.
+    pub fn id(&self) -> String {
+        // FNV-1a: stable across builds, unlike the std hasher
+        let hash = self
+            .text
+            .bytes()
+            .fold(0x811c_9dc5_u32, |hash, b| (hash ^ b as u32).wrapping_mul(0x0100_0193));
+        format!("{:06x}", hash & 0xff_ffff)
+    }
+
+    /// Whether the task text or any note mentions `@person` (case-insensitive).
+    pub fn mentions(&self, person: &str) -> bool {
+        std::iter::once(&self.text)
+            .chain(&self.notes)
+            .flat_map(|line| mentions(line))
+            .any(|name| name.eq_ignore_ascii_case(person))
+    }
+
+    /// Paths attached to the task with `@file(path)` in its text or notes.
+    pub fn attachments(&self) -> Vec<&str> {
+        std::iter::once(&self.text).chain(&self.notes).flat_map(|line| attachments(line)).collect()
+    }
+
+    /// Whether the task text or any note is tagged `#tag` (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        std::iter::once(&self.text)
+            .chain(&self.notes)
+            .flat_map(|line| tags(line))
+            .any(|name| name.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// `@Name` mentions in a line of text. A mention starts at an `@` that does
+/// not follow a word character (so email addresses are skipped) and runs over
+/// letters, digits, `_`, `-` and inner dots. A name followed by `(` is a
+/// directive such as `@file(...)`, not a mention.
+pub fn mentions(text: &str) -> impl Iterator<Item = &str> {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    text.match_indices('@').filter_map(move |(at, _)| {
+        if text[..at].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let rest = &text[at + 1..];
+        let end = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+        if rest[end..].starts_with('(') {
+            return None;
+        }
+        let name = rest[..end].trim_end_matches(['.', '-']);
+        (!name.is_empty()).then_some(name)
+    })
+}
+
+/// Paths attached with `@file(path)` in a line of text, as written. The path
+/// runs to the closing `)`, so it may contain spaces.
+pub fn attachments(text: &str) -> impl Iterator<Item = &str> {
+    text.match_indices("@file(").filter_map(move |(at, directive)| {
+        let rest = &text[at + directive.len()..];
+        let path = rest[..rest.find(')')?].trim();
+        (!path.is_empty()).then_some(path)
+    })
+}
+
+/// `#tag` tags in a line of text. Like a mention, a tag starts at a `#` that
+/// does not follow a word character (so `C#` is skipped) and runs over
+/// letters, digits, `_`, `-` and `/`; all-digit tags such as issue numbers
+/// (`#12`) are not tags.
+pub fn tags(text: &str) -> impl Iterator<Item = &str> {
+    tag_spans(text).map(move |(start, end)| &text[start..end])
+}
+
+/// Byte ranges of the tag names (after the `#`) found by `tags`.
+pub fn tag_spans(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let is_tag_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '/');
+    text.match_indices('#').filter_map(move |(at, _)| {
+        if text[..at].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let rest = &text[at + 1..];
+        let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+        let name = rest[..end].trim_end_matches(['-', '/']);
+        let tag = !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit());
+        tag.then_some((at + 1, at + 1 + name.len()))
+    })
+}
+
+/// Whether `name` can be written as a `#tag`.
+pub fn is_tag_name(name: &str) -> bool {
+    let line = format!("#{}", name);
+    tags(&line).next() == Some(name)
+}
+
+/// Color names a project can be tinted with; `#rrggbb` works too.
+pub const ACCENT_NAMES: [&str; 15] = [
+    "red", "green", "yellow", "blue", "magenta", "cyan", "white", "gray", "darkgray", "lightred", "lightgreen",
+    "lightyellow", "lightblue", "lightmagenta", "lightcyan",
+];
+
+/// Whether `name` is a project color: one of [`ACCENT_NAMES`] or `#rrggbb`.
+pub fn is_accent(name: &str) -> bool {
+    ACCENT_NAMES.contains(&name)
+        || name.strip_prefix('#').is_some_and(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Project {
     pub name: String,
     pub active: bool,
+    /// A reusable list (`📋` in the heading): finishing every item offers a
+    /// reset to Todo instead of archiving, and auto-promote leaves it alone.
+    pub checklist: bool,
+    /// Deadline or milestone date, written after the name as `due:YYYY-MM-DD`.
+    pub deadline: Option<Date>,
+    /// Color tinting the project's rows, written after the name as
+    /// `color:magenta` (see [`is_accent`]).
+    pub accent: Option<String>,
     pub notes: Vec<String>,
     pub tasks: Vec<Task>,
 }
@@ -103,19 +263,48 @@ impl Project {
         Self {
             name,
             active,
+            checklist: false,
+            deadline: None,
+            accent: None,
             notes: Vec::new(),
             tasks: Vec::new(),
         }
     }
 
+    /// Days until the deadline as of `today`; negative once it has passed.
+    pub fn days_left(&self, today: Date) -> Option<i64> {
+        self.deadline.map(|deadline| deadline.days_since(today))
+    }
+
     pub fn is_active(&self) -> bool {
         self.active
     }
+
+    /// Heading markers that precede the name: `🔶 ` when active, `📋 ` for
+    /// checklists.
+    pub fn markers(&self) -> &'static str {
+        match (self.active, self.checklist) {
+            (true, true) => "🔶 📋 ",
+            (true, false) => "🔶 ",
+            (false, true) => "📋 ",
+            (false, false) => "",
+        }
+    }
+
+    /// A checklist whose every item is done, ready to be reset.
+    pub fn is_finished_checklist(&self) -> bool {
+        self.checklist && !self.tasks.is_empty() && self.tasks.iter().all(|t| t.state == TaskState::Done)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Category {
     pub name: String,
+    /// A dormant area (`💤` in the heading): kept in the file but left off
+    /// the Agenda and Backlog until shown again from Settings.
+    pub hidden: bool,
+    /// Lines between the heading and the first project, kept verbatim.
+    pub notes: Vec<String>,
     pub projects: Vec<Project>,
 }
 
@@ -123,45 +312,321 @@ impl Category {
     pub fn new(name: String) -> Self {
         Self {
             name,
+            hidden: false,
+            notes: Vec::new(),
             projects: Vec::new(),
         }
     }
+
+    /// `💤 ` before the name of a hidden category's heading.
+    pub fn marker(&self) -> &'static str {
+        if self.hidden { "💤 " } else { "" }
+    }
+}
+
+/// A GTD area of focus: a named group of categories, by category name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Area {
+    pub name: String,
+    pub categories: Vec<String>,
+}
+
+/// Markdown heading depths for categories and projects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingLevels {
+    pub category: usize,
+    pub project: usize,
+}
+
+impl Default for HeadingLevels {
+    fn default() -> Self {
+        Self { category: 2, project: 3 }
+    }
+}
+
+impl HeadingLevels {
+    /// Categories must sit above projects, within markdown's six levels.
+    pub fn is_valid(&self) -> bool {
+        (1..=6).contains(&self.category) && (1..=6).contains(&self.project) && self.category < self.project
+    }
+
+    /// `## ` for the default levels.
+    pub fn category_prefix(&self) -> String {
+        format!("{} ", "#".repeat(self.category))
+    }
+
+    /// `### ` for the default levels.
+    pub fn project_prefix(&self) -> String {
+        format!("{} ", "#".repeat(self.project))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Document {
     pub preamble: Vec<String>,
     pub categories: Vec<Category>,
-    pub archive: Vec<String>,
+    pub archive: Archive,
+    /// Lines of the `## Done Projects` section, where retired projects are
+    /// kept whole, newest first.
+    pub done_projects: Vec<String>,
     pub trailing: Vec<String>,
+    /// Heading levels the file was read with; the serializer writes them back.
+    pub levels: HeadingLevels,
+    /// Name of the archive section (`Done` unless the file or config says
+    /// otherwise), written back as found.
+    pub archive_heading: String,
+}
+
+pub const DEFAULT_ARCHIVE_HEADING: &str = "Done";
+
+/// Lines of the archive section. An archive read from a file is kept as the
+/// text of its section and only split into lines when something looks at
+/// them, so a Done list of thousands of lines costs little to load, to clone
+/// for every undo step, or to save unchanged. Changing the lines (through
+/// `DerefMut`) lets go of the text.
+#[derive(Debug, Clone, Default)]
+pub struct Archive {
+    /// The section as read, while the lines are still what it says.
+    text: Option<Arc<str>>,
+    /// Shared between clones until one of them changes.
+    lines: OnceLock<Arc<Vec<String>>>,
+}
+
+impl Archive {
+    /// An archive read as `text`, `\n`-separated lines.
+    pub fn from_text(text: &str) -> Self {
+        let archive = Self::default();
+        if text.contains('\r') {
+            // Split now, so the section isn't written back with CRLF endings
+            // in an otherwise LF file
+            let _ = archive.lines.set(Arc::new(text.lines().map(str::to_string).collect()));
+        } else if !text.is_empty() {
+            return Self { text: Some(Arc::from(text)), ..archive };
+        }
+        archive
+    }
+
+    /// The text read, as long as the lines haven't been changed since.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Whether there are no lines, without splitting the text.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none() && self.lines.get().is_none_or(|lines| lines.is_empty())
+    }
+
+    /// These lines, then `other`'s. When either is empty the other is kept
+    /// as it is, unsplit.
+    pub fn followed_by(self, other: &Archive) -> Archive {
+        if self.is_empty() {
+            return other.clone();
+        }
+        if other.is_empty() {
+            return self;
+        }
+        self.iter().chain(other).cloned().collect()
+    }
+
+    /// Whether the lines have been split off the text yet.
+    pub fn is_split(&self) -> bool {
+        self.lines.get().is_some()
+    }
+
+    /// Whether these are the lines of `text`, without splitting an archive
+    /// that hasn't been yet.
+    pub fn matches_text(&self, text: &str) -> bool {
+        match &self.text {
+            Some(own) if **own == *text => true,
+            Some(own) if !self.is_split() => own.lines().eq(text.lines()),
+            _ => self.iter().map(String::as_str).eq(text.lines()),
+        }
+    }
+}
+
+impl Deref for Archive {
+    type Target = Vec<String>;
+
+    fn deref(&self) -> &Vec<String> {
+        self.lines.get_or_init(|| {
+            let text = self.text.as_deref().unwrap_or_default();
+            Arc::new(text.lines().map(str::to_string).collect())
+        })
+    }
+}
+
+impl DerefMut for Archive {
+    fn deref_mut(&mut self) -> &mut Vec<String> {
+        let _ = Deref::deref(self);
+        self.text = None;
+        Arc::make_mut(self.lines.get_mut().expect("split above"))
+    }
 }
 
+impl PartialEq for Archive {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.text, &other.text) {
+            (Some(a), Some(b)) if Arc::ptr_eq(a, b) || a == b => true,
+            _ => **self == **other,
+        }
+    }
+}
+
+impl Eq for Archive {}
+
+impl<'a> IntoIterator for &'a Archive {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Vec<String>> for Archive {
+    fn from(lines: Vec<String>) -> Self {
+        let archive = Self::default();
+        let _ = archive.lines.set(Arc::new(lines));
+        archive
+    }
+}
+
+impl FromIterator<String> for Archive {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+/// Heading of the section retired projects go to.
+pub const DONE_PROJECTS_HEADING: &str = "Done Projects";
+
+/// Project the parser collects task lines found outside any project into,
+/// one per category.
+pub const UNSORTED_PROJECT: &str = "Unsorted";
+
 impl Document {
     pub fn new() -> Self {
         Self {
             preamble: Vec::new(),
             categories: Vec::new(),
-            archive: Vec::new(),
+            archive: Archive::default(),
+            done_projects: Vec::new(),
             trailing: Vec::new(),
+            levels: HeadingLevels::default(),
+            archive_heading: DEFAULT_ARCHIVE_HEADING.to_string(),
         }
     }
 
     pub fn template() -> Self {
         Self {
+            levels: HeadingLevels::default(),
+            archive_heading: DEFAULT_ARCHIVE_HEADING.to_string(),
             preamble: Vec::new(),
             categories: vec![Category {
                 name: "Inbox".to_string(),
+                hidden: false,
+                notes: Vec::new(),
                 projects: vec![Project {
                     name: "Tasks".to_string(),
                     active: true,
+                    checklist: false,
+                    deadline: None,
+                    accent: None,
                     notes: Vec::new(),
                     tasks: vec![Task::new(TaskState::Todo, "Your first task".to_string())],
                 }],
             }],
-            archive: Vec::new(),
+            archive: Archive::default(),
+            done_projects: Vec::new(),
             trailing: Vec::new(),
         }
     }
+
+    /// Index of the `# Title` line in the preamble, if any.
+    pub fn title_idx(&self) -> Option<usize> {
+        self.preamble.iter().position(|l| l.starts_with("# "))
+    }
+
+    /// Document title taken from the first `# ` heading in the preamble.
+    pub fn title(&self) -> Option<&str> {
+        self.title_idx().map(|i| self.preamble[i][2..].trim())
+    }
+
+    /// Areas declared in the preamble, one per line, as
+    /// `Area Health: Medical, Fitness` (a leading `- ` is allowed).
+    pub fn preamble_areas(&self) -> Vec<Area> {
+        self.preamble
+            .iter()
+            .filter_map(|line| {
+                let line = line.trim();
+                let rest = line.strip_prefix("- ").unwrap_or(line).strip_prefix("Area ")?;
+                let (name, categories) = rest.split_once(':')?;
+                Some(Area {
+                    name: name.trim().to_string(),
+                    categories: categories
+                        .split(',')
+                        .map(|c| c.trim().to_string())
+                        .filter(|c| !c.is_empty())
+                        .collect(),
+                })
+            })
+            .filter(|a| !a.name.is_empty())
+            .collect()
+    }
+
+    /// The inbox project: the first project named "Inbox", or else the first
+    /// project of a category named "Inbox" (case-insensitive).
+    pub fn inbox(&self) -> Option<(usize, usize)> {
+        let named = |name: &str| name.eq_ignore_ascii_case("inbox");
+        self.categories
+            .iter()
+            .enumerate()
+            .find_map(|(ci, c)| c.projects.iter().position(|p| named(&p.name)).map(|pi| (ci, pi)))
+            .or_else(|| {
+                self.categories
+                    .iter()
+                    .position(|c| named(&c.name) && !c.projects.is_empty())
+                    .map(|ci| (ci, 0))
+            })
+    }
+
+    /// The first project named `name`, ignoring case.
+    pub fn find_project(&self, name: &str) -> Option<(usize, usize)> {
+        self.categories.iter().enumerate().find_map(|(ci, c)| {
+            c.projects.iter().position(|p| p.name.eq_ignore_ascii_case(name)).map(|pi| (ci, pi))
+        })
+    }
+
+    /// The first task whose id (see `Task::id`) starts with `id`.
+    pub fn find_task_id(&self, id: &str) -> Option<(usize, usize, usize)> {
+        let id = id.to_ascii_lowercase();
+        for (ci, category) in self.categories.iter().enumerate() {
+            for (pi, project) in category.projects.iter().enumerate() {
+                if let Some(ti) = project.tasks.iter().position(|t| t.id().starts_with(&id)) {
+                    return Some((ci, pi, ti));
+                }
+            }
+        }
+        None
+    }
+
+    /// Unsorted projects with open tasks, in document order.
+    pub fn unsorted_projects(&self) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        for (ci, category) in self.categories.iter().enumerate() {
+            for (pi, project) in category.projects.iter().enumerate() {
+                if project.name == UNSORTED_PROJECT && project.tasks.iter().any(|t| t.state != TaskState::Done) {
+                    found.push((ci, pi));
+                }
+            }
+        }
+        found
+    }
+
+    /// Whether the preamble holds anything besides blank lines.
+    pub fn has_preamble(&self) -> bool {
+        self.preamble.iter().any(|l| !l.trim().is_empty())
+    }
 }
 
 impl Default for Document {
@@ -181,10 +646,21 @@ pub struct AgendaItem {
     pub task_idx: usize,
 }
 
+/// Size of one agenda section: its task count and the sum of the `~`
+/// estimates among them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionTotal {
+    pub state: TaskState,
+    pub count: usize,
+    pub minutes: u32,
+}
+
 // --- Tree navigation ---
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TreeNodeKind {
+    Preamble,
+    PreambleLine { line_idx: usize },
     Category { cat_idx: usize },
     Project { cat_idx: usize, proj_idx: usize },
     Task { cat_idx: usize, proj_idx: usize, task_idx: usize },
@@ -200,66 +676,20 @@ pub struct TreeNode {
 
 #[derive(Debug, Clone)]
 pub struct CollapseState {
+    pub preamble_collapsed: bool,
     pub collapsed_categories: HashSet<usize>,
     pub collapsed_projects: HashSet<(usize, usize)>,
     pub collapsed_tasks: HashSet<(usize, usize, usize)>,
-    pub theme_name: String,
 }
 
 impl CollapseState {
     pub fn new() -> Self {
         Self {
+            preamble_collapsed: false,
             collapsed_categories: HashSet::new(),
             collapsed_projects: HashSet::new(),
             collapsed_tasks: HashSet::new(),
-            theme_name: String::new(),
-        }
-    }
-
-    pub fn serialize(&self) -> String {
-        let mut lines = Vec::new();
-        if !self.theme_name.is_empty() {
-            lines.push(format!("theme:{}", self.theme_name));
-        }
-        for idx in &self.collapsed_categories {
-            lines.push(format!("cat:{}", idx));
-        }
-        for (ci, pi) in &self.collapsed_projects {
-            lines.push(format!("proj:{},{}", ci, pi));
-        }
-        for (ci, pi, ti) in &self.collapsed_tasks {
-            lines.push(format!("task:{},{},{}", ci, pi, ti));
-        }
-        lines.join("\n")
-    }
-
-    pub fn deserialize(content: &str) -> Self {
-        let mut state = Self::new();
-        for line in content.lines() {
-            let line = line.trim();
-            if let Some(rest) = line.strip_prefix("theme:") {
-                state.theme_name = rest.to_string();
-            } else if let Some(rest) = line.strip_prefix("cat:") {
-                if let Ok(idx) = rest.parse() {
-                    state.collapsed_categories.insert(idx);
-                }
-            } else if let Some(rest) = line.strip_prefix("proj:") {
-                let parts: Vec<&str> = rest.split(',').collect();
-                if parts.len() == 2 {
-                    if let (Ok(ci), Ok(pi)) = (parts[0].parse(), parts[1].parse()) {
-                        state.collapsed_projects.insert((ci, pi));
-                    }
-                }
-            } else if let Some(rest) = line.strip_prefix("task:") {
-                let parts: Vec<&str> = rest.split(',').collect();
-                if parts.len() == 3 {
-                    if let (Ok(ci), Ok(pi), Ok(ti)) = (parts[0].parse(), parts[1].parse(), parts[2].parse()) {
-                        state.collapsed_tasks.insert((ci, pi, ti));
-                    }
-                }
-            }
         }
-        state
     }
 }
 
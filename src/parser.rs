@@ -1,62 +1,263 @@
+use std::borrow::Cow;
+
+use crate::date::Date;
 use crate::model::*;
 
 const TASK_SYMBOLS: [&str; 4] = ["🔴", "🔵", "🔶", "✅"];
 
+/// How strictly the parser sticks to the gws format, and which headings
+/// it looks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Keep content the format doesn't describe so it survives a save:
+    /// fenced code blocks are opaque (no headings or tasks inside them),
+    /// blank lines within notes are kept, and lines between a category
+    /// heading and its first project become category notes. When off,
+    /// those lines are dropped as they were before.
+    pub fidelity: bool,
+    pub levels: HeadingLevels,
+    /// Names of the archive section, matched case-insensitively. The first
+    /// is used for files that don't have one yet.
+    pub archive_headings: Vec<String>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            fidelity: true,
+            levels: HeadingLevels::default(),
+            archive_headings: vec![DEFAULT_ARCHIVE_HEADING.to_string()],
+        }
+    }
+}
+
+/// A parsed document whose text borrows from the input, so parsing itself
+/// allocates only the structure. `to_document` makes the owned copy the app
+/// edits; `matches` lets a reload skip that copy when nothing changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Outline<'a> {
+    pub preamble: Vec<&'a str>,
+    pub categories: Vec<CategoryRef<'a>>,
+    /// The archive section's lines, as one slice of the input.
+    pub archive: &'a str,
+    pub done_projects: Vec<&'a str>,
+    pub trailing: Vec<&'a str>,
+    pub levels: HeadingLevels,
+    /// Archive heading as written in the file, or the preferred alias.
+    pub archive_heading: Cow<'a, str>,
+    /// Task lines found outside any project, collected into Unsorted projects.
+    pub stray_tasks: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CategoryRef<'a> {
+    pub name: &'a str,
+    pub hidden: bool,
+    pub notes: Vec<&'a str>,
+    pub projects: Vec<ProjectRef<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProjectRef<'a> {
+    pub name: &'a str,
+    pub active: bool,
+    pub checklist: bool,
+    pub deadline: Option<Date>,
+    pub accent: Option<&'a str>,
+    pub notes: Vec<&'a str>,
+    pub tasks: Vec<TaskRef<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TaskRef<'a> {
+    pub state: TaskState,
+    pub text: &'a str,
+    pub notes: Vec<&'a str>,
+}
+
+fn owned_lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+fn lines_match(borrowed: &[&str], owned: &[String]) -> bool {
+    borrowed.len() == owned.len() && borrowed.iter().zip(owned).all(|(a, b)| *a == b)
+}
+
+impl Outline<'_> {
+    /// Copy into an owned Document.
+    pub fn to_document(&self) -> Document {
+        Document {
+            preamble: owned_lines(&self.preamble),
+            categories: self
+                .categories
+                .iter()
+                .map(|c| Category {
+                    name: c.name.to_string(),
+                    hidden: c.hidden,
+                    notes: owned_lines(&c.notes),
+                    projects: c
+                        .projects
+                        .iter()
+                        .map(|p| Project {
+                            name: p.name.to_string(),
+                            active: p.active,
+                            checklist: p.checklist,
+                            deadline: p.deadline,
+                            accent: p.accent.map(str::to_string),
+                            notes: owned_lines(&p.notes),
+                            tasks: p
+                                .tasks
+                                .iter()
+                                .map(|t| Task {
+                                    state: t.state,
+                                    text: t.text.to_string(),
+                                    notes: owned_lines(&t.notes),
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            archive: Archive::from_text(self.archive),
+            done_projects: owned_lines(&self.done_projects),
+            trailing: owned_lines(&self.trailing),
+            levels: self.levels,
+            archive_heading: self.archive_heading.to_string(),
+        }
+    }
+
+    /// Whether `doc` holds exactly this content.
+    pub fn matches(&self, doc: &Document) -> bool {
+        doc.archive.matches_text(self.archive) && self.matches_besides_archive(doc)
+    }
+
+    /// Whether `doc` holds this content for a todo file whose archive is
+    /// kept in a file of its own: this one has none, and `doc`'s is left aside.
+    pub fn matches_apart_from_archive(&self, doc: &Document) -> bool {
+        self.archive.is_empty() && self.matches_besides_archive(doc)
+    }
+
+    fn matches_besides_archive(&self, doc: &Document) -> bool {
+        let task_match = |t: &TaskRef, o: &Task| t.state == o.state && t.text == o.text && lines_match(&t.notes, &o.notes);
+        let project_match = |p: &ProjectRef, o: &Project| {
+            p.name == o.name
+                && p.active == o.active
+                && p.checklist == o.checklist
+                && p.deadline == o.deadline
+                && p.accent == o.accent.as_deref()
+                && lines_match(&p.notes, &o.notes)
+                && p.tasks.len() == o.tasks.len()
+                && p.tasks.iter().zip(&o.tasks).all(|(t, o)| task_match(t, o))
+        };
+        self.levels == doc.levels
+            && self.archive_heading == doc.archive_heading
+            && lines_match(&self.preamble, &doc.preamble)
+            && lines_match(&self.done_projects, &doc.done_projects)
+            && lines_match(&self.trailing, &doc.trailing)
+            && self.categories.len() == doc.categories.len()
+            && self.categories.iter().zip(&doc.categories).all(|(c, o)| {
+                c.name == o.name
+                    && c.hidden == o.hidden
+                    && lines_match(&c.notes, &o.notes)
+                    && c.projects.len() == o.projects.len()
+                    && c.projects.iter().zip(&o.projects).all(|(p, o)| project_match(p, o))
+            })
+    }
+}
+
 /// Parse a markdown line that starts with `- ` and contains a task symbol.
-fn parse_task_line(line: &str) -> Option<Task> {
+fn parse_task_line(line: &str) -> Option<TaskRef<'_>> {
     let trimmed = line.trim();
     let content = trimmed.strip_prefix("- ")?;
 
     for sym in &TASK_SYMBOLS {
         if let Some(rest) = content.strip_prefix(sym) {
             let state = TaskState::from_symbol(sym)?;
-            let text = rest.trim_start().to_string();
-            return Some(Task::new(state, text));
+            return Some(TaskRef {
+                state,
+                text: rest.trim_start(),
+                notes: Vec::new(),
+            });
         }
     }
     None
 }
 
-/// Parse `## Name` category heading (not `## Done`).
-fn parse_category_heading(line: &str) -> Option<String> {
+/// Drop the blank lines ending a section; the serializer puts one back
+/// before the next heading.
+fn trim_blank_lines(lines: &mut Vec<&str>) {
+    while lines.pop_if(|l| l.trim().is_empty()).is_some() {}
+}
+
+/// Parse a `## Name` heading at category level, archive headings included.
+/// `prefix` is the category heading marker, `## ` by default.
+fn parse_category_heading<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
     let trimmed = line.trim();
-    let content = trimmed.strip_prefix("## ")?;
-    let name = content.trim().to_string();
-    if name.eq_ignore_ascii_case("Done") {
-        return None;
+    let content = trimmed.strip_prefix(prefix)?;
+    Some(content.trim())
+}
+
+/// Whether a category-level heading names the archive (`## Done`).
+fn is_archive_heading(name: &str, aliases: &[String]) -> bool {
+    let name = name.to_lowercase();
+    aliases.iter().any(|alias| alias.to_lowercase() == name)
+}
+
+/// A line of the archive section, `- ✅ [Business/Website Redesign] Ship it (2024-05-03)`.
+/// Lines archived before provenance was recorded have no category or
+/// project, and older ones no date either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveEntry<'a> {
+    pub category: Option<&'a str>,
+    pub project: Option<&'a str>,
+    pub text: &'a str,
+    pub date: Option<Date>,
+}
+
+/// Read an archive line; None for lines that aren't done tasks.
+pub fn archive_entry(line: &str) -> Option<ArchiveEntry<'_>> {
+    let mut text = line.trim().strip_prefix("- ✅ ")?;
+    let mut date = None;
+    if let Some((rest, stamp)) = text.strip_suffix(')').and_then(|t| t.rsplit_once(" ("))
+        && let Some(stamp) = Date::parse(stamp)
+    {
+        text = rest;
+        date = Some(stamp);
     }
-    Some(name)
+    let (mut category, mut project) = (None, None);
+    if let Some((origin, rest)) = text.strip_prefix('[').and_then(|t| t.split_once("] "))
+        && let Some((cat, proj)) = origin.split_once('/')
+    {
+        category = Some(cat);
+        project = Some(proj);
+        text = rest;
+    }
+    Some(ArchiveEntry { category, project, text, date })
 }
 
 /// Parse `### 🔶 Name` (active) or `### Name` (inactive) project heading.
-fn parse_project_heading(line: &str) -> Option<(bool, String)> {
+/// `prefix` is the project heading marker, `### ` by default.
+fn parse_project_heading<'a>(line: &'a str, prefix: &str) -> Option<(bool, &'a str)> {
     let trimmed = line.trim();
-    let content = trimmed.strip_prefix("### ")?;
+    let content = trimmed.strip_prefix(prefix)?;
 
     // Check for 🔶 prefix → active project
     if let Some(rest) = content.strip_prefix("🔶") {
-        let name = rest.trim_start().to_string();
-        return Some((true, name));
+        return Some((true, rest.trim_start()));
     }
 
     // Backward compat: old format used any symbol for project state
     // Check for other task symbols at start of project name
     for sym in &TASK_SYMBOLS {
         if let Some(rest) = content.strip_prefix(sym) {
-            let name = rest.trim_start().to_string();
             // 🔶 is active, everything else is inactive
             let active = *sym == "🔶";
-            return Some((active, name));
+            return Some((active, rest.trim_start()));
         }
     }
 
     // No symbol → inactive project
-    Some((false, content.trim().to_string()))
-}
-
-/// Check if a line is the `## Done` archive header.
-fn is_done_header(line: &str) -> bool {
-    line.trim() == "## Done"
+    Some((false, content.trim()))
 }
 
 /// Check if a line is a note (indented by 2+ spaces, not a task line).
@@ -69,131 +270,321 @@ fn is_note_line(line: &str) -> bool {
     has_indent && parse_task_line(line).is_none()
 }
 
+/// The marker of a fenced code block delimiter line (```` ``` ```` or `~~~`).
+fn fence_marker(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m))
+}
+
+/// Where a line that isn't a heading or task belongs: the last task's notes,
+/// else the project's, else the category's. None outside any category.
+fn note_target<'s, 'a>(
+    category: &'s mut Option<CategoryRef<'a>>,
+    project: &'s mut Option<ProjectRef<'a>>,
+) -> Option<&'s mut Vec<&'a str>> {
+    if let Some(proj) = project {
+        return Some(match proj.tasks.last_mut() {
+            Some(task) => &mut task.notes,
+            None => &mut proj.notes,
+        });
+    }
+    category.as_mut().map(|c| &mut c.notes)
+}
+
+/// Append a note line, first flushing blank lines held since the previous
+/// note. Blanks only count between notes, never before the first one.
+fn push_note<'a>(notes: &mut Vec<&'a str>, blanks: &mut Vec<&'a str>, line: &'a str) {
+    if notes.is_empty() {
+        blanks.clear();
+    } else {
+        notes.append(blanks);
+    }
+    notes.push(line);
+}
+
 /// Parse a markdown string into a Document.
 pub fn parse(input: &str) -> Document {
-    let mut doc = Document::new();
-    let lines: Vec<&str> = input.lines().collect();
-    let mut i = 0;
-    let mut in_archive = false;
-    let mut current_category: Option<Category> = None;
-    let mut current_project: Option<Project> = None;
-    let mut _has_categories = false;
-
-    while i < lines.len() {
-        let line = lines[i];
-
-        // Check for ## Done
-        if is_done_header(line) {
+    parse_with(input, ParseOptions::default())
+}
+
+pub fn parse_with(input: &str, options: ParseOptions) -> Document {
+    parse_outline_with(input, options).to_document()
+}
+
+/// Parse a markdown string into an Outline borrowing from `input`.
+pub fn parse_outline(input: &str) -> Outline<'_> {
+    parse_outline_with(input, ParseOptions::default())
+}
+
+pub fn parse_outline_with(input: &str, options: ParseOptions) -> Outline<'_> {
+    let mut doc = Outline {
+        levels: options.levels,
+        archive_heading: Cow::Owned(
+            options
+                .archive_headings
+                .first()
+                .map_or(DEFAULT_ARCHIVE_HEADING, |h| h.as_str())
+                .to_string(),
+        ),
+        ..Outline::default()
+    };
+    let category_prefix = options.levels.category_prefix();
+    let project_prefix = options.levels.project_prefix();
+    let mut in_done_projects = false;
+    let mut current_category: Option<CategoryRef> = None;
+    let mut current_project: Option<ProjectRef> = None;
+    // The current project is an Unsorted one started for stray tasks
+    let mut collecting_strays = false;
+    // Fidelity mode: the open code fence, and blank lines that may turn out
+    // to sit between two notes
+    let mut fence: Option<&str> = None;
+    let mut blanks: Vec<&str> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        // Code blocks pass through untouched, wherever they are
+        if options.fidelity && !in_done_projects && (fence.is_some() || fence_marker(line).is_some()) {
+            match fence {
+                Some(open) if fence_marker(line) == Some(open) => fence = None,
+                Some(_) => {}
+                None => fence = fence_marker(line),
+            }
+            match note_target(&mut current_category, &mut current_project) {
+                Some(notes) => push_note(notes, &mut blanks, line),
+                None => doc.preamble.push(line),
+            }
+            continue;
+        }
+
+        if options.fidelity && line.trim().is_empty() && (current_category.is_some() || current_project.is_some()) {
+            blanks.push(line);
+            continue;
+        }
+        let heading = parse_category_heading(line, &category_prefix);
+
+        // Check for ## Done (or a configured alias)
+        if let Some(name) = heading.filter(|name| is_archive_heading(name, &options.archive_headings)) {
+            doc.archive_heading = Cow::Borrowed(name);
             // Flush current project into current category
-            if let Some(proj) = current_project.take() {
-                if let Some(ref mut cat) = current_category {
-                    cat.projects.push(proj);
-                }
+            if let Some(proj) = current_project.take()
+                && let Some(ref mut cat) = current_category
+            {
+                cat.projects.push(proj);
             }
             // Flush current category
             if let Some(cat) = current_category.take() {
                 doc.categories.push(cat);
             }
-            in_archive = true;
-            i += 1;
-            continue;
+            trim_blank_lines(&mut doc.done_projects);
+            // The rest of the file is the archive, taken whole rather than
+            // line by line: long Done lists are rarely looked at
+            let (archive, trailing) = split_trailing_blanks(&input[offset_after(input, line)..]);
+            doc.archive = archive;
+            doc.trailing = trailing;
+            return doc;
         }
 
-        if in_archive {
-            doc.archive.push(line.to_string());
-            i += 1;
+        // ## Done Projects holds retired projects, kept as written until
+        // the next section
+        if heading.is_some_and(|name| name.eq_ignore_ascii_case(DONE_PROJECTS_HEADING)) {
+            if let Some(proj) = current_project.take()
+                && let Some(ref mut cat) = current_category
+            {
+                cat.projects.push(proj);
+            }
+            if let Some(cat) = current_category.take() {
+                doc.categories.push(cat);
+            }
+            in_done_projects = true;
             continue;
         }
+        if in_done_projects {
+            if heading.is_none() {
+                doc.done_projects.push(line);
+                continue;
+            }
+            trim_blank_lines(&mut doc.done_projects);
+            in_done_projects = false;
+        }
 
         // Check for ## Category heading
-        if let Some(name) = parse_category_heading(line) {
+        if let Some(name) = heading {
             // Flush current project into current category
-            if let Some(proj) = current_project.take() {
-                if let Some(ref mut cat) = current_category {
-                    cat.projects.push(proj);
-                }
+            if let Some(proj) = current_project.take()
+                && let Some(ref mut cat) = current_category
+            {
+                cat.projects.push(proj);
             }
             // Flush current category
             if let Some(cat) = current_category.take() {
                 doc.categories.push(cat);
             }
-            _has_categories = true;
-            current_category = Some(Category::new(name));
-            i += 1;
+            // A leading 💤 marks a hidden category
+            let (hidden, name) = match name.strip_prefix("💤") {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, name),
+            };
+            current_category = Some(CategoryRef {
+                name,
+                hidden,
+                notes: Vec::new(),
+                projects: Vec::new(),
+            });
             continue;
         }
 
         // Check for ### Project heading
-        if let Some((active, name)) = parse_project_heading(line) {
+        if let Some((active, name)) = parse_project_heading(line, &project_prefix) {
             // Flush current project into current category
-            if let Some(proj) = current_project.take() {
-                if let Some(ref mut cat) = current_category {
-                    cat.projects.push(proj);
-                }
+            if let Some(proj) = current_project.take()
+                && let Some(ref mut cat) = current_category
+            {
+                cat.projects.push(proj);
             }
 
+            collecting_strays = false;
+
             // Backward compat: if no ## category seen yet, create "Uncategorized"
             if current_category.is_none() {
-                current_category = Some(Category::new("Uncategorized".to_string()));
+                current_category = Some(CategoryRef {
+                    name: "Uncategorized",
+                    hidden: false,
+                    notes: Vec::new(),
+                    projects: Vec::new(),
+                });
             }
 
-            current_project = Some(Project::new(name, active));
-            i += 1;
+            // Checklist marker follows the active marker
+            let (checklist, name) = match name.strip_prefix("📋") {
+                Some(rest) => (true, rest.trim_start()),
+                None => (false, name),
+            };
+            // Trailing due:YYYY-MM-DD and color:NAME, in either order, are
+            // the project's deadline and color
+            let (mut name, mut deadline, mut accent) = (name, None, None);
+            while let Some((rest, last)) = name.rsplit_once(' ') {
+                if let Some(date) = last.strip_prefix("due:").and_then(Date::parse).filter(|_| deadline.is_none()) {
+                    deadline = Some(date);
+                } else if let Some(color) = last.strip_prefix("color:").filter(|c| accent.is_none() && is_accent(c)) {
+                    accent = Some(color);
+                } else {
+                    break;
+                }
+                name = rest.trim_end();
+            }
+            current_project = Some(ProjectRef {
+                name,
+                active,
+                checklist,
+                deadline,
+                accent,
+                notes: Vec::new(),
+                tasks: Vec::new(),
+            });
             continue;
         }
 
         // Inside a project
         if let Some(ref mut proj) = current_project {
             if let Some(task) = parse_task_line(line) {
+                if collecting_strays {
+                    doc.stray_tasks += 1;
+                    tracing::debug!(line = index + 1, "Task outside any project, kept in {}: {}", UNSORTED_PROJECT, task.text);
+                }
                 proj.tasks.push(task);
-            } else if !proj.tasks.is_empty() && is_note_line(line) {
-                // Note on the last task
-                let last = proj.tasks.last_mut().unwrap();
-                last.notes.push(line.to_string());
-            } else if proj.tasks.is_empty() && !line.trim().is_empty() {
+            } else if let Some(last) = proj.tasks.last_mut() {
+                // Indented lines are notes on the last task; so are raw
+                // non-indented lines from the old format. Blank lines are skipped.
+                if is_note_line(line) || !line.trim().is_empty() {
+                    push_note(&mut last.notes, &mut blanks, line);
+                }
+            } else if !line.trim().is_empty() {
                 // Non-task line before first task → project note
-                proj.notes.push(line.to_string());
-            } else if !proj.tasks.is_empty() && !is_note_line(line) && !line.trim().is_empty() {
-                // Non-indented, non-task line after tasks started → also project note
-                // (e.g. raw lines in old format)
-                let last = proj.tasks.last_mut().unwrap();
-                last.notes.push(line.to_string());
+                push_note(&mut proj.notes, &mut blanks, line);
+            }
+        } else if let Some(task) = parse_task_line(line) {
+            // A task outside any project, under a category or in the
+            // preamble: start an Unsorted project so it and what follows
+            // aren't lost
+            doc.stray_tasks += 1;
+            tracing::debug!(line = index + 1, "Task outside any project, kept in {}: {}", UNSORTED_PROJECT, task.text);
+            collecting_strays = true;
+            current_category.get_or_insert_with(|| CategoryRef {
+                name: "Uncategorized",
+                hidden: false,
+                notes: Vec::new(),
+                projects: Vec::new(),
+            });
+            blanks.clear();
+            current_project = Some(ProjectRef {
+                name: UNSORTED_PROJECT,
+                active: false,
+                checklist: false,
+                deadline: None,
+                accent: None,
+                notes: Vec::new(),
+                tasks: vec![task],
+            });
+        } else if let Some(ref mut cat) = current_category {
+            // Line inside a category but not in a project: kept in fidelity
+            // mode (prose, tables, deeper headings), skipped otherwise
+            if options.fidelity && !line.trim().is_empty() {
+                push_note(&mut cat.notes, &mut blanks, line);
+            } else if !line.trim().is_empty() {
+                tracing::debug!(line = index + 1, "Skipped a line outside any project of {}: {}", cat.name, line);
             }
-            // else: blank line inside project, skip
-        } else if current_category.is_some() {
-            // Line inside a category but not in a project — skip blank lines
-            // Non-blank lines are unusual here but we'll ignore them
         } else {
-            doc.preamble.push(line.to_string());
+            doc.preamble.push(line);
         }
-
-        i += 1;
     }
 
     // Flush remaining
-    if let Some(proj) = current_project.take() {
-        if let Some(ref mut cat) = current_category {
-            cat.projects.push(proj);
-        }
+    if let Some(proj) = current_project.take()
+        && let Some(ref mut cat) = current_category
+    {
+        cat.projects.push(proj);
     }
     if let Some(cat) = current_category.take() {
         doc.categories.push(cat);
     }
 
-    // Trim trailing empty lines from archive into trailing
-    while doc
-        .archive
-        .last()
-        .is_some_and(|l| l.trim().is_empty())
-    {
-        let line = doc.archive.pop().unwrap();
-        doc.trailing.push(line);
+    // Trim trailing empty lines from retired projects into trailing
+    if in_done_projects {
+        while let Some(line) = doc.done_projects.pop_if(|l| l.trim().is_empty()) {
+            doc.trailing.push(line);
+        }
+        doc.trailing.reverse();
     }
-    doc.trailing.reverse();
 
     doc
 }
 
+/// Byte offset of the end of `line`, a slice of `input`.
+fn end_of(input: &str, line: &str) -> usize {
+    line.as_ptr() as usize - input.as_ptr() as usize + line.len()
+}
+
+/// Byte offset just past `line`, one of `input`'s lines, and its line ending.
+fn offset_after(input: &str, line: &str) -> usize {
+    let end = end_of(input, line);
+    let rest = &input[end..];
+    end + if rest.starts_with("\r\n") {
+        2
+    } else {
+        usize::from(rest.starts_with('\n'))
+    }
+}
+
+/// The archive section up to its last non-blank line, and the blank lines
+/// after it that end the file.
+fn split_trailing_blanks(section: &str) -> (&str, Vec<&str>) {
+    let Some(last) = section.lines().rfind(|line| !line.trim().is_empty()) else {
+        return ("", section.lines().collect());
+    };
+    let end = end_of(section, last);
+    // The first of the lines left is the end of the last archive line
+    (&section[..end], section[end..].lines().skip(1).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,15 +681,15 @@ mod tests {
 
     #[test]
     fn test_parse_project_heading() {
-        let (active, name) = parse_project_heading("### 🔶 My Project").unwrap();
+        let (active, name) = parse_project_heading("### 🔶 My Project", "### ").unwrap();
         assert!(active);
         assert_eq!(name, "My Project");
 
-        let (active, name) = parse_project_heading("### Inactive One").unwrap();
+        let (active, name) = parse_project_heading("### Inactive One", "### ").unwrap();
         assert!(!active);
         assert_eq!(name, "Inactive One");
 
-        assert!(parse_project_heading("## Not a project").is_none());
+        assert!(parse_project_heading("## Not a project", "### ").is_none());
     }
 
     #[test]
@@ -344,4 +735,218 @@ Some project note
         assert_eq!(proj.notes.len(), 1);
         assert_eq!(proj.notes[0], "Some project note");
     }
+
+    #[test]
+    fn test_outline_matches_document() {
+        let input = "\
+# Title
+
+## Work
+
+### 🔶 Project
+Project note
+- 🔴 Task
+  A note
+
+## Done
+- ✅ Old
+";
+        let outline = parse_outline(input);
+        let doc = outline.to_document();
+        assert!(outline.matches(&doc));
+        assert_eq!(outline.categories[0].projects[0].tasks[0].text, "Task");
+
+        let mut changed = doc.clone();
+        changed.categories[0].projects[0].tasks[0].notes[0].push('!');
+        assert!(!outline.matches(&changed));
+        changed = doc.clone();
+        changed.archive.clear();
+        assert!(!outline.matches(&changed));
+    }
+
+    #[test]
+    fn test_archive_kept_as_text() {
+        let doc = parse("## Work\n### Launch\n- 🔴 Ship it\n\n## Done\n- ✅ Old\n\n## Not a category\n- ✅ Older\n\n  \n");
+        assert_eq!(doc.archive.text(), Some("- ✅ Old\n\n## Not a category\n- ✅ Older"));
+        assert_eq!(doc.trailing, ["", "  "]);
+        assert!(!doc.archive.is_empty() && !doc.archive.is_split());
+        let joined = Archive::default().followed_by(&doc.archive);
+        assert!(joined.text().is_some() && !joined.is_split());
+        assert_eq!(*doc.archive, ["- ✅ Old", "", "## Not a category", "- ✅ Older"]);
+
+        // Cloning shares the text; changing a copy splits only that one
+        let mut doc = parse("## Done\r\n- ✅ Old\r\n- ✅ Older\r\n");
+        assert_eq!(doc.archive.text(), None);
+        assert_eq!(*doc.archive, ["- ✅ Old", "- ✅ Older"]);
+        doc = parse("## Done\n- ✅ Old\n- ✅ Older\n");
+        let mut changed = doc.clone();
+        changed.archive.remove(0);
+        assert_eq!(changed.archive.text(), None);
+        assert!(!doc.archive.is_split() && doc.archive.text().is_some());
+        assert_ne!(doc, changed);
+        changed.archive.insert(0, "- ✅ Old".to_string());
+        assert_eq!(doc, changed);
+    }
+
+    const RICH: &str = "\
+## Work
+Context for the whole category.
+
+| Owner | Area |
+|-------|------|
+| Ana   | Web  |
+
+### 🔶 Launch
+- 🔴 Write copy
+  ```sh
+  ## not a category
+
+  - 🔴 not a task
+  ```
+
+  Second paragraph of notes.
+#### Deeper heading
+- 🔵 Pick a domain
+";
+
+    #[test]
+    fn test_parse_fidelity_keeps_unknown_blocks() {
+        let doc = parse(RICH);
+        assert_eq!(doc.categories.len(), 1);
+        let work = &doc.categories[0];
+        assert_eq!(work.notes.len(), 5);
+        assert_eq!(work.notes[1], "");
+        assert_eq!(work.notes[4], "| Ana   | Web  |");
+
+        let tasks = &work.projects[0].tasks;
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(
+            tasks[0].notes,
+            [
+                "  ```sh",
+                "  ## not a category",
+                "",
+                "  - 🔴 not a task",
+                "  ```",
+                "",
+                "  Second paragraph of notes.",
+                "#### Deeper heading",
+            ]
+        );
+        assert_eq!(crate::serializer::serialize(&doc), RICH);
+    }
+
+    #[test]
+    fn test_parse_without_fidelity() {
+        let doc = parse_with(
+            RICH,
+            ParseOptions {
+                fidelity: false,
+                ..ParseOptions::default()
+            },
+        );
+        assert_eq!(doc.categories.len(), 2);
+        assert!(doc.categories[0].notes.is_empty());
+        assert!(doc.categories[0].projects[0].tasks[0].notes.iter().all(|n| !n.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_custom_heading_levels() {
+        let input = "\
+# Work
+
+## 🔶 Launch
+### Context
+- 🔴 Write copy
+
+# Done
+- ✅ Old
+";
+        let options = ParseOptions {
+            levels: HeadingLevels { category: 1, project: 2 },
+            ..ParseOptions::default()
+        };
+        let doc = parse_with(input, options);
+        assert_eq!(doc.categories.len(), 1);
+        let launch = &doc.categories[0].projects[0];
+        assert!(launch.active);
+        assert_eq!(launch.notes, ["### Context"]);
+        assert_eq!(*doc.archive, ["- ✅ Old"]);
+        assert_eq!(crate::serializer::serialize(&doc), input);
+    }
+
+    #[test]
+    fn test_parse_archive_heading_aliases() {
+        let options = ParseOptions {
+            archive_headings: vec!["Erledigt".to_string(), "Archive".to_string()],
+            ..ParseOptions::default()
+        };
+
+        // An existing alias is recognized and written back as found
+        let input = "## Work\n\n### Launch\n- 🔴 Write copy\n\n## archive\n- ✅ Old\n";
+        let doc = parse_with(input, options.clone());
+        assert_eq!(doc.categories.len(), 1);
+        assert_eq!(*doc.archive, ["- ✅ Old"]);
+        assert_eq!(doc.archive_heading, "archive");
+        assert_eq!(crate::serializer::serialize(&doc), input);
+
+        // Without one, the first alias names the new section; Done is now
+        // an ordinary category
+        let mut doc = parse_with("## Done\n\n### Launch\n- ✅ Shipped\n", options);
+        assert_eq!(doc.categories[0].name, "Done");
+        doc.archive.push("- ✅ Shipped".to_string());
+        assert!(crate::serializer::serialize(&doc).ends_with("## Erledigt\n- ✅ Shipped\n"));
+    }
+
+    #[test]
+    fn test_archive_entry() {
+        let entry = archive_entry("- ✅ [Business/Website Redesign] Ship it (v2) (2024-05-03)").unwrap();
+        assert_eq!(entry.category, Some("Business"));
+        assert_eq!(entry.project, Some("Website Redesign"));
+        assert_eq!(entry.text, "Ship it (v2)");
+        assert_eq!(entry.date, Date::from_ymd(2024, 5, 3));
+
+        let old = archive_entry("- ✅ Ship it (2024-05-03)").unwrap();
+        assert_eq!((old.category, old.text), (None, "Ship it"));
+        let undated = archive_entry("- ✅ [RFC](https://example.com) review").unwrap();
+        assert_eq!((undated.project, undated.text, undated.date), (None, "[RFC](https://example.com) review", None));
+        assert!(archive_entry("Notes about last year").is_none());
+    }
+
+    #[test]
+    fn test_stray_tasks() {
+        let input = "\
+# Todo
+- 🔴 Stray in preamble
+
+## Work
+Team notes
+- 🔵 Stray under category
+  with a note
+- 🔴 Another
+
+### 🔶 Launch
+- 🔴 Write copy
+";
+        let outline = parse_outline(input);
+        assert_eq!(outline.stray_tasks, 3);
+        let doc = outline.to_document();
+        assert_eq!(doc.preamble, ["# Todo"]);
+        let names: Vec<&str> = doc.categories.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Uncategorized", "Work"]);
+        assert_eq!(doc.categories[0].projects[0].tasks[0].text, "Stray in preamble");
+        let work = &doc.categories[1];
+        assert_eq!(work.notes, ["Team notes"]);
+        let unsorted = &work.projects[0];
+        assert_eq!((unsorted.name.as_str(), unsorted.active), (UNSORTED_PROJECT, false));
+        assert_eq!(unsorted.tasks.len(), 2);
+        assert_eq!(unsorted.tasks[0].notes, ["  with a note"]);
+        assert_eq!(work.projects[1].tasks.len(), 1);
+        assert_eq!(doc.unsorted_projects(), [(0, 0), (1, 0)]);
+
+        // Saved, they are an ordinary project
+        let saved = crate::serializer::serialize(&doc);
+        assert!(saved.contains("## Work\nTeam notes\n\n### Unsorted\n- 🔵 Stray under category\n"));
+        assert_eq!(parse_outline(&saved).stray_tasks, 0);
+    }
 }
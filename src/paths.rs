@@ -0,0 +1,275 @@
+//! Where gws keeps its files: the XDG base directories, with the legacy
+//! `~/.gws` still used for any file that is already there, and the order in
+//! which the todo file is looked for.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The config file in the config directory.
+const CONFIG_FILE: &str = "config.toml";
+/// The todo file in the data directory, and in the working directory.
+const TODO_FILE: &str = "todo.md";
+/// Todo files kept at the root of a git repository, in the order looked for.
+const REPO_FILES: &[&str] = &["TODO.md", ".gws/todo.md"];
+
+/// The base directories gws keeps its files under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    pub home: PathBuf,
+    /// `$XDG_CONFIG_HOME`, when set to an absolute path.
+    pub xdg_config: Option<PathBuf>,
+    /// `$XDG_DATA_HOME`, when set to an absolute path.
+    pub xdg_data: Option<PathBuf>,
+}
+
+impl Dirs {
+    pub fn from_env() -> Self {
+        Self {
+            home: dirs::home_dir().expect("Could not determine home directory"),
+            xdg_config: env_dir("XDG_CONFIG_HOME"),
+            xdg_data: env_dir("XDG_DATA_HOME"),
+        }
+    }
+
+    /// `$XDG_CONFIG_HOME/gws`, or `~/.config/gws`.
+    pub fn config_dir(&self) -> PathBuf {
+        self.xdg_config.clone().unwrap_or_else(|| self.home.join(".config")).join("gws")
+    }
+
+    /// `$XDG_DATA_HOME/gws`, or `~/.local/share/gws`.
+    pub fn data_dir(&self) -> PathBuf {
+        self.xdg_data.clone().unwrap_or_else(|| self.home.join(".local/share")).join("gws")
+    }
+
+    /// The list of recently opened files.
+    pub fn recent_file(&self) -> PathBuf {
+        self.data_dir().join("recent.toml")
+    }
+
+    fn legacy_dir(&self) -> PathBuf {
+        self.home.join(".gws")
+    }
+
+    /// The config file: `config.toml` in the config directory, unless only
+    /// the legacy `~/.gws/config.toml` exists.
+    pub fn config_file(&self) -> PathBuf {
+        self.prefer_existing(self.config_dir().join(CONFIG_FILE), CONFIG_FILE)
+    }
+
+    /// The todo file used when nothing else names one: `todo.md` in the data
+    /// directory, unless only the legacy `~/.gws/todo.md` exists.
+    pub fn data_file(&self) -> PathBuf {
+        self.prefer_existing(self.data_dir().join(TODO_FILE), TODO_FILE)
+    }
+
+    fn prefer_existing(&self, xdg: PathBuf, name: &str) -> PathBuf {
+        let legacy = self.legacy_dir().join(name);
+        if !xdg.exists() && legacy.exists() { legacy } else { xdg }
+    }
+}
+
+/// An absolute directory from the environment; relative ones are ignored, as
+/// the XDG spec asks.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    env::var_os(var).map(PathBuf::from).filter(|p| p.is_absolute())
+}
+
+/// The root of the git repository `dir` is in: the nearest ancestor holding
+/// `.git`, a directory or, in worktrees and submodules, a file.
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|d| d.join(".git").exists()).map(Path::to_path_buf)
+}
+
+/// How the todo file was found, in the order tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// `--file`
+    Flag,
+    /// `--profile`
+    Profile,
+    /// `$GWS_FILE`
+    Env,
+    /// `todo.md` in the working directory
+    WorkingDir,
+    /// `TODO.md` or `.gws/todo.md` at the root of the git repository
+    Repo,
+    /// `default_file` in the config
+    Config,
+    /// The data directory
+    DataDir,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Flag => "--file",
+            Source::Profile => "--profile",
+            Source::Env => "$GWS_FILE",
+            Source::WorkingDir => "./todo.md",
+            Source::Repo => "git repository",
+            Source::Config => "default_file in config",
+            Source::DataDir => "data directory",
+        }
+    }
+}
+
+/// Places the todo file may be named, besides the data directory.
+#[derive(Debug, Clone, Default)]
+pub struct Candidates {
+    pub flag: Option<PathBuf>,
+    /// The file of the profile picked with `--profile`.
+    pub profile: Option<PathBuf>,
+    pub env: Option<PathBuf>,
+    pub working_dir: Option<PathBuf>,
+    pub repo_root: Option<PathBuf>,
+    pub configured: Option<PathBuf>,
+}
+
+impl Candidates {
+    /// `--file` as given, `$GWS_FILE`, the working directory and the git
+    /// repository around it, and the config's `default_file`.
+    pub fn new(flag: Option<PathBuf>, configured: Option<PathBuf>) -> Self {
+        let working_dir = env::current_dir().ok();
+        Self {
+            flag,
+            profile: None,
+            env: env::var_os("GWS_FILE").filter(|v| !v.is_empty()).map(PathBuf::from),
+            repo_root: working_dir.as_deref().and_then(repo_root),
+            working_dir,
+            configured,
+        }
+    }
+
+    /// Skip the places tied to where gws runs (`$GWS_FILE`, the working
+    /// directory and its repository), for `--global`.
+    pub fn global(self) -> Self {
+        Self {
+            env: None,
+            working_dir: None,
+            repo_root: None,
+            ..self
+        }
+    }
+
+    /// The first of `--file`, `--profile`, `$GWS_FILE`, an existing `./todo.md`, an
+    /// existing `TODO.md` or `.gws/todo.md` at the repository root, the
+    /// configured default file, and the data directory's `todo.md`.
+    pub fn find(self, dirs: &Dirs) -> (PathBuf, Source) {
+        let in_working_dir = self
+            .working_dir
+            .map(|dir| dir.join(TODO_FILE))
+            .filter(|path| path.is_file());
+        let in_repo = self
+            .repo_root
+            .and_then(|root| REPO_FILES.iter().map(|name| root.join(name)).find(|path| path.is_file()));
+        self.flag
+            .map(|p| (p, Source::Flag))
+            .or(self.profile.map(|p| (p, Source::Profile)))
+            .or(self.env.map(|p| (p, Source::Env)))
+            .or(in_working_dir.map(|p| (p, Source::WorkingDir)))
+            .or(in_repo.map(|p| (p, Source::Repo)))
+            .or(self.configured.map(|p| (p, Source::Config)))
+            .unwrap_or_else(|| (dirs.data_file(), Source::DataDir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_dirs() {
+        let home = std::env::temp_dir().join(format!("gws-paths-{}", std::process::id()));
+        let dirs = Dirs {
+            home: home.clone(),
+            xdg_config: None,
+            xdg_data: Some(home.join("data")),
+        };
+        assert_eq!(dirs.config_file(), home.join(".config/gws/config.toml"));
+        assert_eq!(dirs.data_file(), home.join("data/gws/todo.md"));
+
+        // A legacy file is kept until one exists in the XDG location
+        fs::create_dir_all(home.join(".gws")).unwrap();
+        fs::write(home.join(".gws/todo.md"), "## Work\n").unwrap();
+        assert_eq!(dirs.data_file(), home.join(".gws/todo.md"));
+        assert_eq!(dirs.config_file(), home.join(".config/gws/config.toml"));
+        fs::create_dir_all(home.join("data/gws")).unwrap();
+        fs::write(home.join("data/gws/todo.md"), "## Work\n").unwrap();
+        assert_eq!(dirs.data_file(), home.join("data/gws/todo.md"));
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_discovery_order() {
+        let root = std::env::temp_dir().join(format!("gws-discovery-{}", std::process::id()));
+        let project = root.join("project");
+        fs::create_dir_all(&project).unwrap();
+        let dirs = Dirs {
+            home: root.join("home"),
+            xdg_config: None,
+            xdg_data: None,
+        };
+        let candidates = Candidates {
+            flag: Some(PathBuf::from("flag.md")),
+            profile: Some(PathBuf::from("profile.md")),
+            env: Some(PathBuf::from("env.md")),
+            working_dir: Some(project.clone()),
+            repo_root: None,
+            configured: Some(PathBuf::from("configured.md")),
+        };
+        let found = |c: &Candidates| c.clone().find(&dirs);
+
+        assert_eq!(found(&candidates), (PathBuf::from("flag.md"), Source::Flag));
+        let candidates = Candidates { flag: None, ..candidates };
+        assert_eq!(found(&candidates), (PathBuf::from("profile.md"), Source::Profile));
+        let candidates = Candidates { profile: None, ..candidates };
+        assert_eq!(found(&candidates), (PathBuf::from("env.md"), Source::Env));
+        let candidates = Candidates { env: None, ..candidates };
+        // ./todo.md only counts when it exists
+        assert_eq!(found(&candidates), (PathBuf::from("configured.md"), Source::Config));
+        fs::write(project.join("todo.md"), "## Work\n").unwrap();
+        assert_eq!(found(&candidates), (project.join("todo.md"), Source::WorkingDir));
+        let candidates = Candidates { working_dir: None, configured: None, ..candidates };
+        assert_eq!(found(&candidates), (root.join("home/.local/share/gws/todo.md"), Source::DataDir));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_repo_local_file() {
+        let root = std::env::temp_dir().join(format!("gws-repo-{}", std::process::id()));
+        let src = root.join("repo/src");
+        fs::create_dir_all(&src).unwrap();
+        let dirs = Dirs {
+            home: root.join("home"),
+            xdg_config: None,
+            xdg_data: None,
+        };
+        assert_eq!(repo_root(&src), None);
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        assert_eq!(repo_root(&src), Some(root.join("repo")));
+
+        let candidates = Candidates {
+            working_dir: Some(src.clone()),
+            repo_root: repo_root(&src),
+            configured: Some(PathBuf::from("configured.md")),
+            ..Candidates::default()
+        };
+        let found = |c: &Candidates| c.clone().find(&dirs);
+        assert_eq!(found(&candidates).1, Source::Config);
+        fs::create_dir_all(root.join("repo/.gws")).unwrap();
+        fs::write(root.join("repo/.gws/todo.md"), "## Work\n").unwrap();
+        assert_eq!(found(&candidates), (root.join("repo/.gws/todo.md"), Source::Repo));
+        fs::write(root.join("repo/TODO.md"), "## Work\n").unwrap();
+        assert_eq!(found(&candidates), (root.join("repo/TODO.md"), Source::Repo));
+        fs::write(src.join("todo.md"), "## Work\n").unwrap();
+        assert_eq!(found(&candidates).1, Source::WorkingDir);
+
+        // --global goes past all of them
+        assert_eq!(found(&candidates.clone().global()), (PathBuf::from("configured.md"), Source::Config));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
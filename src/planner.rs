@@ -0,0 +1,157 @@
+use crate::date::Date;
+use crate::model::{Document, Task, TaskState};
+
+/// Minutes assumed for a task with no `~` estimate.
+pub const DEFAULT_ESTIMATE_MINUTES: u32 = 30;
+
+/// Where a task lives: category, project and task indices.
+pub type TaskIdx = (usize, usize, usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanItem {
+    pub task: TaskIdx,
+    pub minutes: u32,
+    /// False when `minutes` is the default rather than the task's own estimate.
+    pub estimated: bool,
+}
+
+/// A proposed Today list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    pub items: Vec<PlanItem>,
+    pub budget_minutes: u32,
+    /// Open tasks that were candidates but did not fit.
+    pub left_out: usize,
+}
+
+impl Plan {
+    pub fn total_minutes(&self) -> u32 {
+        self.items.iter().map(|item| item.minutes).sum()
+    }
+}
+
+/// Rank a task for the plan: work already started first, then priority,
+/// then the nearest project deadline. Lower sorts first.
+fn rank(task: &Task, days_left: Option<i64>) -> (u8, u8, i64) {
+    let started = match task.state {
+        TaskState::InProgress => 0,
+        TaskState::OnDeck => 1,
+        _ => 2,
+    };
+    (started, task.priority().unwrap_or(u8::MAX), days_left.unwrap_or(i64::MAX))
+}
+
+/// Fill `budget_minutes` with open tasks from active projects, best ranked
+/// first; ties keep document order. A task that doesn't fit is passed over
+/// for smaller ones further down. Tasks in `skip` are left out entirely.
+pub fn plan_day(doc: &Document, budget_minutes: u32, skip: &[TaskIdx], today: Date) -> Plan {
+    let mut candidates = Vec::new();
+    for (ci, category) in doc.categories.iter().enumerate().filter(|(_, c)| !c.hidden) {
+        for (pi, project) in category.projects.iter().enumerate() {
+            if !project.is_active() {
+                continue;
+            }
+            for (ti, task) in project.tasks.iter().enumerate() {
+                if task.state == TaskState::Done || skip.contains(&(ci, pi, ti)) {
+                    continue;
+                }
+                candidates.push((rank(task, project.days_left(today)), (ci, pi, ti), task.estimate()));
+            }
+        }
+    }
+    candidates.sort_by_key(|(rank, _, _)| *rank);
+
+    let mut plan = Plan {
+        budget_minutes,
+        ..Plan::default()
+    };
+    let mut used = 0;
+    for (_, task, estimate) in candidates {
+        let minutes = estimate.unwrap_or(DEFAULT_ESTIMATE_MINUTES);
+        if used + minutes > budget_minutes {
+            plan.left_out += 1;
+            continue;
+        }
+        used += minutes;
+        plan.items.push(PlanItem {
+            task,
+            minutes,
+            estimated: estimate.is_some(),
+        });
+    }
+    plan
+}
+
+/// "1h 30m", "45m", "2h".
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const DOC: &str = "\
+## Work
+
+### 🔶 Launch
+- 🔶 Review copy ~1h
+- 🔴 Book venue ~2h !2
+- 🔴 Call printer
+- 🔴 Order swag ~45m !1
+- ✅ Pick a domain ~1h
+
+### Hiring
+- 🔴 Post job ~15m !1
+
+### 🔶 Audit due:2026-03-12
+- 🔴 Collect receipts ~1h30m
+";
+
+    fn texts(doc: &Document, plan: &Plan) -> Vec<String> {
+        plan.items
+            .iter()
+            .map(|item| {
+                let (ci, pi, ti) = item.task;
+                doc.categories[ci].projects[pi].tasks[ti].text.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_day() {
+        let doc = parse(DOC);
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        let plan = plan_day(&doc, 4 * 60, &[], today);
+        // Started first, then !1, !2; then neither the 90 minute audit nor
+        // the call, at the default half hour, fits
+        assert_eq!(texts(&doc, &plan), ["Review copy ~1h", "Order swag ~45m !1", "Book venue ~2h !2"]);
+        assert_eq!(plan.total_minutes(), 225);
+        assert_eq!(plan.left_out, 2);
+
+        let plan = plan_day(&doc, 6 * 60, &[(0, 0, 2)], today);
+        assert_eq!(
+            texts(&doc, &plan),
+            ["Review copy ~1h", "Order swag ~45m !1", "Book venue ~2h !2", "Collect receipts ~1h30m"]
+        );
+        assert!(plan.items.iter().all(|item| item.estimated));
+    }
+
+    #[test]
+    fn test_estimates() {
+        let estimate = |text: &str| Task::new(TaskState::Todo, text.to_string()).estimate();
+        assert_eq!(estimate("Call ~45m"), Some(45));
+        assert_eq!(estimate("~2h Write report"), Some(120));
+        assert_eq!(estimate("Plan ~1h30m"), Some(90));
+        assert_eq!(estimate("About ~ an hour"), None);
+        assert_eq!(estimate("Nothing ~0m"), None);
+        assert_eq!(format_minutes(45), "45m");
+        assert_eq!(format_minutes(120), "2h");
+        assert_eq!(format_minutes(90), "1h 30m");
+    }
+}
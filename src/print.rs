@@ -0,0 +1,199 @@
+//! `gws print`: the Agenda or Backlog as plain text for pipes and quick
+//! glances, optionally colored with ANSI escapes.
+
+use crate::date::Date;
+use crate::engine;
+use crate::model::{Document, TaskState};
+use crate::query::Query;
+
+/// Which view `print` renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrintView {
+    Agenda,
+    Backlog,
+}
+
+/// ANSI styling, or none.
+#[derive(Debug, Clone, Copy)]
+struct Paint(bool);
+
+const BOLD: &str = "1";
+const DIM: &str = "2";
+
+impl Paint {
+    fn apply(self, code: &str, text: &str) -> String {
+        if self.0 {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn state(self, state: TaskState, text: &str) -> String {
+        let code = match state {
+            TaskState::Todo => "31",
+            TaskState::OnDeck => "34",
+            TaskState::InProgress => "33",
+            TaskState::Done => "32",
+        };
+        self.apply(code, text)
+    }
+}
+
+/// Render `view` of `doc`. The agenda shows `sections` as the TUI does
+/// (deadline boost included); `query` narrows either view to matching tasks.
+pub fn print(doc: &Document, view: PrintView, sections: &[TaskState], query: Option<&Query>, color: bool) -> String {
+    match view {
+        PrintView::Agenda => agenda(doc, sections, query, Paint(color), Date::today()),
+        PrintView::Backlog => backlog(doc, query, Paint(color)),
+    }
+}
+
+fn agenda(doc: &Document, sections: &[TaskState], query: Option<&Query>, paint: Paint, today: Date) -> String {
+    let mut items = engine::build_agenda(doc, sections);
+    engine::boost_deadlines(doc, &mut items, today);
+    if let Some(query) = query {
+        items.retain(|item| {
+            let category = &doc.categories[item.category_idx];
+            let project = &category.projects[item.project_idx];
+            query.matches(category, project, &project.tasks[item.task_idx])
+        });
+    }
+    if items.is_empty() {
+        return "Nothing on the agenda\n".to_string();
+    }
+
+    let mut out = String::new();
+    for &state in sections {
+        let mut section = items.iter().filter(|item| item.task.state == state).peekable();
+        if section.peek().is_none() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&paint.apply(BOLD, state.label()));
+        out.push('\n');
+        for item in section {
+            let project = &doc.categories[item.category_idx].projects[item.project_idx];
+            let context = match project.deadline {
+                Some(deadline) => format!("{}, due {}", project.name, deadline),
+                None => project.name.clone(),
+            };
+            out.push_str(&format!(
+                "  {} {}  {}\n",
+                paint.state(state, state.symbol()),
+                item.task.text,
+                paint.apply(DIM, &context)
+            ));
+        }
+    }
+    out
+}
+
+fn backlog(doc: &Document, query: Option<&Query>, paint: Paint) -> String {
+    let mut out = String::new();
+    for category in &doc.categories {
+        let mut lines = Vec::new();
+        for project in &category.projects {
+            let tasks: Vec<_> = project
+                .tasks
+                .iter()
+                .filter(|task| query.is_none_or(|q| q.matches(category, project, task)))
+                .collect();
+            // A filter leaves out projects with nothing matching
+            if query.is_some() && tasks.is_empty() {
+                continue;
+            }
+            let mut heading = format!("{}{}", project.markers(), project.name);
+            if let Some(deadline) = project.deadline {
+                heading.push_str(&format!("  due {}", deadline));
+            }
+            lines.push(format!("  {}", paint.apply(if project.active { BOLD } else { DIM }, &heading)));
+            for task in tasks {
+                lines.push(format!("    {} {}", paint.state(task.state, task.state.symbol()), task.text));
+                for note in &task.notes {
+                    lines.push(format!("      {}", paint.apply(DIM, note.trim())));
+                }
+            }
+        }
+        if query.is_some() && lines.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&paint.apply(BOLD, &category.name));
+        out.push('\n');
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    if out.is_empty() {
+        out.push_str("Nothing matches\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const DOC: &str = "\
+## Work
+
+### 🔶 Launch due:2026-05-01
+- 🔶 Review copy
+  Focus on pricing
+- 🔵 Book venue
+- 🔴 Print flyers
+
+### Hiring
+- 🔴 Post job
+";
+
+    #[test]
+    fn test_print_agenda() {
+        let doc = parse(DOC);
+        let sections = [TaskState::InProgress, TaskState::OnDeck];
+        let today = Date::parse("2026-01-01").unwrap();
+        assert_eq!(
+            agenda(&doc, &sections, None, Paint(false), today),
+            "In Progress\n  🔶 Review copy  Launch, due 2026-05-01\n\nOn Deck\n  🔵 Book venue  Launch, due 2026-05-01\n"
+        );
+        let query = Query::parse("venue").unwrap();
+        assert_eq!(
+            agenda(&doc, &sections, Some(&query), Paint(true), today),
+            "\x1b[1mOn Deck\x1b[0m\n  \x1b[34m🔵\x1b[0m Book venue  \x1b[2mLaunch, due 2026-05-01\x1b[0m\n"
+        );
+        let query = Query::parse("state:done").unwrap();
+        assert_eq!(agenda(&doc, &sections, Some(&query), Paint(false), today), "Nothing on the agenda\n");
+    }
+
+    #[test]
+    fn test_print_backlog() {
+        let doc = parse(DOC);
+        assert_eq!(
+            backlog(&doc, None, Paint(false)),
+            "\
+Work
+  🔶 Launch  due 2026-05-01
+    🔶 Review copy
+      Focus on pricing
+    🔵 Book venue
+    🔴 Print flyers
+  Hiring
+    🔴 Post job
+"
+        );
+        let query = Query::parse("state:todo").unwrap();
+        assert_eq!(
+            backlog(&doc, Some(&query), Paint(false)),
+            "Work\n  🔶 Launch  due 2026-05-01\n    🔴 Print flyers\n  Hiring\n    🔴 Post job\n"
+        );
+        let query = Query::parse("nothing-like-this").unwrap();
+        assert_eq!(backlog(&doc, Some(&query), Paint(false)), "Nothing matches\n");
+    }
+}
@@ -0,0 +1,161 @@
+//! `gws current`: the task being worked on, one line for shell prompts and
+//! tmux status bars. These run it on every redraw, so the answer is cached
+//! next to the todo file as `<name>.current.toml` and the file is only parsed
+//! again when it, the config or the date has changed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::date::Date;
+use crate::engine;
+use crate::model::{Document, TaskState};
+use crate::parser;
+use crate::webhook;
+
+/// Used when `--format` isn't given.
+pub const DEFAULT_FORMAT: &str = "{state_icon} {text}";
+
+/// The first In Progress task as the agenda orders it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Current {
+    pub state: TaskState,
+    pub text: String,
+    pub project: String,
+    pub category: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cache {
+    /// What the answer was worked out from (see `cache_key`).
+    key: String,
+    current: Option<Current>,
+}
+
+pub fn cache_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("current.toml")
+}
+
+/// The task on top of the In Progress section, deadlines first like the
+/// agenda; `None` when nothing is in progress.
+pub fn current(doc: &Document, today: Date) -> Option<Current> {
+    let mut agenda = engine::build_agenda(doc, &[TaskState::InProgress]);
+    engine::boost_deadlines(doc, &mut agenda, today);
+    let item = agenda.into_iter().next()?;
+    Some(Current {
+        state: item.task.state,
+        text: item.task.text,
+        project: item.project_name,
+        category: doc.categories[item.category_idx].name.clone(),
+    })
+}
+
+/// Fill in `{state_icon}` (`🔶`), `{state}` (`In Progress`), `{text}`,
+/// `{project}` and `{category}`.
+pub fn render(format: &str, current: &Current) -> String {
+    webhook::fill(format, |name| match name {
+        "state_icon" => Some(current.state.symbol()),
+        "state" => Some(current.state.label()),
+        "text" => Some(&current.text),
+        "project" => Some(&current.project),
+        "category" => Some(&current.category),
+        _ => None,
+    })
+}
+
+/// The current task of `file_path`, from the cache when the todo file and
+/// config are unchanged since it was written today. A cache that can't be
+/// read or written is only a slower answer.
+pub fn load_current(file_path: &Path, config_path: &Path, config: &Config, today: Date) -> Result<Option<Current>> {
+    let key = cache_key(file_path, config_path, today);
+    let cached = fs::read_to_string(cache_path(file_path))
+        .ok()
+        .and_then(|content| toml::from_str::<Cache>(&content).ok());
+    if let Some(cache) = cached.filter(|cache| key.as_ref() == Some(&cache.key)) {
+        return Ok(cache.current);
+    }
+
+    let content = fs::read_to_string(file_path).with_context(|| format!("Failed to read {}", file_path.display()))?;
+    let doc = parser::parse_with(&content, config.parse_options());
+    let current = current(&doc, today);
+    if let Some(key) = key {
+        let cache = Cache { key, current: current.clone() };
+        if let Ok(content) = toml::to_string(&cache) {
+            let _ = fs::write(cache_path(file_path), content);
+        }
+    }
+    Ok(current)
+}
+
+/// Size and modification time of the todo file and config, and the date
+/// (deadlines reorder the agenda as days pass). `None` when the todo file's
+/// can't be read, so nothing is cached.
+fn cache_key(file_path: &Path, config_path: &Path, today: Date) -> Option<String> {
+    let stamp = |path: &Path| {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!("{}@{}.{:09}", metadata.len(), modified.as_secs(), modified.subsec_nanos()))
+    };
+    let config = stamp(config_path).unwrap_or_else(|| "none".to_string());
+    Some(format!("{} {} {}", stamp(file_path)?, config, today))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_current() {
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        let doc = parse("## Home\n### 🔶 Garden\n- 🔶 Plant bulbs\n## Work\n### 🔶 Launch\n- 🔶 Write copy\n- 🔵 Call Sam\n");
+        let current = current(&doc, today).unwrap();
+        assert_eq!(render(DEFAULT_FORMAT, &current), "🔶 Plant bulbs");
+        assert_eq!(render("[{category} › {project}] {text} ({state})", &current), "[Home › Garden] Plant bulbs (In Progress)");
+
+        // A close deadline puts its project's task first, as on the agenda
+        let doc = parse("## Home\n### 🔶 Garden\n- 🔶 Plant bulbs\n## Work\n### 🔶 Launch due:2026-03-12\n- 🔶 Write copy\n");
+        assert_eq!(super::current(&doc, today).unwrap().text, "Write copy");
+
+        let doc = parse("## Work\n### 🔶 Launch\n- 🔵 Call Sam\n");
+        assert_eq!(super::current(&doc, today), None);
+
+        // Braces in the task's text are not placeholders
+        let doc = parse("## Work\n### 🔶 Launch\n- 🔶 Fix {project} typo\n");
+        let current = super::current(&doc, today).unwrap();
+        assert_eq!(render("{text} [{project}]", &current), "Fix {project} typo [Launch]");
+    }
+
+    #[test]
+    fn test_load_current_cache() {
+        let dir = std::env::temp_dir().join(format!("gws-current-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("todo.md");
+        let config_path = dir.join("config.toml");
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        fs::write(&file, "## Work\n### 🔶 Launch\n- 🔶 Write copy\n").unwrap();
+
+        let first = load_current(&file, &config_path, &Config::default(), today).unwrap();
+        assert_eq!(first.as_ref().map(|c| c.text.as_str()), Some("Write copy"));
+        assert!(cache_path(&file).exists());
+
+        // A cache with the right key is believed without reading the file
+        let key = cache_key(&file, &config_path, today).unwrap();
+        let planted = Cache { key, current: None };
+        fs::write(cache_path(&file), toml::to_string(&planted).unwrap()).unwrap();
+        assert_eq!(load_current(&file, &config_path, &Config::default(), today).unwrap(), None);
+
+        // Another day, or an edit, and it is worked out again
+        let tomorrow = today.plus_days(1);
+        assert_eq!(load_current(&file, &config_path, &Config::default(), tomorrow).unwrap(), first);
+        fs::write(&file, "## Work\n### 🔶 Launch\n- ✅ Write copy\n- 🔶 Ship it\n").unwrap();
+        let edited = load_current(&file, &config_path, &Config::default(), tomorrow).unwrap();
+        assert_eq!(edited.map(|c| c.text), Some("Ship it".to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
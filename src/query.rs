@@ -0,0 +1,410 @@
+//! Filter expressions for saved filters, e.g. `state:ondeck AND @home` or
+//! `/call|email/ OR project:launch`.
+//!
+//! Terms:
+//! - `state:todo|ondeck|inprogress|done`
+//! - `@name`: the task mentions that person
+//! - `#tag`: the task is tagged
+//! - `project:text`, `category:text`: the name contains `text`, or matches
+//!   it as a glob if it has a `*`; quote values with spaces, `project:"Web site*"`
+//! - `priority:N` or `!N`
+//! - `due<DATE`, `due<=`, `due>`, `due>=`, `due=`: the task's `due:` date, or
+//!   else its project's deadline; dates as in `Date::parse_relative`
+//! - `/regex/` or `re:regex`: case-insensitive match on the task text or notes
+//! - any other word, or `"quoted text"`: the task text or notes contain it
+//!
+//! Terms combine with `AND`, `OR`, `NOT` and parentheses; `AND` binds tighter
+//! than `OR`, and terms side by side are joined with `AND`.
+
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+
+use crate::date::Date;
+use crate::model::{Category, Project, Task, TaskState};
+
+/// How a `project:` or `category:` value matches a name.
+#[derive(Debug, Clone)]
+pub enum NamePattern {
+    /// Lowercased substring.
+    Contains(String),
+    /// Whole-name glob with `*` wildcards.
+    Glob(Regex),
+}
+
+impl NamePattern {
+    fn new(value: &str) -> Self {
+        if value.contains('*') {
+            let pattern: Vec<String> = value.split('*').map(regex::escape).collect();
+            let glob = Regex::new(&format!("(?i)^{}$", pattern.join(".*"))).expect("escaped glob is a valid regex");
+            NamePattern::Glob(glob)
+        } else {
+            NamePattern::Contains(value.to_lowercase())
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Contains(text) => name.to_lowercase().contains(text),
+            NamePattern::Glob(re) => re.is_match(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Before,
+    OnOrBefore,
+    On,
+    OnOrAfter,
+    After,
+}
+
+#[derive(Debug, Clone)]
+pub enum Query {
+    State(TaskState),
+    Mention(String),
+    Tag(String),
+    Project(NamePattern),
+    Category(NamePattern),
+    Priority(u8),
+    Due(Comparison, Date),
+    Regex(Regex),
+    /// Lowercased.
+    Text(String),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Regex(String),
+    Quoted(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '/' | '"' => {
+                chars.next();
+                let mut body = String::new();
+                loop {
+                    match chars.next() {
+                        // `\/` inside a regex is a literal slash
+                        Some('\\') if c == '/' && chars.peek() == Some(&'/') => {
+                            body.push('/');
+                            chars.next();
+                        }
+                        Some(ch) if ch == c => break,
+                        Some(ch) => body.push(ch),
+                        None => bail!("Missing closing {}", c),
+                    }
+                }
+                tokens.push(if c == '/' { Token::Regex(body) } else { Token::Quoted(body) });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || ch == '(' || ch == ')' {
+                        break;
+                    }
+                    chars.next();
+                    // A quoted value, as in project:"Web site"
+                    if ch == '"' {
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(quoted) => word.push(quoted),
+                                None => bail!("Missing closing \""),
+                            }
+                        }
+                    } else {
+                        word.push(ch);
+                    }
+                }
+                tokens.push(match word.to_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_state(name: &str) -> Result<TaskState> {
+    match name.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "todo" | "notstarted" => Ok(TaskState::Todo),
+        "ondeck" => Ok(TaskState::OnDeck),
+        "inprogress" | "progress" => Ok(TaskState::InProgress),
+        "done" => Ok(TaskState::Done),
+        _ => bail!("Unknown state \"{}\"", name),
+    }
+}
+
+fn regex(pattern: &str) -> Result<Query> {
+    Regex::new(&format!("(?i){}", pattern))
+        .map(Query::Regex)
+        .map_err(|e| anyhow!("Invalid regex /{}/: {}", pattern, e))
+}
+
+fn due(word: &str) -> Option<Result<Query>> {
+    let rest = word.get(..3).filter(|key| key.eq_ignore_ascii_case("due")).map(|_| &word[3..])?;
+    let (comparison, value) = [
+        ("<=", Comparison::OnOrBefore),
+        (">=", Comparison::OnOrAfter),
+        ("<", Comparison::Before),
+        (">", Comparison::After),
+        ("=", Comparison::On),
+        (":", Comparison::On),
+    ]
+    .into_iter()
+    .find_map(|(op, comparison)| Some((comparison, rest.strip_prefix(op)?)))?;
+    Some(
+        Date::parse_relative(value, Date::today())
+            .map(|date| Query::Due(comparison, date))
+            .ok_or_else(|| anyhow!("Not a date: {}", value)),
+    )
+}
+
+fn term(word: &str) -> Result<Query> {
+    if let Some(query) = due(word) {
+        return query;
+    }
+    if let Some((key, value)) = word.split_once(':') {
+        match key.to_lowercase().as_str() {
+            "state" => return parse_state(value).map(Query::State),
+            "project" => return Ok(Query::Project(NamePattern::new(value))),
+            "category" => return Ok(Query::Category(NamePattern::new(value))),
+            "priority" => {
+                return value.parse().map(Query::Priority).map_err(|_| anyhow!("Not a priority: {}", value));
+            }
+            "re" => return regex(value),
+            _ => {}
+        }
+    }
+    if let Some(name) = word.strip_prefix('@').filter(|n| !n.is_empty()) {
+        return Ok(Query::Mention(name.to_string()));
+    }
+    if let Some(name) = word.strip_prefix('#').filter(|n| !n.is_empty()) {
+        return Ok(Query::Tag(name.to_string()));
+    }
+    if let Some(n) = word.strip_prefix('!').and_then(|n| n.parse().ok()) {
+        return Ok(Query::Priority(n));
+    }
+    Ok(Query::Text(word.to_lowercase()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut terms = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Query::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut terms = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                None | Some(Token::Or) | Some(Token::Close) => break,
+                // Side by side means AND
+                Some(_) => {}
+            }
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Query::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Query> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => bail!("Missing closing )"),
+                }
+            }
+            Some(Token::Word(word)) => term(&word),
+            Some(Token::Quoted(text)) => Ok(Query::Text(text.to_lowercase())),
+            Some(Token::Regex(pattern)) => regex(&pattern),
+            Some(Token::Close) => bail!("Unexpected )"),
+            Some(Token::And) | Some(Token::Or) => bail!("AND / OR needs a term on each side"),
+            None => bail!("Expected a term"),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+        if parser.tokens.is_empty() {
+            bail!("Empty filter");
+        }
+        let query = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            bail!("Unexpected )");
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, category: &Category, project: &Project, task: &Task) -> bool {
+        let lines = || std::iter::once(&task.text).chain(&task.notes);
+        match self {
+            Query::State(state) => task.state == *state,
+            Query::Mention(name) => task.mentions(name),
+            Query::Tag(name) => task.has_tag(name),
+            Query::Project(pattern) => pattern.matches(&project.name),
+            Query::Category(pattern) => pattern.matches(&category.name),
+            Query::Priority(n) => task.priority() == Some(*n),
+            Query::Due(comparison, date) => task.due().or(project.deadline).is_some_and(|due| match comparison {
+                Comparison::Before => due < *date,
+                Comparison::OnOrBefore => due <= *date,
+                Comparison::On => due == *date,
+                Comparison::OnOrAfter => due >= *date,
+                Comparison::After => due > *date,
+            }),
+            Query::Regex(re) => lines().any(|line| re.is_match(line)),
+            Query::Text(text) => lines().any(|line| line.to_lowercase().contains(text)),
+            Query::Not(inner) => !inner.matches(category, project, task),
+            Query::And(terms) => terms.iter().all(|q| q.matches(category, project, task)),
+            Query::Or(terms) => terms.iter().any(|q| q.matches(category, project, task)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const DOC: &str = "\
+## Home
+
+### 🔶 Chores
+- 🔵 Fix sink with @Sam
+- 🔴 Call plumber !1
+- ✅ Email landlord
+
+## Work
+
+### 🔶 Launch
+- 🔵 Review copy
+  ask @sam about pricing
+- 🔴 Book venue
+  #Errands after lunch
+";
+
+    fn matching(query: &str) -> Vec<String> {
+        let doc = parse(DOC);
+        let query = Query::parse(query).unwrap();
+        let mut hits = Vec::new();
+        for category in &doc.categories {
+            for project in &category.projects {
+                for task in &project.tasks {
+                    if query.matches(category, project, task) {
+                        hits.push(task.text.clone());
+                    }
+                }
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn test_query_terms() {
+        assert_eq!(matching("state:ondeck AND @sam"), ["Fix sink with @Sam", "Review copy"]);
+        assert_eq!(matching("state:on_deck category:home"), ["Fix sink with @Sam"]);
+        assert_eq!(matching("/^(call|email) /"), ["Call plumber !1", "Email landlord"]);
+        assert_eq!(matching("re:PRICING"), ["Review copy"]);
+        assert_eq!(matching("!1 OR \"book venue\""), ["Call plumber !1", "Book venue"]);
+        assert_eq!(matching("project:launch AND NOT state:todo"), ["Review copy"]);
+        assert_eq!(
+            matching("(state:todo OR state:done) category:home"),
+            ["Call plumber !1", "Email landlord"]
+        );
+        assert_eq!(matching("project:\"l*ch\" state:todo"), ["Book venue"]);
+        assert!(matching("project:\"l*c\"").is_empty());
+        assert_eq!(matching("#errands OR state:done"), ["Email landlord", "Book venue"]);
+    }
+
+    #[test]
+    fn test_query_due() {
+        let doc = parse(
+            "## Work\n\n### Launch due:2026-05-01\n- 🔴 Book venue due:2026-04-02\n- 🔴 Print flyers\n\n### Hiring\n- 🔴 Post job\n",
+        );
+        let hits = |query: &str| {
+            let query = Query::parse(query).unwrap();
+            let (category, mut texts) = (&doc.categories[0], Vec::new());
+            for project in &category.projects {
+                for task in &project.tasks {
+                    if query.matches(category, project, task) {
+                        texts.push(task.text.clone());
+                    }
+                }
+            }
+            texts
+        };
+        // Print flyers has no date of its own and falls back to the deadline
+        assert_eq!(hits("due<2026-05-01"), ["Book venue due:2026-04-02"]);
+        assert_eq!(hits("due<=2026-05-01"), ["Book venue due:2026-04-02", "Print flyers"]);
+        assert_eq!(hits("due>2026-04-02"), ["Print flyers"]);
+        assert_eq!(hits("due:2026-04-02"), ["Book venue due:2026-04-02"]);
+        assert!(hits("NOT due>=2000-01-01").contains(&"Post job".to_string()));
+        assert_eq!(Query::parse("due<soon").unwrap_err().to_string(), "Not a date: soon");
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let error = |query| Query::parse(query).unwrap_err().to_string();
+        assert_eq!(error("state:later"), "Unknown state \"later\"");
+        assert_eq!(error("(@sam OR @kim"), "Missing closing )");
+        assert_eq!(error("@sam )"), "Unexpected )");
+        assert_eq!(error("/call"), "Missing closing /");
+        assert_eq!(error("AND @sam"), "AND / OR needs a term on each side");
+        assert!(error("/(unclosed/").starts_with("Invalid regex"));
+        assert_eq!(error("  "), "Empty filter");
+        assert_eq!(error("project:\"web"), "Missing closing \"");
+    }
+}
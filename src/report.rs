@@ -0,0 +1,599 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::activity::{ActivityLog, EventKind};
+use crate::date::Date;
+use crate::engine;
+use crate::model::{Document, Task, TaskState};
+use crate::parser::{self, ArchiveEntry};
+use crate::query::Query;
+
+/// What a report covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Tasks mentioning `@name` in their text or notes.
+    Person(String),
+    /// Every task of the named project.
+    Project(String),
+}
+
+impl Scope {
+    fn title(&self) -> String {
+        match self {
+            Scope::Person(name) => format!("@{}", name),
+            Scope::Project(name) => name.clone(),
+        }
+    }
+}
+
+/// A task in scope with the category and project it lives in.
+struct Entry<'a> {
+    category: &'a str,
+    project: &'a str,
+    task: &'a Task,
+}
+
+/// Report sections: label and the states that fall under it. Not-started
+/// tasks are the ones still waiting their turn.
+const SECTIONS: &[(&str, &[TaskState])] = &[
+    ("Open", &[TaskState::InProgress, TaskState::OnDeck]),
+    ("Waiting", &[TaskState::Todo]),
+    ("Recently completed", &[TaskState::Done]),
+];
+
+fn entries<'a>(doc: &'a Document, scope: &Scope) -> Vec<Entry<'a>> {
+    let mut entries = Vec::new();
+    for category in &doc.categories {
+        for project in &category.projects {
+            if let Scope::Project(name) = scope
+                && !project.name.eq_ignore_ascii_case(name)
+            {
+                continue;
+            }
+            for task in &project.tasks {
+                if let Scope::Person(name) = scope
+                    && !task.mentions(name)
+                {
+                    continue;
+                }
+                entries.push(Entry {
+                    category: &category.name,
+                    project: &project.name,
+                    task,
+                });
+            }
+        }
+    }
+    entries
+}
+
+/// Open, waiting and recently completed (done but not yet archived) tasks
+/// for `scope`, as markdown for meeting notes or as plain text.
+pub fn report(doc: &Document, scope: &Scope, plain: bool) -> Result<String> {
+    let entries = entries(doc, scope);
+    match scope {
+        Scope::Person(name) if entries.is_empty() => bail!("No tasks mention @{}", name),
+        Scope::Project(name)
+            if !doc.categories.iter().flat_map(|c| &c.projects).any(|p| p.name.eq_ignore_ascii_case(name)) =>
+        {
+            bail!("No project named \"{}\"", name)
+        }
+        _ => {}
+    }
+    // The project is already in the title, so only person reports say where each task lives
+    let show_project = matches!(scope, Scope::Person(_));
+
+    let mut out = String::new();
+    if plain {
+        out.push_str(&format!("Report: {}\n", scope.title()));
+    } else {
+        out.push_str(&format!("# Report: {}\n", scope.title()));
+    }
+    for (label, states) in SECTIONS {
+        let section: Vec<&Entry> = entries.iter().filter(|e| states.contains(&e.task.state)).collect();
+        if plain {
+            out.push_str(&format!("\n{}:\n", label));
+        } else {
+            out.push_str(&format!("\n## {}\n\n", label));
+        }
+        if section.is_empty() {
+            out.push_str(if plain { "  (none)\n" } else { "_None_\n" });
+        }
+        for entry in section {
+            let location = if show_project {
+                format!(" ({} › {})", entry.category, entry.project)
+            } else {
+                String::new()
+            };
+            if plain {
+                out.push_str(&format!("  - {}{}\n", entry.task.text, location));
+                for note in &entry.task.notes {
+                    out.push_str(&format!("      {}\n", note.trim()));
+                }
+            } else {
+                out.push_str(&format!("- {} {}{}\n", entry.task.state.symbol(), entry.task.text, location));
+                for note in &entry.task.notes {
+                    out.push_str(&format!("  {}\n", note.trim()));
+                }
+            }
+        }
+    }
+    if let Scope::Project(name) = scope {
+        push_done_history(&mut out, doc, name, plain);
+    }
+    Ok(out)
+}
+
+/// Archived tasks recorded as coming from project `name`, newest first.
+fn push_done_history(out: &mut String, doc: &Document, name: &str, plain: bool) {
+    let history: Vec<ArchiveEntry> = doc
+        .archive
+        .iter()
+        .filter_map(|line| parser::archive_entry(line))
+        .filter(|entry| entry.project.is_some_and(|p| p.eq_ignore_ascii_case(name)))
+        .collect();
+    out.push_str(if plain { "\nDone history:\n" } else { "\n## Done history\n\n" });
+    if history.is_empty() {
+        out.push_str(if plain { "  (none)\n" } else { "_None_\n" });
+    }
+    for entry in history {
+        let date = entry.date.map(|d| format!(" ({})", d)).unwrap_or_default();
+        if plain {
+            out.push_str(&format!("  - {}{}\n", entry.text, date));
+        } else {
+            out.push_str(&format!("- ✅ {}{}\n", entry.text, date));
+        }
+    }
+}
+
+/// Markdown summary of the seven days up to `today`: tasks completed (by
+/// archive date), tasks added and projects activated or deactivated (from the
+/// activity log).
+pub fn weekly(doc: &Document, log: &ActivityLog, today: Date) -> String {
+    let since = today.minus_days(6);
+    let completed: Vec<String> = doc
+        .archive
+        .iter()
+        .filter_map(|line| parser::archive_entry(line))
+        .filter(|entry| entry.date.is_some_and(|date| date >= since))
+        .map(|entry| match entry.category.zip(entry.project) {
+            Some((category, project)) => format!("{} ({} › {})", entry.text, category, project),
+            None => entry.text.to_string(),
+        })
+        .collect();
+    let events = |kind: EventKind| {
+        log.since(since).filter(move |e| e.kind == kind).map(|e| match &e.task {
+            Some(task) => format!("{} ({} › {})", task, e.category, e.project),
+            None => format!("{} › {}", e.category, e.project),
+        })
+    };
+    let sections = [
+        ("Completed", completed),
+        ("Added", events(EventKind::Added).collect()),
+        ("Projects activated", events(EventKind::Activated).collect()),
+        ("Projects deactivated", events(EventKind::Deactivated).collect()),
+    ];
+
+    let mut out = format!("# Week of {} to {}\n", since, today);
+    for (label, items) in sections {
+        out.push_str(&format!("\n## {} ({})\n\n", label, items.len()));
+        if items.is_empty() {
+            out.push_str("_None_\n");
+        }
+        for item in items {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+    out
+}
+
+/// Output of `gws list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+/// One row of `gws list --format json`.
+#[derive(Serialize)]
+struct ListedTask<'a> {
+    /// For `gws --task-id`.
+    id: String,
+    category: &'a str,
+    project: &'a str,
+    #[serde(skip)]
+    symbol: &'static str,
+    state: &'static str,
+    text: &'a str,
+    /// The task's `due:` date, else the project's deadline.
+    due: Option<String>,
+    priority: Option<u8>,
+    notes: Vec<&'a str>,
+}
+
+/// Every task matching `query` (all tasks without one), in document order.
+pub fn list(doc: &Document, query: Option<&Query>, format: ListFormat) -> String {
+    let mut rows = Vec::new();
+    for category in &doc.categories {
+        for project in &category.projects {
+            for task in &project.tasks {
+                if query.is_some_and(|q| !q.matches(category, project, task)) {
+                    continue;
+                }
+                rows.push(ListedTask {
+                    id: task.id(),
+                    category: &category.name,
+                    project: &project.name,
+                    symbol: task.state.symbol(),
+                    state: task.state.label(),
+                    text: &task.text,
+                    due: task.due().or(project.deadline).map(|d| d.to_string()),
+                    priority: task.priority(),
+                    notes: task.notes.iter().map(|n| n.trim()).collect(),
+                });
+            }
+        }
+    }
+
+    match format {
+        ListFormat::Json => {
+            let mut out = serde_json::to_string_pretty(&rows).expect("task rows serialize");
+            out.push('\n');
+            out
+        }
+        ListFormat::Markdown => rows
+            .iter()
+            .map(|row| format!("- {} {} ({} › {})\n", row.symbol, row.text, row.category, row.project))
+            .collect(),
+        ListFormat::Table => {
+            let header = ["State", "Task", "Project", "Due"];
+            let cells: Vec<[String; 4]> = rows
+                .iter()
+                .map(|row| {
+                    [
+                        row.state.to_string(),
+                        row.text.to_string(),
+                        format!("{} › {}", row.category, row.project),
+                        row.due.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            let mut widths = header.map(|h| h.width());
+            for row in &cells {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.width());
+                }
+            }
+            let line = |row: &[&str]| {
+                let padded: Vec<String> = row
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.width())))
+                    .collect();
+                format!("{}\n", padded.join("  ").trim_end())
+            };
+            let mut out = line(&header);
+            for row in &cells {
+                out.push_str(&line(&row.each_ref().map(String::as_str)));
+            }
+            out
+        }
+    }
+}
+
+/// A todo file feeding the global agenda, under its profile's name.
+pub struct AgendaSource<'a> {
+    pub name: &'a str,
+    pub file: &'a Path,
+    pub doc: &'a Document,
+}
+
+/// A task on the global agenda, tagged with the file it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalItem {
+    pub source: String,
+    pub file: PathBuf,
+    pub category: String,
+    pub project: String,
+    pub task: Task,
+}
+
+/// The agendas of several files merged into one: section by section, each
+/// file's tasks in turn, in the order the files are given and as each file's
+/// own agenda orders them.
+pub fn global_agenda(sources: &[AgendaSource], sections: &[TaskState], today: Date) -> Vec<GlobalItem> {
+    let mut items: Vec<GlobalItem> = Vec::new();
+    for source in sources {
+        let mut agenda = engine::build_agenda(source.doc, sections);
+        engine::boost_deadlines(source.doc, &mut agenda, today);
+        items.extend(agenda.into_iter().map(|item| {
+            let project = &source.doc.categories[item.category_idx].projects[item.project_idx];
+            GlobalItem {
+                source: source.name.to_string(),
+                file: source.file.to_path_buf(),
+                category: source.doc.categories[item.category_idx].name.clone(),
+                project: item.project_name,
+                task: project.tasks[item.task_idx].clone(),
+            }
+        }));
+    }
+    // A stable sort keeps the file order within each section
+    items.sort_by_key(|item| sections.iter().position(|&s| s == item.task.state));
+    items
+}
+
+/// `gws agenda`: the global agenda as markdown, one heading per section.
+pub fn global_agenda_text(items: &[GlobalItem], sections: &[TaskState]) -> String {
+    let mut out = "# Agenda\n".to_string();
+    for &state in sections {
+        let tasks: Vec<&GlobalItem> = items.iter().filter(|item| item.task.state == state).collect();
+        out.push_str(&format!("\n## {} ({})\n\n", state.label(), tasks.len()));
+        if tasks.is_empty() {
+            out.push_str("_None_\n");
+        }
+        for item in tasks {
+            out.push_str(&format!(
+                "- {} {} [{}] ({} › {})\n",
+                state.symbol(),
+                item.task.text,
+                item.source,
+                item.category,
+                item.project
+            ));
+        }
+    }
+    out
+}
+
+/// `gws grep`: every task, note and archived task in `sources` matching
+/// `pattern`, file by file in document order. A task is listed once, with
+/// its matching notes under it.
+pub fn grep(sources: &[AgendaSource], pattern: &Regex) -> String {
+    let mut out = String::new();
+    for source in sources {
+        for category in &source.doc.categories {
+            for project in &category.projects {
+                for task in &project.tasks {
+                    let notes: Vec<&str> = task.notes.iter().map(|n| n.trim()).filter(|n| pattern.is_match(n)).collect();
+                    if !pattern.is_match(&task.text) && notes.is_empty() {
+                        continue;
+                    }
+                    out.push_str(&format!(
+                        "{}: {} › {} [{}] {}\n",
+                        source.name,
+                        category.name,
+                        project.name,
+                        task.state.label(),
+                        task.text
+                    ));
+                    for note in notes {
+                        out.push_str(&format!("    {}\n", note));
+                    }
+                }
+            }
+        }
+        for entry in source.doc.archive.iter().filter_map(|line| parser::archive_entry(line)) {
+            if !pattern.is_match(entry.text) {
+                continue;
+            }
+            let origin = match entry.category.zip(entry.project) {
+                Some((category, project)) => format!("{} › {}", category, project),
+                None => source.doc.archive_heading.clone(),
+            };
+            let archived = match entry.date {
+                Some(date) => format!("Archived {}", date),
+                None => "Archived".to_string(),
+            };
+            out.push_str(&format!("{}: {} [{}] {}\n", source.name, origin, archived, entry.text));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::Event;
+    use crate::parser::parse;
+
+    const DOC: &str = "\
+## Work
+
+### 🔶 Launch
+- 🔶 Review copy with @Sarah
+  Focus on pricing
+- 🔴 Book venue
+- ✅ Sign contract with @sarah
+
+### Hiring
+- 🔴 Intro call for @Sarah's referral
+
+## Done
+- ✅ [Work/Hiring] Post job (2026-03-02)
+- ✅ [Work/Launch] Pick a domain (2026-03-01)
+";
+
+    #[test]
+    fn test_person_report() {
+        let out = report(&parse(DOC), &Scope::Person("sarah".to_string()), false).unwrap();
+        assert_eq!(
+            out,
+            "\
+# Report: @sarah
+
+## Open
+
+- 🔶 Review copy with @Sarah (Work › Launch)
+  Focus on pricing
+
+## Waiting
+
+- 🔴 Intro call for @Sarah's referral (Work › Hiring)
+
+## Recently completed
+
+- ✅ Sign contract with @sarah (Work › Launch)
+"
+        );
+        assert!(report(&parse(DOC), &Scope::Person("bob".to_string()), false).is_err());
+    }
+
+    #[test]
+    fn test_project_report_plain() {
+        let out = report(&parse(DOC), &Scope::Project("hiring".to_string()), true).unwrap();
+        assert_eq!(
+            out,
+            "Report: hiring\n\nOpen:\n  (none)\n\nWaiting:\n  - Intro call for @Sarah's referral\n\nRecently completed:\n  (none)\n\nDone history:\n  - Post job (2026-03-02)\n"
+        );
+        assert!(report(&parse(DOC), &Scope::Project("Nope".to_string()), true).is_err());
+    }
+
+    #[test]
+    fn test_weekly_report() {
+        let doc = parse(
+            "## Work\n\n### Launch\n\n## Done\n- ✅ [Work/Launch] Ship it (2026-03-10)\n- ✅ Plan (launch) (2026-03-04)\n- ✅ Old (2026-03-03)\n- ✅ Undated\n",
+        );
+        let log = ActivityLog {
+            events: vec![
+                Event {
+                    date: "2026-03-09".to_string(),
+                    kind: EventKind::Added,
+                    category: "Work".to_string(),
+                    project: "Launch".to_string(),
+                    task: Some("Book venue".to_string()),
+                    state: None,
+                },
+                Event {
+                    date: "2026-03-01".to_string(),
+                    kind: EventKind::Activated,
+                    category: "Work".to_string(),
+                    project: "Launch".to_string(),
+                    task: None,
+                    state: None,
+                },
+            ],
+        };
+        let out = weekly(&doc, &log, Date::from_ymd(2026, 3, 10).unwrap());
+        assert_eq!(
+            out,
+            "\
+# Week of 2026-03-04 to 2026-03-10
+
+## Completed (2)
+
+- Ship it (Work › Launch)
+- Plan (launch)
+
+## Added (1)
+
+- Book venue (Work › Launch)
+
+## Projects activated (0)
+
+_None_
+
+## Projects deactivated (0)
+
+_None_
+"
+        );
+    }
+
+    #[test]
+    fn test_list_formats() {
+        let doc = parse(
+            "## Work\n\n### 🔶 Website redesign due:2026-05-01\n- 🔶 Review copy !1\n  Focus on pricing\n- 🔴 Book venue due:2026-04-02\n- ✅ Pick a domain\n\n### Hiring\n- 🔴 Post job\n",
+        );
+        let query = Query::parse("project:\"website*\" state:todo due<2026-06-01").unwrap();
+        assert_eq!(
+            list(&doc, Some(&query), ListFormat::Markdown),
+            "- 🔴 Book venue due:2026-04-02 (Work › Website redesign)\n"
+        );
+        assert_eq!(
+            list(&doc, Some(&Query::parse("NOT state:done").unwrap()), ListFormat::Table),
+            "\
+State        Task                       Project                  Due
+In Progress  Review copy !1             Work › Website redesign  2026-05-01
+Todo         Book venue due:2026-04-02  Work › Website redesign  2026-04-02
+Todo         Post job                   Work › Hiring
+"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&list(&doc, Some(&Query::parse("!1").unwrap()), ListFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "id": doc.categories[0].projects[0].tasks[0].id(),
+                "category": "Work",
+                "project": "Website redesign",
+                "state": "In Progress",
+                "text": "Review copy !1",
+                "due": "2026-05-01",
+                "priority": 1,
+                "notes": ["Focus on pricing"],
+            }])
+        );
+        assert_eq!(list(&doc, None, ListFormat::Markdown).lines().count(), 4);
+    }
+
+    #[test]
+    fn test_grep() {
+        let work = parse(DOC);
+        let home = parse("## Home\n\n### Chores\n- 🔴 Fix sink\n  Ask about the venue parking\n");
+        let sources = [
+            AgendaSource { name: "work", file: Path::new("/work.md"), doc: &work },
+            AgendaSource { name: "home", file: Path::new("/home.md"), doc: &home },
+        ];
+        let pattern = Regex::new("(?i)venue|PRICING|domain").unwrap();
+        assert_eq!(
+            grep(&sources, &pattern),
+            "\
+work: Work › Launch [In Progress] Review copy with @Sarah
+    Focus on pricing
+work: Work › Launch [Todo] Book venue
+work: Work › Launch [Archived 2026-03-01] Pick a domain
+home: Home › Chores [Todo] Fix sink
+    Ask about the venue parking
+"
+        );
+        assert!(grep(&sources, &Regex::new("nothing").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_global_agenda() {
+        let work = parse(DOC);
+        let home = parse("## Home\n\n### 🔶 Chores\n- 🔴 Mow lawn\n- 🔶 Fix sink\n- 🔵 Water plants\n");
+        let sources = [
+            AgendaSource { name: "work", file: Path::new("/work.md"), doc: &work },
+            AgendaSource { name: "home", file: Path::new("/home.md"), doc: &home },
+        ];
+        let sections = [TaskState::InProgress, TaskState::OnDeck];
+        let items = global_agenda(&sources, &sections, Date::parse("2026-03-10").unwrap());
+        let rows: Vec<(&str, &str)> = items.iter().map(|i| (i.source.as_str(), i.task.text.as_str())).collect();
+        assert_eq!(
+            rows,
+            [("work", "Review copy with @Sarah"), ("home", "Fix sink"), ("home", "Water plants")]
+        );
+        assert_eq!(items[1].file, Path::new("/home.md"));
+        assert_eq!(
+            global_agenda_text(&items, &sections),
+            "\
+# Agenda
+
+## In Progress (2)
+
+- 🔶 Review copy with @Sarah [work] (Work › Launch)
+- 🔶 Fix sink [home] (Home › Chores)
+
+## On Deck (1)
+
+- 🔵 Water plants [home] (Home › Chores)
+"
+        );
+    }
+}
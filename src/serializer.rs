@@ -2,68 +2,159 @@ use crate::model::*;
 
 /// Serialize a Document back to markdown text.
 pub fn serialize(doc: &Document) -> String {
-    let mut lines: Vec<String> = Vec::new();
+    serialize_parts(doc, true)
+}
+
+/// The document without its archive section, for a todo file whose archive
+/// is kept in a file of its own (see `serialize_archive`).
+pub fn serialize_without_archive(doc: &Document) -> String {
+    serialize_parts(doc, false)
+}
+
+/// Only the archive section, under its heading; empty when nothing is archived.
+pub fn serialize_archive(doc: &Document) -> String {
+    let mut out = String::new();
+    push_archive(&mut out, doc);
+    out
+}
+
+fn serialize_parts(doc: &Document, with_archive: bool) -> String {
+    // Written straight into one buffer, one `\n`-terminated line at a time,
+    // rather than collecting a line Vec and joining it.
+    let mut out = String::with_capacity(estimated_len(doc));
+    let category_prefix = doc.levels.category_prefix();
+    let project_prefix = doc.levels.project_prefix();
 
     // Preamble
     for line in &doc.preamble {
-        lines.push(line.clone());
+        push_line(&mut out, line);
     }
 
     // Categories → Projects → Tasks
-    for (_cat_idx, category) in doc.categories.iter().enumerate() {
+    for category in &doc.categories {
         // Blank line before category (unless first thing after preamble)
-        if !lines.is_empty() && !lines.last().is_some_and(|l| l.is_empty()) {
-            lines.push(String::new());
+        if !out.is_empty() && !ends_with_blank_line(&out) {
+            out.push('\n');
         }
 
-        lines.push(format!("## {}", category.name));
+        out.push_str(&category_prefix);
+        out.push_str(category.marker());
+        push_line(&mut out, &category.name);
+        for note in &category.notes {
+            push_line(&mut out, note);
+        }
 
-        for (_proj_idx, project) in category.projects.iter().enumerate() {
-            lines.push(String::new()); // blank line before project
+        for project in &category.projects {
+            out.push('\n'); // blank line before project
 
-            if project.active {
-                lines.push(format!("### 🔶 {}", project.name));
-            } else {
-                lines.push(format!("### {}", project.name));
+            out.push_str(&project_prefix);
+            out.push_str(project.markers());
+            let mut heading = project.name.clone();
+            if let Some(accent) = &project.accent {
+                heading.push_str(&format!(" color:{}", accent));
+            }
+            if let Some(deadline) = project.deadline {
+                heading.push_str(&format!(" due:{}", deadline));
             }
+            push_line(&mut out, &heading);
 
             // Project notes
             for note in &project.notes {
-                lines.push(note.clone());
+                push_line(&mut out, note);
             }
 
             // Tasks
             for task in &project.tasks {
-                lines.push(format!("- {} {}", task.state.symbol(), task.text));
+                out.push_str("- ");
+                out.push_str(task.state.symbol());
+                out.push(' ');
+                push_line(&mut out, &task.text);
                 for note in &task.notes {
-                    lines.push(note.clone());
+                    push_line(&mut out, note);
                 }
             }
         }
     }
 
-    // Archive section
-    if !doc.archive.is_empty() {
-        if !lines.is_empty() {
-            lines.push(String::new());
+    // Retired projects
+    if !doc.done_projects.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
         }
-        lines.push("## Done".to_string());
-        for line in &doc.archive {
-            lines.push(line.clone());
+        out.push_str(&category_prefix);
+        push_line(&mut out, DONE_PROJECTS_HEADING);
+        for line in &doc.done_projects {
+            push_line(&mut out, line);
         }
     }
 
+    if with_archive {
+        push_archive(&mut out, doc);
+    }
+
     // Trailing
     for line in &doc.trailing {
-        lines.push(line.clone());
+        push_line(&mut out, line);
     }
 
-    let mut result = lines.join("\n");
+    // A trailing blank line only contributes the final newline
+    if out.ends_with("\n\n") {
+        out.pop();
+    }
     // Ensure file ends with newline
-    if !result.ends_with('\n') {
-        result.push('\n');
+    if out.is_empty() {
+        out.push('\n');
     }
-    result
+    out
+}
+
+fn push_archive(out: &mut String, doc: &Document) {
+    // An archive unchanged since it was read is written back as read,
+    // without splitting it into lines
+    if doc.archive.is_empty() {
+        return;
+    }
+    let text = doc.archive.text();
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&doc.levels.category_prefix());
+    push_line(out, &doc.archive_heading);
+    match text {
+        Some(text) => push_line(out, text),
+        None => {
+            for line in &doc.archive {
+                push_line(out, line);
+            }
+        }
+    }
+}
+
+fn push_line(out: &mut String, line: &str) {
+    out.push_str(line);
+    out.push('\n');
+}
+
+/// Whether the last line written was empty.
+fn ends_with_blank_line(out: &str) -> bool {
+    out == "\n" || out.ends_with("\n\n")
+}
+
+/// Rough output size, so large files serialize without repeated regrowth.
+fn estimated_len(doc: &Document) -> usize {
+    let lines = |v: &[String]| v.iter().map(|l| l.len() + 1).sum::<usize>();
+    let archive = doc.archive.text().map_or_else(|| lines(&doc.archive), |text| text.len() + 1);
+    let mut len = lines(&doc.preamble) + archive + lines(&doc.done_projects) + lines(&doc.trailing) + 16;
+    for category in &doc.categories {
+        len += category.name.len() + 5 + lines(&category.notes);
+        for project in &category.projects {
+            len += project.name.len() + 12 + lines(&project.notes);
+            for task in &project.tasks {
+                len += task.text.len() + 8 + lines(&task.notes);
+            }
+        }
+    }
+    len
 }
 
 #[cfg(test)]
@@ -145,4 +236,44 @@ Some notes here.
         assert_eq!(doc.categories[0].projects[0].tasks[0].notes, doc2.categories[0].projects[0].tasks[0].notes);
         assert_eq!(doc, doc2);
     }
+
+    #[test]
+    fn test_roundtrip_project_accent() {
+        let input = "## Work\n\n### Launch due:2026-04-01 color:magenta\n- 🔴 Task\n\n### Plan color:#ff8800\n\n### Fix color:mauve\n";
+        let doc = parse(input);
+        let projects = &doc.categories[0].projects;
+        assert_eq!(projects[0].name, "Launch");
+        assert_eq!(projects[0].accent.as_deref(), Some("magenta"));
+        assert!(projects[0].deadline.is_some());
+        assert_eq!(projects[1].accent.as_deref(), Some("#ff8800"));
+        // Not a color, so it stays part of the name
+        assert_eq!(projects[2].name, "Fix color:mauve");
+        assert_eq!(projects[2].accent, None);
+
+        let output = serialize(&doc);
+        assert!(output.contains("### Launch color:magenta due:2026-04-01\n"));
+        assert_eq!(parse(&output), doc);
+    }
+
+    #[test]
+    fn test_roundtrip_project_deadline() {
+        let input = "## Work\n\n### 🔶 📋 Launch due:2026-04-01\n- 🔴 Task\n\n### Plan due:soon\n";
+        let doc = parse(input);
+        let launch = &doc.categories[0].projects[0];
+        assert_eq!(launch.name, "Launch");
+        assert!(launch.checklist);
+        assert_eq!(launch.deadline.map(|d| d.to_string()).as_deref(), Some("2026-04-01"));
+        // Not a date, so it stays part of the name
+        assert_eq!(doc.categories[0].projects[1].name, "Plan due:soon");
+        assert_eq!(serialize(&doc), input);
+    }
+
+    #[test]
+    fn test_serialize_archive_apart() {
+        let doc = parse("## Work\n\n### Launch\n- 🔴 Ship it\n\n## Done\n- ✅ [Work/Launch] Plan it (2026-03-10)\n");
+        assert_eq!(serialize_without_archive(&doc), "## Work\n\n### Launch\n- 🔴 Ship it\n");
+        assert_eq!(serialize_archive(&doc), "## Done\n- ✅ [Work/Launch] Plan it (2026-03-10)\n");
+        assert_eq!(parse(&serialize_archive(&doc)).archive, doc.archive);
+        assert_eq!(serialize_archive(&parse("## Work\n")), "");
+    }
 }
@@ -0,0 +1,537 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, SettingsRow, View};
+use crate::date::Date;
+use crate::model::*;
+use crate::theme::Theme;
+
+/// Per-file UI session, stored next to the todo file as `<name>.session.toml`.
+/// Nodes are referenced by name so the session survives reordering.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub theme: String,
+    pub view: View,
+    pub hide_done: bool,
+    pub show_todo: bool,
+    pub collapsed: CollapsedNodes,
+    /// Agenda tasks buried with `b`, in burial order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub buried: Vec<TaskRef>,
+    /// Day the inbox was last triaged to the end, `YYYY-MM-DD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_review: Option<String>,
+    /// Day the file was last open, `YYYY-MM-DD`, for the new-day routine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_opened: Option<String>,
+    pub cursors: Cursors,
+}
+
+/// Where each view's cursor was, by name, so it can be found again after edits.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cursors {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agenda: Option<TaskRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backlog: Option<NodeRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<String>,
+}
+
+/// A category, project or task in the backlog tree, addressed by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeRef {
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CollapsedNodes {
+    pub preamble: bool,
+    pub categories: Vec<String>,
+    pub projects: Vec<ProjectRef>,
+    pub tasks: Vec<TaskRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ProjectRef {
+    pub category: String,
+    pub project: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TaskRef {
+    pub category: String,
+    pub project: String,
+    pub task: String,
+}
+
+impl Session {
+    /// Capture the persistent parts of the app's UI state.
+    pub fn capture(app: &App) -> Self {
+        Self {
+            theme: app.theme().name.to_string(),
+            view: app.view,
+            hide_done: app.hide_done,
+            show_todo: app.show_todo,
+            collapsed: CollapsedNodes::capture(&app.collapse, &app.doc),
+            // Forget tasks that were since renamed, moved or deleted
+            buried: app.buried.iter().filter(|r| find_task(&app.doc, r).is_some()).cloned().collect(),
+            last_review: app.last_review.map(|date| date.to_string()),
+            last_opened: Some(Date::today().to_string()),
+            cursors: Cursors::capture(app),
+        }
+    }
+
+    /// Apply the session to a freshly loaded app.
+    pub fn apply(&self, app: &mut App) {
+        app.theme_index = Theme::by_name(&self.theme);
+        app.collapse = self.collapsed.resolve(&app.doc);
+        app.show_todo = self.show_todo;
+        app.buried = self.buried.clone();
+        app.set_hide_done(self.hide_done);
+        app.view = self.view;
+        self.cursors.apply(app);
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).context("Failed to parse session file")
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+impl CollapsedNodes {
+    /// Convert index-keyed collapse state into name references.
+    pub fn capture(collapse: &CollapseState, doc: &Document) -> Self {
+        let mut nodes = Self {
+            preamble: collapse.preamble_collapsed,
+            ..Self::default()
+        };
+        for &ci in &collapse.collapsed_categories {
+            if let Some(cat) = doc.categories.get(ci) {
+                nodes.categories.push(cat.name.clone());
+            }
+        }
+        for &(ci, pi) in &collapse.collapsed_projects {
+            if let Some(cat) = doc.categories.get(ci)
+                && let Some(proj) = cat.projects.get(pi)
+            {
+                nodes.projects.push(ProjectRef {
+                    category: cat.name.clone(),
+                    project: proj.name.clone(),
+                });
+            }
+        }
+        for &(ci, pi, ti) in &collapse.collapsed_tasks {
+            if let Some(cat) = doc.categories.get(ci)
+                && let Some(proj) = cat.projects.get(pi)
+                && let Some(task) = proj.tasks.get(ti)
+            {
+                nodes.tasks.push(TaskRef {
+                    category: cat.name.clone(),
+                    project: proj.name.clone(),
+                    task: task.text.clone(),
+                });
+            }
+        }
+        nodes.categories.sort();
+        nodes.projects.sort();
+        nodes.tasks.sort();
+        nodes
+    }
+
+    /// Resolve name references against `doc`. Names that no longer exist are dropped.
+    pub fn resolve(&self, doc: &Document) -> CollapseState {
+        let mut state = CollapseState::new();
+        state.preamble_collapsed = self.preamble;
+        for name in &self.categories {
+            if let Some(ci) = find_category(doc, name) {
+                state.collapsed_categories.insert(ci);
+            }
+        }
+        for r in &self.projects {
+            if let Some(key) = find_project(doc, &r.category, &r.project) {
+                state.collapsed_projects.insert(key);
+            }
+        }
+        for r in &self.tasks {
+            if let Some(key) = find_task(doc, r) {
+                state.collapsed_tasks.insert(key);
+            }
+        }
+        state
+    }
+}
+
+impl Cursors {
+    pub fn capture(app: &App) -> Self {
+        let doc = &app.doc;
+        let agenda = app
+            .agenda_items
+            .get(app.agenda_cursor)
+            .and_then(|item| task_ref(doc, item.category_idx, item.project_idx, item.task_idx));
+
+        let backlog = app.current_tree_node().and_then(|node| {
+            let (ci, pi, ti) = match node.kind {
+                TreeNodeKind::Category { cat_idx } => (cat_idx, None, None),
+                TreeNodeKind::Project { cat_idx, proj_idx } => (cat_idx, Some(proj_idx), None),
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                | TreeNodeKind::Note { cat_idx, proj_idx, task_idx, .. } => (cat_idx, Some(proj_idx), Some(task_idx)),
+                TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => return None,
+            };
+            let cat = doc.categories.get(ci)?;
+            let proj = pi.and_then(|pi| cat.projects.get(pi));
+            Some(NodeRef {
+                category: cat.name.clone(),
+                project: proj.map(|p| p.name.clone()),
+                task: ti.and_then(|ti| proj?.tasks.get(ti)).map(|t| t.text.clone()),
+            })
+        });
+
+        let settings = app
+            .settings_category_idx()
+            .and_then(|ci| doc.categories.get(ci))
+            .map(|c| c.name.clone());
+
+        Self { agenda, backlog, settings }
+    }
+
+    /// Move each view's cursor back onto the referenced item, if it still exists.
+    pub fn apply(&self, app: &mut App) {
+        if let Some(r) = &self.agenda
+            && let Some(addr) = find_task(&app.doc, r)
+            && let Some(i) = app
+                .agenda_items
+                .iter()
+                .position(|item| (item.category_idx, item.project_idx, item.task_idx) == addr)
+        {
+            app.agenda_cursor = i;
+        }
+
+        if let Some(r) = &self.backlog {
+            let kind = match (&r.project, &r.task) {
+                (Some(project), Some(task)) => find_task(
+                    &app.doc,
+                    &TaskRef { category: r.category.clone(), project: project.clone(), task: task.clone() },
+                )
+                .map(|(cat_idx, proj_idx, task_idx)| TreeNodeKind::Task { cat_idx, proj_idx, task_idx }),
+                (Some(project), None) => find_project(&app.doc, &r.category, project)
+                    .map(|(cat_idx, proj_idx)| TreeNodeKind::Project { cat_idx, proj_idx }),
+                _ => find_category(&app.doc, &r.category).map(|cat_idx| TreeNodeKind::Category { cat_idx }),
+            };
+            if let Some(kind) = kind {
+                app.restore_cursor_or_ancestor(&kind);
+            }
+        }
+
+        if let Some(name) = &self.settings
+            && let Some(ci) = find_category(&app.doc, name)
+        {
+            app.settings_cursor = app.settings_row_index(SettingsRow::Category(ci));
+        }
+    }
+}
+
+fn task_ref(doc: &Document, ci: usize, pi: usize, ti: usize) -> Option<TaskRef> {
+    let cat = doc.categories.get(ci)?;
+    let proj = cat.projects.get(pi)?;
+    let task = proj.tasks.get(ti)?;
+    Some(TaskRef {
+        category: cat.name.clone(),
+        project: proj.name.clone(),
+        task: task.text.clone(),
+    })
+}
+
+fn find_task(doc: &Document, r: &TaskRef) -> Option<(usize, usize, usize)> {
+    let (ci, pi) = find_project(doc, &r.category, &r.project)?;
+    let ti = doc.categories[ci].projects[pi].tasks.iter().position(|t| t.text == r.task)?;
+    Some((ci, pi, ti))
+}
+
+fn find_category(doc: &Document, name: &str) -> Option<usize> {
+    doc.categories.iter().position(|c| c.name == name)
+}
+
+fn find_project(doc: &Document, cat_name: &str, proj_name: &str) -> Option<(usize, usize)> {
+    let ci = find_category(doc, cat_name)?;
+    let pi = doc.categories[ci].projects.iter().position(|p| p.name == proj_name)?;
+    Some((ci, pi))
+}
+
+/// How many recently opened files are remembered.
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Todo files opened lately, most recent first. Shared by every file, in
+/// `recent.toml` in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentFiles {
+    pub files: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Move `path` to the front, forgetting the oldest beyond the limit.
+    pub fn touch(&mut self, path: &Path) {
+        self.files.retain(|p| p != path);
+        self.files.insert(0, path.to_path_buf());
+        self.files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Missing or unreadable files yield an empty list.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create data directory")?;
+        }
+        let content = toml::to_string_pretty(self).unwrap_or_default();
+        fs::write(path, content).context("Failed to write recent files")
+    }
+}
+
+pub fn session_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("session.toml")
+}
+
+fn legacy_state_path(file_path: &Path) -> PathBuf {
+    file_path.with_extension("state")
+}
+
+/// Load the session for a todo file, migrating a legacy `.state` file if no
+/// session file exists yet. Missing or unreadable files yield the default session.
+pub fn load(file_path: &Path, doc: &Document) -> Session {
+    if let Ok(content) = fs::read_to_string(session_path(file_path)) {
+        return Session::from_toml(&content).unwrap_or_default();
+    }
+    if let Ok(content) = fs::read_to_string(legacy_state_path(file_path)) {
+        return migrate_legacy_state(&content, doc);
+    }
+    Session::default()
+}
+
+pub fn save(file_path: &Path, session: &Session) -> Result<()> {
+    fs::write(session_path(file_path), session.to_toml()).context("Failed to write session file")
+}
+
+/// Read the old line-based `.state` format. Version 2 files key nodes by name
+/// (tab-separated); older files key them by index (`cat:0`, `proj:0,1`, `task:0,1,2`).
+fn migrate_legacy_state(content: &str, doc: &Document) -> Session {
+    let by_name = content.lines().any(|l| l.trim() == "version:2");
+    let mut session = Session::default();
+    let mut collapse = CollapseState::new();
+
+    for line in content.lines() {
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix("theme:") {
+            session.theme = rest.to_string();
+        } else if line == "preamble" {
+            collapse.preamble_collapsed = true;
+        } else if let Some(rest) = line.strip_prefix("cat:") {
+            if by_name {
+                session.collapsed.categories.push(rest.to_string());
+            } else if let Ok(idx) = rest.trim().parse() {
+                collapse.collapsed_categories.insert(idx);
+            }
+        } else if let Some(rest) = line.strip_prefix("proj:") {
+            if by_name {
+                if let Some((category, project)) = rest.split_once('\t') {
+                    session.collapsed.projects.push(ProjectRef {
+                        category: category.to_string(),
+                        project: project.to_string(),
+                    });
+                }
+            } else {
+                let parts: Vec<&str> = rest.trim().split(',').collect();
+                if parts.len() == 2
+                    && let (Ok(ci), Ok(pi)) = (parts[0].parse(), parts[1].parse())
+                {
+                    collapse.collapsed_projects.insert((ci, pi));
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("task:") {
+            if by_name {
+                let parts: Vec<&str> = rest.splitn(3, '\t').collect();
+                if parts.len() == 3 {
+                    session.collapsed.tasks.push(TaskRef {
+                        category: parts[0].to_string(),
+                        project: parts[1].to_string(),
+                        task: parts[2].to_string(),
+                    });
+                }
+            } else {
+                let parts: Vec<&str> = rest.trim().split(',').collect();
+                if parts.len() == 3
+                    && let (Ok(ci), Ok(pi), Ok(ti)) = (parts[0].parse(), parts[1].parse(), parts[2].parse())
+                {
+                    collapse.collapsed_tasks.insert((ci, pi, ti));
+                }
+            }
+        }
+    }
+
+    if by_name {
+        session.collapsed.preamble = collapse.preamble_collapsed;
+    } else {
+        session.collapsed = CollapsedNodes::capture(&collapse, doc);
+    }
+    session
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::engine;
+    use std::collections::HashSet;
+
+    fn sample_doc() -> Document {
+        parse(
+            "\
+## Work
+
+### 🔶 Alpha
+- 🔴 First
+  A note
+
+### Beta
+- 🔴 Second
+
+## Home
+
+### 🔶 Chores
+- 🔴 Dishes
+",
+        )
+    }
+
+    #[test]
+    fn test_collapsed_nodes_survive_reordering() {
+        let doc = sample_doc();
+        let mut collapse = CollapseState::new();
+        collapse.collapsed_categories.insert(1);
+        collapse.collapsed_projects.insert((0, 1));
+        collapse.collapsed_tasks.insert((0, 0, 0));
+        let nodes = CollapsedNodes::capture(&collapse, &doc);
+
+        let mut reordered = doc.clone();
+        reordered.categories.swap(0, 1);
+        reordered.categories[1].projects.swap(0, 1);
+        let restored = nodes.resolve(&reordered);
+        assert_eq!(restored.collapsed_categories, HashSet::from([0]));
+        assert_eq!(restored.collapsed_projects, HashSet::from([(1, 0)]));
+        assert_eq!(restored.collapsed_tasks, HashSet::from([(1, 1, 0)]));
+    }
+
+    #[test]
+    fn test_session_toml_roundtrip() {
+        let session = Session {
+            theme: "Nord".to_string(),
+            view: View::Backlog,
+            hide_done: true,
+            show_todo: true,
+            collapsed: CollapsedNodes {
+                preamble: true,
+                categories: vec!["Home".to_string()],
+                projects: vec![ProjectRef { category: "Work".to_string(), project: "Beta".to_string() }],
+                tasks: Vec::new(),
+            },
+            buried: vec![TaskRef { category: "Work".to_string(), project: "Beta".to_string(), task: "Second".to_string() }],
+            last_review: Some("2026-03-09".to_string()),
+            last_opened: Some("2026-03-10".to_string()),
+            cursors: Cursors {
+                agenda: None,
+                backlog: Some(NodeRef { category: "Work".to_string(), project: Some("Beta".to_string()), task: None }),
+                settings: Some("Home".to_string()),
+            },
+        };
+        let restored = Session::from_toml(&session.to_toml()).unwrap();
+        assert_eq!(restored, session);
+
+        // Unknown or missing keys fall back to defaults
+        let partial = Session::from_toml("theme = \"Nord\"\n").unwrap();
+        assert_eq!(partial.theme, "Nord");
+        assert!(partial.collapsed.categories.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_legacy_index_state() {
+        let doc = sample_doc();
+        let session = migrate_legacy_state("theme:Nord\ncat:1\nproj:0,1\ntask:0,0,0\n", &doc);
+        assert_eq!(session.theme, "Nord");
+        assert_eq!(session.collapsed.categories, ["Home"]);
+        assert_eq!(session.collapsed.projects, [ProjectRef { category: "Work".to_string(), project: "Beta".to_string() }]);
+        assert_eq!(session.collapsed.tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_legacy_named_state() {
+        let doc = sample_doc();
+        let session = migrate_legacy_state("version:2\ntheme:Nord\npreamble\ncat:Home\nproj:Work\tBeta\n", &doc);
+        assert!(session.collapsed.preamble);
+        assert_eq!(session.collapsed.categories, ["Home"]);
+        assert_eq!(session.collapsed.projects.len(), 1);
+    }
+
+    #[test]
+    fn test_restore_view_and_cursors() {
+        let loaded = || {
+            let mut doc = sample_doc();
+            engine::auto_promote(&mut doc, engine::PromoteStrategy::default());
+            doc
+        };
+        let mut app = App::new(loaded(), PathBuf::from("/tmp/test.md"));
+        app.view = View::Backlog;
+        app.agenda_cursor = app.agenda_items.iter().position(|i| i.task.text == "Dishes").unwrap();
+        app.backlog_cursor = app.tree_nodes.iter().position(|n| n.display == "Second").unwrap();
+        app.settings_cursor = app.settings_row_index(SettingsRow::Category(1));
+        let session = Session::capture(&app);
+
+        // Reorder categories so index-based restore would land elsewhere
+        let mut doc = loaded();
+        doc.categories.swap(0, 1);
+        let mut restored = App::new(doc, PathBuf::from("/tmp/test.md"));
+        session.apply(&mut restored);
+
+        assert_eq!(restored.view, View::Backlog);
+        assert_eq!(restored.agenda_items[restored.agenda_cursor].task.text, "Dishes");
+        assert_eq!(restored.current_tree_node().unwrap().display, "Second");
+        assert_eq!(restored.settings_category_idx(), Some(0));
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let mut recent = RecentFiles::default();
+        for i in 0..12 {
+            recent.touch(Path::new(&format!("/notes/{}.md", i)));
+        }
+        recent.touch(Path::new("/notes/5.md"));
+        assert_eq!(recent.files.len(), MAX_RECENT_FILES);
+        assert_eq!(recent.files[0], Path::new("/notes/5.md"));
+        assert_eq!(recent.files[1], Path::new("/notes/11.md"));
+        assert!(!recent.files.contains(&PathBuf::from("/notes/1.md")));
+
+        let path = std::env::temp_dir().join(format!("gws-recent-{}/recent.toml", std::process::id()));
+        recent.save(&path).unwrap();
+        assert_eq!(RecentFiles::load(&path), recent);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(RecentFiles::load(&path), RecentFiles::default());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::activity::{ActivityLog, EventKind};
+use crate::date::Date;
+use crate::engine;
+use crate::model::{Document, TaskState};
+use crate::parser;
+
+/// Day-by-day pace over a window ending today, oldest day first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Throughput {
+    pub first_day: Date,
+    /// Tasks archived on each day.
+    pub completed: Vec<u64>,
+    /// Open (not done) tasks at the end of each day.
+    pub open: Vec<u64>,
+}
+
+impl Throughput {
+    /// Completions per seven-day week, oldest first; the last week ends today.
+    pub fn weekly(&self) -> Vec<u64> {
+        let mut weeks: Vec<u64> = self.completed.rchunks(7).map(|w| w.iter().sum()).collect();
+        weeks.reverse();
+        weeks
+    }
+}
+
+/// Completions come from archive dates. The open count is worked backwards
+/// from today's: each day adds back the tasks archived after it and removes
+/// the tasks the activity log says were added after it, so days before the
+/// log began are an estimate.
+pub fn throughput(doc: &Document, log: &ActivityLog, today: Date, days: usize) -> Throughput {
+    let first_day = today.minus_days(days as i64 - 1);
+    let index = |date: Date| usize::try_from(date.days_since(first_day)).ok().filter(|&i| i < days);
+
+    let mut completed = vec![0; days];
+    for (_, date) in doc.archive.iter().filter_map(|line| engine::archived(line)) {
+        if let Some(i) = index(date) {
+            completed[i] += 1;
+        }
+    }
+    let mut added = vec![0i64; days];
+    for event in log.since(first_day).filter(|e| e.kind == EventKind::Added) {
+        if let Some(i) = Date::parse(&event.date).and_then(index) {
+            added[i] += 1;
+        }
+    }
+
+    let open_now = doc
+        .categories
+        .iter()
+        .flat_map(|c| &c.projects)
+        .flat_map(|p| &p.tasks)
+        .filter(|t| t.state != TaskState::Done)
+        .count() as i64;
+    let mut open = vec![0; days];
+    let mut count = open_now;
+    for i in (0..days).rev() {
+        open[i] = count.max(0) as u64;
+        // Step back to the end of the previous day
+        count += completed[i] as i64 - added[i];
+    }
+
+    Throughput { first_day, completed, open }
+}
+
+/// Average days from added to done for the tasks of one project or category.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleTime {
+    pub category: String,
+    /// None for the category-wide row.
+    pub project: Option<String>,
+    pub tasks: usize,
+    pub average_days: f64,
+    /// The project's tasks take much longer than the average across projects.
+    pub slow: bool,
+}
+
+/// A project is slow when its average is this many times the overall one.
+const SLOW_FACTOR: f64 = 2.0;
+
+/// Cycle times from the activity log, for tasks whose adding and completion
+/// were both logged (archived tasks included). Each category row is followed
+/// by its project rows; categories and projects keep first-seen order.
+pub fn cycle_times(log: &ActivityLog) -> Vec<CycleTime> {
+    // (category, project, task), added, done
+    type TaskKey<'a> = (&'a str, &'a str, &'a str);
+    let mut spans: Vec<(TaskKey, Option<Date>, Option<Date>)> = Vec::new();
+    for event in &log.events {
+        let (Some(task), Some(date)) = (event.task.as_deref(), Date::parse(&event.date)) else {
+            continue;
+        };
+        let key = (event.category.as_str(), event.project.as_str(), task);
+        let idx = match spans.iter().position(|(k, _, _)| *k == key) {
+            Some(idx) => idx,
+            None => {
+                spans.push((key, None, None));
+                spans.len() - 1
+            }
+        };
+        match (event.kind, event.state) {
+            (EventKind::Added, _) => spans[idx].1 = Some(date),
+            (EventKind::Changed, Some(TaskState::Done)) => spans[idx].2 = Some(date),
+            (EventKind::Changed, Some(_)) => spans[idx].2 = None,
+            _ => {}
+        }
+    }
+    let finished: Vec<(TaskKey, i64)> = spans
+        .into_iter()
+        .filter_map(|(key, added, done)| Some((key, done?.days_since(added?))))
+        .collect();
+    if finished.is_empty() {
+        return Vec::new();
+    }
+    let average = |days: &[i64]| days.iter().sum::<i64>() as f64 / days.len() as f64;
+    let overall = average(&finished.iter().map(|(_, d)| *d).collect::<Vec<_>>());
+
+    let mut rows = Vec::new();
+    let mut categories: Vec<&str> = Vec::new();
+    for ((category, _, _), _) in &finished {
+        if !categories.contains(category) {
+            categories.push(category);
+        }
+    }
+    for category in categories {
+        let in_category: Vec<_> = finished.iter().filter(|((c, _, _), _)| *c == category).collect();
+        let days: Vec<i64> = in_category.iter().map(|(_, d)| *d).collect();
+        rows.push(CycleTime {
+            category: category.to_string(),
+            project: None,
+            tasks: days.len(),
+            average_days: average(&days),
+            slow: false,
+        });
+        let mut projects: Vec<&str> = Vec::new();
+        for ((_, project, _), _) in &in_category {
+            if !projects.contains(project) {
+                projects.push(project);
+            }
+        }
+        for project in projects {
+            let days: Vec<i64> = in_category.iter().filter(|((_, p, _), _)| *p == project).map(|(_, d)| *d).collect();
+            let average_days = average(&days);
+            rows.push(CycleTime {
+                category: category.to_string(),
+                project: Some(project.to_string()),
+                tasks: days.len(),
+                average_days,
+                slow: average_days > overall * SLOW_FACTOR && average_days >= 1.0,
+            });
+        }
+    }
+    rows
+}
+
+/// Tasks archived from one project since a given day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectDone {
+    pub category: String,
+    pub project: String,
+    pub completed: usize,
+}
+
+/// Archived tasks per project from `since` on, most first (ties in
+/// first-seen order). Only archive lines that record their project count.
+pub fn completed_by_project(doc: &Document, since: Date) -> Vec<ProjectDone> {
+    let mut rows: Vec<ProjectDone> = Vec::new();
+    for entry in doc.archive.iter().filter_map(|line| parser::archive_entry(line)) {
+        let (Some(category), Some(project), Some(date)) = (entry.category, entry.project, entry.date) else {
+            continue;
+        };
+        if date < since {
+            continue;
+        }
+        match rows.iter_mut().find(|r| r.category == category && r.project == project) {
+            Some(row) => row.completed += 1,
+            None => rows.push(ProjectDone {
+                category: category.to_string(),
+                project: project.to_string(),
+                completed: 1,
+            }),
+        }
+    }
+    rows.sort_by_key(|r| std::cmp::Reverse(r.completed));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::Event;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_throughput() {
+        let doc = parse(
+            "## Work\n\n### Launch\n- 🔴 Write copy\n- 🔴 Book venue\n\n## Done\n- ✅ Ship it (2026-03-10)\n- ✅ Plan (2026-03-09)\n- ✅ Draft (2026-03-09)\n- ✅ Old (2026-01-01)\n",
+        );
+        let log = ActivityLog {
+            events: vec![Event {
+                date: "2026-03-10".to_string(),
+                kind: EventKind::Added,
+                category: "Work".to_string(),
+                project: "Launch".to_string(),
+                task: Some("Book venue".to_string()),
+                state: None,
+            }],
+        };
+        let today = Date::from_ymd(2026, 3, 10).unwrap();
+        let stats = throughput(&doc, &log, today, 8);
+        assert_eq!(stats.first_day.to_string(), "2026-03-03");
+        assert_eq!(stats.completed, [0, 0, 0, 0, 0, 0, 2, 1]);
+        assert_eq!(stats.open, [4, 4, 4, 4, 4, 4, 2, 2]);
+        assert_eq!(stats.weekly(), [0, 3]);
+    }
+
+    #[test]
+    fn test_cycle_times() {
+        let event = |date: &str, kind, project: &str, task: &str, state| Event {
+            date: date.to_string(),
+            kind,
+            category: "Work".to_string(),
+            project: project.to_string(),
+            task: Some(task.to_string()),
+            state,
+        };
+        let log = ActivityLog {
+            events: vec![
+                event("2026-03-01", EventKind::Added, "Launch", "A", None),
+                event("2026-03-02", EventKind::Changed, "Launch", "A", Some(TaskState::Done)),
+                event("2026-03-01", EventKind::Added, "Launch", "B", None),
+                event("2026-03-03", EventKind::Changed, "Launch", "B", Some(TaskState::Done)),
+                event("2026-03-01", EventKind::Added, "Taxes", "C", None),
+                event("2026-03-13", EventKind::Changed, "Taxes", "C", Some(TaskState::Done)),
+                // Never finished, so not counted
+                event("2026-03-01", EventKind::Added, "Taxes", "D", None),
+            ],
+        };
+        let rows = cycle_times(&log);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|r| (r.project.as_deref(), r.tasks, r.average_days, r.slow))
+            .collect();
+        assert_eq!(
+            summary,
+            [(None, 3, 5.0, false), (Some("Launch"), 2, 1.5, false), (Some("Taxes"), 1, 12.0, true)]
+        );
+    }
+
+    #[test]
+    fn test_completed_by_project() {
+        let doc = parse(
+            "## Done\n- ✅ [Work/Launch] Ship it (2026-03-10)\n- ✅ [Home/Chores] Fix sink (2026-03-09)\n- ✅ [Work/Launch] Plan (2026-03-08)\n- ✅ [Work/Launch] Too old (2026-01-01)\n- ✅ Unattributed (2026-03-09)\n",
+        );
+        let rows = completed_by_project(&doc, Date::from_ymd(2026, 3, 1).unwrap());
+        let counts: Vec<(&str, usize)> = rows.iter().map(|r| (r.project.as_str(), r.completed)).collect();
+        assert_eq!(counts, [("Launch", 2), ("Chores", 1)]);
+    }
+}
@@ -0,0 +1,210 @@
+//! Durable file replacement for saving the document, alone or together
+//! with a second file.
+
+use std::fs::{self, File, Permissions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Where `path` really lives, with symlinks followed, even to a target not
+/// created yet. Paths that can't be resolved are returned as they are.
+pub fn resolve(path: &Path) -> PathBuf {
+    if let Ok(real) = fs::canonicalize(path) {
+        return real;
+    }
+    // A dangling link: follow it by hand, giving up on long chains and loops
+    let mut path = path.to_path_buf();
+    for _ in 0..8 {
+        let Ok(target) = fs::read_link(&path) else {
+            break;
+        };
+        path = match path.parent() {
+            Some(dir) => dir.join(target),
+            None => target,
+        };
+    }
+    path
+}
+
+/// Replace `path` with `content` so a crash leaves either the old file or the
+/// new one, never a partial or empty one. The new content goes to a temp file
+/// in the same directory (a rename across filesystems is not atomic), which is
+/// flushed to disk and given the old file's permissions before the rename;
+/// the directory is then flushed so the rename itself survives a crash.
+/// A symlink is written through: its target is replaced and the link kept.
+pub fn save_atomic(path: &Path, content: &str) -> Result<()> {
+    Staged::write(path, content)?.commit()
+}
+
+/// Replace two files so that either both change or neither does, as far as
+/// the filesystem allows: both new versions are flushed to disk first, and
+/// if the second can't be put in place the first is restored.
+pub fn save_both(first: (&Path, &str), second: (&Path, &str)) -> Result<()> {
+    let previous = fs::read_to_string(resolve(first.0)).ok();
+    let staged_first = Staged::write(first.0, first.1)?;
+    let staged_second = match Staged::write(second.0, second.1) {
+        Ok(staged) => staged,
+        Err(e) => {
+            staged_first.discard();
+            return Err(e);
+        }
+    };
+    let first_path = staged_first.path.clone();
+    if let Err(e) = staged_first.commit() {
+        staged_second.discard();
+        return Err(e);
+    }
+    staged_second.commit().inspect_err(|_| {
+        let _ = match &previous {
+            Some(content) => save_atomic(&first_path, content),
+            None => fs::remove_file(&first_path).map_err(Into::into),
+        };
+    })
+}
+
+/// New content flushed to a temp file next to the file it replaces.
+struct Staged {
+    path: PathBuf,
+    tmp_path: PathBuf,
+}
+
+impl Staged {
+    fn write(path: &Path, content: &str) -> Result<Self> {
+        let path = resolve(path);
+        let name = path.file_name().context("No file name to save to")?;
+        let tmp_path = dir_of(&path).join(format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+        let permissions = fs::metadata(&path).ok().map(|m| m.permissions());
+        let staged = Self { path, tmp_path };
+        match write_synced(&staged.tmp_path, content, permissions) {
+            Ok(()) => Ok(staged),
+            Err(e) => {
+                staged.discard();
+                Err(e)
+            }
+        }
+    }
+
+    /// Rename the temp file over the target and flush the directory.
+    fn commit(self) -> Result<()> {
+        if let Err(e) = fs::rename(&self.tmp_path, &self.path) {
+            self.discard();
+            return Err(e).context("Failed to replace the file");
+        }
+        sync_dir(dir_of(&self.path))
+    }
+
+    fn discard(self) {
+        let _ = fs::remove_file(&self.tmp_path);
+    }
+}
+
+fn dir_of(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn write_synced(path: &Path, content: &str, permissions: Option<Permissions>) -> Result<()> {
+    let mut file = File::create(path).context("Failed to write temp file")?;
+    file.write_all(content.as_bytes()).context("Failed to write temp file")?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions).context("Failed to copy file permissions")?;
+    }
+    file.sync_all().context("Failed to flush temp file to disk")
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .context("Failed to flush directory to disk")
+}
+
+/// Directories can't be opened for flushing on Windows; the rename is as
+/// durable as it gets there.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_atomic() {
+        let dir = std::env::temp_dir().join(format!("gws-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.md");
+
+        save_atomic(&path, "## Work\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "## Work\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, Permissions::from_mode(0o600)).unwrap();
+            save_atomic(&path, "## Home\n").unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        save_atomic(&path, "## Home\n- 🔴 Fix sink\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "## Home\n- 🔴 Fix sink\n");
+        // No temp files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(save_atomic(&dir.join("missing/todo.md"), "## Work\n").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_both() {
+        let dir = std::env::temp_dir().join(format!("gws-save-both-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (work, home) = (dir.join("work.md"), dir.join("home.md"));
+        fs::write(&work, "## Work\n").unwrap();
+
+        save_both((&home, "## Home\n"), (&work, "## Work\n- 🔴 Call\n")).unwrap();
+        assert_eq!(fs::read_to_string(&home).unwrap(), "## Home\n");
+        assert_eq!(fs::read_to_string(&work).unwrap(), "## Work\n- 🔴 Call\n");
+
+        // The second can't be written: the first keeps its old content
+        let missing = dir.join("missing/todo.md");
+        assert!(save_both((&home, "## Changed\n"), (&missing, "## Lost\n")).is_err());
+        assert_eq!(fs::read_to_string(&home).unwrap(), "## Home\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_through_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("gws-symlink-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sync")).unwrap();
+        let target = dir.join("sync/todo.md");
+        let link = dir.join("todo.md");
+        fs::write(&target, "## Work\n").unwrap();
+        symlink("sync/todo.md", &link).unwrap();
+        assert_eq!(resolve(&link), fs::canonicalize(&target).unwrap());
+
+        save_atomic(&link, "## Home\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "## Home\n");
+
+        // A link to a file not created yet is followed too
+        let dangling = dir.join("later.md");
+        symlink(dir.join("sync/later.md"), &dangling).unwrap();
+        assert_eq!(resolve(&dangling), dir.join("sync/later.md"));
+        save_atomic(&dangling, "## Later\n").unwrap();
+        assert!(fs::symlink_metadata(&dangling).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(dir.join("sync/later.md")).unwrap(), "## Later\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+//! Moving a category or project between files: `extract` copies one into a
+//! standalone document and `merge` folds such a document back into another.
+
+use crate::activity::ActivityLog;
+use crate::model::{Category, Document};
+use crate::parser;
+
+/// A document holding category `cat_idx` (only project `proj_idx` of it, if
+/// given) with its tasks and notes, plus its archive lines. Lines archived
+/// before they recorded their project are matched by text against tasks the
+/// activity log saw in the same category and project.
+pub fn extract(doc: &Document, log: &ActivityLog, cat_idx: usize, proj_idx: Option<usize>) -> Option<Document> {
+    let source = doc.categories.get(cat_idx)?;
+    let category = match proj_idx {
+        Some(pi) => Category {
+            name: source.name.clone(),
+            hidden: source.hidden,
+            notes: Vec::new(),
+            projects: vec![source.projects.get(pi)?.clone()],
+        },
+        None => source.clone(),
+    };
+
+    let logged: Vec<&str> = log
+        .events
+        .iter()
+        .filter(|e| {
+            e.category.eq_ignore_ascii_case(&category.name)
+                && category.projects.iter().any(|p| p.name.eq_ignore_ascii_case(&e.project))
+        })
+        .filter_map(|e| e.task.as_deref())
+        .collect();
+    let belongs = |line: &str| {
+        let Some(entry) = parser::archive_entry(line) else {
+            return false;
+        };
+        match entry.category.zip(entry.project) {
+            Some((c, p)) => {
+                c.eq_ignore_ascii_case(&category.name) && category.projects.iter().any(|proj| proj.name.eq_ignore_ascii_case(p))
+            }
+            None => logged.contains(&entry.text),
+        }
+    };
+    let archive = doc.archive.iter().filter(|line| belongs(line)).cloned().collect();
+
+    Some(Document {
+        categories: vec![category],
+        archive,
+        levels: doc.levels,
+        archive_heading: doc.archive_heading.clone(),
+        ..Document::new()
+    })
+}
+
+/// What `merge` added.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Merged {
+    pub projects: usize,
+    pub tasks: usize,
+    pub archived: usize,
+}
+
+/// Fold `other` into `doc`. Categories and projects are matched by name
+/// (ignoring case); new ones are added at the end, and existing projects get
+/// the tasks they don't already have. Archive lines already present are skipped.
+pub fn merge(doc: &mut Document, other: Document) -> Merged {
+    let mut merged = Merged::default();
+    for category in other.categories {
+        let Some(ci) = doc.categories.iter().position(|c| c.name.eq_ignore_ascii_case(&category.name)) else {
+            merged.projects += category.projects.len();
+            merged.tasks += category.projects.iter().map(|p| p.tasks.len()).sum::<usize>();
+            doc.categories.push(category);
+            continue;
+        };
+        let target = &mut doc.categories[ci];
+        for project in category.projects {
+            match target.projects.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&project.name)) {
+                Some(existing) => {
+                    for task in project.tasks {
+                        if !existing.tasks.iter().any(|t| t.text == task.text) {
+                            existing.tasks.push(task);
+                            merged.tasks += 1;
+                        }
+                    }
+                    existing.deadline = existing.deadline.or(project.deadline);
+                }
+                None => {
+                    merged.projects += 1;
+                    merged.tasks += project.tasks.len();
+                    target.projects.push(project);
+                }
+            }
+        }
+    }
+    for line in other.archive.iter() {
+        if !doc.archive.contains(line) {
+            doc.archive.push(line.clone());
+            merged.archived += 1;
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::{Event, EventKind};
+    use crate::parser::parse;
+    use crate::serializer::serialize;
+
+    const DOC: &str = "\
+## Work
+Quarterly goals
+
+### 🔶 Launch due:2026-05-01
+- 🔶 Review copy
+  Focus on pricing
+- 🔴 Book venue
+
+### Hiring
+- 🔴 Post job
+
+## Done
+- ✅ [Work/Launch] Book caterer (2026-03-10)
+- ✅ Sign contract (2026-03-09)
+- ✅ Renew lease (2026-03-08)
+- ✅ [Home/Chores] Fix sink (2026-03-07)
+";
+
+    fn added(project: &str, task: &str) -> Event {
+        Event {
+            date: "2026-03-01".to_string(),
+            kind: EventKind::Added,
+            category: "Work".to_string(),
+            project: project.to_string(),
+            task: Some(task.to_string()),
+            state: None,
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let doc = parse(DOC);
+        let log = ActivityLog {
+            events: vec![added("Launch", "Sign contract"), added("Hiring", "Renew lease")],
+        };
+        let project = extract(&doc, &log, 0, Some(0)).unwrap();
+        assert_eq!(
+            serialize(&project),
+            "## Work\n\n### 🔶 Launch due:2026-05-01\n- 🔶 Review copy\n  Focus on pricing\n- 🔴 Book venue\n\n## Done\n- ✅ [Work/Launch] Book caterer (2026-03-10)\n- ✅ Sign contract (2026-03-09)\n"
+        );
+        let category = extract(&doc, &log, 0, None).unwrap();
+        assert_eq!(category.categories[0].notes, doc.categories[0].notes);
+        assert_eq!(category.categories[0].projects.len(), 2);
+        assert_eq!(category.archive.len(), 3);
+        assert!(extract(&doc, &log, 0, Some(5)).is_none());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut doc = parse(DOC);
+        let incoming = parse(
+            "## work\n\n### Launch\n- 🔴 Book venue\n- 🔴 Print flyers\n\n### Press\n- 🔴 Draft release\n\n## Home\n\n### Chores\n- 🔴 Fix sink\n\n## Done\n- ✅ Sign contract (2026-03-09)\n- ✅ Pick a domain (2026-02-01)\n",
+        );
+        let merged = merge(&mut doc, incoming);
+        assert_eq!(merged, Merged { projects: 2, tasks: 3, archived: 1 });
+        let launch = &doc.categories[0].projects[0];
+        assert_eq!(launch.tasks.last().unwrap().text, "Print flyers");
+        assert_eq!(launch.tasks.len(), 3);
+        assert_eq!(doc.categories[0].projects[2].name, "Press");
+        assert_eq!(doc.categories[1].name, "Home");
+        assert_eq!(doc.archive.last().unwrap(), "- ✅ Pick a domain (2026-02-01)");
+    }
+}
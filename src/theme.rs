@@ -245,3 +245,8 @@ impl Theme {
             .unwrap_or(0)
     }
 }
+
+/// The terminal color for a project's `color:` name.
+pub fn accent_color(name: &str) -> Option<Color> {
+    name.parse().ok()
+}
@@ -0,0 +1,106 @@
+//! What the event loop waits on: terminal input read on its own thread, a
+//! steady tick for timers and the file watcher, and results sent in from
+//! background work, all arriving on one channel.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crossterm::event::{self, Event};
+
+/// How often timers (autosave) and the file watcher are checked.
+pub const TICK_RATE: Duration = Duration::from_millis(200);
+
+/// How long the input thread waits for a key before checking whether to stop.
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+pub enum AppEvent {
+    /// A key press, paste or resize
+    Terminal(Event),
+    /// Reading the terminal failed; the loop should stop
+    InputError(io::Error),
+    /// Time to check timers and the file watcher
+    Tick,
+    /// A page title fetched for `:titles`, with its URL
+    LinkTitle(String, anyhow::Result<String>),
+}
+
+/// The event channel and the threads feeding it. Dropping it stops the
+/// input thread, so nothing reads the terminal once the TUI is gone.
+pub struct Events {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+    stop: Arc<AtomicBool>,
+    input: Option<JoinHandle<()>>,
+}
+
+impl Events {
+    /// Start reading the terminal and ticking every `tick_rate`.
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let input = {
+            let sender = sender.clone();
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match event::poll(INPUT_POLL).and_then(|ready| ready.then(event::read).transpose()) {
+                        Ok(None) => {}
+                        Ok(Some(event)) => {
+                            if sender.send(AppEvent::Terminal(event)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            let _ = sender.send(AppEvent::InputError(e));
+                            break;
+                        }
+                    }
+                }
+            })
+        };
+
+        // Ends on its first send after the receiver is gone
+        let ticks = sender.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(tick_rate);
+                if ticks.send(AppEvent::Tick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { sender, receiver, stop, input: Some(input) }
+    }
+
+    /// For background work to send its results through.
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    /// Wait for the next event.
+    pub fn next(&self) -> AppEvent {
+        // Never disconnected: `self` holds a sender
+        self.receiver.recv().unwrap_or(AppEvent::Tick)
+    }
+
+    /// The next event if one is already waiting.
+    pub fn try_next(&self) -> Option<AppEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(input) = self.input.take() {
+            let _ = input.join();
+        }
+    }
+}
@@ -0,0 +1,992 @@
+//! Headless driver for UI tests: renders the app into a `TestBackend` and
+//! feeds it scripted key presses.
+
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::App;
+use crate::engine::{self, PromoteStrategy};
+use crate::parser;
+use crate::tui::{input, ui};
+
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Harness {
+    pub fn new(content: &str, width: u16, height: u16) -> Self {
+        // Promoted as loading the file does with the default config
+        let mut doc = parser::parse(content);
+        engine::auto_promote(&mut doc, PromoteStrategy::default());
+        let app = App::new(doc, PathBuf::from("/tmp/gws-harness.md"));
+        let terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend");
+        Self { app, terminal }
+    }
+
+    /// Feed a key script. Plain characters are typed as-is; `<Enter>`,
+    /// `<Esc>`, `<Tab>`, `<Space>`, `<BS>`, `<Up>`, `<Down>`, `<PgUp>`,
+    /// `<PgDn>` and `<C-x>` (Ctrl+x) name special keys, and `<lt>` types `<`.
+    pub fn keys(&mut self, script: &str) -> &mut Self {
+        for key in parse_keys(script) {
+            // Render between keys so scroll state matches what a user would see
+            self.render();
+            input::handle_key(&mut self.app, key);
+        }
+        self
+    }
+
+    /// Draw a frame and return it as lines of text, trailing spaces trimmed.
+    pub fn render(&mut self) -> String {
+        self.terminal
+            .draw(|frame| ui::draw(frame, &mut self.app))
+            .expect("draw");
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| {
+                // A wide character fills two cells; skip the filler after it
+                let mut line = String::new();
+                let mut skip = 0;
+                for cell in row {
+                    if skip > 0 {
+                        skip -= 1;
+                        continue;
+                    }
+                    line.push_str(cell.symbol());
+                    skip = cell.symbol().width().saturating_sub(1);
+                }
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render and assert that `text` appears somewhere on screen.
+    pub fn assert_visible(&mut self, text: &str) -> &mut Self {
+        let screen = self.render();
+        assert!(screen.contains(text), "expected {:?} on screen:\n{}", text, screen);
+        self
+    }
+
+    pub fn assert_not_visible(&mut self, text: &str) -> &mut Self {
+        let screen = self.render();
+        assert!(!screen.contains(text), "did not expect {:?} on screen:\n{}", text, screen);
+        self
+    }
+
+    /// The rendered line containing `text`, for checks on a single row.
+    pub fn line_with(&mut self, text: &str) -> String {
+        let screen = self.render();
+        screen
+            .lines()
+            .find(|l| l.contains(text))
+            .unwrap_or_else(|| panic!("no line with {:?} on screen:\n{}", text, screen))
+            .to_string()
+    }
+}
+
+fn parse_keys(script: &str) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut rest = script;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+        {
+            keys.push(named_key(&rest[1..end]));
+            rest = &rest[end + 1..];
+            continue;
+        }
+        keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+fn named_key(name: &str) -> KeyEvent {
+    if let Some(c) = name.strip_prefix("C-").and_then(|s| s.chars().next()) {
+        return KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+    }
+    let code = match name {
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "Space" => KeyCode::Char(' '),
+        "BS" => KeyCode::Backspace,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "PgUp" => KeyCode::PageUp,
+        "PgDn" => KeyCode::PageDown,
+        "lt" => KeyCode::Char('<'),
+        other => panic!("unknown key <{}>", other),
+    };
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "\
+## Work
+
+### 🔶 Launch
+- 🔵 Pick a domain
+- 🔴 Write copy
+
+### Someday
+- 🔴 Learn piano
+";
+
+    #[test]
+    fn test_parse_keys() {
+        let keys = parse_keys("a<Enter><C-d><lt>");
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys[1].code, KeyCode::Enter);
+        assert_eq!(keys[2].modifiers, KeyModifiers::CONTROL);
+        assert_eq!(keys[3].code, KeyCode::Char('<'));
+    }
+
+    #[test]
+    fn test_agenda_renders_sections() {
+        let mut h = Harness::new(DOC, 80, 12);
+        h.assert_visible("── On Deck (1) ──")
+            .assert_visible("Not Started (1 hidden)")
+            .assert_not_visible("Learn piano");
+        assert!(h.line_with("Pick a domain").contains("▸"));
+    }
+
+    #[test]
+    fn test_add_task_flow() {
+        let mut h = Harness::new(DOC, 80, 16);
+        // Backlog, down to the Launch project, add a task
+        h.keys("<Tab>ja").assert_visible("Add Task");
+        h.keys("Book venue<Enter>")
+            .assert_not_visible("Add Task")
+            .assert_visible("Book venue")
+            .assert_visible("[modified]");
+    }
+
+    #[test]
+    fn test_filter_flow() {
+        let mut h = Harness::new(DOC, 80, 16);
+        h.keys("<Tab>/piano")
+            .assert_visible("Backlog /piano▏")
+            .assert_visible("Learn piano")
+            .assert_not_visible("Pick a domain");
+        h.keys("<Esc>").assert_visible("Pick a domain");
+    }
+
+    #[test]
+    fn test_accessible_rendering() {
+        let mut h = Harness::new(DOC, 80, 12);
+        h.app.force_accessible = true;
+        let screen = h.render();
+        assert!(screen.contains("Task, On Deck, Pick a domain, project Launch"));
+        assert!(screen.contains("Status: ready."));
+        assert!(!screen.contains('─') && !screen.contains('▸'));
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let doc = "## 仕事\n\n### 🔶 発売\n- 🔵 ウェブサイトのデザインを完成させてチームと共有する\n";
+        let mut h = Harness::new(doc, 40, 12);
+        // Long CJK text is cut to fit, keeping the project name on screen
+        let row = h.line_with("(発売)");
+        assert!(row.contains('…'), "{}", row);
+
+        // Typing multibyte text and moving over it keeps the cursor on
+        // character boundaries and in display columns
+        h.keys("<Tab>ja日本語<BS>x");
+        assert_eq!(h.app.input_buffer, "日本x");
+        h.render();
+        let cursor = h.terminal.get_cursor_position().unwrap();
+        let screen = h.render();
+        let input_row = screen.lines().nth(cursor.y as usize).unwrap();
+        let text_col = crate::tui::widgets::display_width(&input_row[..input_row.find("日本x").unwrap()]);
+        assert_eq!(cursor.x as usize, text_col + 5);
+    }
+
+    #[test]
+    fn test_merge_flow() {
+        let mut h = Harness::new(DOC, 100, 16);
+        h.keys("<Tab>jjJ").assert_visible("Merging \"Pick a domain\"");
+        h.keys("jJ").assert_visible("Merged \"Pick a domain\" into \"Write copy\"");
+        let launch = &h.app.doc.categories[0].projects[0];
+        assert_eq!(launch.tasks.len(), 1);
+        assert_eq!(launch.tasks[0].state, crate::model::TaskState::OnDeck);
+        assert_eq!(launch.tasks[0].notes, ["  Pick a domain"]);
+
+        // Esc drops a pending mark
+        h.keys("J<Esc>").assert_visible("Merge cancelled");
+        assert!(h.app.merge_mark.is_none());
+    }
+
+    #[test]
+    fn test_areas_overview() {
+        let mut h = Harness::new(DOC, 80, 16);
+        h.keys("O").assert_visible("No areas");
+
+        let doc = format!("Area Career: Work\n\n{}", DOC);
+        let mut h = Harness::new(&doc, 80, 16);
+        h.keys("O").assert_visible("Areas of Focus");
+        assert!(h.line_with("Career").contains("2 (1 active)"));
+        h.keys("<Esc>").assert_not_visible("Areas of Focus");
+    }
+
+    #[test]
+    fn test_checklist_reset_flow() {
+        let mut h = Harness::new(DOC, 80, 16);
+        // Make Launch a checklist, then finish both of its items
+        h.keys("<Tab>jC").assert_visible("▼ 🔶 📋 Launch");
+        h.keys("jppjppp").assert_visible("Checklist done");
+        h.keys("y").assert_visible("Checklist \"Launch\" reset");
+        let launch = &h.app.doc.categories[0].projects[0];
+        assert!(launch.tasks.iter().all(|t| t.state == crate::model::TaskState::Todo));
+    }
+
+    #[test]
+    fn test_people_filter() {
+        let mut h = Harness::new(DOC, 80, 16);
+        h.keys("@").assert_visible("No one mentioned");
+
+        let doc = DOC.replace("Write copy", "Write copy with @Sam").replace("Learn piano", "Learn piano from @Samantha");
+        let mut h = Harness::new(&doc, 80, 16);
+        h.keys("@").assert_visible("@Sam · 1 task, 1 open").assert_visible("@Samantha");
+        h.keys("<Enter>")
+            .assert_visible("Backlog /@Sam")
+            .assert_visible("Write copy with @Sam")
+            .assert_not_visible("Learn piano");
+    }
+
+    #[test]
+    fn test_stats_view() {
+        let today = crate::date::Date::today();
+        let doc = format!("{}\n## Done\n- ✅ [Work/Launch] Ship it ({})\n", DOC, today);
+        let mut h = Harness::new(&doc, 100, 30);
+        h.keys("<Tab><Tab>")
+            .assert_visible("Done this week: 1")
+            .assert_visible("Open now: 3")
+            .assert_visible("Completed per week")
+            .assert_visible("Open tasks")
+            .assert_visible("No finished tasks logged yet")
+            .assert_visible("Work › Launch  1 done");
+        h.keys("jG<Tab>").assert_visible("Settings");
+    }
+
+    #[test]
+    fn test_header_breadcrumb_and_counts() {
+        let mut h = Harness::new(DOC, 100, 16);
+        assert!(h.line_with("GWS ·").contains("Work › Launch"));
+        h.assert_visible("Agenda (1 on deck)").assert_visible("Backlog (3 open)");
+        h.keys("<Tab>");
+        assert!(!h.line_with("GWS ·").contains("›"));
+        h.keys("jjjjj");
+        assert!(h.line_with("GWS ·").contains("Work › Someday"));
+    }
+
+    #[test]
+    fn test_titles_show_modifiers() {
+        let mut h = Harness::new(DOC, 100, 16);
+        h.keys("cD").assert_visible("Agenda — Work, Done hidden");
+        h.keys("<Tab>/copy<Enter>").assert_visible("Backlog /copy — Done hidden");
+        h.keys("X").assert_visible("Filters cleared").assert_visible("Learn piano");
+        h.keys("<Tab><Tab><Tab>").assert_not_visible("Agenda —");
+        h.keys("X").assert_visible("No filters to clear");
+    }
+
+    #[test]
+    fn test_task_history_line() {
+        let mut h = Harness::new(DOC, 80, 16);
+        let today = crate::date::Date::today();
+        let before = crate::parser::parse(&DOC.replace("🔵 Pick", "🔴 Pick"));
+        h.app.activity.record(&crate::model::Document::new(), &before, today.minus_days(5));
+        h.app.activity.record(&before, &h.app.doc.clone(), today.minus_days(2));
+        h.assert_visible("created 5d ago, on deck 2d");
+        h.keys("<Tab>").assert_not_visible("created");
+    }
+
+    #[test]
+    fn test_stats_cycle_times() {
+        let mut h = Harness::new(DOC, 120, 30);
+        let today = crate::date::Date::today();
+        let before = crate::parser::parse(&DOC.replace("🔵 Pick", "🔴 Pick"));
+        let after = crate::parser::parse(&DOC.replace("🔵 Pick", "✅ Pick"));
+        h.app.activity.record(&crate::model::Document::new(), &before, today.minus_days(4));
+        h.app.activity.record(&before, &after, today);
+        h.keys("<Tab><Tab>").assert_visible("Work › Launch  4.0d avg over 1 task");
+    }
+
+    #[test]
+    fn test_inbox_triage() {
+        let mut h = Harness::new(DOC, 80, 20);
+        h.keys("I").assert_visible("No inbox");
+
+        let doc = format!(
+            "{}\n## Inbox\n\n### Inbox\n- 🔴 Call plumber\n- 🔴 Renew passport\n- 🔴 Ask about budget\n- 🔴 Read book\n- 🔴 Old idea\n- 🔴 Maybe later\n",
+            DOC
+        );
+        let mut h = Harness::new(&doc, 80, 20);
+        h.keys("I").assert_visible("Inbox Triage: 6 left").assert_visible("Call plumber");
+        h.keys("p").assert_visible("Renew passport").assert_visible("5 left");
+        h.keys("f+1<Enter>").assert_visible("Ask about budget");
+        h.keys("wSam<Enter>").assert_visible("Read book");
+        h.keys("m<Enter>").assert_visible("Old idea");
+        h.keys("s").assert_visible("Maybe later");
+        h.keys("d").assert_visible("Inbox triaged: 6 tasks handled");
+
+        let inbox = &h.app.doc.categories[1].projects[0].tasks;
+        let texts: Vec<&str> = inbox.iter().map(|t| t.text.as_str()).collect();
+        let due = crate::date::Date::today().plus_days(1);
+        assert_eq!(
+            texts,
+            ["Call plumber", &format!("Renew passport due:{}", due), "Ask about budget @Sam"]
+        );
+        assert_eq!(inbox[0].state, crate::model::TaskState::OnDeck);
+        let launch = &h.app.doc.categories[0].projects[0];
+        assert_eq!(launch.tasks.last().unwrap().text, "Read book");
+        let someday = &h.app.doc.categories[0].projects[1];
+        assert_eq!(someday.tasks.last().unwrap().text, "Old idea");
+    }
+
+    #[test]
+    fn test_stalled_in_progress() {
+        let doc = DOC.replace("🔵 Pick a domain", "🔶 Pick a domain");
+        let mut h = Harness::new(&doc, 80, 16);
+        let today = crate::date::Date::today();
+        let before = crate::parser::parse(DOC);
+        h.app.activity.record(&before, &h.app.doc.clone(), today.minus_days(9));
+        h.assert_not_visible("stalled");
+
+        h.app.config.agenda.stalled_after_days = 7;
+        h.app.check_stalled();
+        h.assert_visible("Pick a domain (Launch) stalled 9d").assert_visible("1 task In Progress for 7+ days");
+
+        h.app.config.agenda.demote_stalled = true;
+        h.app.check_stalled();
+        h.assert_visible("Moved 1 task back to On Deck").assert_not_visible("stalled 9d");
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks[0].state, crate::model::TaskState::OnDeck);
+        h.keys("u");
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks[0].state, crate::model::TaskState::InProgress);
+    }
+
+    #[test]
+    fn test_project_deadline() {
+        let mut h = Harness::new(DOC, 100, 16);
+        // Backlog, on the Launch project
+        h.keys("<Tab>jT+12<Enter>").assert_visible("Launch  12 days left");
+        let today = crate::date::Date::today();
+        assert_eq!(h.app.doc.categories[0].projects[0].deadline, Some(today.plus_days(12)));
+
+        h.keys(&format!("T{}2020-01-01<Enter>", "<BS>".repeat(10)));
+        h.assert_visible("days overdue");
+        h.keys("<Tab><Tab>");
+        h.app.settings_cursor = 0;
+        h.keys("k");
+        assert!(h.line_with("(2 projects, Launch").contains("days overdue"));
+        h.keys("<Tab><Tab>jT");
+        assert_eq!(h.app.input_buffer, "2020-01-01");
+        h.keys("<Esc>u").assert_not_visible("overdue");
+    }
+
+    #[test]
+    fn test_plan_my_day() {
+        let doc = DOC
+            .replace("Write copy", "Write copy ~3h !1")
+            .replace("### Someday", "### 🔶 Errands\n- 🔴 Buy stamps ~20m\n- 🔴 Renew passport ~4h\n\n### Someday");
+        let mut h = Harness::new(&doc, 100, 20);
+        h.app.config.planner.hours = 5;
+        h.keys("W")
+            .assert_visible("Plan My Day")
+            .assert_visible("30m? Pick a domain (Launch)")
+            .assert_visible("3h  Write copy ~3h !1")
+            .assert_visible("20m  Buy stamps")
+            .assert_visible("3h 50m of 5h planned, 1 more didn't fit");
+        // Drop Write copy: the passport now fits
+        h.keys("jjd").assert_visible("Renew passport").assert_not_visible("Write copy");
+        h.keys("r").assert_visible("Write copy");
+        h.keys("+").assert_visible("of 6h planned");
+        h.keys("<Enter>").assert_visible("Planned 3 tasks (3h 50m of 6h)");
+        let states: Vec<_> = h.app.doc.categories[0].projects.iter().flat_map(|p| &p.tasks).map(|t| t.state).collect();
+        use crate::model::TaskState::*;
+        assert_eq!(states, [OnDeck, OnDeck, OnDeck, Todo, Todo]);
+        h.keys(":plan<Enter><Esc>").assert_not_visible("Plan My Day");
+    }
+
+    #[test]
+    fn test_saved_filters() {
+        let doc = DOC.replace("Write copy", "Write copy with @Sam").replace("Learn piano", "Learn piano with @sam");
+        let mut h = Harness::new(&doc, 100, 16);
+        h.keys("F").assert_visible("No saved filters");
+
+        h.app.config.filters = vec![
+            crate::config::SavedFilter {
+                name: "Sam".to_string(),
+                query: "@sam AND NOT state:ondeck".to_string(),
+            },
+            crate::config::SavedFilter {
+                name: "Broken".to_string(),
+                query: "state:soon".to_string(),
+            },
+        ];
+        h.keys("Fjj<Enter>").assert_visible("Filter \"Broken\": Unknown state \"soon\"");
+        h.keys("Fj<Enter>t")
+            .assert_visible("Agenda — Not Started shown, Filter: Sam")
+            .assert_visible("Write copy with @Sam")
+            .assert_not_visible("Pick a domain");
+        h.keys("<Tab>")
+            .assert_visible("Filter: Sam")
+            .assert_visible("Learn piano with @sam")
+            .assert_not_visible("Pick a domain");
+        h.keys("X").assert_visible("Pick a domain").assert_not_visible("Filter: Sam");
+    }
+
+    #[test]
+    fn test_unarchive() {
+        let mut h = Harness::new(DOC, 90, 16);
+        h.keys(":unarchive<Enter>").assert_visible("Nothing archived");
+
+        let doc = format!("{}\n## Done\n- ✅ [Work/Launch] Book venue (2026-03-10)\n- ✅ [Home/Chores] Fix sink\n", DOC);
+        let mut h = Harness::new(&doc, 90, 16);
+        h.keys("U")
+            .assert_visible("Reopen from Archive")
+            .assert_visible("Book venue  (Work › Launch, 2026-03-10)")
+            .assert_visible("Fix sink  (Home › Chores)");
+        h.keys("<Enter>").assert_visible("Reopened \"Book venue\" in Work › Launch");
+        assert_eq!(h.app.doc.archive.len(), 1);
+        let launch = &h.app.doc.categories[0].projects[0];
+        assert_eq!(launch.tasks.last().unwrap().state, crate::model::TaskState::Todo);
+        h.keys("<Tab>").assert_visible("Book venue");
+        h.keys("u");
+        assert_eq!(h.app.doc.archive.len(), 2);
+    }
+
+    #[test]
+    fn test_paste_import() {
+        let mut h = Harness::new(DOC, 80, 20);
+        // On the Agenda a paste is ignored rather than run as keys
+        input::handle_paste(&mut h.app, "dq");
+        assert_eq!(h.app.dialog, crate::app::Dialog::None);
+        assert!(h.app.running);
+
+        h.keys("<Tab>jV")
+            .assert_visible("Paste Tasks into Launch")
+            .assert_visible("Paste a list, or type one task per line");
+        h.keys("- Call plumber<Enter><Enter>* [ ] Book flights")
+            .assert_visible("Call plumber")
+            .assert_visible("Book flights")
+            .assert_visible("2 tasks to add");
+        h.keys("<Esc>");
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks.len(), 2);
+
+        // A paste on the Backlog opens the dialog with it
+        input::handle_paste(&mut h.app, "1. Pack\r\n2. Water plants\r\n");
+        h.assert_visible("2 tasks to add");
+        h.keys("<C-s>").assert_visible("Added 2 tasks to Launch");
+        let tasks: Vec<&str> = h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(tasks, ["Pick a domain", "Write copy", "Pack", "Water plants"]);
+        h.keys("u");
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks.len(), 2);
+
+        // Other dialogs get a paste on one line
+        h.keys("a");
+        input::handle_paste(&mut h.app, "Call\nthe bank");
+        assert_eq!(h.app.input_buffer, "Call the bank");
+    }
+
+    #[test]
+    fn test_paste_into_confirm_dialog() {
+        let mut h = Harness::new(DOC, 80, 20);
+        // A paste is not an answer: "yes" leaves the delete unconfirmed
+        h.keys("<Tab>jjd");
+        assert_eq!(h.app.dialog, crate::app::Dialog::ConfirmDelete);
+        input::handle_paste(&mut h.app, "yes");
+        assert_eq!(h.app.dialog, crate::app::Dialog::ConfirmDelete);
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks.len(), 2);
+        h.keys("n");
+        assert_eq!(h.app.dialog, crate::app::Dialog::None);
+    }
+
+    #[test]
+    fn test_unsorted_triage() {
+        let mut h = Harness::new(DOC, 80, 20);
+        h.app.offer_unsorted_triage(1);
+        h.assert_not_visible("Triage the Unsorted");
+        h.app.start_unsorted_triage();
+        h.render();
+        h.assert_visible("No stray tasks to triage");
+
+        let doc = DOC.replace("## Work\n", "- 🔴 Call plumber\n\n## Work\n- 🔴 Renew passport\n");
+        let mut h = Harness::new(&doc, 80, 20);
+        h.app.offer_unsorted_triage(2);
+        h.render();
+        h.assert_visible("Found 2 tasks outside any project").assert_visible("Triage the Unsorted tasks now?");
+        h.keys("y").assert_visible("Unsorted Triage: 1 left").assert_visible("Call plumber");
+        h.keys("n").assert_visible("Renew passport");
+        h.keys("d").assert_visible("Unsorted triaged: 1 task handled");
+        assert_eq!(h.app.dialog, crate::app::Dialog::None);
+        assert_eq!(h.app.doc.unsorted_projects(), [(0, 0)]);
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let mut h = Harness::new(DOC, 80, 20);
+        h.keys(":dedupe<Enter>").assert_visible("No duplicate names");
+
+        let doc = format!("{}\n### someday\n- 🔴 Learn piano\n- 🔴 Learn Go\n", DOC);
+        let mut h = Harness::new(&doc, 80, 20);
+        h.app.offer_duplicate_fix(false);
+        h.render();
+        h.assert_visible("Duplicate names: Work › someday").assert_visible("Merge into the first");
+        h.keys("r").assert_visible("Renamed 1 duplicate");
+        assert_eq!(h.app.doc.categories[0].projects[2].name, "someday (2)");
+
+        h.keys("u:dedupe<Enter>m").assert_visible("Merged 1 duplicate");
+        let projects = &h.app.doc.categories[0].projects;
+        assert_eq!(projects.len(), 2);
+        let tasks: Vec<&str> = projects[1].tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(tasks, ["Learn piano", "Learn Go"]);
+    }
+
+    #[test]
+    fn test_global_agenda() {
+        use crate::report::{self, AgendaSource};
+        use std::path::Path;
+
+        let mut h = Harness::new(DOC, 100, 20);
+        let home = parser::parse("## Home\n\n### 🔶 Chores\n- 🔶 Fix sink\n- 🔵 Water plants\n");
+        let sources = [
+            AgendaSource { name: "work", file: Path::new("/tmp/gws-harness.md"), doc: &h.app.doc.clone() },
+            AgendaSource { name: "home", file: Path::new("/tmp/home.md"), doc: &home },
+        ];
+        let items = report::global_agenda(&sources, &h.app.global_agenda_sections(), crate::date::Date::today());
+        h.app.open_global_agenda(items);
+        h.render();
+        h.assert_visible("Agenda: All Profiles")
+            .assert_visible("[home] Fix sink  Home › Chores")
+            .assert_visible("[home] Water plants")
+            .assert_visible("[work] Pick a domain  Work › Launch");
+        h.keys("E").assert_not_visible("Agenda: All Profiles");
+        assert!(h.app.global_agenda.is_empty());
+    }
+
+    #[test]
+    fn test_status_says_what_changed() {
+        let mut h = Harness::new(DOC, 100, 16);
+        h.keys("p").assert_visible("\"Pick a domain\" → In Progress");
+        h.keys("p").assert_visible("\"Pick a domain\" → Done");
+        h.keys("Ay").assert_visible("Archived 1 task · promoted \"Write copy\" in Launch");
+        h.keys("r").assert_visible("Nothing to promote");
+        h.keys("u").assert_visible("Undone: 1 task added, 1 changed state");
+    }
+
+    #[test]
+    fn test_complete_project_names() {
+        let mut h = Harness::new(DOC, 100, 20);
+        h.keys("aCall Sam +la").assert_visible("Add to Agenda").assert_visible("▸ Launch");
+        h.keys("<Tab>").assert_visible("Call Sam +Launch").assert_not_visible("▸ Launch");
+        h.keys("<Enter>").assert_visible("Added \"Call Sam\" to Launch");
+        h.keys("aBuy milk<Enter>").assert_visible("No inbox");
+        h.keys("aPost job +Press<Enter>").assert_visible("No project named Press");
+
+        // Rows: Work, Launch, Pick a domain, Write copy
+        h.keys("<Tab>jjj>").assert_visible("Move Task to Project").assert_visible("▸ Work › Someday");
+        h.keys("<Esc>j><Enter>").assert_visible("Moved \"Call Sam\" to Work › Someday");
+        assert_eq!(h.app.doc.categories[0].projects[1].tasks.len(), 2);
+    }
+
+    #[test]
+    fn test_tags_screen() {
+        let mut h = Harness::new(DOC, 100, 20);
+        h.keys("#").assert_visible("No tags");
+
+        let doc = DOC.replace("Write copy", "Write copy #errands").replace("Learn piano", "Learn piano #Errands #music");
+        let mut h = Harness::new(&doc, 100, 20);
+        h.keys("#").assert_visible("#errands · 2 tasks, 2 open").assert_visible("#music · 1 task, 1 open");
+        h.keys("<Enter>")
+            .assert_visible("Backlog /#errands")
+            .assert_visible("Learn piano #Errands")
+            .assert_not_visible("Pick a domain");
+
+        h.keys("#r").assert_visible("Rename Tag Everywhere");
+        h.keys("<BS><BS><BS><BS><BS><BS><BS>#shop<Enter>").assert_visible("Renamed #errands to #shop on 2 tasks");
+        h.keys("#jd").assert_visible("Remove this tag from every task?");
+        h.keys("y").assert_visible("Removed #music from 1 task");
+        let someday = &h.app.doc.categories[0].projects[1];
+        assert_eq!(someday.tasks[0].text, "Learn piano #shop");
+        h.keys("u").assert_visible("Undone");
+        assert_eq!(h.app.doc.categories[0].projects[1].tasks[0].text, "Learn piano #shop #music");
+
+        // Tags complete while typing a task
+        h.keys("XGn#mu").assert_visible("Add Note").assert_visible("▸ music");
+        h.keys("<Tab>").assert_visible("#music");
+    }
+
+    #[test]
+    fn test_agenda_note_preview() {
+        let mut h = Harness::new(DOC, 80, 16);
+        h.keys("K").assert_visible("┌ Work › Launch").assert_visible("Pick a domain");
+        h.keys("<Esc>").assert_not_visible("┌ Work › Launch");
+
+        let doc = DOC.replace("- 🔵 Pick a domain\n", "- 🔵 Pick a domain\n  Registrar: 555-0100\n  Ask for the renewal price\n");
+        let mut h = Harness::new(&doc, 80, 16);
+        h.assert_visible("Registrar: 555-0100").assert_not_visible("renewal price");
+        h.keys("K").assert_visible("┌ Work › Launch").assert_visible("Ask for the renewal price");
+        h.keys("<Esc>").assert_not_visible("renewal price");
+    }
+
+    #[test]
+    fn test_note_management() {
+        let doc = DOC.replace("- 🔴 Write copy\n", "- 🔴 Write copy\n  Headline first\n  - Ask Sam for quotes\n");
+        let mut h = Harness::new(&doc, 80, 20);
+        let notes = |h: &Harness| h.app.doc.categories[0].projects[0].tasks[1].notes.clone();
+
+        // Rows: Work, Launch, Pick a domain, Write copy, then its notes
+        h.keys("<Tab>jjjjmj<Esc>").assert_visible("Move cancelled");
+        assert_eq!(notes(&h), ["  Headline first", "  - Ask Sam for quotes"]);
+        h.keys("mj<Enter>");
+        assert_eq!(notes(&h), ["  - Ask Sam for quotes", "  Headline first"]);
+
+        h.keys("knThen the body<Enter>").assert_visible("Note added");
+        assert_eq!(notes(&h), ["  - Ask Sam for quotes", "  Then the body", "  Headline first"]);
+
+        h.keys("kt").assert_visible("Note made a task: \"Ask Sam for quotes\"");
+        assert_eq!(notes(&h), ["  Then the body", "  Headline first"]);
+        let tasks = &h.app.doc.categories[0].projects[0].tasks;
+        assert_eq!(tasks[2].text, "Ask Sam for quotes");
+        assert!(h.line_with("Ask Sam for quotes").contains("▸"));
+    }
+
+    #[test]
+    fn test_link_titles() {
+        let mut h = Harness::new(DOC, 100, 16);
+        h.keys(":titles<Enter>").assert_visible("No bare links");
+
+        let doc = DOC.replace("- 🔴 Learn piano\n", "- 🔴 https://example.com/piano\n");
+        let mut h = Harness::new(&doc, 100, 16);
+        h.app.config.general.fetch_link_titles = false;
+        h.keys(":titles<Enter>").assert_visible("Fetching link titles is off");
+        h.app.config.general.fetch_link_titles = true;
+        h.keys(":titles<Enter>").assert_visible("Fetching 1 link title...");
+
+        h.app.apply_link_title("https://example.com/piano", Err("timed out".to_string()));
+        h.keys("").assert_visible("No title for https://example.com/piano: timed out");
+        h.app.apply_link_title("https://example.com/piano", Ok("Piano Lessons".to_string()));
+        h.keys("<Tab>").assert_visible("Piano Lessons (https://example.com/piano)");
+        assert!(h.app.dirty);
+        h.keys("u").assert_visible("https://example.com/piano").assert_not_visible("Piano Lessons");
+    }
+
+    #[test]
+    fn test_keyboard_layout() {
+        let mut h = Harness::new(DOC, 200, 40);
+        h.app.config.general.keyboard_layout = crate::keymap::KeyboardLayout::Dvorak;
+        // Dvorak's h sits where QWERTY has j; the hints follow
+        h.keys("<Tab>").assert_visible("h/t:Nav");
+        h.keys("h");
+        assert!(h.line_with("🔶 Launch").contains("▸"));
+        // What's typed into a dialog is taken as it is
+        h.keys("aHot tea<Enter>").assert_visible("Hot tea");
+        h.keys("<Tab><Tab>").assert_visible("Keyboard layout").assert_visible("Dvorak").assert_visible("h / t");
+
+        // Dialog answers are the letters they show: Dvorak's e opens the
+        // delete (QWERTY's d) and y confirms it
+        let mut h = Harness::new(DOC, 200, 40);
+        h.app.config.general.keyboard_layout = crate::keymap::KeyboardLayout::Dvorak;
+        h.keys("<Tab>hhe").assert_visible("y: Yes   n: No").assert_visible("y:Yes  n/Esc:No");
+        h.keys("y");
+        assert_eq!(h.app.dialog, crate::app::Dialog::None);
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_move_block() {
+        let mut h = Harness::new("## Work\n### Launch\n- 🔴 A\n- 🔴 B\n- 🔴 C\n- 🔴 D\n", 120, 20);
+        let texts = |h: &Harness| h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+
+        // V undoes the single-task step and selects from B down to C
+        h.keys("<Tab>jjjmj");
+        assert_eq!(texts(&h), ["A", "C", "B", "D"]);
+        h.keys("Vj").assert_visible("j/k:Select  m:Move");
+        assert_eq!(texts(&h), ["A", "B", "C", "D"]);
+        assert!(h.line_with("C").contains("↕"));
+        assert!(!h.line_with("D").contains("↕"));
+
+        h.keys("mj<Enter>").assert_visible("Moved 2 tasks");
+        assert_eq!(texts(&h), ["A", "D", "B", "C"]);
+        assert!(crate::serializer::serialize(&h.app.doc).contains("- 🔴 D\n- 🔴 B\n- 🔴 C\n"));
+        h.keys("u");
+        assert_eq!(texts(&h), ["A", "B", "C", "D"]);
+
+        // Esc puts a moved block back
+        h.keys("kkmVjmk");
+        assert_eq!(texts(&h), ["B", "C", "A", "D"]);
+        h.keys("<Esc>").assert_visible("Move cancelled");
+        assert_eq!(texts(&h), ["A", "B", "C", "D"]);
+
+        h.keys("mVjp").assert_visible("Promoted 2 tasks");
+        let states: Vec<_> = h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.state).collect();
+        assert_eq!(states[1], states[2]);
+        assert_ne!(states[1], states[3]);
+
+        h.keys("mVkd").assert_visible("Delete these 2 tasks?");
+        h.keys("y").assert_visible("Deleted 2 tasks");
+        assert_eq!(texts(&h), ["A", "D"]);
+        h.keys("u");
+        assert_eq!(texts(&h), ["A", "B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_project_accent() {
+        for name in crate::model::ACCENT_NAMES {
+            assert!(crate::theme::accent_color(name).is_some(), "{}", name);
+        }
+
+        let mut h = Harness::new(DOC, 100, 16);
+        // On the Agenda, the focused task's project
+        h.keys(":color Magenta<Enter>").assert_visible("\"Launch\" is now magenta");
+        assert_eq!(h.app.doc.categories[0].projects[0].accent.as_deref(), Some("magenta"));
+        h.keys(":color mauve<Enter>").assert_visible("Not a color: mauve");
+
+        // The Agenda row and the Backlog heading are tinted
+        h.keys("t");
+        let fg_of = |h: &mut Harness, text: &str| {
+            h.render();
+            let buffer = h.terminal.backend().buffer();
+            // Below the header, which names the focused project
+            let row = (3..buffer.area.height)
+                .find(|&y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>().contains(text))
+                .unwrap();
+            let symbols: Vec<&str> = (0..buffer.area.width).map(|x| buffer[(x, row)].symbol()).collect();
+            let x = (0..symbols.len()).find(|&x| symbols[x..].concat().starts_with(text)).unwrap();
+            buffer[(x as u16, row)].fg
+        };
+        assert_eq!(fg_of(&mut h, "Write copy"), ratatui::style::Color::Magenta);
+        h.keys("<Tab>jj");
+        assert_eq!(fg_of(&mut h, "Launch"), ratatui::style::Color::Magenta);
+        assert_eq!(fg_of(&mut h, "Someday"), h.app.theme().project);
+        assert!(crate::serializer::serialize(&h.app.doc).contains("### 🔶 Launch color:magenta\n"));
+
+        h.keys(":color<Enter>").assert_visible("\"Launch\" has no color");
+        assert_eq!(h.app.doc.categories[0].projects[0].accent, None);
+        h.keys("u");
+        assert_eq!(h.app.doc.categories[0].projects[0].accent.as_deref(), Some("magenta"));
+    }
+
+    #[test]
+    fn test_truncation_keeps_graphemes() {
+        use crate::tui::widgets::truncate_to_width;
+        // A ZWJ sequence, a flag and a combining accent are cut whole or not at all
+        assert_eq!(truncate_to_width("ab👩‍💻cd", 4), "ab…");
+        assert_eq!(truncate_to_width("ab👩‍💻cd", 5), "ab👩‍💻…");
+        assert_eq!(truncate_to_width("🇫🇷🇩🇪", 3), "🇫🇷…");
+        assert_eq!(truncate_to_width("cafe\u{301} au lait", 5), "cafe\u{301}…");
+        assert_eq!(truncate_to_width("short", 5), "short");
+
+        let long = "Book the 👩‍💻 workshop venue and send invitations to everyone on the list";
+        let doc = format!("## Work\n\n### 🔶 Launch\n- 🔵 {}\n", long);
+        let mut h = Harness::new(&doc, 50, 16);
+        h.assert_visible("Book the 👩‍💻 workshop").assert_visible("…").assert_not_visible("on the list");
+        // K shows it in full, wrapped
+        h.keys("K").assert_visible("everyone on").assert_visible("the list");
+        h.keys("<Esc><Tab>jjK").assert_visible("the list");
+    }
+
+    #[test]
+    fn test_agenda_show_category() {
+        let doc = "## Work\n\n### 🔶 Inbox\n- 🔵 Call the bank\n\n## Home\n\n### 🔶 Inbox\n- 🔵 Fix the sink\n";
+        let mut h = Harness::new(doc, 80, 30);
+        h.assert_visible("Call the bank (Inbox)").assert_visible("Fix the sink (Inbox)");
+
+        // Toggled in Settings, under Agenda
+        h.keys("<Tab><Tab><Tab>");
+        let row = h.app.settings_row_index(crate::app::SettingsRow::ShowCategory);
+        h.app.settings_cursor = row;
+        h.keys("<Enter>");
+        assert!(h.app.config.agenda.show_category);
+        h.keys("<Tab>")
+            .assert_visible("Call the bank (Work › Inbox)")
+            .assert_visible("Fix the sink (Home › Inbox)");
+    }
+
+    #[test]
+    fn test_retire_project() {
+        let mut h = Harness::new(DOC, 80, 20);
+        h.keys(":retire<Enter>").assert_visible("Focus a project in the Backlog to retire it");
+        h.keys("<Tab>jj:retire<Enter>").assert_visible("Retired \"Launch\" and its 2 tasks to Done Projects");
+        h.assert_not_visible("Pick a domain").assert_visible("▸    ▼ Someday");
+        assert_eq!(h.app.doc.categories[0].projects.len(), 1);
+        let output = crate::serializer::serialize(&h.app.doc);
+        assert!(output.contains("## Done Projects\n### Launch\nRetired "), "{}", output);
+        assert!(output.contains("from Work\n- 🔵 Pick a domain\n- 🔴 Write copy\n"), "{}", output);
+
+        h.keys("u").assert_visible("Pick a domain");
+        assert!(h.app.doc.done_projects.is_empty());
+    }
+
+    #[test]
+    fn test_hidden_category() {
+        let doc = "## Work\n\n### 🔶 Launch\n- 🔵 Pick a domain\n\n## 💤 Garden\n\n### 🔶 Beds\n- 🔴 Plant bulbs\n";
+        let mut h = Harness::new(doc, 80, 30);
+        assert!(h.app.doc.categories[1].hidden);
+        // Auto-promote leaves it alone too
+        assert_eq!(h.app.doc.categories[1].projects[0].tasks[0].state, crate::model::TaskState::Todo);
+        h.assert_visible("Pick a domain").assert_not_visible("Plant bulbs");
+        h.keys("<Tab>").assert_visible("Work").assert_not_visible("Garden");
+
+        h.keys("<Tab><Tab>");
+        h.app.settings_cursor = h.app.settings_row_index(crate::app::SettingsRow::Category(1));
+        h.assert_visible("Garden").assert_visible("(1 projects) hidden");
+        h.keys("<Space>").assert_visible("\"Garden\" shown again");
+        h.keys("<Tab><Tab>").assert_visible("Plant bulbs");
+        assert!(crate::serializer::serialize(&h.app.doc).contains("\n## Garden\n"));
+
+        h.keys("u").assert_not_visible("Garden");
+        assert!(crate::serializer::serialize(&h.app.doc).contains("\n## 💤 Garden\n"));
+    }
+
+    #[test]
+    fn test_bury() {
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- 🔶 Now\n- 🔵 A\n- 🔵 B\n- 🔵 C\n", 120, 20);
+        let agenda = |h: &Harness| h.app.agenda_items.iter().map(|i| i.task.text.clone()).collect::<Vec<_>>();
+
+        // The agenda sinks A within On Deck and keeps the cursor on it
+        h.keys("jb").assert_visible("Buried \"A\" at the bottom of On Deck");
+        assert_eq!(agenda(&h), ["Now", "B", "C", "A"]);
+        assert_eq!(h.app.agenda_items[h.app.agenda_cursor].task.text, "A");
+
+        // Still buried after the agenda is rebuilt
+        h.keys("kp");
+        assert_eq!(agenda(&h), ["Now", "C", "B", "A"]);
+        h.keys("x");
+        assert_eq!(agenda(&h), ["Now", "B", "C", "A"]);
+        h.keys("Gb").assert_visible("Unburied \"A\"");
+        assert_eq!(agenda(&h), ["Now", "A", "B", "C"]);
+
+        // The backlog moves the task itself to the bottom of its project
+        h.keys("<Tab>gjjjb").assert_visible("Moved \"A\" to the bottom of Launch");
+        let texts = |h: &Harness| h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(&h), ["Now", "B", "C", "A"]);
+        h.keys("b").assert_visible("Already at the bottom");
+        h.keys("u");
+        assert_eq!(texts(&h), ["Now", "A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_sink_done() {
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- ✅ Shipped\n- 🔶 Now\n- 🔵 Next\n", 80, 30);
+        let texts = |h: &Harness| h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.text.clone()).collect::<Vec<_>>();
+
+        // Turning it on sorts right away
+        h.keys("<Tab><Tab><Tab>");
+        h.app.settings_cursor = h.app.settings_row_index(crate::app::SettingsRow::SinkDone);
+        h.keys("<Enter>");
+        assert!(h.app.config.agenda.sink_done);
+        assert_eq!(texts(&h), ["Now", "Next", "Shipped"]);
+
+        // Finishing a task sends it down with the other done ones
+        h.keys("<Tab><Tab>gjjp");
+        assert_eq!(texts(&h), ["Next", "Now", "Shipped"]);
+    }
+
+    #[test]
+    fn test_single_tasking() {
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- 🔶 Now\n- 🔵 Next\n", 80, 20);
+        h.app.config.agenda.single_tasking = true;
+        h.keys("jp").assert_visible("\"Next\" → In Progress · \"Now\" back to On Deck");
+        let states: Vec<_> = h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.state).collect();
+        assert_eq!(states, [crate::model::TaskState::OnDeck, crate::model::TaskState::InProgress]);
+        assert_eq!(h.app.agenda_items[h.app.agenda_cursor].task.text, "Next");
+
+        // Finishing the one task doesn't touch anything else
+        h.keys("p").assert_visible("\"Next\" → Done").assert_not_visible("back to On Deck");
+
+        // A block promote keeps only the cursor's task In Progress
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- 🔶 Now\n- 🔵 A\n- 🔵 B\n", 80, 20);
+        h.app.config.agenda.single_tasking = true;
+        h.keys("<Tab>jjjmVjp").assert_visible("Promoted 2 tasks · 2 tasks back to On Deck");
+        let states: Vec<_> = h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.state).collect();
+        let (on_deck, in_progress) = (crate::model::TaskState::OnDeck, crate::model::TaskState::InProgress);
+        assert_eq!(states, [on_deck, on_deck, in_progress]);
+    }
+
+    #[test]
+    fn test_agenda_section_totals() {
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- 🔶 Draft ~1h\n- 🔶 Review ~30m\n- 🔵 Call\n", 80, 12);
+        h.assert_visible("── In Progress (2 · 1h 30m) ──").assert_visible("── On Deck (1) ──");
+        // Totals follow what is shown
+        h.keys("p").assert_visible("── In Progress (1 · 30m) ──");
+    }
+
+    #[test]
+    fn test_review_reminder() {
+        let doc = format!("{}\n## Inbox\n\n### Inbox\n- 🔴 Call plumber\n", DOC);
+        let mut h = Harness::new(&doc, 80, 20);
+        let today = crate::date::Date::today();
+
+        // Off by default
+        h.app.check_review_due();
+        assert!(h.app.status_msg.is_empty());
+
+        h.app.config.general.review_every_days = 7;
+        h.app.check_review_due();
+        h.assert_visible("Review due: press I to triage the inbox");
+
+        h.app.status_msg.clear();
+        h.app.last_review = Some(today.minus_days(9));
+        h.app.check_review_due();
+        h.assert_visible("Review due: inbox last triaged 9 days ago");
+
+        // Triaging to the end counts as the review; stopping early doesn't
+        h.keys("I<Esc>");
+        assert_eq!(h.app.last_review, Some(today.minus_days(9)));
+        h.keys("Is");
+        assert_eq!(h.app.last_review, Some(today));
+        h.app.status_msg.clear();
+        h.app.check_review_due();
+        assert!(h.app.status_msg.is_empty());
+    }
+
+    #[test]
+    fn test_new_day() {
+        let due = crate::date::Date::today();
+        let doc = format!("## Work\n### 🔶 Launch\n- ✅ Shipped\n- 🔵 Draft\n- 🔴 Renew passport due:{}\n- 🔴 Book venue\n", due);
+        let mut h = Harness::new(&doc, 120, 20);
+        h.app.start_new_day();
+        h.assert_visible("New Day")
+            .assert_visible("Archived 1 task")
+            .assert_visible("Due: \"Renew passport due:")
+            .assert_visible("in Launch → On Deck");
+        let states: Vec<_> = h.app.doc.categories[0].projects[0].tasks.iter().map(|t| t.state).collect();
+        assert_eq!(states, [crate::model::TaskState::OnDeck, crate::model::TaskState::OnDeck, crate::model::TaskState::Todo]);
+
+        // One u takes the lot back
+        h.keys("u").assert_not_visible("New Day");
+        assert_eq!(h.app.doc.categories[0].projects[0].tasks.len(), 4);
+
+        let mut h = Harness::new("## Work\n### 🔶 Launch\n- 🔵 Book venue\n", 80, 20);
+        h.app.start_new_day();
+        h.assert_visible("New day: nothing to tidy").assert_not_visible("New Day ·");
+    }
+
+    #[test]
+    fn test_archive_split_on_demand() {
+        let archive: String = (0..20_000).map(|i| format!("- ✅ [Work/Launch] Task {} (2026-03-10)\n", i)).collect();
+        let doc = format!("{}\n## Done\n{}", DOC, archive);
+        let mut h = Harness::new(&doc, 90, 16);
+        h.keys("x<Tab>jju").assert_visible("Learn piano");
+        let saved = h.app.serialize();
+        assert!(saved.ends_with("- ✅ [Work/Launch] Task 19999 (2026-03-10)\n"));
+        assert!(!h.app.doc.archive.is_split());
+
+        h.keys("U").assert_visible("Task 0  (Work › Launch, 2026-03-10)");
+        assert!(h.app.doc.archive.is_split());
+        assert_eq!(h.app.doc.archive.len(), 20_000);
+    }
+}
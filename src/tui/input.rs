@@ -1,55 +1,226 @@
+use std::path::PathBuf;
+
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::app::{App, Dialog, View};
+use crate::app::{App, Dialog, Retry, SettingsRow, View};
 use crate::model::TreeNodeKind;
 
 /// Action returned by input handling to tell the event loop what to do.
+#[derive(Debug)]
 pub enum Action {
     None,
     Save,
     Reload,
     Quit,
+    /// Quit without saving pending changes
+    QuitDiscard,
+    /// Hand the terminal back to the shell and stop (Ctrl-Z)
+    Suspend,
+    /// Write the focused category or project to a new file
+    Export(PathBuf),
+    /// Merge a file into the document
+    Import(PathBuf),
+    /// Write today's plan to a new .ics file
+    ExportIcs(PathBuf),
+    /// Save over a file that changed on disk, after merging it in
+    SaveMerged,
+    /// Save over a file that changed on disk, dropping its changes
+    SaveOverwrite,
+    /// Switch to another todo file, saving unsaved changes first
+    Open(PathBuf),
+    /// Move the focused Backlog task or project to another file
+    Move(PathBuf),
+    /// Gather the agenda of every profile
+    GlobalAgenda,
+    /// List the focused task's attached files, checking they are there
+    Attachments,
+    /// Open an attached file with the system's default application
+    OpenAttachment(PathBuf),
+    /// Fetch the page titles of these links in the background
+    FetchTitles(Vec<String>),
+    /// Read the latest lines of the debug log
+    ShowLog,
 }
 
 /// Handle a key event, mutating app state and returning an action for the event loop.
 pub fn handle_key(app: &mut App, key: KeyEvent) -> Action {
+    let action = dispatch_key(app, key);
+    app.advance_tutorial();
+    action
+}
+
+fn dispatch_key(app: &mut App, key: KeyEvent) -> Action {
+    // Command keys are read by their place on the keyboard; dialogs (typed
+    // text, and answers labelled by letter) and jump labels take keys as
+    // they are
+    let key = if app.dialog != Dialog::None || app.is_jumping() {
+        key
+    } else {
+        app.config.general.keyboard_layout.translate(key)
+    };
+
     // Dialog handling takes priority
     if app.dialog != Dialog::None {
         return handle_dialog_input(app, key);
     }
 
+    // Jump mode consumes label keys
+    if app.is_jumping() {
+        match key.code {
+            KeyCode::Char(c) => app.jump_key(c),
+            _ => app.cancel_jump(),
+        }
+        return Action::None;
+    }
+
+    // Second key of a two-key command
+    if let Some(prefix) = app.pending_key.take() {
+        app.count = None;
+        return handle_pending_key(app, prefix, key);
+    }
+
+    // Numeric count prefix (`5j`, `3p`, `10G`); a leading 0 is not a count
+    if let KeyCode::Char(c @ '0'..='9') = key.code
+        && (c != '0' || app.count.is_some())
+    {
+        let digit = c as usize - '0' as usize;
+        app.count = Some(app.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        return Action::None;
+    }
+    let count = app.count.take();
+
     // Move mode takes priority over normal view keys
     if app.is_moving() {
-        return handle_move_input(app, key);
+        return handle_move_input(app, key, count);
     }
 
     match app.view {
-        View::Agenda => handle_agenda_key(app, key),
-        View::Backlog => handle_backlog_key(app, key),
-        View::Settings => handle_settings_key(app, key),
+        View::Agenda => handle_agenda_key(app, key, count),
+        View::Backlog => handle_backlog_key(app, key, count),
+        View::Stats => handle_global_key(app, &key).unwrap_or(Action::None),
+        View::Settings => handle_settings_key(app, key, count),
+    }
+}
+
+/// Switch files, asking first what to do with unsaved changes.
+fn request_open(app: &mut App, path: PathBuf) -> Action {
+    if path == app.file_path {
+        app.status_msg = format!("{} is already open", path.display());
+        Action::None
+    } else if app.dirty {
+        app.pending_open = Some(path);
+        app.open_dialog(Dialog::ConfirmOpen);
+        Action::None
+    } else {
+        Action::Open(path)
+    }
+}
+
+/// Quit right away when there is nothing to lose; otherwise ask first.
+fn request_quit(app: &mut App) -> Action {
+    if app.dirty {
+        app.open_dialog(Dialog::ConfirmQuit);
+        Action::None
+    } else {
+        Action::Quit
+    }
+}
+
+/// Largest count prefix honoured, so a stray `999999999j` can't hang the UI.
+const MAX_COUNT: usize = 999;
+
+/// How many times the count prefix asks for (at least once).
+fn times(count: Option<usize>) -> usize {
+    count.unwrap_or(1).min(MAX_COUNT)
+}
+
+/// Run `f` once per repetition of the count prefix (at least once).
+fn repeat(app: &mut App, count: Option<usize>, f: fn(&mut App)) {
+    for _ in 0..times(count) {
+        f(app);
     }
 }
 
 // --- Move mode ---
 
-fn handle_move_input(app: &mut App, key: KeyEvent) -> Action {
+fn handle_move_input(app: &mut App, key: KeyEvent, count: Option<usize>) -> Action {
     match key.code {
-        KeyCode::Char('j') | KeyCode::Down => app.move_step(1),
-        KeyCode::Char('k') | KeyCode::Up => app.move_step(-1),
+        KeyCode::Char('j') | KeyCode::Down => repeat(app, count, |app| app.move_step(1)),
+        KeyCode::Char('k') | KeyCode::Up => repeat(app, count, |app| app.move_step(-1)),
         KeyCode::Enter => app.accept_move(),
         KeyCode::Esc => app.cancel_move(),
+        KeyCode::Char('V') if app.view == View::Backlog => app.start_block_selection(),
+        KeyCode::Char('m') => app.grab_block(),
+        KeyCode::Char('p') => app.cycle_block(true),
+        KeyCode::Char('x') => app.cycle_block(false),
+        KeyCode::Char('d') if app.block_range().is_some() => app.open_dialog(Dialog::ConfirmDelete),
         _ => {}
     }
     Action::None
 }
 
+// --- Two-key commands ---
+
+fn handle_pending_key(app: &mut App, prefix: char, key: KeyEvent) -> Action {
+    if prefix == 'z' && app.view == View::Backlog {
+        match key.code {
+            KeyCode::Char('M') => app.fold_to_depth(1),
+            KeyCode::Char('P') => app.fold_to_depth(2),
+            KeyCode::Char('R') => app.fold_to_depth(u8::MAX),
+            KeyCode::Char(c @ '1'..='4') => app.fold_to_depth(c as u8 - b'0'),
+            _ => {}
+        }
+    }
+    Action::None
+}
+
 // --- Global keys (shared across views) ---
 
 fn handle_global_key(app: &mut App, key: &KeyEvent) -> Option<Action> {
     match key.code {
-        KeyCode::Char('q') => Some(Action::Quit),
+        KeyCode::Char('q') => Some(request_quit(app)),
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            Some(Action::Quit)
+            Some(request_quit(app))
+        }
+        KeyCode::Char(':') => {
+            app.open_dialog(Dialog::Command);
+            Some(Action::None)
+        }
+        KeyCode::Char('O') => {
+            app.open_areas();
+            Some(Action::None)
+        }
+        KeyCode::Char('@') => {
+            app.open_people();
+            Some(Action::None)
+        }
+        KeyCode::Char('#') => {
+            app.open_tags();
+            Some(Action::None)
+        }
+        KeyCode::Char('I') => {
+            app.start_triage();
+            Some(Action::None)
+        }
+        KeyCode::Char('W') => {
+            app.open_day_plan();
+            Some(Action::None)
+        }
+        KeyCode::Char('F') => {
+            app.open_filter_picker();
+            Some(Action::None)
+        }
+        KeyCode::Char('B') => {
+            app.open_profile_picker();
+            Some(Action::None)
+        }
+        KeyCode::Char('E') => Some(Action::GlobalAgenda),
+        KeyCode::Char('U') => {
+            app.open_unarchive_picker();
+            Some(Action::None)
+        }
+        KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Action::Suspend)
         }
         KeyCode::Tab => {
             app.cycle_view();
@@ -57,36 +228,84 @@ fn handle_global_key(app: &mut App, key: &KeyEvent) -> Option<Action> {
         }
         KeyCode::Char('s') => Some(Action::Save),
         KeyCode::Char('R') => Some(Action::Reload),
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.half_page(1);
+            Some(Action::None)
+        }
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.half_page(-1);
+            Some(Action::None)
+        }
+        KeyCode::PageDown => {
+            app.full_page(1);
+            Some(Action::None)
+        }
+        KeyCode::PageUp => {
+            app.full_page(-1);
+            Some(Action::None)
+        }
+        KeyCode::Char(c @ ('H' | 'M' | 'L')) => {
+            app.move_to_viewport(c);
+            Some(Action::None)
+        }
+        KeyCode::Char('f') => {
+            app.start_jump();
+            Some(Action::None)
+        }
+        KeyCode::Char('u') => {
+            app.undo();
+            Some(Action::None)
+        }
+        KeyCode::Char('D') => {
+            app.toggle_hide_done();
+            Some(Action::None)
+        }
+        KeyCode::Char('X') => {
+            app.clear_modifiers();
+            Some(Action::None)
+        }
         _ => None,
     }
 }
 
 // --- Agenda view ---
 
-fn handle_agenda_key(app: &mut App, key: KeyEvent) -> Action {
+fn handle_agenda_key(app: &mut App, key: KeyEvent, count: Option<usize>) -> Action {
     if let Some(action) = handle_global_key(app, &key) {
         return action;
     }
 
     match key.code {
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.move_down(),
-        KeyCode::Char('k') | KeyCode::Up => app.move_up(),
+        KeyCode::Char('j') | KeyCode::Down => repeat(app, count, App::move_down),
+        KeyCode::Char('k') | KeyCode::Up => repeat(app, count, App::move_up),
         KeyCode::Char('g') => app.move_top(),
-        KeyCode::Char('G') => app.move_bottom(),
+        KeyCode::Char('G') => match count {
+            Some(line) => app.move_to_line(line),
+            None => app.move_bottom(),
+        },
         KeyCode::Char('l') => app.center_cursor(app.visible_height),
 
-        // Jump to backlog
+        // Jump to backlog, or just read the task's notes
         KeyCode::Enter => app.jump_to_backlog_task(),
+        KeyCode::Char('K') => app.open_task_details(),
+        KeyCode::Char('v') => return request_attachments(app),
 
-        // Move mode
+        // Move mode, or bury in one keypress
         KeyCode::Char('m') => app.start_move(),
+        KeyCode::Char('b') => app.bury_focused(),
 
         // Mutations
-        KeyCode::Char('p') => app.promote_selected_agenda(),
-        KeyCode::Char('x') => app.demote_selected_agenda(),
+        KeyCode::Char('p') => app.promote_selected_agenda(times(count)),
+        KeyCode::Char('x') => app.demote_selected_agenda(times(count)),
         KeyCode::Char('r') => app.run_auto_promote(),
         KeyCode::Char('A') => app.open_dialog(Dialog::ConfirmArchive),
+        KeyCode::Char('a') => app.open_dialog(Dialog::QuickAdd),
+
+        // Scope
+        KeyCode::Char('c') => app.cycle_agenda_category(),
+        KeyCode::Char('P') => app.open_scope_picker(),
+        KeyCode::Char('t') => app.toggle_show_todo(),
 
         _ => {}
     }
@@ -96,28 +315,43 @@ fn handle_agenda_key(app: &mut App, key: KeyEvent) -> Action {
 
 // --- Backlog view ---
 
-fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
+fn handle_backlog_key(app: &mut App, key: KeyEvent, count: Option<usize>) -> Action {
     if let Some(action) = handle_global_key(app, &key) {
         return action;
     }
 
     match key.code {
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.move_down(),
-        KeyCode::Char('k') | KeyCode::Up => app.move_up(),
+        KeyCode::Char('j') | KeyCode::Down => repeat(app, count, App::move_down),
+        KeyCode::Char('k') | KeyCode::Up => repeat(app, count, App::move_up),
         KeyCode::Char('g') => app.move_top(),
-        KeyCode::Char('G') => app.move_bottom(),
+        KeyCode::Char('G') => match count {
+            Some(line) => app.move_to_line(line),
+            None => app.move_bottom(),
+        },
         KeyCode::Char('l') => app.center_cursor(app.visible_height),
 
         // Collapse/expand
         KeyCode::Char(' ') => app.toggle_collapse(),
+        KeyCode::Char('z') => app.pending_key = Some('z'),
 
         // Promote/demote
-        KeyCode::Char('p') => app.promote_selected_backlog(),
-        KeyCode::Char('x') => app.demote_selected_backlog(),
+        KeyCode::Char('p') => app.promote_selected_backlog(times(count)),
+        KeyCode::Char('x') => app.demote_selected_backlog(times(count)),
 
-        // Move mode
+        // Move mode, or straight to the bottom of the project
         KeyCode::Char('m') => app.start_move(),
+        KeyCode::Char('b') => app.bury_focused(),
+
+        // Merge (join) tasks, or send one to another project
+        KeyCode::Char('J') => app.mark_or_merge(),
+        KeyCode::Char('>') => app.open_move_to_project(),
+
+        // Checklist flag on the focused project
+        KeyCode::Char('C') => app.toggle_checklist_focused(),
+        KeyCode::Char('T') => app.open_deadline_dialog(),
+        KeyCode::Char('V') => app.open_paste_import(""),
+        KeyCode::Esc if app.merge_mark.is_some() => app.cancel_merge(),
 
         // Add
         KeyCode::Char('a') => {
@@ -126,6 +360,9 @@ fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
                     TreeNodeKind::Category { .. } => {
                         app.open_dialog(Dialog::AddProject);
                     }
+                    TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => {
+                        app.open_dialog(Dialog::AddPreambleLine);
+                    }
                     _ => {
                         app.open_dialog(Dialog::AddTask);
                     }
@@ -153,6 +390,10 @@ fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
                         let text = app.focused_edit_text();
                         app.open_dialog_with_text(Dialog::EditExistingNote, &text);
                     }
+                    TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. } => {
+                        let text = app.focused_edit_text();
+                        app.open_dialog_with_text(Dialog::EditPreamble, &text);
+                    }
                 }
             }
         }
@@ -163,7 +404,8 @@ fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
                 match &node.kind {
                     TreeNodeKind::Task { .. }
                     | TreeNodeKind::Project { .. }
-                    | TreeNodeKind::Note { .. } => {
+                    | TreeNodeKind::Note { .. }
+                    | TreeNodeKind::PreambleLine { .. } => {
                         app.open_dialog(Dialog::ConfirmDelete);
                     }
                     _ => {}
@@ -171,14 +413,30 @@ fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
             }
         }
 
-        // Add note
+        // Filter
+        KeyCode::Char('/') => app.start_filter(),
+        KeyCode::Esc if !app.backlog_filter.is_empty() => app.clear_filter(),
+
+        // Sort menu
+        KeyCode::Char('o')
+            if app
+                .current_tree_node()
+                .is_some_and(|n| !matches!(n.kind, TreeNodeKind::Preamble | TreeNodeKind::PreambleLine { .. })) =>
+        {
+            app.open_dialog(Dialog::SortMenu);
+        }
+
+        // Add note (below the focused one on a note), or make a note a task
         KeyCode::Char('n') => {
-            if let Some(node) = app.current_tree_node() {
-                if matches!(&node.kind, TreeNodeKind::Task { .. }) {
-                    app.open_dialog(Dialog::EditNote);
-                }
+            if let Some(node) = app.current_tree_node()
+                && matches!(&node.kind, TreeNodeKind::Task { .. } | TreeNodeKind::Note { .. })
+            {
+                app.open_dialog(Dialog::EditNote);
             }
         }
+        KeyCode::Char('t') => app.note_to_task_focused(),
+        KeyCode::Char('K') => app.open_task_details(),
+        KeyCode::Char('v') => return request_attachments(app),
 
         // Auto-promote & archive
         KeyCode::Char('r') => app.run_auto_promote(),
@@ -192,42 +450,65 @@ fn handle_backlog_key(app: &mut App, key: KeyEvent) -> Action {
 
 // --- Settings view ---
 
-fn handle_settings_key(app: &mut App, key: KeyEvent) -> Action {
+fn handle_settings_key(app: &mut App, key: KeyEvent, count: Option<usize>) -> Action {
     if let Some(action) = handle_global_key(app, &key) {
         return action;
     }
 
-    // Theme row: cursor == 0
-    let on_theme_row = app.settings_cursor == 0;
+    let row = app.settings_row(app.settings_cursor);
+    let on_theme_row = row == SettingsRow::Theme;
 
     match key.code {
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => app.move_down(),
-        KeyCode::Char('k') | KeyCode::Up => app.move_up(),
+        KeyCode::Char('j') | KeyCode::Down => repeat(app, count, App::move_down),
+        KeyCode::Char('k') | KeyCode::Up => repeat(app, count, App::move_up),
 
-        // Theme cycling (h/l/arrows) when on theme row; l centers otherwise
+        // Theme cycling (h/l/arrows) when on the theme row; l centers otherwise
         KeyCode::Char('h') | KeyCode::Left if on_theme_row => app.prev_theme(),
         KeyCode::Char('l') | KeyCode::Right if on_theme_row => app.next_theme(),
         KeyCode::Char('l') => app.center_cursor(app.visible_height),
 
+        // Edit the preference under the cursor
+        KeyCode::Char(' ') | KeyCode::Enter => match row {
+            SettingsRow::RestoreSession => app.toggle_restore_session(),
+            SettingsRow::NewDay => app.toggle_new_day(),
+            SettingsRow::Accessible => app.toggle_accessible(),
+            SettingsRow::Autosave => app.toggle_autosave(),
+            SettingsRow::KeyboardLayout => app.next_keyboard_layout(),
+            SettingsRow::AgendaSection(_) => app.toggle_agenda_section(),
+            SettingsRow::PromoteMode => app.next_promote_mode(),
+            SettingsRow::PromoteStrategy => app.toggle_promote_strategy(),
+            SettingsRow::StalledAfter => app.next_stalled_after(),
+            SettingsRow::DemoteStalled => app.toggle_demote_stalled(),
+            SettingsRow::ShowCategory => app.toggle_show_category(),
+            SettingsRow::SinkDone => app.toggle_sink_done(),
+            SettingsRow::SingleTasking => app.toggle_single_tasking(),
+            SettingsRow::Theme => app.next_theme(),
+            SettingsRow::DefaultFile => open_default_file_dialog(app),
+            SettingsRow::PreserveUnknown => app.toggle_preserve_unknown(),
+            SettingsRow::RepoLocal => app.toggle_repo_local(),
+            SettingsRow::Category(_) => app.toggle_category_hidden(),
+            _ => {}
+        },
+
         // Add category
         KeyCode::Char('a') => app.open_dialog(Dialog::AddCategory),
 
+        KeyCode::Char('e') if row == SettingsRow::DefaultFile => open_default_file_dialog(app),
+
         // Rename category (only when on a category row)
         KeyCode::Char('e') => {
-            if let Some(cat_idx) = app.settings_category_idx() {
-                if let Some(cat) = app.doc.categories.get(cat_idx) {
-                    let name = cat.name.clone();
-                    app.open_dialog_with_text(Dialog::EditCategory, &name);
-                }
+            if let Some(cat_idx) = app.settings_category_idx()
+                && let Some(cat) = app.doc.categories.get(cat_idx)
+            {
+                let name = cat.name.clone();
+                app.open_dialog_with_text(Dialog::EditCategory, &name);
             }
         }
 
         // Delete category (only when on a category row)
-        KeyCode::Char('d') => {
-            if app.settings_category_idx().is_some() && !app.doc.categories.is_empty() {
-                app.open_dialog(Dialog::ConfirmDeleteCategory);
-            }
+        KeyCode::Char('d') if app.settings_category_idx().is_some() && !app.doc.categories.is_empty() => {
+            app.open_dialog(Dialog::ConfirmDeleteCategory);
         }
 
         // Move mode (only when on a category row)
@@ -239,18 +520,32 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> Action {
     Action::None
 }
 
+fn open_default_file_dialog(app: &mut App) {
+    let current = app
+        .config
+        .files
+        .default_file
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+    app.open_dialog_with_text(Dialog::EditDefaultFile, &current);
+}
+
 // --- Dialog input handling ---
 
 fn handle_dialog_input(app: &mut App, key: KeyEvent) -> Action {
     match app.dialog {
         Dialog::ConfirmArchive => handle_confirm_input(app, key, |app| app.archive_done()),
+        Dialog::ConfirmResetChecklist => handle_confirm_input(app, key, |app| app.reset_finished_checklist()),
         Dialog::ConfirmDelete => handle_confirm_input(app, key, |app| app.delete_focused()),
         Dialog::ConfirmDeleteCategory => handle_confirm_input(app, key, |app| app.delete_selected_category()),
-        Dialog::AddTask => handle_text_input(app, key, |app| app.add_task_to_focused()),
+        Dialog::AddTask => handle_completing_input(app, key, |app| app.add_task_to_focused()),
         Dialog::AddProject => handle_text_input(app, key, |app| app.add_project_to_focused()),
-        Dialog::EditTask | Dialog::EditProject => handle_text_input(app, key, |app| app.apply_edit()),
-        Dialog::EditNote => handle_text_input(app, key, |app| app.add_note_to_focused()),
-        Dialog::EditExistingNote => handle_text_input(app, key, |app| app.apply_edit()),
+        Dialog::EditTask | Dialog::EditExistingNote => handle_completing_input(app, key, |app| app.apply_edit()),
+        Dialog::EditProject => handle_text_input(app, key, |app| app.apply_edit()),
+        Dialog::EditNote => handle_completing_input(app, key, |app| app.add_note_to_focused()),
+        Dialog::EditPreamble => handle_text_input(app, key, |app| app.apply_edit()),
+        Dialog::AddPreambleLine => handle_text_input(app, key, |app| app.add_preamble_line_to_focused()),
         Dialog::AddCategory => handle_text_input(app, key, |app| app.add_category_from_input()),
         Dialog::EditCategory => {
             if app.view == View::Settings {
@@ -260,10 +555,484 @@ fn handle_dialog_input(app: &mut App, key: KeyEvent) -> Action {
                 handle_text_input(app, key, |app| app.apply_edit())
             }
         }
+        Dialog::SortMenu => handle_sort_menu_input(app, key),
+        Dialog::Filter => handle_filter_input(app, key),
+        Dialog::ConfirmQuit => handle_quit_input(app, key),
+        Dialog::Command => handle_command_input(app, key),
+        Dialog::EditDefaultFile => handle_text_input(app, key, |app| app.set_default_file_from_input()),
+        Dialog::ScopePicker => handle_picker_input(app, key, |app| app.apply_scope_picker()),
+        Dialog::People => handle_picker_input(app, key, |app| app.apply_people_picker()),
+        Dialog::Tags => handle_tags_input(app, key),
+        Dialog::RenameTag => handle_text_input(app, key, |app| app.rename_tag_from_input()),
+        Dialog::ConfirmDeleteTag => handle_confirm_input(app, key, |app| app.delete_tag()),
+        Dialog::PlanDay => handle_plan_input(app, key),
+        Dialog::PasteImport => handle_paste_import_input(app, key),
+        Dialog::ConfirmTriageUnsorted => handle_unsorted_prompt_input(app, key),
+        Dialog::Duplicates => handle_duplicates_input(app, key),
+        Dialog::SaveConflict => handle_save_conflict_input(app, key),
+        Dialog::Error => handle_error_input(app, key),
+        Dialog::RecentFiles => handle_recent_files_input(app, key),
+        Dialog::ConfirmOpen => handle_confirm_open_input(app, key),
+        Dialog::ProfilePicker => handle_profile_picker_input(app, key),
+        Dialog::MoveTarget => handle_move_target_input(app, key),
+        Dialog::GlobalAgenda => handle_global_agenda_input(app, key),
+        Dialog::Attachments => handle_attachments_input(app, key),
+        Dialog::Log => handle_log_input(app, key),
+        Dialog::QuickAdd => handle_completing_input(app, key, |app| app.apply_quick_add()),
+        Dialog::MoveToProject => handle_completing_input(app, key, |app| app.apply_move_to_project()),
+        Dialog::FilterPicker => handle_picker_input(app, key, |app| app.apply_filter_picker()),
+        Dialog::Unarchive => handle_picker_input(app, key, |app| app.apply_unarchive()),
+        Dialog::ProjectDeadline => handle_text_input(app, key, |app| app.set_deadline_from_input()),
+        Dialog::Triage => handle_triage_input(app, key),
+        Dialog::TriageDefer => {
+            handle_text_input(app, key, |app| app.triage_defer_from_input());
+            resume_triage(app)
+        }
+        Dialog::TriageDelegate => {
+            handle_text_input(app, key, |app| app.triage_delegate_from_input());
+            resume_triage(app)
+        }
+        Dialog::TriageMove => {
+            handle_picker_input(app, key, |app| app.apply_triage_move());
+            resume_triage(app)
+        }
+        Dialog::Areas => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q' | 'O')) {
+                app.close_dialog();
+            }
+            Action::None
+        }
+        Dialog::TaskDetails => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q' | 'K')) {
+                app.close_dialog();
+            }
+            Action::None
+        }
+        Dialog::NewDay => {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => app.close_dialog(),
+                KeyCode::Char('u') => {
+                    app.close_dialog();
+                    app.undo();
+                }
+                _ => {}
+            }
+            Action::None
+        }
         Dialog::None => Action::None,
     }
 }
 
+fn handle_triage_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('p') => app.triage_do_now(),
+        KeyCode::Char('f') => app.open_dialog(Dialog::TriageDefer),
+        KeyCode::Char('w') => app.open_dialog(Dialog::TriageDelegate),
+        KeyCode::Char('m') => app.open_triage_move_picker(),
+        KeyCode::Char('s') => app.triage_someday(),
+        KeyCode::Char('d') => app.triage_delete(),
+        KeyCode::Char('n') | KeyCode::Char(' ') => app.triage_skip(),
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.stop_triage();
+            app.status_msg = "Triage stopped".to_string();
+        }
+        _ => {}
+    }
+    Action::None
+}
+
+fn handle_plan_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.plan_move_cursor(1),
+        KeyCode::Char('k') | KeyCode::Up => app.plan_move_cursor(-1),
+        KeyCode::Char('d') | KeyCode::Char('x') => app.plan_drop(),
+        KeyCode::Char('r') => app.plan_regenerate(),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.plan_adjust_hours(1),
+        KeyCode::Char('-') => app.plan_adjust_hours(-1),
+        KeyCode::Enter => {
+            app.accept_day_plan();
+            app.close_dialog();
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.cancel_day_plan();
+            app.close_dialog();
+        }
+        _ => {}
+    }
+    Action::None
+}
+
+fn handle_recent_files_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => {
+            let picked = app.picked_recent_file();
+            app.close_dialog();
+            if let Some(path) = picked {
+                return request_open(app, path);
+            }
+        }
+        _ => return handle_picker_input(app, key, |_| {}),
+    }
+    Action::None
+}
+
+fn handle_tags_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('r') => app.open_rename_tag(),
+        KeyCode::Char('d') => app.open_delete_tag(),
+        _ => return handle_picker_input(app, key, |app| app.apply_tags_picker()),
+    }
+    Action::None
+}
+
+fn handle_profile_picker_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => {
+            let picked = app.picked_profile().map(|p| (p.name.clone(), p.file()));
+            app.close_dialog();
+            match picked {
+                Some((name, _)) if app.profile.as_deref() == Some(&name) => {
+                    app.status_msg = format!("Already on profile {}", name);
+                }
+                Some((_, file)) => return request_open(app, file),
+                None => {}
+            }
+        }
+        _ => return handle_picker_input(app, key, |_| {}),
+    }
+    Action::None
+}
+
+fn handle_move_target_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => {
+            let picked = app.picked_move_target();
+            app.close_dialog();
+            picked.map_or(Action::None, Action::Move)
+        }
+        _ => handle_picker_input(app, key, |_| {}),
+    }
+}
+
+fn handle_global_agenda_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.picker_move(1),
+        KeyCode::Char('k') | KeyCode::Up => app.picker_move(-1),
+        // Switch to the file of the task under the cursor
+        KeyCode::Enter => {
+            let file = app.global_agenda.get(app.picker.cursor).map(|item| item.file.clone());
+            app.close_global_agenda();
+            if let Some(file) = file.filter(|file| *file != app.file_path) {
+                return request_open(app, file);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('E') => app.close_global_agenda(),
+        _ => {}
+    }
+    Action::None
+}
+
+/// List the focused task's attached files, if it has any.
+fn request_attachments(app: &mut App) -> Action {
+    if app.focused_attachments().is_empty() {
+        app.status_msg = "No attached files: write @file(path) in the task or a note".to_string();
+        return Action::None;
+    }
+    Action::Attachments
+}
+
+fn handle_attachments_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Enter => {
+            let picked = app.picked_attachment();
+            app.close_dialog();
+            if let Some(path) = picked {
+                return Action::OpenAttachment(path);
+            }
+        }
+        KeyCode::Char('v') => app.close_dialog(),
+        _ => return handle_picker_input(app, key, |_| {}),
+    }
+    Action::None
+}
+
+fn handle_log_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('g') | KeyCode::Home => app.picker.cursor = 0,
+        KeyCode::Char('G') | KeyCode::End => app.picker.cursor = app.picker.items.len().saturating_sub(1),
+        KeyCode::Char('q') | KeyCode::Enter => app.close_dialog(),
+        _ => return handle_picker_input(app, key, |_| {}),
+    }
+    Action::None
+}
+
+fn handle_confirm_open_input(app: &mut App, key: KeyEvent) -> Action {
+    let save = match key.code {
+        KeyCode::Char('s') | KeyCode::Char('y') | KeyCode::Enter => true,
+        KeyCode::Char('d') | KeyCode::Char('n') => false,
+        KeyCode::Char('c') | KeyCode::Esc => {
+            app.pending_open = None;
+            app.close_dialog();
+            return Action::None;
+        }
+        _ => return Action::None,
+    };
+    app.close_dialog();
+    // Left dirty, the file is saved before switching
+    if !save {
+        app.dirty = false;
+    }
+    app.pending_open.take().map_or(Action::None, Action::Open)
+}
+
+fn handle_error_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('r') => {
+            let retry = app.io_error.as_ref().map(|e| e.retry);
+            app.close_error();
+            match retry {
+                Some(Retry::Save) => Action::Save,
+                Some(Retry::Reload) => Action::Reload,
+                None => Action::None,
+            }
+        }
+        KeyCode::Enter | KeyCode::Esc => {
+            app.close_error();
+            Action::None
+        }
+        _ => Action::None,
+    }
+}
+
+fn handle_save_conflict_input(app: &mut App, key: KeyEvent) -> Action {
+    let action = match key.code {
+        KeyCode::Char('m') => Action::SaveMerged,
+        KeyCode::Char('o') => Action::SaveOverwrite,
+        KeyCode::Char('r') => Action::Reload,
+        KeyCode::Char('c') | KeyCode::Esc => {
+            app.cancel_save_conflict();
+            return Action::None;
+        }
+        _ => return Action::None,
+    };
+    app.close_dialog();
+    action
+}
+
+fn handle_duplicates_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char(c @ ('r' | 'm')) => {
+            app.close_dialog();
+            app.fix_duplicates(c == 'm');
+        }
+        KeyCode::Char('l') | KeyCode::Esc => app.close_dialog(),
+        _ => {}
+    }
+    Action::None
+}
+
+/// Triage opens its own dialog, so the prompt closes before it starts.
+fn handle_unsorted_prompt_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            app.close_dialog();
+            app.start_unsorted_triage();
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.close_dialog(),
+        _ => {}
+    }
+    Action::None
+}
+
+/// The paste-import dialog takes lines as typed, Enter included, since
+/// terminals without bracketed paste send a paste as key presses.
+fn handle_paste_import_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.accept_paste_import();
+            app.close_dialog();
+        }
+        KeyCode::Esc => {
+            app.cancel_paste_import();
+            app.close_dialog();
+        }
+        KeyCode::Enter => app.paste_import_push("\n"),
+        KeyCode::Backspace => app.paste_import_backspace(),
+        KeyCode::Char(c) => app.paste_import_push(c.encode_utf8(&mut [0; 4])),
+        _ => {}
+    }
+    Action::None
+}
+
+/// Text pasted with bracketed paste. It goes into the paste-import dialog,
+/// opening one on the Backlog; other text dialogs get it as typed keys, with
+/// line breaks as spaces so a paste can't submit them. Anywhere else it is
+/// ignored rather than run as commands or dialog answers.
+pub fn handle_paste(app: &mut App, text: &str) {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    match app.dialog {
+        Dialog::PasteImport => app.paste_import_push(&text),
+        Dialog::None if app.view == View::Backlog => app.open_paste_import(&text),
+        dialog if !dialog.takes_text() => {}
+        _ => {
+            for c in text.chars() {
+                let c = if c == '\n' { ' ' } else { c };
+                handle_key(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+        }
+    }
+}
+
+/// Back to the triage card once a sub-dialog closes, if there is still
+/// something to triage.
+fn resume_triage(app: &mut App) -> Action {
+    if app.dialog == Dialog::None && app.triage.is_some() {
+        app.open_dialog(Dialog::Triage);
+    }
+    Action::None
+}
+
+fn handle_quit_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char('s') | KeyCode::Char('y') | KeyCode::Enter => {
+            app.close_dialog();
+            Action::Quit
+        }
+        KeyCode::Char('d') | KeyCode::Char('n') => {
+            app.close_dialog();
+            Action::QuitDiscard
+        }
+        KeyCode::Char('c') | KeyCode::Esc => {
+            app.close_dialog();
+            Action::None
+        }
+        _ => Action::None,
+    }
+}
+
+/// Vim-style command line: `:w`, `:q`, `:wq`/`:x`, `:q!` to quit
+/// without saving, `:e [file]` to switch files, `:move [file]` to move the
+/// focused task or project to another file, and `:plan` to plan the day.
+fn handle_command_input(app: &mut App, key: KeyEvent) -> Action {
+    if key.code != KeyCode::Enter {
+        return handle_text_input(app, key, |_| {});
+    }
+    let command = app.input_buffer.trim().to_string();
+    app.close_dialog();
+    match command.as_str() {
+        "w" => Action::Save,
+        "q" => request_quit(app),
+        "q!" => Action::QuitDiscard,
+        "wq" | "x" => Action::Quit,
+        "plan" => {
+            app.open_day_plan();
+            Action::None
+        }
+        "unarchive" => {
+            app.open_unarchive_picker();
+            Action::None
+        }
+        "retire" => {
+            app.retire_focused_project();
+            Action::None
+        }
+        "dedupe" => {
+            app.offer_duplicate_fix(true);
+            Action::None
+        }
+        "e" | "edit" => {
+            app.open_recent_picker();
+            Action::None
+        }
+        "move" => {
+            app.open_move_picker();
+            Action::None
+        }
+        "export" | "import" | "ics" => {
+            app.status_msg = format!("Usage: :{} <file>", command);
+            Action::None
+        }
+        "titles" => app.request_link_titles().map_or(Action::None, Action::FetchTitles),
+        "log" => match app.log_file {
+            Some(_) => Action::ShowLog,
+            None => {
+                app.status_msg = "No debug log: start gws with --log-file <path> or GWS_LOG=<path>".to_string();
+                Action::None
+            }
+        },
+        "color" => {
+            app.set_focused_accent("");
+            Action::None
+        }
+        "" => Action::None,
+        other if other.starts_with("export ") => Action::Export(app.resolve_path(other["export ".len()..].trim())),
+        other if other.starts_with("import ") => Action::Import(app.resolve_path(other["import ".len()..].trim())),
+        other if other.starts_with("ics ") => Action::ExportIcs(app.resolve_path(other["ics ".len()..].trim())),
+        other if other.starts_with("color ") => {
+            app.set_focused_accent(&other["color ".len()..]);
+            Action::None
+        }
+        other if other.starts_with("move ") => Action::Move(app.resolve_path(other["move ".len()..].trim())),
+        other if other.starts_with("e ") || other.starts_with("edit ") => {
+            let path = other.split_once(' ').map_or("", |(_, path)| path.trim());
+            let path = app.resolve_path(path);
+            request_open(app, path)
+        }
+        other => {
+            app.status_msg = format!("Unknown command: {}", other);
+            Action::None
+        }
+    }
+}
+
+fn handle_filter_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Esc => {
+            app.clear_filter();
+            app.close_dialog();
+            return Action::None;
+        }
+        KeyCode::Enter => {
+            app.close_dialog();
+            return Action::None;
+        }
+        KeyCode::Backspace => app.input_backspace(),
+        KeyCode::Delete => app.input_delete(),
+        KeyCode::Left => app.input_move_left(),
+        KeyCode::Right => app.input_move_right(),
+        KeyCode::Char(c) => app.input_char(c),
+        _ => return Action::None,
+    }
+    app.update_filter();
+    Action::None
+}
+
+fn handle_picker_input(app: &mut App, key: KeyEvent, on_confirm: fn(&mut App)) -> Action {
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down => app.picker_move(1),
+        KeyCode::Char('k') | KeyCode::Up => app.picker_move(-1),
+        KeyCode::Enter => {
+            on_confirm(app);
+            app.close_dialog();
+        }
+        KeyCode::Esc => app.close_dialog(),
+        _ => {}
+    }
+    Action::None
+}
+
+fn handle_sort_menu_input(app: &mut App, key: KeyEvent) -> Action {
+    match key.code {
+        KeyCode::Char(c @ ('n' | 'a' | 's' | 'p' | 'd')) => {
+            app.apply_sort(c);
+            app.close_dialog();
+        }
+        KeyCode::Esc => app.close_dialog(),
+        _ => {}
+    }
+    Action::None
+}
+
 fn handle_text_input(app: &mut App, key: KeyEvent, on_confirm: fn(&mut App)) -> Action {
     match key.code {
         KeyCode::Esc => {
@@ -293,6 +1062,26 @@ fn handle_text_input(app: &mut App, key: KeyEvent, on_confirm: fn(&mut App)) ->
     Action::None
 }
 
+/// A text dialog with a completion list under it: Tab takes the highlighted
+/// name, Up/Down (or Ctrl+P/N) pick another, anything else edits the text.
+fn handle_completing_input(app: &mut App, key: KeyEvent, on_confirm: fn(&mut App)) -> Action {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Tab => {
+            app.accept_completion();
+        }
+        KeyCode::Down => app.completion_step(1),
+        KeyCode::Up => app.completion_step(-1),
+        KeyCode::Char('n') if ctrl => app.completion_step(1),
+        KeyCode::Char('p') if ctrl => app.completion_step(-1),
+        _ => {
+            handle_text_input(app, key, on_confirm);
+            app.update_completion();
+        }
+    }
+    Action::None
+}
+
 fn handle_confirm_input(app: &mut App, key: KeyEvent, on_confirm: fn(&mut App)) -> Action {
     match key.code {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
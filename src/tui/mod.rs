@@ -1,3 +1,6 @@
+#[cfg(test)]
+pub mod harness;
+pub mod events;
 pub mod input;
 pub mod ui;
 pub mod views;
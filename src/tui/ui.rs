@@ -1,11 +1,12 @@
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, Tabs};
+use ratatui::widgets::{Paragraph, Tabs};
 use ratatui::Frame;
 
 use crate::app::{App, Dialog, View};
-use crate::tui::views::{agenda, backlog, settings};
+use crate::model::{TaskState, TreeNodeKind};
+use crate::tui::views::{agenda, backlog, settings, stats};
 use crate::tui::widgets;
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
@@ -25,44 +26,151 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
     match app.view {
         View::Agenda => agenda::draw(frame, app, chunks[1]),
         View::Backlog => backlog::draw(frame, app, chunks[1]),
+        View::Stats => stats::draw(frame, app, chunks[1]),
         View::Settings => settings::draw(frame, app, chunks[1]),
     }
 
     draw_status_bar(frame, app, chunks[2]);
+    widgets::draw_tutorial_overlay(frame, app, chunks[1]);
 
     // Draw dialogs on top
     match app.dialog {
-        Dialog::AddTask => widgets::draw_input_dialog(frame, app, "Add Task"),
+        Dialog::AddTask => widgets::draw_completing_dialog(frame, app, "Add Task"),
         Dialog::AddProject => widgets::draw_input_dialog(frame, app, "Add Project"),
         Dialog::EditTask | Dialog::EditProject | Dialog::EditCategory | Dialog::EditExistingNote => {
-            widgets::draw_input_dialog(frame, app, "Edit")
+            widgets::draw_completing_dialog(frame, app, "Edit")
         }
-        Dialog::EditNote => widgets::draw_input_dialog(frame, app, "Add Note"),
+        Dialog::EditNote => widgets::draw_completing_dialog(frame, app, "Add Note"),
+        Dialog::EditPreamble => widgets::draw_input_dialog(frame, app, "Edit Preamble"),
+        Dialog::AddPreambleLine => widgets::draw_input_dialog(frame, app, "Add Preamble Line"),
         Dialog::AddCategory => widgets::draw_input_dialog(frame, app, "Add Category"),
+        Dialog::EditDefaultFile => widgets::draw_input_dialog(frame, app, "Default File (empty for the data directory)"),
         Dialog::ConfirmArchive => widgets::draw_confirm_dialog(frame, app, "Archive all done tasks?"),
-        Dialog::ConfirmDelete => widgets::draw_confirm_dialog(frame, app, "Delete this item?"),
+        Dialog::ConfirmDelete => match app.block_delete_count() {
+            Some(1) => widgets::draw_confirm_dialog(frame, app, "Delete this task?"),
+            Some(count) => widgets::draw_confirm_dialog(frame, app, &format!("Delete these {} tasks?", count)),
+            None => widgets::draw_confirm_dialog(frame, app, "Delete this item?"),
+        },
+        Dialog::ConfirmResetChecklist => widgets::draw_confirm_dialog(frame, app, "Checklist done. Reset to Todo?"),
+        Dialog::ConfirmTriageUnsorted => {
+            widgets::draw_confirm_dialog(frame, app, "Triage the Unsorted tasks now?")
+        }
         Dialog::ConfirmDeleteCategory => widgets::draw_confirm_dialog(frame, app, "Delete this category and all its projects?"),
-        Dialog::None => {}
+        Dialog::ConfirmQuit => widgets::draw_menu_dialog(
+            frame,
+            app,
+            "Unsaved Changes",
+            &[("s", "Save & quit"), ("d", "Quit without saving"), ("c", "Cancel")],
+        ),
+        Dialog::Error => widgets::draw_error_dialog(frame, app),
+        Dialog::RecentFiles => widgets::draw_picker_dialog(frame, app, "Open Recent"),
+        Dialog::ProfilePicker => widgets::draw_picker_dialog(frame, app, "Profiles"),
+        Dialog::MoveTarget => widgets::draw_picker_dialog(frame, app, "Move To"),
+        Dialog::GlobalAgenda => widgets::draw_global_agenda_dialog(frame, app),
+        Dialog::QuickAdd => widgets::draw_completing_dialog(frame, app, "Add to Agenda (+Project, else the inbox)"),
+        Dialog::MoveToProject => widgets::draw_completing_dialog(frame, app, "Move Task to Project"),
+        Dialog::ConfirmOpen => widgets::draw_menu_dialog(
+            frame,
+            app,
+            "Unsaved Changes",
+            &[("s", "Save & open"), ("d", "Open without saving"), ("c", "Cancel")],
+        ),
+        Dialog::SaveConflict => widgets::draw_menu_dialog(
+            frame,
+            app,
+            "File Changed on Disk",
+            &[
+                ("m", "Merge it in, then save"),
+                ("o", "Overwrite it"),
+                ("r", "Reload it (drop my changes)"),
+                ("c", "Cancel"),
+            ],
+        ),
+        Dialog::Duplicates => widgets::draw_menu_dialog(
+            frame,
+            app,
+            "Duplicate Names",
+            &[("r", "Rename (number the later ones)"), ("m", "Merge into the first"), ("l", "Leave as is")],
+        ),
+        Dialog::Command => widgets::draw_input_dialog(frame, app, ":"),
+        Dialog::SortMenu => {
+            let on_category = matches!(
+                app.current_tree_node().map(|n| &n.kind),
+                Some(TreeNodeKind::Category { .. })
+            );
+            let mut options = vec![("n", "Projects by name"), ("a", "Active projects first")];
+            if !on_category {
+                options.extend([("s", "Tasks by state"), ("p", "Tasks by priority"), ("d", "Tasks by due date")]);
+            }
+            widgets::draw_menu_dialog(frame, app, "Sort", &options);
+        }
+        Dialog::ScopePicker => widgets::draw_picker_dialog(frame, app, "Agenda Scope"),
+        Dialog::People => widgets::draw_picker_dialog(frame, app, "People"),
+        Dialog::Tags => widgets::draw_picker_dialog(frame, app, "Tags"),
+        Dialog::RenameTag => widgets::draw_input_dialog(frame, app, "Rename Tag Everywhere"),
+        Dialog::ConfirmDeleteTag => widgets::draw_confirm_dialog(frame, app, "Remove this tag from every task?"),
+        Dialog::PlanDay => widgets::draw_plan_dialog(frame, app),
+        Dialog::PasteImport => widgets::draw_paste_import_dialog(frame, app),
+        Dialog::FilterPicker => widgets::draw_picker_dialog(frame, app, "Saved Filters"),
+        Dialog::Unarchive => widgets::draw_picker_dialog(frame, app, "Reopen from Archive"),
+        Dialog::ProjectDeadline => {
+            widgets::draw_input_dialog(frame, app, "Project deadline (YYYY-MM-DD, +2w; empty clears)")
+        }
+        Dialog::Areas => widgets::draw_areas_dialog(frame, app),
+        Dialog::NewDay => widgets::draw_new_day_dialog(frame, app),
+        Dialog::TaskDetails => widgets::draw_task_details_dialog(frame, app),
+        Dialog::Attachments => widgets::draw_picker_dialog(frame, app, "Attached Files"),
+        Dialog::Log => widgets::draw_wide_picker_dialog(frame, app, "Debug Log", 90),
+        Dialog::Triage => widgets::draw_triage_dialog(frame, app),
+        Dialog::TriageDefer => widgets::draw_input_dialog(frame, app, "Defer until (YYYY-MM-DD, tomorrow, +3, +2w)"),
+        Dialog::TriageDelegate => widgets::draw_input_dialog(frame, app, "Delegate to @"),
+        Dialog::TriageMove => widgets::draw_picker_dialog(frame, app, "Move to Project"),
+        Dialog::Filter | Dialog::None => {}
     }
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
-    let titles = vec![" Agenda ", " Backlog ", " Settings "];
+    let titles = vec![
+        tab_title("Agenda", &agenda_counts(app)),
+        tab_title("Backlog", &backlog_counts(app)),
+        " Stats ".to_string(),
+        " Settings ".to_string(),
+    ];
     let selected = match app.view {
         View::Agenda => 0,
         View::Backlog => 1,
-        View::Settings => 2,
+        View::Stats => 2,
+        View::Settings => 3,
+    };
+
+    // File name on the left, where the focused item lives on the right
+    let file_name = app
+        .file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let title = match &app.profile {
+        Some(profile) => format!(" GWS · {} · {} ", profile, file_name),
+        None => format!(" GWS · {} ", file_name),
     };
+    let mut block = widgets::block(app).title(title);
+    let crumbs = app.breadcrumb();
+    if !crumbs.is_empty() {
+        let separator = if app.accessible() { " > " } else { " › " };
+        block = block.title(
+            Line::from(Span::styled(
+                format!(" {} ", crumbs.join(separator)),
+                Style::default().fg(theme.text_dim),
+            ))
+            .right_aligned(),
+        );
+    }
 
     let tabs = Tabs::new(titles)
-        .block(
-            Block::default()
-                .title(" GWS - Getting Work Sorted ")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(theme.border)),
-        )
+        .block(block)
         .select(selected)
+        .divider(widgets::glyphs(app).divider)
         .style(Style::default().fg(theme.tab_inactive))
         .highlight_style(
             Style::default()
@@ -73,31 +181,118 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(tabs, area);
 }
 
+fn tab_title(name: &str, counts: &[String]) -> String {
+    if counts.is_empty() {
+        format!(" {} ", name)
+    } else {
+        format!(" {} ({}) ", name, counts.join(", "))
+    }
+}
+
+/// Agenda rows by state, e.g. ["3 in progress", "5 on deck"].
+fn agenda_counts(app: &App) -> Vec<String> {
+    [(TaskState::InProgress, "in progress"), (TaskState::OnDeck, "on deck"), (TaskState::Done, "done")]
+        .into_iter()
+        .filter_map(|(state, label)| {
+            let n = app.agenda_items.iter().filter(|item| item.task.state == state).count();
+            (n > 0).then(|| format!("{} {}", n, label))
+        })
+        .collect()
+}
+
+/// Open tasks across the whole document.
+fn backlog_counts(app: &App) -> Vec<String> {
+    let open = app
+        .doc
+        .categories
+        .iter()
+        .flat_map(|c| &c.projects)
+        .flat_map(|p| &p.tasks)
+        .filter(|t| t.state != TaskState::Done)
+        .count();
+    if open == 0 { Vec::new() } else { vec![format!("{} open", open)] }
+}
+
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
     let dirty_indicator = if app.dirty { " [modified]" } else { "" };
-    let status = if app.status_msg.is_empty() {
-        String::new()
-    } else {
-        format!(" {} ", app.status_msg)
+    let status = match (app.count, app.status_msg.is_empty()) {
+        // Accessible mode always starts the line the same way so screen
+        // readers announce changes predictably
+        (Some(count), _) if app.accessible() => format!(" Status: count {}. ", count),
+        (None, true) if app.accessible() => " Status: ready. ".to_string(),
+        (None, false) if app.accessible() => format!(" Status: {}. ", app.status_msg),
+        (Some(count), _) => format!(" {} ", count),
+        (None, true) => String::new(),
+        (None, false) => format!(" {} ", app.status_msg),
     };
 
-    let help = if app.is_moving() {
+    let help = if app.is_jumping() {
+        "Type label to jump  Esc:Cancel"
+    } else if app.block_grabbed() {
+        "j/k:Move  Enter:Accept  Esc:Cancel"
+    } else if app.block_range().is_some() {
+        "j/k:Select  m:Move  p/x:Cycle  d:Del  Esc:Cancel"
+    } else if app.is_moving() && app.view == View::Backlog {
+        "j/k:Move  V:Select  Enter:Accept  Esc:Cancel"
+    } else if app.is_moving() {
         "j/k:Move  Enter:Accept  Esc:Cancel"
     } else {
         match app.dialog {
             Dialog::None => match app.view {
-                View::Agenda => "q:Quit  Tab:View  j/k:Nav  l:Center  m:Move  p:Promote  x:Demote  r:Auto  A:Archive  s:Save",
-                View::Backlog => "q:Quit  Tab:View  j/k:Nav  l:Center  Space:Fold  p/x:Cycle  a:Add  e:Edit  d:Del  m:Move  n:Note  s:Save",
-                View::Settings => "q:Quit  Tab:View  j/k:Nav  l:Center  h/l:Theme  a:Add  e:Rename  d:Del  m:Move  s:Save",
+                View::Agenda => "q:Quit  Tab:View  j/k:Nav  l:Center  K:Details  v:Files  a:Add  m:Move  p:Promote  x:Demote  r:Auto  A:Archive  c/P:Scope  t:Not Started  D:Hide Done  u:Undo  s:Save",
+                View::Backlog => "q:Quit  Tab:View  j/k:Nav  l:Center  Space/z:Fold  p/x:Cycle  a:Add  V:Paste  e:Edit  d:Del  m:Move  >:To Project  J:Merge  C:Checklist  T:Deadline  n:Note  t:Note→Task  K:Details  v:Files  /:Filter  o:Sort  D:Hide Done  u:Undo  s:Save",
+                View::Stats => "q:Quit  Tab:View  O:Areas  @:People  #:Tags  s:Save",
+                View::Settings => "q:Quit  Tab:View  j/k:Nav  l:Center  Space/Enter:Change  h/l:Theme  a:Add  e:Rename  d:Del  m:Move  s:Save",
             },
-            Dialog::ConfirmArchive | Dialog::ConfirmDelete | Dialog::ConfirmDeleteCategory => {
+            Dialog::ConfirmArchive
+            | Dialog::ConfirmDelete
+            | Dialog::ConfirmDeleteCategory
+            | Dialog::ConfirmResetChecklist
+            | Dialog::ConfirmTriageUnsorted
+            | Dialog::ConfirmDeleteTag => {
                 "y:Yes  n/Esc:No"
             }
+            Dialog::SortMenu => "n/a/s/p/d:Sort  Esc:Cancel",
+            Dialog::ConfirmQuit => "s:Save & Quit  d:Discard  c/Esc:Cancel",
+            Dialog::Duplicates => "r:Rename  m:Merge  l/Esc:Leave as is",
+            Dialog::Error => "r:Retry  Enter/Esc:Close",
+            Dialog::RecentFiles | Dialog::ProfilePicker => "j/k:Nav  Enter:Open  Esc:Cancel",
+            Dialog::MoveTarget => "j/k:Nav  Enter:Move  Esc:Cancel",
+            Dialog::GlobalAgenda => "j/k:Nav  Enter:Open its file  Esc/E:Close",
+            Dialog::QuickAdd => "+:Project  #:Tag  Tab:Complete  Up/Down:Choose  Enter:Add  Esc:Cancel",
+            Dialog::MoveToProject => "Tab:Complete  Up/Down:Choose  Enter:Move  Esc:Cancel",
+            Dialog::ConfirmOpen => "s:Save & Open  d:Discard  c/Esc:Cancel",
+            Dialog::SaveConflict => "m:Merge & Save  o:Overwrite  r:Reload  c/Esc:Cancel",
+            Dialog::Command => "w:Save  q:Quit  wq:Save & Quit  q!:Quit without saving",
+            Dialog::AddTask | Dialog::EditTask | Dialog::EditNote | Dialog::EditExistingNote => {
+                "#:Tag  Tab:Complete  Enter:Confirm  Esc:Cancel"
+            }
+            Dialog::Filter => "Type to filter  Enter:Keep  Esc:Clear",
+            Dialog::ScopePicker => "j/k:Nav  Enter:Select  Esc:Cancel",
+            Dialog::People => "j/k:Nav  Enter:Show tasks  Esc:Close",
+            Dialog::Tags => "j/k:Nav  Enter:Show tasks  r:Rename  d:Delete  Esc:Close",
+            Dialog::FilterPicker => "j/k:Nav  Enter:Apply  Esc:Cancel",
+            Dialog::Unarchive => "j/k:Nav  Enter:Reopen  Esc:Cancel",
+            Dialog::Triage => "p:Do now  f:Defer  w:Delegate  m:Move  s:Someday  d:Delete  n:Skip  Esc:Stop",
+            Dialog::TriageMove => "j/k:Nav  Enter:Move  Esc:Back",
+            Dialog::Areas => "Esc:Close",
+            Dialog::NewDay => "Enter/Esc:Close  u:Undo all",
+            Dialog::TaskDetails => "Enter/Esc/K:Close",
+            Dialog::Attachments => "j/k:Nav  Enter:Open  Esc:Close",
+            Dialog::Log => "j/k:Scroll  g/G:Oldest/Newest  Esc:Close",
+            Dialog::PlanDay => "j/k:Nav  d:Drop  r:Regenerate  +/-:Hours  Enter:Accept  Esc:Cancel",
+            Dialog::PasteImport => "Paste or type one task per line  Enter:New line  Ctrl+S:Add all  Esc:Cancel",
             _ => "Enter:Confirm  Esc:Cancel",
         }
     };
 
+    let help = if app.dialog != Dialog::None || app.is_jumping() {
+        help.to_string()
+    } else {
+        app.config.general.keyboard_layout.relabel(help)
+    };
+
     let line = Line::from(vec![
         Span::styled(
             &status,
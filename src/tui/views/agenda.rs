@@ -1,13 +1,17 @@
 use ratatui::layout::Rect;
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
+use ratatui::widgets::{List, ListItem, ListState};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, View};
+use crate::date::Date;
 use crate::model::TaskState;
+use crate::planner;
+use crate::theme::accent_color;
+use crate::tui::widgets;
 
-fn section_label(state: TaskState) -> &'static str {
+pub fn section_label(state: TaskState) -> &'static str {
     match state {
         TaskState::Todo => "Not Started",
         TaskState::InProgress => "In Progress",
@@ -16,15 +20,22 @@ fn section_label(state: TaskState) -> &'static str {
     }
 }
 
+/// Narrowest a task's text is squeezed to before the project name gives way.
+const MIN_TEXT_WIDTH: usize = 12;
+
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
     let visible_height = area.height.saturating_sub(2) as usize; // borders
+    let inner_width = area.width.saturating_sub(2) as usize;
     let mut items: Vec<ListItem> = Vec::new();
 
     let scroll = app.agenda_scroll;
     let is_moving = app.is_moving();
+    let glyphs = widgets::glyphs(app);
+    let accessible = app.accessible();
+    let today = Date::today();
 
-    if app.agenda_items.is_empty() {
+    if app.agenda_items.is_empty() && app.hidden_todo_count == 0 {
         items.push(ListItem::new(Line::from(Span::styled(
             "  No active tasks. Press Tab to go to Backlog.",
             Style::default().fg(theme.text_dim),
@@ -39,10 +50,17 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
             let current_state = agenda_item.task.state;
 
             // Render section header if state changed (or first visible item)
-            if prev_state.map_or(true, |prev| prev != current_state) {
+            if prev_state != Some(current_state) {
                 let label = section_label(current_state);
+                let size = match app.agenda_totals.iter().find(|t| t.state == current_state) {
+                    Some(total) if total.minutes > 0 => {
+                        format!("{} · {}", total.count, planner::format_minutes(total.minutes))
+                    }
+                    Some(total) => total.count.to_string(),
+                    None => String::new(),
+                };
                 items.push(ListItem::new(Line::from(Span::styled(
-                    format!("  ── {} ──", label),
+                    format!("  {} {} ({}) {}", glyphs.rule, label, size, glyphs.rule),
                     Style::default()
                         .fg(theme.text_dim)
                         .add_modifier(Modifier::BOLD),
@@ -64,6 +82,8 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
                 TaskState::Done => theme.state_done,
             };
 
+            let project = &app.doc.categories[agenda_item.category_idx].projects[agenda_item.project_idx];
+            let accent = project.accent.as_deref().and_then(accent_color);
             let style = if is_selected && is_moving {
                 Style::default()
                     .fg(theme.moving)
@@ -73,15 +93,15 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
                     .fg(theme.selected)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(theme.text)
+                Style::default().fg(accent.unwrap_or(theme.text))
             };
 
             let prefix = if is_selected && is_moving {
-                " ↕ "
+                format!(" {} ", glyphs.moving)
             } else if is_selected {
-                " ▸ "
+                format!(" {} ", glyphs.cursor)
             } else {
-                "   "
+                "   ".to_string()
             };
 
             let prefix_style = if is_selected {
@@ -94,28 +114,85 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
                 Style::default()
             };
 
-            let project_label = format!(" ({})", agenda_item.project_name);
-
-            items.push(ListItem::new(Line::from(vec![
-                Span::styled(prefix.to_string(), prefix_style),
-                Span::styled(
+            let prefix = widgets::jump_label_span(app, item_idx, 3)
+                .unwrap_or_else(|| Span::styled(prefix, prefix_style));
+            let stalled = app.stalled_days(agenda_item.category_idx, agenda_item.project_idx, agenda_item.task_idx);
+            let line = if accessible {
+                let mut description = widgets::describe_agenda_item(agenda_item, &app.agenda_item_place(agenda_item));
+                if let Some(days) = stalled {
+                    description.push_str(&format!(", stalled {} days", days));
+                }
+                Line::from(vec![prefix, Span::styled(description, style)])
+            } else {
+                let dot = Span::styled(
                     format!("{} ", agenda_item.task.state.dot()),
                     Style::default().fg(dot_color),
-                ),
-                Span::styled(agenda_item.task.text.clone(), style),
-                Span::styled(project_label, Style::default().fg(theme.text_dim)),
-            ])));
+                );
+                let overdue = project.days_left(today).is_some_and(|days| days < 0);
+                let project = Span::styled(
+                    format!(" ({})", app.agenda_item_place(agenda_item)),
+                    Style::default().fg(if overdue { theme.status_error } else { accent.unwrap_or(theme.text_dim) }),
+                );
+                let flag = Span::styled(
+                    stalled.map_or(String::new(), |days| format!(" stalled {}d", days)),
+                    Style::default().fg(theme.status_error),
+                );
+                // Shorten long task text rather than letting the project name
+                // fall off the right edge
+                let room = inner_width.saturating_sub(prefix.width() + dot.width() + project.width() + flag.width());
+                let text = if room >= MIN_TEXT_WIDTH {
+                    widgets::truncate_to_width(&agenda_item.task.text, room)
+                } else {
+                    agenda_item.task.text.clone()
+                };
+                Line::from(vec![prefix, dot, Span::styled(text, style), project, flag])
+            };
+            items.push(ListItem::new(line));
             rows_used += 1;
             item_idx += 1;
+
+            // First note line under the selected task, e.g. a phone number
+            if let Some(note) = app.agenda_note_preview().filter(|_| is_selected && rows_used < visible_height) {
+                let indent = if accessible { "   Note: " } else { "     " };
+                let note = widgets::truncate_to_width(note, inner_width.saturating_sub(indent.len()));
+                items.push(ListItem::new(Line::from(Span::styled(
+                    format!("{}{}", indent, note),
+                    Style::default().fg(theme.text_dim).add_modifier(Modifier::ITALIC),
+                ))));
+                rows_used += 1;
+            }
+        }
+
+        // Collapsed Not Started section sits at the end, where it would render
+        if app.hidden_todo_count > 0 && item_idx >= app.agenda_items.len() && rows_used < visible_height {
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!(
+                    "  {} {} ({} hidden) {}",
+                    glyphs.rule,
+                    section_label(TaskState::Todo),
+                    app.hidden_todo_count,
+                    glyphs.rule
+                ),
+                Style::default().fg(theme.text_dim),
+            ))));
         }
     }
 
-    let list = List::new(items).block(
-        Block::default()
-            .title(" Agenda ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.border)),
-    );
+    let modifiers = app.view_modifiers(View::Agenda);
+    let title = if modifiers.is_empty() {
+        " Agenda ".to_string()
+    } else {
+        format!(" Agenda {} {} ", if accessible { "-" } else { "—" }, modifiers.join(", "))
+    };
+
+    let mut block = widgets::block(app).title(title);
+    if let Some(history) = app.focused_task_history() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {} ", history),
+            Style::default().fg(theme.text_dim),
+        )));
+    }
+    let list = List::new(items).block(block);
 
     let mut state = ListState::default();
     frame.render_stateful_widget(list, area, &mut state);
@@ -1,12 +1,14 @@
 use ratatui::layout::Rect;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
+use ratatui::widgets::{List, ListItem, ListState};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, Dialog, View};
+use crate::date::Date;
 use crate::model::{TaskState, TreeNodeKind};
-use crate::theme::Theme;
+use crate::theme::{accent_color, Theme};
+use crate::tui::widgets;
 
 fn dot_color(theme: &Theme, state: TaskState) -> Color {
     match state {
@@ -20,16 +22,25 @@ fn dot_color(theme: &Theme, state: TaskState) -> Color {
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
     let theme = app.theme();
     let visible_height = area.height.saturating_sub(2) as usize; // borders
+    let inner_width = area.width.saturating_sub(2) as usize;
     let mut items: Vec<ListItem> = Vec::new();
 
     let scroll = app.backlog_scroll;
     let end = (scroll + visible_height).min(app.tree_nodes.len());
 
     let is_moving = app.is_moving();
+    let block = app.block_range();
+    let glyphs = widgets::glyphs(app);
+    let accessible = app.accessible();
+    let today = Date::today();
 
     for idx in scroll..end {
         let node = &app.tree_nodes[idx];
-        let is_selected = idx == app.backlog_cursor;
+        let in_block = block.is_some_and(|(ci, pi, start, end)| {
+            matches!(node.kind, TreeNodeKind::Task { cat_idx, proj_idx, task_idx }
+                if (cat_idx, proj_idx) == (ci, pi) && (start..=end).contains(&task_idx))
+        });
+        let is_selected = idx == app.backlog_cursor || in_block;
 
         let indent = "    ".repeat(node.depth as usize);
 
@@ -43,6 +54,17 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
             }
             _ => None,
         };
+        let days_left = match &node.kind {
+            TreeNodeKind::Project { cat_idx, proj_idx } => app.doc.categories[*cat_idx].projects[*proj_idx].days_left(today),
+            _ => None,
+        };
+        let overdue = days_left.is_some_and(|days| days < 0);
+        let accent = match &node.kind {
+            TreeNodeKind::Project { cat_idx, proj_idx } => {
+                app.doc.categories[*cat_idx].projects[*proj_idx].accent.as_deref().and_then(accent_color)
+            }
+            _ => None,
+        };
 
         let (line, style) = if is_selected && is_moving {
             let style = Style::default()
@@ -51,6 +73,22 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
             (node.display.clone(), style)
         } else {
             match &node.kind {
+                TreeNodeKind::Preamble => {
+                    let style = Style::default()
+                        .fg(theme.category)
+                        .add_modifier(Modifier::BOLD | Modifier::ITALIC);
+                    (node.display.clone(), style)
+                }
+                TreeNodeKind::PreambleLine { .. } => {
+                    let style = if is_selected {
+                        Style::default()
+                            .fg(theme.selected)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(theme.text_dim)
+                    };
+                    (node.display.clone(), style)
+                }
                 TreeNodeKind::Category { .. } => {
                     let style = Style::default()
                         .fg(theme.category)
@@ -62,13 +100,21 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
                         Style::default()
                             .fg(theme.selected)
                             .add_modifier(Modifier::BOLD)
+                    } else if overdue {
+                        Style::default().fg(theme.status_error).add_modifier(Modifier::BOLD)
                     } else {
-                        Style::default().fg(theme.project)
+                        Style::default().fg(accent.unwrap_or(theme.project))
                     };
                     (node.display.clone(), style)
                 }
-                TreeNodeKind::Task { .. } => {
-                    let style = if is_selected {
+                TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+                    let marked = app.merge_mark.as_ref().is_some_and(|m| {
+                        (m.cat_idx, m.proj_idx, m.task_idx) == (*cat_idx, *proj_idx, *task_idx)
+                    });
+                    let style = if marked {
+                        // Waiting to be merged into another task
+                        Style::default().fg(theme.moving).add_modifier(Modifier::ITALIC)
+                    } else if is_selected {
                         Style::default()
                             .fg(theme.selected)
                             .add_modifier(Modifier::BOLD)
@@ -85,9 +131,9 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
         };
 
         let prefix = if is_selected && is_moving {
-            "↕"
+            glyphs.moving
         } else if is_selected {
-            "▸"
+            glyphs.cursor
         } else {
             " "
         };
@@ -103,36 +149,65 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
         };
 
         let mut spans = vec![
-            Span::styled(prefix.to_string(), prefix_style),
+            widgets::jump_label_span(app, idx, 2)
+                .unwrap_or_else(|| Span::styled(prefix.to_string(), prefix_style)),
             Span::styled(indent, Style::default()),
         ];
 
         // Add colored dot for task nodes
-        if let Some(state) = task_state {
+        let line = if accessible { widgets::describe_tree_node(app, node) } else { line };
+        if let (Some(state), false) = (task_state, accessible) {
             spans.push(Span::styled(
                 format!("{} ", state.dot()),
                 Style::default().fg(dot_color(theme, state)),
             ));
         }
 
-        spans.push(Span::styled(line, style));
+        let deadline = match (days_left, accessible) {
+            (Some(days), false) => Span::styled(
+                format!("  {}", widgets::deadline_label(days)),
+                Style::default().fg(if overdue { theme.status_error } else { theme.text_dim }),
+            ),
+            _ => Span::raw(""),
+        };
+        let room = inner_width.saturating_sub(spans.iter().map(Span::width).sum::<usize>() + deadline.width());
+        spans.push(Span::styled(widgets::truncate_to_width(&line, room), style));
+        spans.push(deadline);
 
         items.push(ListItem::new(Line::from(spans)));
     }
 
     if items.is_empty() {
         items.push(ListItem::new(Line::from(Span::styled(
-            "  No categories. Press 'a' to add one.",
+            if app.backlog_filter.is_empty() && app.active_filter.is_none() {
+                "  No categories. Press 'a' to add one."
+            } else {
+                "  No matches."
+            },
             Style::default().fg(theme.text_dim),
         ))));
     }
 
-    let list = List::new(items).block(
-        Block::default()
-            .title(" Backlog ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.border)),
-    );
+    let mut title = if app.dialog == Dialog::Filter {
+        format!(" Backlog /{}{} ", app.backlog_filter, glyphs.text_cursor)
+    } else if !app.backlog_filter.is_empty() {
+        format!(" Backlog /{} ", app.backlog_filter)
+    } else {
+        " Backlog ".to_string()
+    };
+    let modifiers = app.view_modifiers(View::Backlog);
+    if !modifiers.is_empty() {
+        title.push_str(&format!("{} {} ", if accessible { "-" } else { "—" }, modifiers.join(", ")));
+    }
+
+    let mut block = widgets::block(app).title(title);
+    if let Some(history) = app.focused_task_history() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {} ", history),
+            Style::default().fg(theme.text_dim),
+        )));
+    }
+    let list = List::new(items).block(block);
 
     let mut state = ListState::default();
     frame.render_stateful_widget(list, area, &mut state);
@@ -1,3 +1,4 @@
 pub mod agenda;
 pub mod backlog;
 pub mod settings;
+pub mod stats;
@@ -1,102 +1,125 @@
 use ratatui::layout::Rect;
-use ratatui::style::{Modifier, Style};
-use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
 use ratatui::Frame;
 
-use crate::app::App;
+use crate::app::{App, SettingsRow, KEYBINDINGS};
+use crate::date::Date;
 use crate::theme::Theme;
+use crate::tui::views::agenda::section_label;
+use crate::tui::widgets::{self, FormRow, FormValue};
 
 pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
-    let theme = app.theme();
-    let visible_height = area.height.saturating_sub(2) as usize; // borders
-    let mut items: Vec<ListItem> = Vec::new();
-
-    let total = app.settings_total();
-    let scroll = app.settings_scroll;
-    let end = (scroll + visible_height).min(total);
-
-    for idx in scroll..end {
-        let is_selected = idx == app.settings_cursor;
-
-        if idx == 0 {
-            // Theme row
-            let theme_name = Theme::all()[app.theme_index].name;
-            let prefix = if is_selected { " ▸ " } else { "   " };
-            let prefix_style = if is_selected {
-                Style::default()
-                    .fg(theme.cursor)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(theme.text)
-            };
-            let label_style = if is_selected {
-                Style::default()
-                    .fg(theme.selected)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(theme.text)
-            };
+    let rows: Vec<FormRow> = app.settings_rows().into_iter().map(|row| form_row(app, row)).collect();
+    widgets::draw_form(frame, app, area, "Settings", &rows);
+}
 
-            items.push(ListItem::new(Line::from(vec![
-                Span::styled(prefix, prefix_style),
-                Span::styled("Theme:  ", label_style),
-                Span::styled("◀ ", Style::default().fg(theme.text_dim)),
-                Span::styled(theme_name, Style::default().fg(theme.tab_active).add_modifier(Modifier::BOLD)),
-                Span::styled(" ▶", Style::default().fg(theme.text_dim)),
-            ])));
-        } else {
-            // Category row (idx - 1 is the category index)
-            let cat_idx = idx - 1;
+fn form_row(app: &App, row: SettingsRow) -> FormRow {
+    let (label, value) = match row {
+        SettingsRow::Section(section) => (section.label().to_string(), FormValue::Heading),
+        SettingsRow::RestoreSession => (
+            "Restore last session".to_string(),
+            FormValue::Toggle(app.config.general.restore_session),
+        ),
+        SettingsRow::Accessible => (
+            "Accessible text mode".to_string(),
+            FormValue::Toggle(app.config.general.accessible),
+        ),
+        SettingsRow::Autosave => (
+            "Save after every change".to_string(),
+            FormValue::Toggle(app.config.general.autosave),
+        ),
+        SettingsRow::KeyboardLayout => (
+            "Keyboard layout".to_string(),
+            FormValue::Choice(app.config.general.keyboard_layout.label().to_string()),
+        ),
+        SettingsRow::NewDay => (
+            "New-day tidy-up on first launch".to_string(),
+            FormValue::Toggle(app.config.general.new_day),
+        ),
+        SettingsRow::AgendaSection(idx) => {
+            let state = app.config.agenda.order[idx];
+            (
+                section_label(state).to_string(),
+                FormValue::Toggle(!app.config.agenda.is_hidden(state)),
+            )
+        }
+        SettingsRow::PromoteMode => (
+            "Auto-promote".to_string(),
+            FormValue::Choice(app.config.agenda.auto_promote.label().to_string()),
+        ),
+        SettingsRow::PromoteStrategy => (
+            "Auto-promote picks".to_string(),
+            FormValue::Choice(app.config.agenda.promote.label().to_string()),
+        ),
+        SettingsRow::StalledAfter => (
+            "Stalled after".to_string(),
+            FormValue::Choice(match app.config.agenda.stalled_after_days {
+                0 => "Off".to_string(),
+                days => format!("{} days In Progress", days),
+            }),
+        ),
+        SettingsRow::DemoteStalled => (
+            "Demote stalled tasks to On Deck".to_string(),
+            FormValue::Toggle(app.config.agenda.demote_stalled),
+        ),
+        SettingsRow::ShowCategory => (
+            "Show category on rows".to_string(),
+            FormValue::Toggle(app.config.agenda.show_category),
+        ),
+        SettingsRow::SinkDone => (
+            "Sink done tasks to project bottom".to_string(),
+            FormValue::Toggle(app.config.agenda.sink_done),
+        ),
+        SettingsRow::SingleTasking => (
+            "Single-tasking (one In Progress)".to_string(),
+            FormValue::Toggle(app.config.agenda.single_tasking),
+        ),
+        SettingsRow::Keybinding(idx) => {
+            let (key, action) = KEYBINDINGS[idx];
+            let key = app.config.general.keyboard_layout.relabel_keys(key, " / ");
+            (key, FormValue::Info(action.to_string()))
+        }
+        SettingsRow::Theme => (
+            "Theme".to_string(),
+            FormValue::Choice(Theme::all()[app.theme_index].name.to_string()),
+        ),
+        SettingsRow::DefaultFile => (
+            "Default file".to_string(),
+            FormValue::Text(
+                app.config
+                    .files
+                    .default_file
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            ),
+        ),
+        SettingsRow::PreserveUnknown => (
+            "Keep unrecognized markdown".to_string(),
+            FormValue::Toggle(app.config.files.preserve_unknown),
+        ),
+        SettingsRow::RepoLocal => (
+            "Repo-local todo files".to_string(),
+            FormValue::Toggle(app.config.files.repo_local),
+        ),
+        SettingsRow::Category(cat_idx) => {
             let category = &app.doc.categories[cat_idx];
-            let project_count = category.projects.len();
-            let is_moving = app.is_moving();
-
-            let style = if is_selected && is_moving {
-                Style::default()
-                    .fg(theme.moving)
-                    .add_modifier(Modifier::BOLD)
-            } else if is_selected {
-                Style::default()
-                    .fg(theme.selected)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(theme.text)
-            };
-
-            let prefix = if is_selected && is_moving {
-                " ↕ "
-            } else if is_selected {
-                " ▸ "
-            } else {
-                "   "
-            };
-
-            items.push(ListItem::new(Line::from(vec![
-                Span::styled(prefix, style),
-                Span::styled(category.name.clone(), style),
-                Span::styled(
-                    format!("  ({} projects)", project_count),
-                    Style::default().fg(theme.text_dim),
-                ),
-            ])));
+            let today = Date::today();
+            let next_deadline = category
+                .projects
+                .iter()
+                .filter_map(|p| Some((p.days_left(today)?, p)))
+                .min_by_key(|(days, _)| *days)
+                .map_or(String::new(), |(days, p)| format!(", {} {}", p.name, widgets::deadline_label(days)));
+            (
+                category.name.clone(),
+                FormValue::Info(format!(
+                    "({} projects{}){}",
+                    category.projects.len(),
+                    next_deadline,
+                    if category.hidden { " hidden" } else { "" }
+                )),
+            )
         }
-    }
-
-    if items.is_empty() {
-        items.push(ListItem::new(Line::from(Span::styled(
-            "  No categories. Press 'a' to add one.",
-            Style::default().fg(theme.text_dim),
-        ))));
-    }
-
-    let list = List::new(items).block(
-        Block::default()
-            .title(" Settings ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.border)),
-    );
-
-    let mut state = ListState::default();
-    frame.render_stateful_widget(list, area, &mut state);
+    };
+    FormRow { label, value }
 }
@@ -0,0 +1,205 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::symbols;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Bar, BarChart, BarGroup, Chart, Dataset, GraphType, Paragraph, Sparkline};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::date::Date;
+use crate::stats::{self, CycleTime, ProjectDone, Throughput};
+use crate::tui::widgets;
+
+/// Days covered by the weekly bars and the open-task trend.
+const WINDOW_DAYS: usize = 12 * 7;
+/// Days covered by the daily sparkline.
+const DAILY_DAYS: usize = 30;
+
+pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme();
+    let today = Date::today();
+    let stats = stats::throughput(&app.doc, &app.activity, today, WINDOW_DAYS);
+    let weeks = stats.weekly();
+    let daily = &stats.completed[WINDOW_DAYS - DAILY_DAYS..];
+
+    let block = widgets::block(app).title(" Stats ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let this_week = weeks.last().copied().unwrap_or(0);
+    let last_week = weeks.iter().rev().nth(1).copied().unwrap_or(0);
+    let average = daily.iter().sum::<u64>() as f64 / DAILY_DAYS as f64;
+    let open_now = stats.open.last().copied().unwrap_or(0);
+    let summary = format!(
+        " Done this week: {}   Last week: {}   {}-day average: {:.1}/day   Open now: {}",
+        this_week, last_week, DAILY_DAYS, average, open_now
+    );
+
+    let cycle_times = stats::cycle_times(&app.activity);
+    let by_project = stats::completed_by_project(&app.doc, stats.first_day);
+
+    if app.accessible() {
+        let mut lines = vec![Line::from(summary), Line::from(""), Line::from(" Completed per week, oldest first:")];
+        for (i, count) in weeks.iter().enumerate() {
+            let start = stats.first_day.plus_days(i as i64 * 7);
+            lines.push(Line::from(format!("   Week of {}: {}", start, count)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(" Cycle time, added to done:"));
+        lines.extend(cycle_times.iter().map(|row| Line::from(format!("   {}", cycle_time_text(row, true)))));
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!(" Archived per project, last {} weeks:", WINDOW_DAYS / 7)));
+        lines.extend(by_project.iter().map(|row| Line::from(format!("   {}", project_done_text(row, true)))));
+        frame.render_widget(Paragraph::new(lines).style(Style::default().fg(theme.text)), inner);
+        return;
+    }
+
+    let [summary_area, daily_area, weekly_area, trend_area] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(6),
+        Constraint::Length(8),
+        Constraint::Min(6),
+    ])
+    .areas(inner);
+
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(summary, Style::default().fg(theme.text)))),
+        summary_area,
+    );
+
+    let sparkline = Sparkline::default()
+        .block(widgets::block(app).title(format!(" Completed per day (last {} days) ", DAILY_DAYS)))
+        .data(daily)
+        .style(Style::default().fg(theme.state_done));
+    frame.render_widget(sparkline, daily_area);
+
+    let bars: Vec<Bar> = weeks
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let weeks_ago = weeks.len() - 1 - i;
+            let label = if weeks_ago == 0 { "now".to_string() } else { format!("-{}w", weeks_ago) };
+            Bar::default().value(count).label(Line::from(label))
+        })
+        .collect();
+    let barchart = BarChart::default()
+        .block(widgets::block(app).title(" Completed per week "))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(4)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(theme.state_done))
+        .value_style(Style::default().fg(theme.text).bg(theme.state_done));
+    frame.render_widget(barchart, weekly_area);
+
+    let [trend_area, side_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(trend_area);
+    let [cycle_area, project_area] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(side_area);
+    draw_open_trend(frame, app, trend_area, &stats);
+    draw_cycle_times(frame, app, cycle_area, &cycle_times);
+    draw_completed_by_project(frame, app, project_area, &by_project);
+}
+
+/// "Work › Launch  12 done".
+fn project_done_text(row: &ProjectDone, accessible: bool) -> String {
+    format!(
+        "{} {} {}  {} done",
+        row.category,
+        if accessible { ">" } else { "›" },
+        row.project,
+        row.completed
+    )
+}
+
+fn draw_completed_by_project(frame: &mut Frame, app: &App, area: Rect, rows: &[ProjectDone]) {
+    let theme = app.theme();
+    let lines: Vec<Line> = if rows.is_empty() {
+        vec![Line::from(Span::styled(
+            " Nothing archived from a project yet",
+            Style::default().fg(theme.text_dim),
+        ))]
+    } else {
+        rows.iter()
+            .map(|row| {
+                let text = widgets::truncate_to_width(&project_done_text(row, false), area.width.saturating_sub(3) as usize);
+                Line::from(Span::styled(format!(" {}", text), Style::default().fg(theme.text)))
+            })
+            .collect()
+    };
+    let paragraph = Paragraph::new(lines).block(widgets::block(app).title(" Archived by project "));
+    frame.render_widget(paragraph, area);
+}
+
+/// "Work › Launch  1.5d avg over 2 tasks", with a warning for slow projects.
+fn cycle_time_text(row: &CycleTime, accessible: bool) -> String {
+    let name = match &row.project {
+        Some(project) => format!("{} {} {}", row.category, if accessible { ">" } else { "›" }, project),
+        None => row.category.clone(),
+    };
+    format!(
+        "{}  {:.1}d avg over {} task{}{}",
+        name,
+        row.average_days,
+        row.tasks,
+        if row.tasks == 1 { "" } else { "s" },
+        if row.slow { "  (slow)" } else { "" }
+    )
+}
+
+fn draw_cycle_times(frame: &mut Frame, app: &App, area: Rect, rows: &[CycleTime]) {
+    let theme = app.theme();
+    let lines: Vec<Line> = if rows.is_empty() {
+        vec![Line::from(Span::styled(
+            " No finished tasks logged yet",
+            Style::default().fg(theme.text_dim),
+        ))]
+    } else {
+        rows.iter()
+            .map(|row| {
+                let style = match (&row.project, row.slow) {
+                    (None, _) => Style::default().fg(theme.category).add_modifier(Modifier::BOLD),
+                    (Some(_), true) => Style::default().fg(theme.status_error),
+                    (Some(_), false) => Style::default().fg(theme.text),
+                };
+                let indent = if row.project.is_some() { "   " } else { " " };
+                let room = (area.width as usize).saturating_sub(2 + indent.len());
+                Line::from(Span::styled(format!("{}{}", indent, widgets::truncate_to_width(&cycle_time_text(row, false), room)), style))
+            })
+            .collect()
+    };
+    let paragraph = Paragraph::new(lines).block(widgets::block(app).title(" Cycle time (added → done) "));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_open_trend(frame: &mut Frame, app: &App, area: Rect, stats: &Throughput) {
+    let theme = app.theme();
+    let points: Vec<(f64, f64)> = stats
+        .open
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| (i as f64, n as f64))
+        .collect();
+    let max = stats.open.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let last_day = (stats.open.len().max(1) - 1) as f64;
+
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(theme.state_inprogress))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(widgets::block(app).title(" Open tasks "))
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.text_dim))
+                .bounds([0.0, last_day])
+                .labels([stats.first_day.to_string(), "today".to_string()]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.text_dim))
+                .bounds([0.0, max])
+                .labels(["0".to_string(), format!("{}", max as u64)]),
+        );
+    frame.render_widget(chart, area);
+}
@@ -1,10 +1,172 @@
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::{Modifier, Style};
+use ratatui::symbols::border;
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use ratatui::Frame;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::App;
+use crate::app::{App, Dialog};
+use crate::date::Date;
+use crate::engine;
+use crate::planner;
+use crate::report::GlobalItem;
+use crate::tui::views::agenda::section_label;
+use crate::model::{AgendaItem, TaskState, TreeNode, TreeNodeKind};
+
+/// Columns `text` takes on screen; CJK characters and most emoji are two wide.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Cut `text` to at most `width` columns, ending in `…` when anything was cut.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    // Whole graphemes, so an emoji sequence or an accent is never split
+    for grapheme in text.graphemes(true) {
+        let w = grapheme.width();
+        // Leave a column for the ellipsis
+        if used + w + 1 > width {
+            break;
+        }
+        out.push_str(grapheme);
+        used += w;
+    }
+    if width > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// `text` followed by spaces out to `width` columns.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let mut out = text.to_string();
+    out.extend(std::iter::repeat_n(' ', width.saturating_sub(text.width())));
+    out
+}
+
+/// Cursor, marker and rule characters, with plain-ASCII stand-ins for
+/// accessible mode.
+pub struct Glyphs {
+    pub cursor: &'static str,
+    pub moving: &'static str,
+    pub rule: &'static str,
+    pub prev: &'static str,
+    pub next: &'static str,
+    pub text_cursor: &'static str,
+    pub divider: &'static str,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    cursor: "▸",
+    moving: "↕",
+    rule: "──",
+    prev: "◀",
+    next: "▶",
+    text_cursor: "▏",
+    divider: "│",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    cursor: ">",
+    moving: "*",
+    rule: "--",
+    prev: "<",
+    next: ">",
+    text_cursor: "_",
+    divider: "|",
+};
+
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+pub fn glyphs(app: &App) -> &'static Glyphs {
+    if app.accessible() { &ASCII_GLYPHS } else { &UNICODE_GLYPHS }
+}
+
+/// A bordered block in the theme's border colour; ASCII borders in accessible mode.
+pub fn block(app: &App) -> Block<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(app.theme().border));
+    if app.accessible() {
+        block.border_set(ASCII_BORDER)
+    } else {
+        block
+    }
+}
+
+/// Spoken-style description of an agenda row, e.g.
+/// "Task, On Deck, Buy milk, project Essential", where `place` is the
+/// project as the row labels it.
+pub fn describe_agenda_item(item: &AgendaItem, place: &str) -> String {
+    format!("Task, {}, {}, project {}", item.task.state.label(), item.task.text, place)
+}
+
+/// Days remaining on a project deadline, e.g. "12 days left" or "2 days overdue".
+pub fn deadline_label(days_left: i64) -> String {
+    match days_left {
+        0 => "due today".to_string(),
+        1 => "1 day left".to_string(),
+        -1 => "1 day overdue".to_string(),
+        n if n < 0 => format!("{} days overdue", -n),
+        n => format!("{} days left", n),
+    }
+}
+
+/// Spoken-style description of a backlog row.
+pub fn describe_tree_node(app: &App, node: &TreeNode) -> String {
+    let doc = &app.doc;
+    let fold = |collapsed: bool| if collapsed { "collapsed" } else { "expanded" };
+    match node.kind {
+        TreeNodeKind::Preamble => format!(
+            "Title, {}, {}",
+            doc.title().unwrap_or("Preamble"),
+            fold(app.collapse.preamble_collapsed)
+        ),
+        TreeNodeKind::PreambleLine { line_idx } => format!("Preamble, {}", doc.preamble[line_idx].trim()),
+        TreeNodeKind::Category { cat_idx } => format!(
+            "Category, {}, {}",
+            doc.categories[cat_idx].name,
+            fold(app.collapse.collapsed_categories.contains(&cat_idx))
+        ),
+        TreeNodeKind::Project { cat_idx, proj_idx } => {
+            let project = &doc.categories[cat_idx].projects[proj_idx];
+            format!(
+                "Project, {}, {}{}{}{}",
+                project.name,
+                if project.active { "active, " } else { "" },
+                if project.checklist { "checklist, " } else { "" },
+                project
+                    .days_left(Date::today())
+                    .map_or(String::new(), |days| format!("{}, ", deadline_label(days))),
+                fold(app.collapse.collapsed_projects.contains(&(cat_idx, proj_idx)))
+            )
+        }
+        TreeNodeKind::Task { cat_idx, proj_idx, task_idx } => {
+            let task = &doc.categories[cat_idx].projects[proj_idx].tasks[task_idx];
+            match task.notes.len() {
+                0 => format!("Task, {}, {}", task.state.label(), task.text),
+                1 => format!("Task, {}, {}, 1 note", task.state.label(), task.text),
+                n => format!("Task, {}, {}, {} notes", task.state.label(), task.text, n),
+            }
+        }
+        TreeNodeKind::Note { .. } => format!("Note, {}", node.display),
+    }
+}
 
 /// Draw a centered popup area.
 fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
@@ -23,14 +185,67 @@ fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
     .split(vertical[1])[1]
 }
 
+/// Jump-mode label for a list row, padded to `width` columns so rows stay aligned.
+pub fn jump_label_span(app: &App, idx: usize, width: usize) -> Option<Span<'static>> {
+    let label = app.jump_label_for(idx)?;
+    Some(Span::styled(
+        format!("{:>width$} ", label, width = width.saturating_sub(1)),
+        Style::default()
+            .fg(app.theme().moving)
+            .add_modifier(Modifier::BOLD),
+    ))
+}
+
 pub fn draw_input_dialog(frame: &mut Frame, app: &App, title: &str) {
+    draw_input_box(frame, app, title);
+}
+
+/// An input dialog with `app.completion` listed under it.
+pub fn draw_completing_dialog(frame: &mut Frame, app: &App, title: &str) {
+    let input = draw_input_box(frame, app, title);
+    let matches = &app.completion.matches;
+    if matches.is_empty() {
+        return;
+    }
+    let theme = app.theme();
+    let below = frame.area().height.saturating_sub(input.y + input.height);
+    let rows = matches.len().min(below.saturating_sub(2) as usize).min(8);
+    if rows == 0 {
+        return;
+    }
+    let area = Rect { y: input.y + input.height, height: rows as u16 + 2, ..input };
+    frame.render_widget(Clear, area);
+
+    let scroll = app.completion.cursor.saturating_sub(rows - 1);
+    let lines: Vec<Line> = matches
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(rows)
+        .map(|(i, name)| {
+            if i == app.completion.cursor {
+                Line::from(vec![
+                    Span::styled(format!(" {} ", glyphs(app).cursor), Style::default().fg(theme.cursor)),
+                    Span::styled(name.as_str(), Style::default().fg(theme.selected).add_modifier(Modifier::BOLD)),
+                ])
+            } else {
+                Line::from(Span::styled(format!("   {}", name), Style::default().fg(theme.text)))
+            }
+        })
+        .collect();
+    let list = Paragraph::new(lines).block(block(app).border_style(Style::default().fg(theme.dialog_border)));
+    frame.render_widget(list, area);
+}
+
+/// Draw the one-line input box of a dialog, returning where it went.
+fn draw_input_box(frame: &mut Frame, app: &App, title: &str) -> Rect {
     let theme = app.theme();
     let area = centered_rect(50, 3, frame.area());
     frame.render_widget(Clear, area);
 
     // Show cursor in input
     let display_text = if app.input_buffer.is_empty() {
-        String::from("Type task name...")
+        String::from(if app.dialog == Dialog::MoveToProject { "Type a project name..." } else { "Type task name..." })
     } else {
         app.input_buffer.clone()
     };
@@ -41,19 +256,27 @@ pub fn draw_input_dialog(frame: &mut Frame, app: &App, title: &str) {
         Style::default().fg(theme.dialog_text)
     };
 
-    let input = Paragraph::new(Line::from(Span::styled(&display_text, style))).block(
-        Block::default()
-            .title(format!(" {} ", title))
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.dialog_border)),
-    );
+    // Cursor column in display cells, scrolling the text left once it would
+    // run past the right border
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let cursor_col = display_width(&app.input_buffer[..app.input_cursor]);
+    let scroll = cursor_col.saturating_sub(inner_width.saturating_sub(1));
+
+    let input = Paragraph::new(Line::from(Span::styled(&display_text, style)))
+        .scroll((0, scroll as u16))
+        .block(
+            block(app)
+                .title(format!(" {} ", title))
+                .border_style(Style::default().fg(theme.dialog_border)),
+        );
 
     frame.render_widget(input, area);
 
     // Position cursor
-    let cursor_x = area.x + 1 + app.input_cursor as u16;
+    let cursor_x = area.x + 1 + (cursor_col - scroll) as u16;
     let cursor_y = area.y + 1;
     frame.set_cursor_position((cursor_x, cursor_y));
+    area
 }
 
 pub fn draw_confirm_dialog(frame: &mut Frame, app: &App, message: &str) {
@@ -76,11 +299,567 @@ pub fn draw_confirm_dialog(frame: &mut Frame, app: &App, message: &str) {
     ];
 
     let dialog = Paragraph::new(text).block(
-        Block::default()
+        block(app)
             .title(" Confirm ")
-            .borders(Borders::ALL)
             .border_style(Style::default().fg(theme.dialog_border)),
     );
 
     frame.render_widget(dialog, area);
 }
+
+/// Draw `app.io_error`, wrapped, in a dialog bordered in the error color.
+pub fn draw_error_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let area = centered_rect(60, 7, frame.area());
+    frame.render_widget(Clear, area);
+
+    let message = app.io_error.as_ref().map(|e| e.message.as_str()).unwrap_or_default();
+    let dialog = Paragraph::new(vec![
+        Line::from(Span::styled(message, Style::default().fg(theme.dialog_text))),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Your work in GWS is kept. r: Retry   Esc: Close",
+            Style::default().fg(theme.text_dim),
+        )),
+    ])
+    .wrap(Wrap { trim: true })
+    .block(
+        block(app)
+            .title(" Error ")
+            .border_style(Style::default().fg(theme.status_error)),
+    );
+
+    frame.render_widget(dialog, area);
+}
+
+/// Draw a scrollable list picker over `app.picker`.
+pub fn draw_picker_dialog(frame: &mut Frame, app: &App, title: &str) {
+    draw_wide_picker_dialog(frame, app, title, 50);
+}
+
+/// The picker dialog at `percent_x` of the screen's width, for long rows.
+pub fn draw_wide_picker_dialog(frame: &mut Frame, app: &App, title: &str, percent_x: u16) {
+    let theme = app.theme();
+    let max_rows = frame.area().height.saturating_sub(6).max(1) as usize;
+    let rows = app.picker.items.len().clamp(1, max_rows);
+    let area = centered_rect(percent_x, rows as u16 + 2, frame.area());
+    frame.render_widget(Clear, area);
+
+    let scroll = app.picker.cursor.saturating_sub(rows - 1);
+    let lines: Vec<Line> = app
+        .picker
+        .items
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(rows)
+        .map(|(i, label)| {
+            let label = truncate_to_width(label, area.width.saturating_sub(5) as usize);
+            if i == app.picker.cursor {
+                Line::from(vec![
+                    Span::styled(format!(" {} ", glyphs(app).cursor), Style::default().fg(theme.cursor)),
+                    Span::styled(
+                        label,
+                        Style::default()
+                            .fg(theme.selected)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ])
+            } else {
+                Line::from(Span::styled(format!("   {}", label), Style::default().fg(theme.text)))
+            }
+        })
+        .collect();
+
+    let dialog = Paragraph::new(lines).block(
+        block(app)
+            .title(format!(" {} ", title))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+
+    frame.render_widget(dialog, area);
+}
+
+/// Overview of the areas of focus: categories, projects and task counts.
+pub fn draw_areas_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let summaries = engine::area_summaries(&app.doc, &app.areas());
+    let area = centered_rect(70, summaries.len() as u16 + 5, frame.area());
+    frame.render_widget(Clear, area);
+
+    let name_width = summaries.iter().map(|s| display_width(&s.name)).max().unwrap_or(0).max(4);
+    let columns = |name: &str, cats: String, projects: String, open: String, done: String| {
+        format!(
+            " {}  {:>5}  {:>13}  {:>5}  {:>5}",
+            pad_to_width(name, name_width),
+            cats,
+            projects,
+            open,
+            done
+        )
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(
+            columns("Area", "Cats".into(), "Projects".into(), "Open".into(), "Done".into()),
+            Style::default().fg(theme.text_dim).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for s in &summaries {
+        lines.push(Line::from(Span::styled(
+            columns(
+                &s.name,
+                s.categories.to_string(),
+                format!("{} ({} active)", s.projects, s.active_projects),
+                s.open_tasks.to_string(),
+                s.done_tasks.to_string(),
+            ),
+            Style::default().fg(theme.text),
+        )));
+    }
+
+    let dialog = Paragraph::new(lines).block(
+        block(app)
+            .title(" Areas of Focus ")
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// What the new-day routine did, one change per line.
+pub fn draw_new_day_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let max_rows = frame.area().height.saturating_sub(6).max(1) as usize;
+    let rows = app.new_day_summary.len().clamp(1, max_rows);
+    let area = centered_rect(70, rows as u16 + 2, frame.area());
+    frame.render_widget(Clear, area);
+
+    let width = area.width.saturating_sub(4) as usize;
+    let lines: Vec<Line> = app
+        .new_day_summary
+        .iter()
+        .take(rows)
+        .map(|line| Line::from(Span::styled(format!(" {}", truncate_to_width(line, width)), Style::default().fg(theme.text))))
+        .collect();
+
+    let dialog = Paragraph::new(lines).block(
+        block(app)
+            .title(format!(" New Day · {} ", Date::today()))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// Rows `text` takes when wrapped to `width` columns.
+fn wrapped_rows(text: &str, width: usize) -> usize {
+    text.width().div_ceil(width.max(1)).max(1)
+}
+
+/// Draw the focused task in full with all its notes, titled with its
+/// category and project.
+pub fn draw_task_details_dialog(frame: &mut Frame, app: &App) {
+    let Some(task) = app.focused_task() else {
+        return;
+    };
+    let theme = app.theme();
+    let max_rows = frame.area().height.saturating_sub(6).max(1) as usize;
+    let width = centered_rect(70, 3, frame.area()).width.saturating_sub(2) as usize;
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!(" {}", task.text),
+        Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+    ))];
+    let mut rows = wrapped_rows(&task.text, width.saturating_sub(1));
+    if task.notes.iter().any(|note| !note.trim().is_empty()) {
+        lines.push(Line::from(""));
+        rows += 1;
+        for note in &task.notes {
+            let note = format!(" {}", note.trim_end());
+            rows += wrapped_rows(&note, width);
+            lines.push(Line::from(Span::styled(note, Style::default().fg(theme.text))));
+        }
+    }
+    let area = centered_rect(70, rows.min(max_rows) as u16 + 2, frame.area());
+    frame.render_widget(Clear, area);
+
+    let separator = if app.accessible() { " > " } else { " › " };
+    let title = truncate_to_width(&app.breadcrumb().join(separator), area.width.saturating_sub(4) as usize);
+    let dialog = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        block(app)
+            .title(format!(" {} ", title))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// Draw the agenda of every profile: a heading per section, and each task
+/// tagged with its profile.
+pub fn draw_global_agenda_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let separator = if app.accessible() { ">" } else { "›" };
+    let mut lines = Vec::new();
+    let mut cursor_line = 0;
+    for state in app.global_agenda_sections() {
+        let items: Vec<(usize, &GlobalItem)> =
+            app.global_agenda.iter().enumerate().filter(|(_, item)| item.task.state == state).collect();
+        if items.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(
+            format!(" {} ({})", section_label(state), items.len()),
+            Style::default().fg(theme.category).add_modifier(Modifier::BOLD),
+        )));
+        for (i, item) in items {
+            let selected = i == app.picker.cursor;
+            if selected {
+                cursor_line = lines.len();
+            }
+            let prefix = if selected { format!(" {} ", glyphs(app).cursor) } else { "   ".to_string() };
+            let text_style = if selected {
+                Style::default().fg(theme.selected).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(prefix, Style::default().fg(theme.cursor)),
+                Span::styled(format!("[{}] ", item.source), Style::default().fg(theme.text_dim)),
+                Span::styled(item.task.text.clone(), text_style),
+                Span::styled(
+                    format!("  {} {} {}", item.category, separator, item.project),
+                    Style::default().fg(theme.text_dim),
+                ),
+            ]));
+        }
+    }
+
+    let rows = lines.len().clamp(1, frame.area().height.saturating_sub(6).max(1) as usize);
+    let area = centered_rect(80, rows as u16 + 2, frame.area());
+    frame.render_widget(Clear, area);
+    let scroll = cursor_line.saturating_sub(rows - 1);
+    let dialog = Paragraph::new(lines).scroll((scroll as u16, 0)).block(
+        block(app)
+            .title(" Agenda: All Profiles ")
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// Draw the proposed Today list: estimate, task and project per row, with
+/// the time used out of the hours available.
+pub fn draw_plan_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let Some(day) = &app.day_plan else { return };
+    let area = centered_rect(70, day.plan.items.len().max(1) as u16 + 6, frame.area());
+    frame.render_widget(Clear, area);
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let glyphs = glyphs(app);
+
+    let mut lines = Vec::new();
+    if day.plan.items.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " Nothing to plan: no open tasks in active projects fit",
+            Style::default().fg(theme.text_dim),
+        )));
+    }
+    for (i, item) in day.plan.items.iter().enumerate() {
+        let (ci, pi, ti) = item.task;
+        let project = &app.doc.categories[ci].projects[pi];
+        let selected = i == day.cursor;
+        let prefix = if selected { format!(" {} ", glyphs.cursor) } else { "   ".to_string() };
+        // A trailing ? marks the default estimate
+        let minutes = format!(
+            "{:>7}{} ",
+            planner::format_minutes(item.minutes),
+            if item.estimated { " " } else { "?" }
+        );
+        let suffix = format!(" ({})", project.name);
+        let room = inner_width.saturating_sub(display_width(&prefix) + display_width(&minutes) + display_width(&suffix));
+        let style = if selected {
+            Style::default().fg(theme.selected).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(prefix, Style::default().fg(theme.cursor)),
+            Span::styled(minutes, Style::default().fg(theme.text_dim)),
+            Span::styled(truncate_to_width(&project.tasks[ti].text, room), style),
+            Span::styled(suffix, Style::default().fg(theme.text_dim)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    let mut summary = format!(
+        " {} of {}h planned",
+        planner::format_minutes(day.plan.total_minutes()),
+        day.hours
+    );
+    if day.plan.left_out > 0 {
+        summary.push_str(&format!(", {} more didn't fit", day.plan.left_out));
+    }
+    lines.push(Line::from(Span::styled(summary, Style::default().fg(theme.text_dim))));
+
+    let dialog = Paragraph::new(lines).block(
+        block(app)
+            .title(" Plan My Day ")
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// The paste-import dialog: a preview of the tasks the pasted lines become,
+/// newest at the bottom, with the line being typed shown with a cursor.
+pub fn draw_paste_import_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let Some(paste) = &app.paste_import else { return };
+    let tasks = app.paste_import_preview();
+    let max_rows = frame.area().height.saturating_sub(8).max(1) as usize;
+    let rows = tasks.len().clamp(1, max_rows);
+    let area = centered_rect(60, rows as u16 + 4, frame.area());
+    frame.render_widget(Clear, area);
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let symbol = if app.accessible() { "-" } else { TaskState::Todo.symbol() };
+
+    let mut lines = Vec::new();
+    if tasks.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " Paste a list, or type one task per line",
+            Style::default().fg(theme.dialog_placeholder),
+        )));
+    }
+    let hidden = tasks.len().saturating_sub(rows);
+    let typing = !paste.text.is_empty() && !paste.text.ends_with('\n');
+    for (i, task) in tasks.iter().enumerate().skip(hidden) {
+        let cursor = if typing && i + 1 == tasks.len() { glyphs(app).text_cursor } else { "" };
+        let text = truncate_to_width(task, inner_width.saturating_sub(5));
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", symbol), Style::default().fg(theme.text_dim)),
+            Span::styled(format!("{}{}", text, cursor), Style::default().fg(theme.dialog_text)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    let mut summary = format!(" {} task{} to add", tasks.len(), if tasks.len() == 1 { "" } else { "s" });
+    if hidden > 0 {
+        summary.push_str(&format!(" ({} above)", hidden));
+    }
+    lines.push(Line::from(Span::styled(summary, Style::default().fg(theme.text_dim))));
+
+    let project = &app.doc.categories[paste.cat_idx].projects[paste.proj_idx];
+    let dialog = Paragraph::new(lines).block(
+        block(app)
+            .title(format!(" Paste Tasks into {} ", project.name))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// Draw a single-key choice menu: each option is a key and its description.
+pub fn draw_menu_dialog(frame: &mut Frame, app: &App, title: &str, options: &[(&str, &str)]) {
+    let theme = app.theme();
+    let area = centered_rect(40, options.len() as u16 + 4, frame.area());
+    frame.render_widget(Clear, area);
+
+    let mut text = vec![Line::from("")];
+    for (key, label) in options {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!("  {}: ", key),
+                Style::default()
+                    .fg(theme.dialog_text)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(*label, Style::default().fg(theme.text)),
+        ]));
+    }
+
+    let dialog = Paragraph::new(text).block(
+        block(app)
+            .title(format!(" {} ", title))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+
+    frame.render_widget(dialog, area);
+}
+
+/// Keys offered while triaging the inbox.
+pub const TRIAGE_OPTIONS: &[(&str, &str)] = &[
+    ("p", "Do now (promote)"),
+    ("f", "Defer (set a due date)"),
+    ("w", "Delegate (waiting on @someone)"),
+    ("m", "Move to a project"),
+    ("s", "Someday / maybe"),
+    ("d", "Delete"),
+    ("n", "Skip for now"),
+];
+
+/// Draw the inbox task under review with the triage choices below it.
+pub fn draw_triage_dialog(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let Some(task) = app.triage_task() else { return };
+    let notes = task.notes.len().min(3);
+    let area = centered_rect(60, (TRIAGE_OPTIONS.len() + notes) as u16 + 6, frame.area());
+    frame.render_widget(Clear, area);
+    let inner_width = area.width.saturating_sub(4) as usize;
+
+    let mut text = vec![
+        Line::from(Span::styled(
+            format!(" {}", truncate_to_width(&task.text, inner_width)),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )),
+    ];
+    for note in task.notes.iter().take(notes) {
+        text.push(Line::from(Span::styled(
+            format!("   {}", truncate_to_width(note.trim(), inner_width.saturating_sub(2))),
+            Style::default().fg(theme.text_dim),
+        )));
+    }
+    text.push(Line::from(""));
+    for (key, label) in TRIAGE_OPTIONS {
+        text.push(Line::from(vec![
+            Span::styled(
+                format!("  {}: ", key),
+                Style::default()
+                    .fg(theme.dialog_text)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(*label, Style::default().fg(theme.text)),
+        ]));
+    }
+
+    let remaining = app.triage_remaining();
+    let dialog = Paragraph::new(text).block(
+        block(app)
+            .title(format!(" {} Triage: {} left ", app.triage_label(), remaining))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(dialog, area);
+}
+
+/// Draw the tutorial's step card in the lower right of `area`.
+pub fn draw_tutorial_overlay(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(tutorial) = &app.tutorial else { return };
+    let theme = app.theme();
+    let width = (area.width * 3 / 5).max(30).min(area.width);
+    let height = 6.min(area.height);
+    let card = Rect {
+        x: area.x + area.width - width,
+        y: area.y + area.height - height,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, card);
+
+    let title_style = if tutorial.is_finished() {
+        Style::default().fg(theme.state_done).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.selected).add_modifier(Modifier::BOLD)
+    };
+    let text = vec![
+        Line::from(Span::styled(tutorial.title(), title_style)),
+        Line::from(Span::styled(tutorial.hint(app), Style::default().fg(theme.text))),
+    ];
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: true }).block(
+        block(app)
+            .title(format!(" Tutorial {} ", tutorial.progress()))
+            .border_style(Style::default().fg(theme.dialog_border)),
+    );
+    frame.render_widget(paragraph, card);
+}
+
+/// The value side of a settings form row.
+pub enum FormValue {
+    /// A section heading; the label is the section name.
+    Heading,
+    Toggle(bool),
+    /// One of several values, cycled with h/l.
+    Choice(String),
+    /// Free text edited in a dialog; empty shows a placeholder.
+    Text(String),
+    /// Read-only detail.
+    Info(String),
+}
+
+pub struct FormRow {
+    pub label: String,
+    pub value: FormValue,
+}
+
+/// Draw a scrollable settings form with the cursor on `app.settings_cursor`.
+pub fn draw_form(frame: &mut Frame, app: &App, area: Rect, title: &str, rows: &[FormRow]) {
+    let theme = app.theme();
+    let visible_height = area.height.saturating_sub(2) as usize; // borders
+    let label_width = rows
+        .iter()
+        .filter(|r| !matches!(r.value, FormValue::Heading))
+        .map(|r| display_width(&r.label))
+        .max()
+        .unwrap_or(0)
+        .min(24);
+    let is_moving = app.is_moving();
+    let glyphs = glyphs(app);
+    let accessible = app.accessible();
+
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .skip(app.settings_scroll)
+        .take(visible_height)
+        .map(|(idx, row)| {
+            let is_selected = idx == app.settings_cursor;
+            let (prefix, style) = if is_selected && is_moving {
+                (format!(" {} ", glyphs.moving), Style::default().fg(theme.moving).add_modifier(Modifier::BOLD))
+            } else if is_selected {
+                (format!(" {} ", glyphs.cursor), Style::default().fg(theme.selected).add_modifier(Modifier::BOLD))
+            } else {
+                ("   ".to_string(), Style::default().fg(theme.text))
+            };
+            let prefix_style = match (is_selected, is_moving) {
+                (true, true) => Style::default().fg(theme.moving),
+                (true, false) => Style::default().fg(theme.cursor).add_modifier(Modifier::BOLD),
+                _ => Style::default(),
+            };
+            let prefix = jump_label_span(app, idx, 3).unwrap_or_else(|| Span::styled(prefix, prefix_style));
+            let dim = Style::default().fg(theme.text_dim);
+
+            let mut spans = vec![prefix];
+            match &row.value {
+                FormValue::Heading => {
+                    let heading_style = if is_selected { style } else { dim.add_modifier(Modifier::BOLD) };
+                    spans.push(Span::styled(format!("{} {} {}", glyphs.rule, row.label, glyphs.rule), heading_style));
+                }
+                value => {
+                    let label = pad_to_width(&truncate_to_width(&row.label, label_width), label_width);
+                    spans.push(Span::styled(format!("  {}  ", label), style));
+                    match value {
+                        FormValue::Toggle(on) => {
+                            let mark = match (accessible, *on) {
+                                (true, true) => "on",
+                                (true, false) => "off",
+                                (false, true) => "[x]",
+                                (false, false) => "[ ]",
+                            };
+                            spans.push(Span::styled(mark, dim));
+                        }
+                        FormValue::Choice(choice) => {
+                            spans.push(Span::styled(format!("{} ", glyphs.prev), dim));
+                            spans.push(Span::styled(
+                                choice.clone(),
+                                Style::default().fg(theme.tab_active).add_modifier(Modifier::BOLD),
+                            ));
+                            spans.push(Span::styled(format!(" {}", glyphs.next), dim));
+                        }
+                        FormValue::Text(text) if text.is_empty() => spans.push(Span::styled("(not set)", dim)),
+                        FormValue::Text(text) => spans.push(Span::styled(text.clone(), Style::default().fg(theme.text))),
+                        FormValue::Info(info) => spans.push(Span::styled(info.clone(), dim)),
+                        FormValue::Heading => {}
+                    }
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let form = Paragraph::new(lines).block(block(app).title(format!(" {} ", title)));
+    frame.render_widget(form, area);
+}
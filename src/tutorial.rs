@@ -0,0 +1,162 @@
+use crate::app::{App, Dialog, View};
+use crate::model::Document;
+
+/// Sandbox document loaded by `gws tutorial`.
+pub const SANDBOX: &str = "\
+# Tutorial
+
+## Work
+
+### 🔶 Launch Website
+- 🔵 Pick a domain name
+- 🔴 Write the landing page
+- 🔴 Set up analytics
+
+### 🔶 Team
+- ✅ Book the offsite venue
+- 🔴 Plan the agenda
+
+## Home
+
+### Garden
+- 🔴 Buy seeds
+";
+
+/// One step of the tutorial: what to do, where, and how to tell it is done.
+struct Step {
+    title: &'static str,
+    instructions: &'static str,
+    /// View the step is performed in; None when reaching a view is the goal.
+    view: Option<View>,
+    done: fn(&App, &Checkpoint) -> bool,
+}
+
+/// App state captured when a step starts, for steps that check for a change.
+struct Checkpoint {
+    doc: Document,
+    agenda_cursor: usize,
+}
+
+const STEPS: &[Step] = &[
+    Step {
+        title: "Moving around",
+        instructions: "The Agenda lists the work in your active projects. Press j or k to move the cursor.",
+        view: Some(View::Agenda),
+        done: |app, start| app.agenda_cursor != start.agenda_cursor,
+    },
+    Step {
+        title: "Promoting a task",
+        instructions: "Press p to promote the task under the cursor to its next state (x demotes).",
+        view: Some(View::Agenda),
+        done: |app, start| states(&app.doc) != states(&start.doc),
+    },
+    Step {
+        title: "The Backlog",
+        instructions: "Press Tab to switch to the Backlog, the full tree of categories, projects and tasks.",
+        view: None,
+        done: |app, _| app.view == View::Backlog,
+    },
+    Step {
+        title: "Adding a task",
+        instructions: "Put the cursor on a project or task, press a, type a task, and press Enter.",
+        view: Some(View::Backlog),
+        done: |app, start| task_count(&app.doc) > task_count(&start.doc),
+    },
+    Step {
+        title: "Moving a task",
+        instructions: "Put the cursor on a task, press m, use j/k to move it, and press Enter to drop it.",
+        view: Some(View::Backlog),
+        done: |app, start| !app.is_moving() && task_order(&app.doc) != task_order(&start.doc),
+    },
+    Step {
+        title: "Archiving",
+        instructions: "Tab back to the Agenda, press A, then y to archive every Done task.",
+        view: Some(View::Agenda),
+        done: |app, start| app.doc.archive.len() > start.doc.archive.len(),
+    },
+];
+
+fn tasks(doc: &Document) -> impl Iterator<Item = &crate::model::Task> {
+    doc.categories
+        .iter()
+        .flat_map(|c| c.projects.iter())
+        .flat_map(|p| p.tasks.iter())
+}
+
+fn task_count(doc: &Document) -> usize {
+    tasks(doc).count()
+}
+
+fn states(doc: &Document) -> Vec<crate::model::TaskState> {
+    tasks(doc).map(|t| t.state).collect()
+}
+
+fn task_order(doc: &Document) -> Vec<&str> {
+    tasks(doc).map(|t| t.text.as_str()).collect()
+}
+
+/// Progress through the guided tutorial, checked after every key press.
+pub struct Tutorial {
+    step: usize,
+    start: Checkpoint,
+}
+
+impl Tutorial {
+    pub fn new(app: &App) -> Self {
+        Self {
+            step: 0,
+            start: Self::checkpoint(app),
+        }
+    }
+
+    fn checkpoint(app: &App) -> Checkpoint {
+        Checkpoint {
+            doc: app.doc.clone(),
+            agenda_cursor: app.agenda_cursor,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    /// Advance past the current step once its goal is met. Waits while a
+    /// dialog is open so half-finished actions don't count.
+    pub fn update(&mut self, app: &App) {
+        let Some(step) = STEPS.get(self.step) else { return };
+        if app.dialog == Dialog::None && (step.done)(app, &self.start) {
+            self.step += 1;
+            self.start = Self::checkpoint(app);
+        }
+    }
+
+    /// Step counter, e.g. "2/6".
+    pub fn progress(&self) -> String {
+        format!("{}/{}", (self.step + 1).min(STEPS.len()), STEPS.len())
+    }
+
+    pub fn title(&self) -> &'static str {
+        STEPS.get(self.step).map_or("All done", |s| s.title)
+    }
+
+    /// Instructions for the current step, or a nudge when the user is in the
+    /// wrong view for it.
+    pub fn hint(&self, app: &App) -> String {
+        let Some(step) = STEPS.get(self.step) else {
+            return "You know the basics. Press q to leave the tutorial; nothing here is saved to your todo file."
+                .to_string();
+        };
+        match step.view {
+            Some(view) if view != app.view => {
+                let name = match view {
+                    View::Agenda => "Agenda",
+                    View::Backlog => "Backlog",
+                    View::Stats => "Stats",
+                    View::Settings => "Settings",
+                };
+                format!("Press Tab until the {} view is showing.", name)
+            }
+            _ => step.instructions.to_string(),
+        }
+    }
+}
@@ -1,45 +1,171 @@
-use std::path::PathBuf;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
+use crate::storage;
+
 /// Events from the file watcher.
 #[derive(Debug)]
 pub enum FileEvent {
     Modified,
 }
 
-/// Start watching a file for modifications.
-/// Returns a receiver that emits FileEvent when the file changes.
-pub fn watch_file(
+/// Identity of a file version, recorded after our own saves so the events
+/// they trigger can be told apart from external edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl Fingerprint {
+    /// Fingerprint the file as it is on disk now.
+    pub fn of_file(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let content = fs::read(path).ok()?;
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+        })
+    }
+
+    /// Whether the file on disk is still the version this fingerprint recorded.
+    pub fn matches(&self, path: &Path) -> bool {
+        Self::of_file(path).as_ref() == Some(self)
+    }
+}
+
+/// Watches the todo file for changes. A symlink is followed: the directory of
+/// its target is watched as well as the link's own, so edits to the real file
+/// are seen, and so is pointing the link elsewhere, after which the new target
+/// is watched instead.
+pub struct FileWatch {
     path: PathBuf,
-) -> anyhow::Result<(mpsc::Receiver<FileEvent>, RecommendedWatcher)> {
+    target: PathBuf,
+    rx: mpsc::Receiver<FileEvent>,
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatch {
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let target = storage::resolve(path);
+        let (rx, watcher) = watch_file(path.to_path_buf(), target.clone())?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            target,
+            rx,
+            _watcher: watcher,
+        })
+    }
+
+    /// Check for a change without blocking.
+    pub fn poll(&mut self) -> Option<FileEvent> {
+        let event = self.rx.recv_timeout(Duration::from_millis(0)).ok()?;
+        self.follow_link();
+        Some(event)
+    }
+
+    /// Start watching the link's new target if it has been repointed.
+    /// Returns whether it had.
+    fn follow_link(&mut self) -> bool {
+        if storage::resolve(&self.path) == self.target {
+            return false;
+        }
+        // Keep the old watch if the new target can't be watched (yet)
+        match Self::new(&self.path) {
+            Ok(rewatched) => {
+                tracing::debug!(link = %self.path.display(), target = %rewatched.target.display(), "Link repointed");
+                *self = rewatched;
+            }
+            Err(e) => tracing::warn!(link = %self.path.display(), "Could not watch the link's new target: {:#}", e),
+        }
+        true
+    }
+}
+
+/// Watch `path` and, when it is a symlink, its `target`, sending FileEvent
+/// whenever either changes. Parent directories are watched to catch renames
+/// (atomic writes).
+fn watch_file(path: PathBuf, target: PathBuf) -> anyhow::Result<(mpsc::Receiver<FileEvent>, RecommendedWatcher)> {
     let (tx, rx) = mpsc::channel();
-    let watch_path = path.clone();
+    let mut dirs: Vec<PathBuf> = [&path, &target]
+        .iter()
+        .map(|p| p.parent().unwrap_or(p).to_path_buf())
+        .collect();
+    dirs.dedup();
+    let watched = [path, target];
 
     let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         if let Ok(event) = res {
             match event.kind {
-                EventKind::Modify(_) | EventKind::Create(_) => {
-                    // Only send if the event is for our file
-                    if event.paths.iter().any(|p| p == &watch_path) {
-                        let _ = tx.send(FileEvent::Modified);
-                    }
+                // Only send if the event is for our file
+                EventKind::Modify(_) | EventKind::Create(_) if event.paths.iter().any(|p| watched.contains(p)) => {
+                    let _ = tx.send(FileEvent::Modified);
                 }
                 _ => {}
             }
         }
     })?;
 
-    // Watch the parent directory to catch renames (atomic writes)
-    let parent = path.parent().unwrap_or(&path);
-    watcher.watch(parent.as_ref(), RecursiveMode::NonRecursive)?;
+    for dir in dirs {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
 
     Ok((rx, watcher))
 }
 
-/// Check for file events without blocking.
-pub fn poll_file_events(rx: &mpsc::Receiver<FileEvent>) -> Option<FileEvent> {
-    rx.recv_timeout(Duration::from_millis(0)).ok()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_detects_external_writes() {
+        let dir = std::env::temp_dir().join(format!("gws-watcher-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("todo.md");
+
+        fs::write(&path, "## Work\n").unwrap();
+        let ours = Fingerprint::of_file(&path).unwrap();
+        assert!(ours.matches(&path));
+
+        fs::write(&path, "## Work\n- 🔴 Added elsewhere\n").unwrap();
+        assert!(!ours.matches(&path));
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!ours.matches(&path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_follows_repointed_link() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("gws-relink-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("a/todo.md"), "## Work\n").unwrap();
+        fs::write(dir.join("b/todo.md"), "## Home\n").unwrap();
+        let link = dir.join("todo.md");
+        symlink(dir.join("a/todo.md"), &link).unwrap();
+
+        let mut watch = FileWatch::new(&link).unwrap();
+        assert_eq!(watch.target, storage::resolve(&dir.join("a/todo.md")));
+        assert!(!watch.follow_link());
+
+        fs::remove_file(&link).unwrap();
+        symlink(dir.join("b/todo.md"), &link).unwrap();
+        assert!(watch.follow_link());
+        assert_eq!(watch.target, storage::resolve(&dir.join("b/todo.md")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
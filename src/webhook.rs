@@ -0,0 +1,169 @@
+//! Completion announcements: when a save finishes or starts tasks, post a
+//! message about each one to the `[[webhooks]]` that want it, as the JSON
+//! `{"text": ...}` that Slack, Mattermost and most chat webhooks take.
+
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::Webhook;
+use crate::diff::{self, Change};
+use crate::model::{Category, Document, Project, Task};
+use crate::query::Query;
+
+/// How long one post may take before it is given up on.
+pub const POST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A message ready to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub url: String,
+    pub text: String,
+}
+
+/// Messages for the tasks that changed state from `old` to `new`, one per
+/// task and webhook whose states and filter it matches. Webhooks with a
+/// filter that doesn't parse are skipped.
+pub fn announcements(webhooks: &[Webhook], old: &Document, new: &Document) -> Vec<Announcement> {
+    // An empty filter matches every task
+    let hooks: Vec<(&Webhook, Option<Query>)> = webhooks
+        .iter()
+        .filter_map(|hook| match hook.filter.trim() {
+            "" => Some((hook, None)),
+            filter => match Query::parse(filter) {
+                Ok(query) => Some((hook, Some(query))),
+                Err(e) => {
+                    tracing::warn!(url = %hook.url, "Webhook filter: {:#}", e);
+                    None
+                }
+            },
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for change in diff::diff(old, new) {
+        let Change::StateChanged { address, to, .. } = change else {
+            continue;
+        };
+        let Some((category, project, task)) = find(new, &address) else {
+            continue;
+        };
+        for (hook, filter) in &hooks {
+            let wanted = hook.on.contains(&to) && filter.as_ref().is_none_or(|q| q.matches(category, project, task));
+            if wanted {
+                out.push(Announcement { url: hook.url.clone(), text: render(&hook.template, category, project, task) });
+            }
+        }
+    }
+    out
+}
+
+fn find<'a>(doc: &'a Document, address: &diff::TaskAddress) -> Option<(&'a Category, &'a Project, &'a Task)> {
+    let category = doc.categories.iter().find(|c| c.name == address.category)?;
+    let project = category.projects.iter().find(|p| p.name == address.project)?;
+    let task = project.tasks.iter().find(|t| t.text == address.text)?;
+    Some((category, project, task))
+}
+
+/// Fill in `{text}`, `{project}`, `{category}`, `{state}` (`Done`) and
+/// `{icon}` (`✅`).
+pub fn render(template: &str, category: &Category, project: &Project, task: &Task) -> String {
+    fill(template, |name| match name {
+        "text" => Some(&task.text),
+        "project" => Some(&project.name),
+        "category" => Some(&category.name),
+        "state" => Some(task.state.label()),
+        "icon" => Some(task.state.symbol()),
+        _ => None,
+    })
+}
+
+/// Replace each `{name}` that `value` knows in one pass over `template`, so
+/// braces in the values themselves are left alone.
+pub(crate) fn fill<'a>(template: &str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match rest.find('}').and_then(|end| Some((end, value(&rest[1..end])?))) {
+            Some((end, text)) => {
+                out.push_str(text);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// POST `{"text": text}` to `url` with `curl`.
+pub fn post(url: &str, text: &str, timeout: Duration) -> Result<()> {
+    let body = serde_json::json!({ "text": text }).to_string();
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", &timeout.as_secs().max(1).to_string()])
+        .args(["--header", "Content-Type: application/json", "--data-binary", &body, "--", url])
+        .output()
+        .context("Could not run curl")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Send each announcement in the background. Failures go to the debug log;
+/// they never hold up or fail a save.
+pub fn spawn_posts(announcements: Vec<Announcement>) {
+    for Announcement { url, text } in announcements {
+        std::thread::spawn(move || {
+            if let Err(e) = post(&url, &text, POST_TIMEOUT) {
+                tracing::warn!(url = %url, "Webhook failed: {:#}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TaskState;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_announcements() {
+        let old = parse("## Work\n### Ops\n- 🔶 Set up staging environment\n- 🔵 Rotate keys #security\n### Home\n- 🔵 Fix sink\n");
+        let new = parse("## Work\n### Ops\n- ✅ Set up staging environment\n- ✅ Rotate keys #security\n### Home\n- 🔶 Fix sink\n");
+        let hook = |filter: &str, on: Vec<TaskState>, template: &str| Webhook {
+            url: "https://hooks.example.com/x".to_string(),
+            filter: filter.to_string(),
+            on,
+            template: template.to_string(),
+        };
+        let texts = |hooks: &[Webhook]| announcements(hooks, &old, &new).into_iter().map(|a| a.text).collect::<Vec<_>>();
+
+        assert_eq!(
+            texts(&[hook("", vec![TaskState::Done], "{icon} Finished: {text}")]),
+            ["✅ Finished: Set up staging environment", "✅ Finished: Rotate keys #security"]
+        );
+        assert_eq!(texts(&[hook("#security", vec![TaskState::Done], "{text} ({project})")]), ["Rotate keys #security (Ops)"]);
+        assert_eq!(texts(&[hook("", vec![TaskState::InProgress], "{category}: {text} → {state}")]), ["Work: Fix sink → In Progress"]);
+        assert!(texts(&[hook("state:", vec![TaskState::Done], "{text}")]).is_empty());
+        assert!(announcements(&[hook("", vec![TaskState::Done], "{text}")], &new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let doc = parse("## Work\n### Ops\n- ✅ Fix {project} typo in {{icon}\n");
+        let category = &doc.categories[0];
+        let project = &category.projects[0];
+        let task = &project.tasks[0];
+        // Placeholders in the task's own text are left as written
+        assert_eq!(render("{icon} {text} ({project}) {unknown}", category, project, task), "✅ Fix {project} typo in {{icon} (Ops) {unknown}");
+        assert_eq!(render("{{text}}", category, project, task), "{Fix {project} typo in {{icon}}");
+    }
+}